@@ -1,21 +1,36 @@
 use chrono::{DateTime, Utc};
+use log::{info, warn};
+use itertools::Itertools;
+use risico::models::input::InputVariableName;
 use risico::models::output::OutputVariableName;
+use risico::modules::haines::config::HainesVariant;
+use risico::modules::risico::config::RequiredInput;
+use serde::Deserialize as _;
 use serde_derive::{Deserialize, Serialize};
 use serde_yaml;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::Read;
 use std::str::FromStr;
-use std::{collections::HashMap, io};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
 
-use crate::common::helpers::RISICOError;
+use crate::common::helpers::{
+    is_required_input_available, required_input_sources, InputOptions, RISICOError,
+};
 use crate::common::io::models::grid::ClusterMode;
 use crate::common::io::models::output::OutputVariable;
+use crate::common::io::readers::gaps::TimelineGapConfig;
+#[cfg(feature = "grib")]
+use crate::common::io::readers::grib::GribInputConfiguration;
 use crate::common::io::readers::netcdf::NetCdfInputConfiguration;
 
 use super::models::{
     WARM_STATE_HOUR,
     WARM_STATE_LAG_DAYS,
+    WARM_STATE_MAX_AGE_DAYS,
     RISICOConfig,
     FWIConfig,
     Mark5Config,
@@ -25,8 +40,11 @@ use super::models::{
     NesterovConfig,
     SharplesConfig,
     OrieuxConfig,
-//    PortugueseConfig,
+    PortugueseConfig,
     HdwConfig,
+    HainesConfig,
+    ChandlerConfig,
+    GfdiConfig,
 };
 
 pub type PaletteMap = HashMap<String, String>;
@@ -36,6 +54,8 @@ const MODEL_NAME_KEY: &str = "MODELNAME";
 const WARM_STATE_PATH_KEY: &str = "STATO0";
 const WARM_STATE_HOUR_KEY: &str = "STATO0_HOUR";
 const WARM_STATE_LAG_DAYS_KEY: &str = "STATO0_LAG_DAYS";
+const WARM_STATE_MAX_AGE_DAYS_KEY: &str = "STATO0_MAX_AGE_DAYS";
+const WARM_STATE_FILE_KEY: &str = "STATO0_FILE";
 const CELLS_FILE_KEY: &str = "CELLE";
 const VEGETATION_FILE_KEY: &str = "VEG";
 const PPF_FILE_KEY: &str = "PPF";
@@ -106,20 +126,140 @@ pub fn read_config(file_name: impl Into<String>) -> Result<ConfigMap, RISICOErro
     Ok(config_map)
 }
 
+/// Hours at which the warm state is written: a single hour or a list of hours
+/// (the daily models update their state once a day, at the first of the hours)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WarmStateHours {
+    Single(i64),
+    Multiple(Vec<i64>),
+}
+
+impl WarmStateHours {
+    /// The hours, sorted and without duplicates (the default hour if the list is empty)
+    pub fn hours(&self) -> Vec<i64> {
+        let mut hours = match self {
+            WarmStateHours::Single(hour) => vec![*hour],
+            WarmStateHours::Multiple(hours) => hours.clone(),
+        };
+        if hours.is_empty() {
+            hours.push(WARM_STATE_HOUR);
+        }
+        hours.sort();
+        hours.dedup();
+        hours
+    }
+}
+
+/// Hours at which the warm state is written, the default hour if not configured
+pub fn warm_state_hours(hours: &Option<WarmStateHours>) -> Vec<i64> {
+    hours
+        .as_ref()
+        .map_or_else(|| vec![WARM_STATE_HOUR], WarmStateHours::hours)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RISICOConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub vegetation_file: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub ppf_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
     // pub use_temperature_effect: bool,  // DEPRECATED
     // pub use_ndvi: bool,  // DEPRECATED
     pub output_time_resolution: u32,
     pub model_version: String,
+    #[serde(default)]
+    pub ndwi_validity_hours: Option<i64>,
+    #[serde(default)]
+    pub phenology_file: Option<String>,
+    #[serde(default)]
+    pub output_trigger: OutputTrigger,
+    #[serde(default)]
+    pub wind_dir_fallback: bool,
+    // factor of the wind gust overriding the wind speed of the v2025 rate of spread
+    // (default: wind speed only)
+    #[serde(default)]
+    pub wind_gust_factor: Option<f32>,
+    // inputs required for computing a cell (default: compute whenever possible)
+    #[serde(default)]
+    pub required_inputs: Vec<RequiredInput>,
+    // slope and aspect [deg] used for cells with missing or out of range values (default: flat)
+    #[serde(default)]
+    pub default_slope: Option<f32>,
+    #[serde(default)]
+    pub default_aspect: Option<f32>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+    // format of the written warm state files (default: Text), both are read
+    #[serde(default)]
+    pub warm_state_format: WarmStateFormat,
+}
+
+/// Format of the warm state files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WarmStateFormat {
+    /// one line of whitespace-separated values per cell
+    #[default]
+    #[serde(alias = "text")]
+    Text,
+    /// little-endian f32 values after a magic and version header
+    #[serde(alias = "binary")]
+    Binary,
+}
+
+/// When the outputs of a model are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OutputTrigger {
+    /// every `output_time_resolution` hours from the run date
+    #[default]
+    TimeResolution,
+    /// at the timesteps where new satellite data are available
+    SatelliteUpdate,
+}
+
+/// How cells with the same coordinates in the cells file are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DuplicateCells {
+    /// keep all the cells and warn with the number of duplicates
+    #[default]
+    Warn,
+    /// keep only the first cell with the given coordinates
+    Deduplicate,
+}
+
+/// Layout of the lines of the JSONL outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum JsonlLayout {
+    /// one object per cell with the values of all the variables
+    #[default]
+    #[serde(alias = "cell")]
+    Cell,
+    /// one object per cell and variable, with the variable name and its value
+    #[serde(alias = "value")]
+    Value,
+}
+
+/// Handling of the times already in an existing NetCDF output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetcdfAppend {
+    /// write the times again, replacing the values in the file
+    Overwrite,
+    /// keep the values already in the file for the times
+    Skip,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -127,11 +267,39 @@ pub struct FWIConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
     pub output_time_resolution: Option<u32>,
     pub model_version: String,
+    // calibration of the codes (default: standard FWI values)
+    #[serde(default)]
+    pub ffmc_init: Option<f32>,
+    #[serde(default)]
+    pub dmc_init: Option<f32>,
+    #[serde(default)]
+    pub dc_init: Option<f32>,
+    #[serde(default)]
+    pub ffmc_rain_threshold: Option<f32>,
+    #[serde(default)]
+    pub dmc_rain_threshold: Option<f32>,
+    #[serde(default)]
+    pub dc_rain_threshold: Option<f32>,
+    // compute the Intensity FWI (default: true)
+    #[serde(default)]
+    pub compute_ifwi: Option<bool>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -139,10 +307,28 @@ pub struct Mark5ConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
+    // hours between the outputs (default: outputs written with the daily update)
+    #[serde(default)]
+    pub output_time_resolution: Option<u32>,
+    // hour of the day of the first output [0-23] (default: 0 with output_time_resolution)
+    #[serde(default)]
+    pub output_hour: Option<u32>,
     pub model_version: String,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -150,10 +336,32 @@ pub struct KbdiConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
+    // hours between the outputs (default: outputs written with the daily update)
+    #[serde(default)]
+    pub output_time_resolution: Option<u32>,
+    // hour of the day of the first output [0-23] (default: 0 with output_time_resolution)
+    #[serde(default)]
+    pub output_hour: Option<u32>,
     pub model_version: String,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+    // assumed antecedent dryness [0-1] seeding the KBDI from the mean annual rain of the cells
+    // when no warm state is found (default: KBDI_INIT on all the cells)
+    #[serde(default)]
+    pub cold_start_dryness: Option<f32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -162,6 +370,12 @@ pub struct AngstromConfigBuilder {
     pub cells_file_path: String,
     pub output_types: Vec<OutputTypeConfig>,
     pub output_time_resolution: u32,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -170,6 +384,12 @@ pub struct FosbergConfigBuilder {
     pub cells_file_path: String,
     pub output_types: Vec<OutputTypeConfig>,
     pub output_time_resolution: u32,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -177,9 +397,21 @@ pub struct NesterovConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -188,6 +420,22 @@ pub struct SharplesConfigBuilder {
     pub cells_file_path: String,
     pub output_types: Vec<OutputTypeConfig>,
     pub output_time_resolution: u32,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+    // offset added to the air temperature to obtain the dead fuel temperature [°C]
+    // (default: no correction)
+    #[serde(default)]
+    pub temperature_offset: Option<f32>,
+    // coefficients of the fuel moisture index FMI = intercept - slope * (T - RH)
+    // (default: 10 and 0.25)
+    #[serde(default)]
+    pub fmi_intercept: Option<f32>,
+    #[serde(default)]
+    pub fmi_slope: Option<f32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -195,19 +443,44 @@ pub struct OrieuxConfigBuilder {
     pub model_name: String,
     pub cells_file_path: String,
     pub warm_state_path: String,
-    pub warm_state_hour: Option<i64>,
+    pub warm_state_hour: Option<WarmStateHours>,
     pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
     pub output_types: Vec<OutputTypeConfig>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
-// #[derive(Debug, Serialize, Deserialize)]
-// pub struct PortugueseConfigBuilder {
-//     pub model_name: String,
-//     pub cells_file_path: String,
-//     pub warm_state_path: String,
-//     pub warm_state_hour: i64,
-//     pub output_types: Vec<OutputTypeConfig>,
-// }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortugueseConfigBuilder {
+    pub model_name: String,
+    pub cells_file_path: String,
+    pub warm_state_path: String,
+    pub warm_state_hour: Option<WarmStateHours>,
+    pub warm_state_lag_days: Option<i64>,
+    // oldest warm state accepted, in days before the run date (default: 3)
+    #[serde(default)]
+    pub warm_state_max_age_days: Option<i64>,
+    // warm state file loaded instead of searching the dates before the run date
+    #[serde(default)]
+    pub warm_state_file: Option<String>,
+    pub output_types: Vec<OutputTypeConfig>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HdwConfigBuilder {
@@ -215,6 +488,62 @@ pub struct HdwConfigBuilder {
     pub cells_file_path: String,
     pub output_types: Vec<OutputTypeConfig>,
     pub output_time_resolution: u32,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HainesConfigBuilder {
+    pub model_name: String,
+    pub cells_file_path: String,
+    pub output_types: Vec<OutputTypeConfig>,
+    pub output_time_resolution: u32,
+    // pressure levels of the index: Low, Mid or High (default: Mid)
+    #[serde(default)]
+    pub variant: HainesVariant,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChandlerConfigBuilder {
+    pub model_name: String,
+    pub cells_file_path: String,
+    pub output_types: Vec<OutputTypeConfig>,
+    pub output_time_resolution: u32,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GfdiConfigBuilder {
+    pub model_name: String,
+    pub cells_file_path: String,
+    pub output_types: Vec<OutputTypeConfig>,
+    pub output_time_resolution: u32,
+    // degree of curing of the grass [%] of the cells without a curing column in the cells file
+    // (default: 100, fully cured)
+    #[serde(default)]
+    pub curing: Option<f32>,
+    // maximum number of cells in the cells file (default: MAX_CELLS)
+    #[serde(default)]
+    pub max_cells: Option<usize>,
+    // handling of cells with the same coordinates (default: Warn)
+    #[serde(default)]
+    pub duplicate_cells: DuplicateCells,
 }
 
 
@@ -231,8 +560,11 @@ pub enum ConfigBuilderType {
     Nesterov(NesterovConfigBuilder),
     Sharples(SharplesConfigBuilder),
     Orieux(OrieuxConfigBuilder),
-//     Portuguese(PortugueseConfigBuilder),
+    Portuguese(PortugueseConfigBuilder),
     Hdw(HdwConfigBuilder),
+    Haines(HainesConfigBuilder),
+    Chandler(ChandlerConfigBuilder),
+    Gfdi(GfdiConfigBuilder),
 }
 
 
@@ -248,8 +580,78 @@ impl ConfigBuilderType {
             ConfigBuilderType::Nesterov(_) => "Nesterov",
             ConfigBuilderType::Sharples(_) => "Sharples",
             ConfigBuilderType::Orieux(_) => "Orieux",
-//             ConfigBuilderType::Portuguese(_) => "Portuguese",
+            ConfigBuilderType::Portuguese(_) => "Portuguese",
             ConfigBuilderType::Hdw(_) => "Hdw",
+            ConfigBuilderType::Haines(_) => "Haines",
+            ConfigBuilderType::Chandler(_) => "Chandler",
+            ConfigBuilderType::Gfdi(_) => "Gfdi",
+        }
+    }
+
+    /// Get the hours at which the warm state is written (None for models without warm state)
+    pub fn get_warm_state_hours(&self) -> Option<Vec<i64>> {
+        let warm_state_hour = match self {
+            ConfigBuilderType::RISICO(c) => &c.warm_state_hour,
+            ConfigBuilderType::FWI(c) => &c.warm_state_hour,
+            ConfigBuilderType::Mark5(c) => &c.warm_state_hour,
+            ConfigBuilderType::KBDI(c) => &c.warm_state_hour,
+            ConfigBuilderType::Nesterov(c) => &c.warm_state_hour,
+            ConfigBuilderType::Orieux(c) => &c.warm_state_hour,
+            ConfigBuilderType::Portuguese(c) => &c.warm_state_hour,
+            ConfigBuilderType::Angstrom(_)
+            | ConfigBuilderType::Fosberg(_)
+            | ConfigBuilderType::Sharples(_)
+            | ConfigBuilderType::Hdw(_)
+            | ConfigBuilderType::Haines(_)
+            | ConfigBuilderType::Chandler(_)
+            | ConfigBuilderType::Gfdi(_) => return None,
+        };
+        Some(warm_state_hours(warm_state_hour))
+    }
+
+    /// Get the output types configured for the model
+    pub fn get_output_types(&self) -> &[OutputTypeConfig] {
+        match self {
+            ConfigBuilderType::RISICO(c) => &c.output_types,
+            ConfigBuilderType::FWI(c) => &c.output_types,
+            ConfigBuilderType::Mark5(c) => &c.output_types,
+            ConfigBuilderType::KBDI(c) => &c.output_types,
+            ConfigBuilderType::Angstrom(c) => &c.output_types,
+            ConfigBuilderType::Fosberg(c) => &c.output_types,
+            ConfigBuilderType::Nesterov(c) => &c.output_types,
+            ConfigBuilderType::Sharples(c) => &c.output_types,
+            ConfigBuilderType::Orieux(c) => &c.output_types,
+            ConfigBuilderType::Portuguese(c) => &c.output_types,
+            ConfigBuilderType::Hdw(c) => &c.output_types,
+            ConfigBuilderType::Haines(c) => &c.output_types,
+            ConfigBuilderType::Chandler(c) => &c.output_types,
+            ConfigBuilderType::Gfdi(c) => &c.output_types,
+        }
+    }
+
+    /// Get the inputs the model needs from the input data
+    pub fn get_required_inputs(&self) -> Vec<RequiredInput> {
+        use RequiredInput::*;
+        match self {
+            // the wind direction is not needed with the direction-independent wind effect
+            ConfigBuilderType::RISICO(c) if c.wind_dir_fallback => {
+                vec![Temperature, Humidity, WindSpeed, Rain]
+            }
+            ConfigBuilderType::RISICO(_) => vec![Temperature, Humidity, WindSpeed, WindDir, Rain],
+            ConfigBuilderType::FWI(_) => vec![Temperature, Humidity, WindSpeed, Rain],
+            ConfigBuilderType::Mark5(_) => vec![Temperature, Humidity, WindSpeed, Rain],
+            ConfigBuilderType::KBDI(_) => vec![Temperature, Rain],
+            ConfigBuilderType::Angstrom(_) => vec![Temperature, Humidity],
+            ConfigBuilderType::Fosberg(_) => vec![Temperature, Humidity, WindSpeed],
+            ConfigBuilderType::Nesterov(_) => vec![Temperature, Humidity, Rain],
+            ConfigBuilderType::Sharples(_) => vec![Temperature, Humidity, WindSpeed],
+            ConfigBuilderType::Orieux(_) => vec![Temperature, WindSpeed, Rain],
+            ConfigBuilderType::Portuguese(_) => vec![Temperature, Humidity, Rain],
+            ConfigBuilderType::Hdw(_) => vec![Temperature, Humidity, WindSpeed],
+            // only upper-air variables, missing values give nodata outputs
+            ConfigBuilderType::Haines(_) => vec![],
+            ConfigBuilderType::Chandler(_) => vec![Temperature, Humidity],
+            ConfigBuilderType::Gfdi(_) => vec![Temperature, Humidity, WindSpeed],
         }
     }
 }
@@ -259,6 +661,16 @@ pub struct ConfigContainer {
     pub models: Vec<ConfigBuilderType>,
     pub palettes: PaletteMap,
     pub netcdf_input_configuration: Option<NetCdfInputConfiguration>,
+    #[cfg(feature = "grib")]
+    #[serde(default)]
+    pub grib_input_configuration: Option<GribInputConfiguration>,
+    #[serde(default)]
+    pub timeline_gaps: Option<TimelineGapConfig>,
+    #[serde(default)]
+    pub input_options: InputOptions,
+    /// maximum number of concurrent output writes (default: one per output type)
+    #[serde(default)]
+    pub max_write_concurrency: Option<usize>,
 }
 
 impl ConfigContainer {
@@ -310,6 +722,13 @@ impl ConfigContainer {
                 grid_path: grid_path.into(),
                 format: format.into(),
                 variables: Vec::new(),
+                min_valid_fraction: None,
+                fill_value: None,
+                compression_level: None,
+                append: None,
+                consolidate_times: false,
+                epsg: None,
+                jsonl_layout: JsonlLayout::default(),
             };
 
             output_types_vec.push(output_type);
@@ -325,21 +744,30 @@ impl ConfigContainer {
 
             let precision = precision.parse::<i32>().map_err(|_| "Invalid precision")?;
 
-            output_types_vec
+            for _type in output_types_vec
                 .iter_mut()
                 .filter(|_type| _type.internal_name == output_type)
-                .for_each(|_type| {
-                    let internal_name = OutputVariableName::from_str(internal_name)
-                        .unwrap_or_else(|_| panic!("Invalid Variable Name {}", &internal_name));
-                    let cluster_mode = ClusterMode::from_str(cluster_mode)
-                        .unwrap_or_else(|_| panic!("Invalid ClusterMode {}", &cluster_mode));
-                    _type.variables.push(OutputVariable::new(
-                        internal_name,
-                        name,
-                        cluster_mode,
-                        precision,
-                    ))
-                });
+            {
+                let internal_name = OutputVariableName::from_str(internal_name).map_err(|_| {
+                    format!(
+                        "Invalid variable name '{}' in output type {}, \
+                         see --list-variables for the valid names",
+                        internal_name, output_type
+                    )
+                })?;
+                let cluster_mode = ClusterMode::from_str(cluster_mode).map_err(|_| {
+                    format!(
+                        "Invalid cluster mode '{}' for variable {} in output type {}",
+                        cluster_mode, internal_name, output_type
+                    )
+                })?;
+                _type.variables.push(OutputVariable::new(
+                    internal_name,
+                    name,
+                    cluster_mode,
+                    precision,
+                ))
+            }
         }
 
         Ok(output_types_vec)
@@ -359,8 +787,10 @@ impl ConfigContainer {
         
         // try to get the warm state hour, otherwise default
         let warm_state_hour = match config_map.first(WARM_STATE_HOUR_KEY) {
-            Some(value) => Some(value.parse::<i64>().unwrap_or(WARM_STATE_HOUR)),
-            None => Some(WARM_STATE_HOUR),
+            Some(value) => Some(WarmStateHours::Single(
+                value.parse::<i64>().unwrap_or(WARM_STATE_HOUR),
+            )),
+            None => Some(WarmStateHours::Single(WARM_STATE_HOUR)),
         };
 
         // try to get the warm state offset, otherwise default
@@ -369,6 +799,14 @@ impl ConfigContainer {
             None => Some(WARM_STATE_LAG_DAYS),
         };
 
+        // try to get the lookback window of the warm state, otherwise default
+        let warm_state_max_age_days = match config_map.first(WARM_STATE_MAX_AGE_DAYS_KEY) {
+            Some(value) => Some(value.parse::<i64>().unwrap_or(WARM_STATE_MAX_AGE_DAYS)),
+            None => Some(WARM_STATE_MAX_AGE_DAYS),
+        };
+
+        let warm_state_file = config_map.first(WARM_STATE_FILE_KEY);
+
         let cells_file_path = config_map
             .first(CELLS_FILE_KEY)
             .ok_or(format!("Error: {CELLS_FILE_KEY} not found in config"))?;
@@ -425,6 +863,8 @@ impl ConfigContainer {
             warm_state_path,
             warm_state_hour,
             warm_state_lag_days,
+            warm_state_max_age_days,
+            warm_state_file,
             cells_file_path,
             vegetation_file,
             ppf_file,
@@ -433,12 +873,28 @@ impl ConfigContainer {
             // use_ndvi,  // DEPRECATED
             output_time_resolution,
             model_version,
+            ndwi_validity_hours: None,
+            phenology_file: None,
+            output_trigger: OutputTrigger::default(),
+            wind_dir_fallback: false,
+            wind_gust_factor: None,
+            required_inputs: Vec::new(),
+            default_slope: None,
+            default_aspect: None,
+            max_cells: None,
+            duplicate_cells: DuplicateCells::default(),
+            warm_state_format: WarmStateFormat::default(),
         };
 
         let config_container = ConfigContainer {
             models: vec![ConfigBuilderType::RISICO(config)],
             palettes,
             netcdf_input_configuration,
+            #[cfg(feature = "grib")]
+            grib_input_configuration: None,
+            timeline_gaps: None,
+            input_options: InputOptions::default(),
+            max_write_concurrency: None,
         };
 
         Ok(config_container)
@@ -447,6 +903,137 @@ impl ConfigContainer {
     pub fn get_netcdf_input_config(&self) -> &Option<NetCdfInputConfiguration> {
         &self.netcdf_input_configuration
     }
+
+    /// Check that all the models write the warm state at the same hours
+    pub fn check_warm_state_hours(&self, strict: bool) -> Result<(), RISICOError> {
+        let hours: Vec<(&str, Vec<i64>)> = self
+            .models
+            .iter()
+            .filter_map(|model| {
+                model
+                    .get_warm_state_hours()
+                    .map(|hours| (model.get_model_name(), hours))
+            })
+            .collect();
+
+        for (name, hours) in &hours {
+            info!("Model {} writes warm state at hours {:?}", name, hours);
+        }
+
+        let consistent = hours.windows(2).all(|w| w[0].1 == w[1].1);
+        if consistent {
+            return Ok(());
+        }
+        let msg = format!(
+            "Warm state hours differ across models: {}",
+            hours
+                .iter()
+                .map(|(name, hours)| format!("{name}={hours:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if strict {
+            return Err(msg.into());
+        }
+        warn!("{}", msg);
+        Ok(())
+    }
+
+    /// Check that no two output types (of the same or different models) write to the same files
+    pub fn check_output_collisions(&self) -> Result<(), RISICOError> {
+        let mut owners: HashMap<String, String> = HashMap::new();
+        for model in &self.models {
+            for output_type in model.get_output_types() {
+                let owner = format!("{}/{}", model.get_model_name(), output_type.internal_name);
+                for pattern in output_type.file_patterns() {
+                    if let Some(other) = owners.get(&pattern) {
+                        if *other != owner {
+                            return Err(format!(
+                                "Output types {} and {} both write to {}",
+                                other, owner, pattern
+                            )
+                            .into());
+                        }
+                        return Err(format!(
+                            "Output type {} writes more than one variable to {}",
+                            owner, pattern
+                        )
+                        .into());
+                    }
+                    owners.insert(pattern, owner.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the input data provide the inputs required by each model
+    pub fn check_required_inputs(
+        &self,
+        variables: &HashSet<InputVariableName>,
+    ) -> Result<(), RISICOError> {
+        let mut missing_models = Vec::new();
+        for model in &self.models {
+            let missing: Vec<String> = model
+                .get_required_inputs()
+                .into_iter()
+                .filter(|required| !is_required_input_available(*required, variables))
+                .map(|required| {
+                    let sources = required_input_sources(required)
+                        .iter()
+                        .map(|group| group.iter().map(|var| var.to_string()).join("+"))
+                        .join(" or ");
+                    format!("{:?} ({})", required, sources)
+                })
+                .collect();
+            if !missing.is_empty() {
+                missing_models.push(format!(
+                    "model {} is missing {}",
+                    model.get_model_name(),
+                    missing.join(", ")
+                ));
+            }
+        }
+        if !missing_models.is_empty() {
+            return Err(format!(
+                "Missing required input variables: {}",
+                missing_models.join("; ")
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check that the input has at least one timestep, otherwise the models would write nothing
+    pub fn check_input_timeline(&self, timeline: &[DateTime<Utc>]) -> Result<(), RISICOError> {
+        if !timeline.is_empty() {
+            return Ok(());
+        }
+        let expected = self
+            .models
+            .iter()
+            .map(|model| {
+                let variables = model
+                    .get_required_inputs()
+                    .into_iter()
+                    .map(|required| {
+                        let sources = required_input_sources(required)
+                            .iter()
+                            .map(|group| group.iter().map(|var| var.to_string()).join("+"))
+                            .join(" or ");
+                        format!("{:?} ({})", required, sources)
+                    })
+                    .join(", ");
+                format!("model {} expects {}", model.get_model_name(), variables)
+            })
+            .join("; ");
+        Err(format!(
+            "No usable input timesteps found, check that the input variable names match \
+             the configuration: {}",
+            expected
+        )
+        .into())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -457,6 +1044,85 @@ pub struct OutputTypeConfig {
     pub grid_path: String,
     pub format: String,
     pub variables: Vec<OutputVariable>,
+    // variables with a lower fraction of valid values are not written (default: always written)
+    #[serde(default)]
+    pub min_valid_fraction: Option<f32>,
+    // value written in place of NODATAVAL, a number or `nan`, by the NETCDF, GEOTIFF and COG
+    // writers (default: NODATAVAL)
+    #[serde(default, deserialize_with = "deserialize_fill_value")]
+    pub fill_value: Option<f32>,
+    // zstd compression level of the ZBIN files, requires the zstd feature (default: gzip)
+    #[serde(default)]
+    pub compression_level: Option<i32>,
+    // NETCDF only: append to the existing file of each variable, growing its time dimension,
+    // with the given handling of the times already written (default: the files are recreated)
+    #[serde(default)]
+    pub append: Option<NetcdfAppend>,
+    // NETCDF only: write the outputs at a time already written by the run into the same
+    // time slice (default: a new time slice for each write)
+    #[serde(default)]
+    pub consolidate_times: bool,
+    // GEOTIFF and COG only: EPSG code of the coordinates of the grid file, written as the
+    // spatial reference of the files (default: 4326, lat/lon)
+    #[serde(default)]
+    pub epsg: Option<u32>,
+    // JSONL only: one line per cell or per cell and variable (default: Cell)
+    #[serde(default)]
+    pub jsonl_layout: JsonlLayout,
+}
+
+/// Deserialize a fill value given as a number or as `nan`
+fn deserialize_fill_value<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FillValue {
+        Number(f32),
+        Text(String),
+    }
+    match Option::<FillValue>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(FillValue::Number(value)) => Ok(Some(value)),
+        Some(FillValue::Text(text)) if text.eq_ignore_ascii_case("nan") => Ok(Some(f32::NAN)),
+        Some(FillValue::Text(text)) => Err(serde::de::Error::custom(format!(
+            "invalid fill value '{text}', expected a number or nan"
+        ))),
+    }
+}
+
+impl OutputTypeConfig {
+    /// Get the pattern of the files written for each variable
+    /// (run date and date placeholders are kept, as they are shared by all the outputs)
+    pub fn file_patterns(&self) -> Vec<String> {
+        let path = self.path.trim_end_matches('/');
+        #[cfg(feature = "flatgeobuf")]
+        if self.format == "FGB" {
+            // all the variables in the same file
+            return vec![format!("{}/{}_<run>_<date>.fgb", path, self.name)];
+        }
+        if self.format == "CSV" {
+            // all the variables in the same file
+            return vec![format!("{}/{}_<run>_<date>.csv", path, self.name)];
+        }
+        if self.format == "JSONL" {
+            // all the variables in the same file
+            return vec![format!("{}/{}_<run>_<date>.jsonl", path, self.name)];
+        }
+        self.variables
+            .iter()
+            .map(|variable| match self.format.as_str() {
+                "NETCDF" => format!("{}/{}.nc", path, variable.name()),
+                "PNGWJSON" => format!("{}/{}_<run>_<date>_{}.png", path, self.name, variable.name()),
+                #[cfg(feature = "gdal")]
+                "GEOTIFF" => format!("{}/{}_<run>_<date>_{}.tif", path, self.name, variable.name()),
+                #[cfg(feature = "gdal")]
+                "COG" => format!("{}/{}_<run>_<date>_{}.tif", path, self.name, variable.name()),
+                _ => format!("{}/{}_<run>_<date>_{}.zbin", path, self.name, variable.name()),
+            })
+            .collect()
+    }
 }
 
 impl RISICOConfigBuilder {
@@ -552,15 +1218,15 @@ impl OrieuxConfigBuilder {
     }
 }
 
-// impl PortugueseConfigBuilder {
-//     pub fn build(
-//         &self,
-//         date: &DateTime<Utc>,
-//         palettes: &PaletteMap,
-//     ) -> Result<PortugueseConfig, RISICOError> {
-//         PortugueseConfig::new(self, *date, palettes)
-//     }
-// }
+impl PortugueseConfigBuilder {
+    pub fn build(
+        &self,
+        date: &DateTime<Utc>,
+        palettes: &PaletteMap,
+    ) -> Result<PortugueseConfig, RISICOError> {
+        PortugueseConfig::new(self, *date, palettes)
+    }
+}
 
 impl HdwConfigBuilder {
     pub fn build(
@@ -572,6 +1238,36 @@ impl HdwConfigBuilder {
     }
 }
 
+impl HainesConfigBuilder {
+    pub fn build(
+        &self,
+        date: &DateTime<Utc>,
+        palettes: &PaletteMap,
+    ) -> Result<HainesConfig, RISICOError> {
+        HainesConfig::new(self, *date, palettes)
+    }
+}
+
+impl ChandlerConfigBuilder {
+    pub fn build(
+        &self,
+        date: &DateTime<Utc>,
+        palettes: &PaletteMap,
+    ) -> Result<ChandlerConfig, RISICOError> {
+        ChandlerConfig::new(self, *date, palettes)
+    }
+}
+
+impl GfdiConfigBuilder {
+    pub fn build(
+        &self,
+        date: &DateTime<Utc>,
+        palettes: &PaletteMap,
+    ) -> Result<GfdiConfig, RISICOError> {
+        GfdiConfig::new(self, *date, palettes)
+    }
+}
+
 
 pub fn load_palettes(config_map: &ConfigMap) -> HashMap<String, String> {
     let mut palettes: HashMap<String, String> = HashMap::new();
@@ -1,3 +1,4 @@
 pub mod builder;
 pub mod data;
 pub mod models;
+pub mod schedule;
@@ -1,29 +1,41 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
-    io::{self, BufRead, BufReader, BufWriter, Write},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
 };
 
 use std::f32::consts::PI;
-use std::fs;
 use std::sync::Arc;
 
 use chrono::*;
 use chrono::{DateTime, Utc};
 use log::{info, warn};
+use ndarray::Array1;
 use rayon::prelude::*;
 use risico::{
     constants::NODATAVAL,
-    models::output::Output,
+    models::input::{Input, InputVariableName},
+    models::output::{Output, OutputVariableName},
     modules::angstrom::models::{
         AngstromCellPropertiesContainer, AngstromProperties, AngstromState,
     },
+    modules::chandler::models::{
+        ChandlerCellPropertiesContainer, ChandlerProperties, ChandlerState,
+    },
     modules::fosberg::models::{FosbergCellPropertiesContainer, FosbergProperties, FosbergState},
     modules::fwi::{
-        config::FWIModelConfig,
+        config::{FWIModelConfig, FWIParameters},
         models::{FWICellPropertiesContainer, FWIProperties, FWIState, FWIWarmState},
     },
-    //    modules::portuguese::models::{PortugueseCellPropertiesContainer, PortugueseProperties, PortugueseState, PortugueseWarmState},
+    modules::gfdi::{
+        constants::CURING_DEFAULT,
+        models::{GfdiCellPropertiesContainer, GfdiProperties, GfdiState},
+    },
+    modules::haines::{
+        config::{HainesModelConfig, HainesVariant},
+        models::{HainesCellPropertiesContainer, HainesProperties, HainesState},
+    },
     modules::hdw::models::{HdwCellPropertiesContainer, HdwProperties, HdwState},
     modules::kbdi::{
         config::KBDIModelConfig,
@@ -39,70 +51,384 @@ use risico::{
     modules::orieux::models::{
         OrieuxCellPropertiesContainer, OrieuxProperties, OrieuxState, OrieuxWarmState,
     },
+    modules::portuguese::models::{
+        PortugueseCellPropertiesContainer, PortugueseProperties, PortugueseState,
+        PortugueseWarmState,
+    },
     modules::risico::{
-        config::RISICOModelConfig,
+        config::{RISICOModelConfig, RequiredInput},
         models::{
-            RISICOCellPropertiesContainer, RISICOProperties, RISICOState, RISICOVegetation,
-            RISICOWarmState,
+            RISICOCellPropertiesContainer, RISICOPhenology, RISICOProperties, RISICOState,
+            RISICOVegetation, RISICOWarmState,
         },
     },
-    modules::sharples::models::{
-        SharplesCellPropertiesContainer, SharplesProperties, SharplesState,
+    modules::sharples::{
+        config::SharplesModelConfig,
+        models::{SharplesCellPropertiesContainer, SharplesProperties, SharplesState},
     },
 };
 
 use super::builder::{
     AngstromConfigBuilder,
+    ChandlerConfigBuilder,
+    DuplicateCells,
+    GfdiConfigBuilder,
     FWIConfigBuilder,
     FosbergConfigBuilder,
-    //    PortugueseConfigBuilder,
+    HainesConfigBuilder,
     HdwConfigBuilder,
     KbdiConfigBuilder,
     Mark5ConfigBuilder,
     NesterovConfigBuilder,
     OrieuxConfigBuilder,
+    OutputTrigger,
+    PortugueseConfigBuilder,
     OutputTypeConfig,
     RISICOConfigBuilder,
     SharplesConfigBuilder,
+    WarmStateFormat,
+    warm_state_hours,
 };
 
+use super::schedule::{OutputSchedule, PlannedWrites, WarmStateSchedule};
 use crate::common::helpers::RISICOError;
-use crate::common::io::models::{output::OutputType, palette::Palette};
+use crate::common::io::models::{
+    output::{OutputCache, OutputType, WriteErrors},
+    palette::Palette,
+};
+use crate::common::io::readers::source::open_source;
 
 pub type PaletteMap = HashMap<String, Box<Palette>>;
 // pub type ConfigMap = HashMap<String, Vec<String>>;
 
-pub fn check_write_warm_state(time: &DateTime<Utc>, warm_state_hour: i64) -> bool {
-    time.hour() as i64 == warm_state_hour
-}
-
 pub const WARM_STATE_HOUR: i64 = 0;  // hour for writing warm state
 pub const WARM_STATE_LAG_DAYS: i64 = 1; // number of days before the run date to search for the warm state file
+pub const WARM_STATE_MAX_AGE_DAYS: i64 = 3; // oldest warm state accepted, in days before the run date
+pub const MAX_CELLS: usize = 50_000_000; // default maximum number of cells in a cells file
+
+const RISICO_WARM_STATE_MAGIC: &[u8] = b"RISICOWS"; // header of the binary warm state files
+const RISICO_WARM_STATE_VERSION: u32 = 1;
+const RISICO_WARM_STATE_VALUES: usize = 9; // values per cell in the binary warm state files
+const CHECKPOINT_SUFFIX: &str = ".checkpoint"; // extension of the checkpoint files
+
+/// Check that the number of cells read from the cells file does not exceed the limit
+pub fn check_domain_size(
+    n_cells: usize,
+    max_cells: Option<usize>,
+    cells_file: &str,
+) -> Result<(), RISICOError> {
+    let max_cells = max_cells.unwrap_or(MAX_CELLS);
+    if n_cells > max_cells {
+        return Err(format!(
+            "{n_cells} cells in {cells_file} exceeds limit {max_cells} (set max_cells to raise it)"
+        )
+        .into());
+    }
+    Ok(())
+}
 
+/// Check the cells file for cells with the same coordinates of a previous cell.
+/// Returns the mask of the cells to keep when they have to be deduplicated.
+pub fn check_duplicate_cells(
+    lats: &[f32],
+    lons: &[f32],
+    mode: DuplicateCells,
+    cells_file: &str,
+) -> Option<Vec<bool>> {
+    let mut seen = HashSet::with_capacity(lats.len());
+    let keep: Vec<bool> = lats
+        .iter()
+        .zip(lons)
+        .map(|(lat, lon)| seen.insert((lat.to_bits(), lon.to_bits())))
+        .collect();
+    let n_duplicates = keep.iter().filter(|k| !**k).count();
+    if n_duplicates == 0 {
+        return None;
+    }
+    match mode {
+        DuplicateCells::Warn => {
+            warn!("{cells_file}: {n_duplicates} cells have the same coordinates of a previous cell");
+            None
+        }
+        DuplicateCells::Deduplicate => {
+            warn!("{cells_file}: removed {n_duplicates} cells with the same coordinates of a previous cell");
+            Some(keep)
+        }
+    }
+}
+
+/// Keep only the values of the cells in the mask
+pub fn retain_cells<T>(values: &mut Vec<T>, keep: &[bool]) {
+    let mut mask = keep.iter();
+    values.retain(|_| *mask.next().unwrap_or(&true));
+}
+
+/// Find the latest warm state file between lag_days and max_age_days before the run date,
+/// or open the fixed file if set (its date is read from the YYYYmmDDHHMM suffix of the name)
 pub fn find_warm_state(
     base_warm_file: &str,
     run_date: DateTime<Utc>,
-    hour: i64,
-    lag_days: i64
-) -> (Option<File>, DateTime<Utc>) {
+    hours: &[i64],
+    lag_days: i64,
+    max_age_days: i64,
+    fixed_file: Option<&str>,
+) -> (Option<File>, String, DateTime<Utc>) {
+    if let Some(fixed_file) = fixed_file {
+        let date = warm_state_file_date(fixed_file).unwrap_or_else(|| {
+            warn!("Cannot read the date of the warm state file {fixed_file} from its name");
+            run_date - Duration::try_days(1).expect("Should be valid")
+        });
+        return (File::open(fixed_file).ok(), fixed_file.to_string(), date);
+    }
     // for the last n days before date, try to read the warm state
     // compose the filename as base_warm_file_YYYYmmDDHHMM
     let mut current_date = run_date;
     let mut file: Option<File> = None;
-    let end_search: i64 = lag_days + 4;  // search for warm state files up to 4 days before the lag_days
-    for days_before in lag_days..end_search {
-        current_date = run_date - Duration::try_days(days_before).expect("Should be valid");
-        // add the time to the warm state time
-        current_date += Duration::try_hours(hour).expect("Should be valid");
-        let filename = format!("{}{}", base_warm_file, current_date.format("%Y%m%d%H%M"));
-        let file_handle = File::open(filename);
-        if file_handle.is_err() {
+    let end_search: i64 = max_age_days.max(lag_days);
+    'search: for days_before in lag_days..=end_search {
+        let day = run_date - Duration::try_days(days_before).expect("Should be valid");
+        // the latest hour of the day first
+        for hour in hours.iter().rev() {
+            // add the time to the warm state time
+            current_date = day + Duration::try_hours(*hour).expect("Should be valid");
+            let file_handle = File::open(warm_state_file_name(base_warm_file, &current_date));
+            if file_handle.is_err() {
+                continue;
+            }
+            file = Some(file_handle.expect("Should unwrap"));
+            break 'search;
+        }
+    }
+    let age = run_date - current_date;
+    if file.is_some() && age > Duration::try_days(1).expect("Should be valid") {
+        warn!(
+            "Using a stale warm state from {}: {} days and {} hours before the run date",
+            current_date.format("%Y-%m-%d %H:%M"),
+            age.num_days(),
+            age.num_hours() % 24
+        );
+    }
+    (file, warm_state_file_name(base_warm_file, &current_date), current_date)
+}
+
+/// Date of a warm state file, from the YYYYmmDDHHMM suffix of its name
+fn warm_state_file_date(file_name: &str) -> Option<DateTime<Utc>> {
+    let start = file_name.len().checked_sub(12)?;
+    let suffix = file_name.get(start..)?;
+    NaiveDateTime::parse_from_str(suffix, "%Y%m%d%H%M")
+        .ok()
+        .map(|naive| DateTime::from_naive_utc_and_offset(naive, Utc))
+}
+
+/// Fail when the warm state file requested in the configuration cannot be loaded,
+/// instead of silently cold-starting the model
+pub fn required_warm_state<T>(
+    warm_state: Option<T>,
+    fixed_file: &Option<String>,
+) -> Result<Option<T>, RISICOError> {
+    match (warm_state, fixed_file) {
+        (None, Some(fixed_file)) if !Path::new(fixed_file).is_file() => {
+            Err(format!("Warm state file {fixed_file} not found").into())
+        }
+        (None, Some(fixed_file)) => {
+            Err(format!("Cannot read the warm state file {fixed_file}").into())
+        }
+        (warm_state, _) => Ok(warm_state),
+    }
+}
+
+/// Name of the warm state file at the date: base_warm_file_YYYYmmDDHHMM
+pub fn warm_state_file_name(base_warm_file: &str, date: &DateTime<Utc>) -> String {
+    format!("{}{}", base_warm_file, date.format("%Y%m%d%H%M"))
+}
+
+/// Name of the checkpoint file at the date of a run:
+/// base_warm_file_YYYYmmDDHHMM.runYYYYmmDDHHMM.checkpoint, with the date of the run last
+pub fn checkpoint_file_name(
+    base_warm_file: &str,
+    run_date: &DateTime<Utc>,
+    date: &DateTime<Utc>,
+) -> String {
+    format!(
+        "{}.run{}{}",
+        warm_state_file_name(base_warm_file, date),
+        run_date.format("%Y%m%d%H%M"),
+        CHECKPOINT_SUFFIX
+    )
+}
+
+/// Checkpoint files of the warm state path, with their dates and the dates of their runs
+/// (None for the files without the date of the run)
+fn list_checkpoints(base_warm_file: &str) -> Vec<(String, DateTime<Utc>, Option<DateTime<Utc>>)> {
+    // the warm state path is a directory followed by the prefix of the file names
+    let (dir, prefix) = match base_warm_file.rsplit_once('/') {
+        Some((dir, prefix)) if !dir.is_empty() => (dir, prefix),
+        Some((_, prefix)) => ("/", prefix),
+        None => (".", base_warm_file),
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let parse_date = |date: &str| {
+        NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M")
+            .ok()
+            .map(|date| DateTime::from_naive_utc_and_offset(date, Utc))
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let dates = name.strip_prefix(prefix)?.strip_suffix(CHECKPOINT_SUFFIX)?;
+            let (date, run_date) = match dates.split_once(".run") {
+                Some((date, run_date)) => (parse_date(date)?, parse_date(run_date)),
+                None => (parse_date(dates)?, None),
+            };
+            let file_name = match dir {
+                "." if !base_warm_file.starts_with("./") => name,
+                _ => format!("{}/{}", dir.trim_end_matches('/'), name),
+            };
+            Some((file_name, date, run_date))
+        })
+        .collect()
+}
+
+/// Latest checkpoint of the run date in the warm state path, the checkpoints left by
+/// the runs of other dates are ignored
+fn latest_checkpoint(
+    base_warm_file: &str,
+    run_date: &DateTime<Utc>,
+) -> Option<(String, DateTime<Utc>)> {
+    let (own, foreign): (Vec<_>, Vec<_>) = list_checkpoints(base_warm_file)
+        .into_iter()
+        .partition(|(_, _, checkpoint_run_date)| checkpoint_run_date.as_ref() == Some(run_date));
+    for (file_name, _, _) in foreign {
+        warn!("Ignoring checkpoint {}, not taken by a run of the same date", file_name);
+    }
+    own.into_iter()
+        .max_by_key(|(_, date, _)| *date)
+        .map(|(file_name, date, _)| (file_name, date))
+}
+
+/// Remove the checkpoints of the run date in the warm state path, except the one to keep
+fn remove_checkpoints(base_warm_file: &str, run_date: &DateTime<Utc>, keep: Option<&str>) {
+    for (file_name, _, checkpoint_run_date) in list_checkpoints(base_warm_file) {
+        if checkpoint_run_date.as_ref() != Some(run_date) || Some(file_name.as_str()) == keep {
             continue;
         }
-        file = Some(file_handle.expect("Should unwrap"));
-        break;
+        if let Err(err) = std::fs::remove_file(&file_name) {
+            warn!("Cannot remove checkpoint {}: {}", file_name, err);
+        }
+    }
+}
+
+/// Write a checkpoint of the run at the date with the writer of the warm state files: the file
+/// is written under a temporary name and renamed, so an interrupted write leaves the previous
+/// checkpoint in place
+fn write_checkpoint(
+    base_warm_file: &str,
+    run_date: &DateTime<Utc>,
+    date: &DateTime<Utc>,
+    write: impl FnOnce(&str) -> Result<(), RISICOError>,
+) -> Result<(), RISICOError> {
+    let file_name = checkpoint_file_name(base_warm_file, run_date, date);
+    let tmp_file_name = format!("{}.tmp", file_name);
+    write(&tmp_file_name)?;
+    std::fs::rename(&tmp_file_name, &file_name)
+        .map_err(|err| format!("error renaming {} to {}, {}", tmp_file_name, file_name, err))?;
+    remove_checkpoints(base_warm_file, run_date, Some(&file_name));
+    Ok(())
+}
+
+/// Check that a line of a warm state file has the expected number of columns,
+/// a truncated line invalidates the whole file
+fn has_warm_state_columns(
+    components: &[&str],
+    expected: usize,
+    file_name: &str,
+    index: usize,
+) -> bool {
+    if components.len() < expected {
+        warn!(
+            "Invalid warm state file {file_name} at line #{index}: expected {expected} columns, found {}, using the default warm state",
+            components.len()
+        );
+        return false;
+    }
+    true
+}
+
+/// Parse a value of a warm state file, None with a warning naming the file and the line
+/// if it is not a number, so the model falls back to the default warm state
+fn parse_warm_state_value(value: &str, name: &str, file_name: &str, index: usize) -> Option<f32> {
+    match value.parse::<f32>() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            warn!(
+                "Invalid warm state file {file_name} at line #{index}: cannot parse {name} from '{value}', using the default warm state"
+            );
+            None
+        }
+    }
+}
+
+/// Parse a comma-separated list of values of a warm state file, see parse_warm_state_value
+fn parse_warm_state_values(
+    values: &str,
+    name: &str,
+    file_name: &str,
+    index: usize,
+) -> Option<Vec<f32>> {
+    values
+        .split(',')
+        .map(|value| parse_warm_state_value(value, name, file_name, index))
+        .collect()
+}
+
+/// Parse a comma-separated list of dates (YYYYmmDDHHMM) of a warm state file,
+/// None with a warning if a date is not valid
+fn parse_warm_state_dates(
+    dates: &str,
+    file_name: &str,
+    index: usize,
+) -> Option<Vec<DateTime<Utc>>> {
+    dates
+        .split(',')
+        .map(|date| match NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M") {
+            Ok(naive) => Some(DateTime::from_naive_utc_and_offset(naive, Utc)),
+            Err(_) => {
+                warn!(
+                    "Invalid warm state file {file_name} at line #{index}: cannot parse a date from '{date}', using the default warm state"
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Comparison of the final state of a run with the loaded warm state, counting the cells
+/// that are not a sensible continuation of it
+#[derive(Debug, Default)]
+pub struct WarmStateContinuity {
+    pub n_cells: usize,
+    /// cells reset to the default warm state values
+    pub n_reset: usize,
+    /// cells that became invalid (NODATAVAL) from a valid warm state
+    pub n_invalid: usize,
+    /// cells with values out of their physical range
+    pub n_implausible: usize,
+}
+
+impl WarmStateContinuity {
+    pub fn is_ok(&self) -> bool {
+        self.n_reset == 0 && self.n_invalid == 0 && self.n_implausible == 0
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} cells: {} reset to defaults, {} became invalid, {} out of range",
+            self.n_cells, self.n_reset, self.n_invalid, self.n_implausible
+        )
     }
-    (file, current_date)
 }
 
 pub struct RISICOConfig {
@@ -110,14 +436,22 @@ pub struct RISICOConfig {
     warm_state_path: String,
     warm_state: Vec<RISICOWarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
     properties: RISICOProperties,
     palettes: PaletteMap,
     // use_temperature_effect: bool,  // DEPRECATED
     // use_ndvi: bool,  // DEPRECATED
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    // cells kept from the cells file, None if no duplicate was removed
+    duplicates_mask: Option<Vec<bool>>,
     model_version: String,
+    ndwi_validity_hours: Option<i64>,
+    output_trigger: OutputTrigger,
+    wind_dir_fallback: bool,
+    wind_gust_factor: Option<f32>,
+    required_inputs: Vec<RequiredInput>,
+    warm_state_format: WarmStateFormat,
 }
 
 pub struct FWIConfig {
@@ -125,12 +459,14 @@ pub struct FWIConfig {
     warm_state_path: String,
     warm_state: Vec<FWIWarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
     properties: FWIProperties,
     palettes: PaletteMap,
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
     model_version: String,
+    parameters: FWIParameters,
 }
 
 pub struct Mark5Config {
@@ -138,10 +474,12 @@ pub struct Mark5Config {
     warm_state_path: String,
     warm_state: Vec<Mark5WarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
+    output_schedule: Option<OutputSchedule>, // None: outputs written with the daily update
     properties: Mark5Properties,
     palettes: PaletteMap,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
     model_version: String,
 }
 
@@ -150,10 +488,12 @@ pub struct KbdiConfig {
     warm_state_path: String,
     warm_state: Vec<KBDIWarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
+    output_schedule: Option<OutputSchedule>, // None: outputs written with the daily update
     properties: KBDIProperties,
     palettes: PaletteMap,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
     model_version: String,
 }
 
@@ -161,16 +501,18 @@ pub struct AngstromConfig {
     run_date: DateTime<Utc>,
     properties: AngstromProperties,
     palettes: PaletteMap,
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
 pub struct FosbergConfig {
     run_date: DateTime<Utc>,
     properties: FosbergProperties,
     palettes: PaletteMap,
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
 pub struct NesterovConfig {
@@ -178,18 +520,21 @@ pub struct NesterovConfig {
     warm_state_path: String,
     warm_state: Vec<NesterovWarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
     properties: NesterovProperties,
     palettes: PaletteMap,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
 pub struct SharplesConfig {
     run_date: DateTime<Utc>,
     properties: SharplesProperties,
+    model_config: SharplesModelConfig,
     palettes: PaletteMap,
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
 pub struct OrieuxConfig {
@@ -197,33 +542,66 @@ pub struct OrieuxConfig {
     warm_state_path: String,
     warm_state: Vec<OrieuxWarmState>,
     warm_state_time: DateTime<Utc>,
-    warm_state_hour: i64,
+    warm_state_schedule: WarmStateSchedule,
     properties: OrieuxProperties,
     palettes: PaletteMap,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
-// pub struct PortugueseConfig {
-//     run_date: DateTime<Utc>,
-//     warm_state_path: String,
-//     warm_state: Vec<PortugueseWarmState>,
-//     warm_state_time: DateTime<Utc>,
-//     warm_state_hour: i64,
-//     properties: PortugueseProperties,
-//     palettes: PaletteMap,
-//     output_types_defs: Vec<OutputTypeConfig>,
-// }
+pub struct PortugueseConfig {
+    run_date: DateTime<Utc>,
+    warm_state_path: String,
+    warm_state: Vec<PortugueseWarmState>,
+    warm_state_time: DateTime<Utc>,
+    warm_state_schedule: WarmStateSchedule,
+    properties: PortugueseProperties,
+    palettes: PaletteMap,
+    output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
+}
 
 pub struct HdwConfig {
     run_date: DateTime<Utc>,
     properties: HdwProperties,
     palettes: PaletteMap,
-    output_time_resolution: u32,
+    output_schedule: OutputSchedule,
+    output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
+}
+
+pub struct HainesConfig {
+    run_date: DateTime<Utc>,
+    variant: HainesVariant,
+    properties: HainesProperties,
+    palettes: PaletteMap,
+    output_schedule: OutputSchedule,
+    output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
+}
+
+pub struct ChandlerConfig {
+    run_date: DateTime<Utc>,
+    properties: ChandlerProperties,
+    palettes: PaletteMap,
+    output_schedule: OutputSchedule,
+    output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
+}
+
+pub struct GfdiConfig {
+    run_date: DateTime<Utc>,
+    properties: GfdiProperties,
+    palettes: PaletteMap,
+    output_schedule: OutputSchedule,
     output_types_defs: Vec<OutputTypeConfig>,
+    duplicates_mask: Option<Vec<bool>>,
 }
 
 pub struct OutputWriter {
     outputs: Vec<OutputType>,
+    /// thread pool capping the concurrent writes, None to use the global pool
+    pool: Option<rayon::ThreadPool>,
 }
 
 impl OutputWriter {
@@ -231,13 +609,67 @@ impl OutputWriter {
         outputs_defs: &[OutputTypeConfig],
         date: &DateTime<Utc>,
         palettes: &PaletteMap,
+        n_cells: usize,
+        duplicates_mask: Option<&[bool]>,
     ) -> Self {
         Self {
             outputs: outputs_defs
                 .iter()
-                .filter_map(|t| OutputType::new(t, date, palettes).ok())
+                .filter_map(|t| {
+                    OutputType::new(t, date, palettes, n_cells, duplicates_mask)
+                        .map_err(|err| warn!("Output {} skipped: {err}", t.name))
+                        .ok()
+                })
                 .collect(),
+            pool: None,
+        }
+    }
+
+    /// Limit the number of output files written at the same time
+    pub fn with_max_concurrency(mut self, max_concurrency: Option<usize>) -> Self {
+        let Some(max_concurrency) = max_concurrency else {
+            return self;
+        };
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(max_concurrency.max(1))
+            .build()
+        {
+            Ok(pool) => self.pool = Some(pool),
+            Err(err) => warn!("Cannot limit the concurrent output writes: {err}"),
         }
+        self
+    }
+
+    /// Record the model version in the metadata of the outputs
+    pub fn with_model_version(mut self, model_version: &str) -> Self {
+        self.outputs
+            .iter_mut()
+            .for_each(|output_type| output_type.set_model_version(model_version));
+        self
+    }
+
+    /// Input variables whose source grid indexes are written by the outputs
+    pub fn source_variables(&self) -> HashSet<InputVariableName> {
+        self.outputs
+            .iter()
+            .flat_map(|output_type| output_type.source_variables())
+            .collect()
+    }
+
+    /// Set the source grid indexes of the cells written by the outputs
+    pub fn set_source_indexes(&mut self, indexes: &HashMap<InputVariableName, Array1<f32>>) {
+        self.outputs
+            .iter_mut()
+            .for_each(|output_type| output_type.set_source_indexes(indexes));
+    }
+
+    /// Record the model name and the input data path in the metadata of the outputs
+    pub fn with_provenance(mut self, model_name: &str, input_path: &str) -> Self {
+        self.outputs.iter_mut().for_each(|output_type| {
+            output_type.set_global_attribute("model_name", model_name);
+            output_type.set_global_attribute("input_path", input_path);
+        });
+        self
     }
 
     pub fn write_output(
@@ -245,14 +677,47 @@ impl OutputWriter {
         lats: &[f32],
         lons: &[f32],
         output: &Output,
-    ) -> Result<(), RISICOError> {
-        self.outputs.par_iter_mut().for_each(|output_type| {
-            match output_type.write_variables(lats, lons, output) {
-                Ok(_) => (),
-                Err(e) => warn!("Error writing output: {}", e),
+    ) -> Result<(), WriteErrors> {
+        // compute each requested variable only once
+        let names: HashSet<OutputVariableName> = self
+            .outputs
+            .iter()
+            .flat_map(|output_type| output_type.variable_names())
+            .collect();
+        let cache: OutputCache = names
+            .par_iter()
+            .filter_map(|name| output.get(name).map(|values| (*name, values)))
+            .collect();
+
+        let outputs = &mut self.outputs;
+        let write = || -> Vec<Result<(), WriteErrors>> {
+            outputs
+                .par_iter_mut()
+                .map(|output_type| output_type.write_variables(lats, lons, output, &cache))
+                .collect()
+        };
+        let results = match &self.pool {
+            Some(pool) => pool.install(write),
+            None => write(),
+        };
+        // the failed variables of all the output types, the others are written anyway
+        let mut errors = WriteErrors::default();
+        results
+            .into_iter()
+            .filter_map(Result::err)
+            .for_each(|err| errors.extend(err));
+        errors.into_result()
+    }
+
+    /// Write the daily aggregates of the last (possibly partial) day, at the end of the run
+    pub fn finish(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), WriteErrors> {
+        let mut errors = WriteErrors::default();
+        for output_type in self.outputs.iter_mut() {
+            if let Err(err) = output_type.finish(lats, lons) {
+                errors.extend(err);
             }
-        });
-        Ok(())
+        }
+        errors.into_result()
     }
 }
 
@@ -260,8 +725,11 @@ pub fn load_palettes(palettes_defs: &HashMap<String, String>) -> HashMap<String,
     let mut palettes: HashMap<String, Box<Palette>> = HashMap::new();
 
     for (name, path) in palettes_defs.iter() {
-        if let Ok(palette) = Palette::load_palette(path) {
-            palettes.insert(name.to_string(), Box::new(palette));
+        match Palette::load_palette(path) {
+            Ok(palette) => {
+                palettes.insert(name.to_string(), Box::new(palette));
+            }
+            Err(err) => warn!("Cannot load palette {name}, using default palette: {err}"),
         }
     }
     palettes
@@ -277,10 +745,29 @@ impl RISICOConfig {
 
         let cells_file = &config_defs.cells_file_path;
 
-        let props_container = RISICOConfig::properties_from_file(cells_file)
-            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let mut props_container = RISICOConfig::properties_from_file(
+            cells_file,
+            config_defs.default_slope.unwrap_or(0.0),
+            config_defs.default_aspect.unwrap_or(0.0),
+        )
+        .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.slopes, keep);
+            retain_cells(&mut props_container.aspects, keep);
+            retain_cells(&mut props_container.vegetations, keep);
+        }
 
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len()
             || n_cells != props_container.slopes.len()
             || n_cells != props_container.aspects.len()
@@ -289,14 +776,38 @@ impl RISICOConfig {
             panic!("All properties must have the same length");
         }
 
-        let vegetations_dict = RISICOConfig::read_vegetation(&config_defs.vegetation_file)
+        let mut vegetations_dict = RISICOConfig::read_vegetation(&config_defs.vegetation_file)
             .map_err(|error| format!("error reading {}, {error}", &config_defs.vegetation_file))?;
 
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
-        let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
+        if let Some(phenology_file) = &config_defs.phenology_file {
+            let phenology = RISICOConfig::read_phenology(phenology_file)
+                .map_err(|error| format!("error reading {}, {error}", phenology_file))?;
+            for (id, veg) in vegetations_dict.iter_mut() {
+                if let Some(p) = phenology.get(id) {
+                    *veg = Arc::new(RISICOVegetation {
+                        phenology: Some(p.clone()),
+                        ..(**veg).clone()
+                    });
+                }
+            }
+        }
 
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
+        let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = RISICOConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
         let (warm_state, warm_state_time) =
-            RISICOConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
                 .unwrap_or((
                     vec![RISICOWarmState::default(); n_cells],
                     date - Duration::try_days(1).expect("Should be a valid duration"),
@@ -304,8 +815,15 @@ impl RISICOConfig {
 
         let ppf_file = &config_defs.ppf_file;
         let ppf = match ppf_file {
-            Some(ppf_file) => RISICOConfig::read_ppf(ppf_file)
-                .map_err(|error| format!("error reading {}, {}", &ppf_file, error))?,
+            Some(ppf_file) => {
+                let mut ppf = RISICOConfig::read_ppf(ppf_file)
+                    .map_err(|error| format!("error reading {}, {}", &ppf_file, error))?;
+                // the ppf file has a line for each cell of the cells file
+                if let Some(keep) = &duplicates_mask {
+                    retain_cells(&mut ppf, keep);
+                }
+                ppf
+            }
             None => vec![(1.0, 1.0); n_cells],
         };
         let ppf_summer = ppf.iter().map(|(s, _)| *s).collect();
@@ -320,14 +838,21 @@ impl RISICOConfig {
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
             properties: props,
             palettes,
             // use_temperature_effect: config_defs.use_temperature_effect,  // DEPRECATED
             // use_ndvi: config_defs.use_ndvi,  // DEPRECATED
-            output_time_resolution: config_defs.output_time_resolution,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
             model_version: config_defs.model_version.clone(),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+            ndwi_validity_hours: config_defs.ndwi_validity_hours,
+            output_trigger: config_defs.output_trigger,
+            wind_dir_fallback: config_defs.wind_dir_fallback,
+            wind_gust_factor: config_defs.wind_gust_factor,
+            required_inputs: config_defs.required_inputs.clone(),
+            warm_state_format: config_defs.warm_state_format,
         };
 
         Ok(config)
@@ -335,17 +860,23 @@ impl RISICOConfig {
 
     /// Read the cells from a file.
     /// :param file_path: The path to the file.
+    /// :param default_slope: slope [deg] used for missing or out of range values.
+    /// :param default_aspect: aspect [deg] used for missing or out of range values.
     /// :return: A list of cells.
     pub fn properties_from_file(
         file_path: &str,
+        default_slope: f32,
+        default_aspect: f32,
     ) -> Result<RISICOCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
 
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let mut slopes: Vec<f32> = Vec::new();
         let mut aspects: Vec<f32> = Vec::new();
         let mut vegetations: Vec<String> = Vec::new();
+        let mut n_invalid_slopes = 0;
+        let mut n_invalid_aspects = 0;
 
         let reader = BufReader::new(file);
 
@@ -386,6 +917,20 @@ impl RISICOConfig {
 
             let vegetation = line_parts[4].to_string();
 
+            // sentinel values (e.g. -9999) are replaced by the defaults
+            let slope = if (0.0..=90.0).contains(&slope) {
+                slope
+            } else {
+                n_invalid_slopes += 1;
+                default_slope
+            };
+            let aspect = if (0.0..=360.0).contains(&aspect) {
+                aspect
+            } else {
+                n_invalid_aspects += 1;
+                default_aspect
+            };
+
             let slope = slope * PI / 180.0;
             let aspect = aspect * PI / 180.0;
 
@@ -396,6 +941,13 @@ impl RISICOConfig {
             vegetations.push(vegetation);
         }
 
+        if n_invalid_slopes > 0 || n_invalid_aspects > 0 {
+            warn!(
+                "{file_path}: replaced {n_invalid_slopes} invalid slopes with {default_slope} deg \
+                and {n_invalid_aspects} invalid aspects with {default_aspect} deg"
+            );
+        }
+
         let props = RISICOCellPropertiesContainer {
             lats,
             lons,
@@ -412,7 +964,7 @@ impl RISICOConfig {
     pub fn read_vegetation(
         file_path: &str,
     ) -> Result<HashMap<String, Arc<RISICOVegetation>>, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("Can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("Can't open file: {err}."))?;
         let mut vegetations: HashMap<String, Arc<RISICOVegetation>> = HashMap::new();
 
         let reader = BufReader::new(file);
@@ -477,6 +1029,7 @@ impl RISICOConfig {
                 sat,
                 name,
                 use_ndvi,
+                phenology: None,
             });
 
             vegetations.insert(veg_id, veg);
@@ -485,12 +1038,55 @@ impl RISICOConfig {
         Result::Ok(vegetations)
     }
 
+    /// Reads the phenology file and returns the seasonal factors for each vegetation id
+    /// The phenology file is a text file with the following structure:
+    /// id d0_summer d0_winter d1_summer d1_winter umid_summer umid_winter
+    pub fn read_phenology(file_path: &str) -> Result<HashMap<String, RISICOPhenology>, RISICOError> {
+        let file = open_source(file_path).map_err(|err| format!("Can't open file: {err}."))?;
+        let mut phenology: HashMap<String, RISICOPhenology> = HashMap::new();
+
+        let reader = BufReader::new(file);
+
+        for (index, line) in reader.lines().enumerate() {
+            let line =
+                line.map_err(|err| format!("Error reading {file_path} at line #{index}: {err}"))?;
+            if line.starts_with("#") || line.trim().is_empty() {
+                // skip header and empty lines
+                continue;
+            }
+            let line_elements: Vec<&str> = line.split_whitespace().collect::<Vec<&str>>();
+            if line_elements.len() < 7 {
+                return Err(format!("Invalid line in file {file_path}: {line}").into());
+            }
+
+            let values = line_elements[1..7]
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| format!("Invalid value in file {file_path} at line #{index}: '{line}'"))?;
+
+            phenology.insert(
+                line_elements[0].to_string(),
+                RISICOPhenology {
+                    d0_summer: values[0],
+                    d0_winter: values[1],
+                    d1_summer: values[2],
+                    d1_winter: values[3],
+                    umid_summer: values[4],
+                    umid_winter: values[5],
+                },
+            );
+        }
+        Ok(phenology)
+    }
+
     /// Reads the PPF file and returns a vector of with (ppf_summer, ppf_winter) tuples
     /// The PPF file is a text file with the following structure:
     /// ppf_summer ppf_winter
     /// where ppf_summer and ppf_winter are floats
+    /// the lines starting with `#` are comments
     pub fn read_ppf(ppf_file: &str) -> Result<Vec<(f32, f32)>, RISICOError> {
-        let file = File::open(ppf_file)
+        let file = open_source(ppf_file)
             .map_err(|error| format!("Could not open file {}: {}", ppf_file, error))?;
 
         let reader = io::BufReader::new(file);
@@ -502,7 +1098,14 @@ impl RISICOConfig {
                     return Err(format!("Error reading PPF file {}: {}", ppf_file, error).into());
                 }
             };
+            // comments, e.g. the header of the PPF computed from the historical outputs
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
             let components: Vec<&str> = line.split_whitespace().collect();
+            if components.len() < 2 {
+                return Err(format!("Invalid line in PPF file {}: '{}'", ppf_file, line).into());
+            }
             let ppf_summer = components[0].parse::<f32>().map_err(|err| {
                 format!("Could not parse value from PPF file {}: {}", ppf_file, err)
             })?;
@@ -521,7 +1124,16 @@ impl RISICOConfig {
 
     pub fn new_state(&self) -> RISICOState {
         log::info!("Model version: {}", &self.model_version);
-        let config = RISICOModelConfig::new(&self.model_version);
+        let mut config = RISICOModelConfig::new(&self.model_version);
+        if let Some(hours) = self.ndwi_validity_hours {
+            config.ndwi_seconds_validity = hours * 3600;
+        }
+        config.wind_dir_fallback = self.wind_dir_fallback;
+        if self.wind_gust_factor.is_some() && self.model_version != "v2025" {
+            warn!("The wind gust is used only by the v2025 model version, ignored");
+        }
+        config.wind_gust_factor = self.wind_gust_factor;
+        config.required_inputs = self.required_inputs.clone();
         RISICOState::new(&self.warm_state, &self.warm_state_time, config)
     }
 
@@ -530,17 +1142,33 @@ impl RISICOConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
-        ))
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        )
+        .with_model_version(&self.model_version))
     }
 
-    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+    pub fn should_write_output(&self, time: &DateTime<Utc>, input: &Input) -> bool {
+        match self.output_trigger {
+            OutputTrigger::TimeResolution => self.output_schedule.is_due(time),
+            OutputTrigger::SatelliteUpdate => input.has_satellite_data(),
+        }
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time (outputs on satellite updates depend on the input)
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        let output = match self.output_trigger {
+            OutputTrigger::TimeResolution => Some(self.output_schedule.is_due(time)),
+            OutputTrigger::SatelliteUpdate => None,
+        };
+        PlannedWrites {
+            output,
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -548,15 +1176,18 @@ impl RISICOConfig {
     /// The warm state is stored in a file with the following structure:
     /// base_warm_file_YYYYmmDDHHMM
     /// where <base_warm_file> is the base name of the file and `YYYYmmDDHHMM` is the date of the warm state
-    /// The warm state is stored in a text file with the following structure:
-    /// dffm
+    /// The warm state is stored in a text or binary file (detected from the header),
+    /// see parse_text_warm_state and parse_binary_warm_state
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
+        hours: &[i64],
         lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
     ) -> Option<(Vec<RISICOWarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -571,12 +1202,37 @@ impl RISICOConfig {
             "Loading warm state from {}",
             current_date.format("%Y-%m-%d %H:%M")
         );
+        let warm_state = RISICOConfig::read_warm_state_file(file, &file_name)?;
+        Some((warm_state, current_date))
+    }
 
-        let mut warm_state: Vec<RISICOWarmState> = Vec::new();
+    /// Read a warm state file, in the text or binary format (detected from the header)
+    fn read_warm_state_file(file: File, file_name: &str) -> Option<Vec<RISICOWarmState>> {
+        let mut reader = io::BufReader::new(file);
+        let is_binary = match reader.fill_buf() {
+            Ok(buffer) => buffer.starts_with(RISICO_WARM_STATE_MAGIC),
+            Err(err) => {
+                warn!("Error reading warm state file: {}", err);
+                return None;
+            }
+        };
+        if is_binary {
+            RISICOConfig::parse_binary_warm_state(reader)
+        } else {
+            RISICOConfig::parse_text_warm_state(reader, file_name)
+        }
+    }
 
-        let reader = io::BufReader::new(file);
+    #[allow(non_snake_case)]
+    /// Parse a text warm state, with a line of values per cell:
+    /// dffm snow_cover snow_cover_time MSI MSI_TTL NDVI NDVI_TIME [NDWI NDWI_TIME]
+    fn parse_text_warm_state(
+        reader: impl BufRead,
+        file_name: &str,
+    ) -> Option<Vec<RISICOWarmState>> {
+        let mut warm_state: Vec<RISICOWarmState> = Vec::new();
 
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
                 return None;
@@ -584,38 +1240,26 @@ impl RISICOConfig {
             let line = line.expect("Should unwrap line");
 
             let components: Vec<&str> = line.split_whitespace().collect();
-            let dffm = components[0]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse dffm from {}", line));
-            let snow_cover = components[1]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
-            let snow_cover_time = components[2]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover_time from {}", line));
-            let MSI = components[3]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse MSI from {}", line));
-            let MSI_TTL = components[4]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse MSI_TTL from {}", line));
-            let NDVI = components[5]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse NDVI from {}", line));
-            let NDVI_TIME = components[6]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse NDVI_TIME from {}", line));
+            // the NDWI columns are optional, but come in pairs
+            let expected = if components.len() > 7 { 9 } else { 7 };
+            if !has_warm_state_columns(&components, expected, file_name, index) {
+                return None;
+            }
+            let dffm = parse_warm_state_value(components[0], "dffm", file_name, index)?;
+            let snow_cover = parse_warm_state_value(components[1], "snow_cover", file_name, index)?;
+            let snow_cover_time =
+                parse_warm_state_value(components[2], "snow_cover_time", file_name, index)?;
+            let MSI = parse_warm_state_value(components[3], "MSI", file_name, index)?;
+            let MSI_TTL = parse_warm_state_value(components[4], "MSI_TTL", file_name, index)?;
+            let NDVI = parse_warm_state_value(components[5], "NDVI", file_name, index)?;
+            let NDVI_TIME = parse_warm_state_value(components[6], "NDVI_TIME", file_name, index)?;
 
             let mut NDWI = NODATAVAL;
             let mut NDWI_TIME = 0.0;
 
             if components.len() > 7 {
-                NDWI = components[7]
-                    .parse::<f32>()
-                    .unwrap_or_else(|_| panic!("Could not parse NDWI from {}", line));
-                NDWI_TIME = components[8]
-                    .parse::<f32>()
-                    .unwrap_or_else(|_| panic!("Could not parse NDWI_TIME from {}", line));
+                NDWI = parse_warm_state_value(components[7], "NDWI", file_name, index)?;
+                NDWI_TIME = parse_warm_state_value(components[8], "NDWI_TIME", file_name, index)?;
             }
 
             warm_state.push(RISICOWarmState {
@@ -631,23 +1275,101 @@ impl RISICOConfig {
             });
         }
 
-        Some((warm_state, current_date))
+        Some(warm_state)
+    }
+
+    /// Parse a binary warm state: magic, version (u32), number of cells (u64),
+    /// number of values per cell (u32), then the values of each cell (f32), all little-endian
+    fn parse_binary_warm_state(mut reader: impl Read) -> Option<Vec<RISICOWarmState>> {
+        let mut bytes = Vec::new();
+        if let Err(err) = reader.read_to_end(&mut bytes) {
+            warn!("Error reading warm state file: {}", err);
+            return None;
+        }
+        let header_len = RISICO_WARM_STATE_MAGIC.len() + 16;
+        if bytes.len() < header_len {
+            warn!("Invalid binary warm state: truncated header");
+            return None;
+        }
+        let header = &bytes[RISICO_WARM_STATE_MAGIC.len()..header_len];
+        let version = u32::from_le_bytes(header[0..4].try_into().expect("Should be 4 bytes"));
+        let n_cells = u64::from_le_bytes(header[4..12].try_into().expect("Should be 8 bytes"));
+        let n_values = u32::from_le_bytes(header[12..16].try_into().expect("Should be 4 bytes")) as usize;
+        if version != RISICO_WARM_STATE_VERSION || n_values != RISICO_WARM_STATE_VALUES {
+            warn!("Unsupported binary warm state version {version} with {n_values} values per cell");
+            return None;
+        }
+        // the number of cells comes from the file, a corrupted header must not overflow
+        let expected_len = usize::try_from(n_cells)
+            .ok()
+            .and_then(|n_cells| n_cells.checked_mul(n_values * 4));
+        let values = &bytes[header_len..];
+        if expected_len != Some(values.len()) {
+            warn!("Invalid binary warm state: expected {n_cells} cells, found {} bytes", values.len());
+            return None;
+        }
+        let warm_state = values
+            .chunks_exact(n_values * 4)
+            .map(|cell| {
+                let v: Vec<f32> = cell
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes(b.try_into().expect("Should be 4 bytes")))
+                    .collect();
+                RISICOWarmState {
+                    dffm: v[0],
+                    snow_cover: v[1],
+                    snow_cover_time: v[2],
+                    MSI: v[3],
+                    MSI_TTL: v[4],
+                    NDVI: v[5],
+                    NDVI_TIME: v[6],
+                    NDWI: v[7],
+                    NDWI_TIME: v[8],
+                }
+            })
+            .collect();
+        Some(warm_state)
     }
 
-    #[allow(non_snake_case)]
     pub fn write_warm_state(
         &self,
         state: &RISICOState,
         warm_state_time: DateTime<Utc>,
     ) -> Result<(), RISICOError> {
-        let date_string = warm_state_time.format("%Y%m%d%H%M").to_string();
-        let warm_state_name = format!("{}{}", self.warm_state_path, date_string);
-        let mut warm_state_file = File::create(&warm_state_name)
-            .map_err(|error| format!("error creating {}, {}", &warm_state_name, error))?;
+        let warm_state_name = warm_state_file_name(&self.warm_state_path, &warm_state_time);
+        self.write_warm_state_file(&warm_state_name, &state.snapshot())
+    }
+
+    #[allow(non_snake_case)]
+    fn write_warm_state_file(
+        &self,
+        warm_state_name: &str,
+        warm_state: &[RISICOWarmState],
+    ) -> Result<(), RISICOError> {
+        let mut warm_state_file = File::create(warm_state_name)
+            .map_err(|error| format!("error creating {}, {}", warm_state_name, error))?;
 
         let mut warm_state_writer = BufWriter::new(&mut warm_state_file);
 
-        for state in &state.data {
+        match self.warm_state_format {
+            WarmStateFormat::Binary => {
+                RISICOConfig::write_binary_warm_state(&mut warm_state_writer, warm_state)
+            }
+            WarmStateFormat::Text => {
+                RISICOConfig::write_text_warm_state(&mut warm_state_writer, warm_state)
+            }
+        }
+        .map_err(|error| format!("error writing to {}, {}", warm_state_name, error))?;
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    /// Write the warm state in the text format read by parse_text_warm_state
+    fn write_text_warm_state(
+        writer: &mut impl Write,
+        warm_state: &[RISICOWarmState],
+    ) -> io::Result<()> {
+        for state in warm_state {
             let dffm = state.dffm;
 
             let MSI = state.MSI; //cell.state.MSI;
@@ -663,10 +1385,100 @@ impl RISICOConfig {
                 "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                 dffm, snow_cover, snow_cover_time, MSI, MSI_TTL, NDVI, NDVI_TIME, NDWI, NDWI_TIME
             );
-            writeln!(warm_state_writer, "{}", line)
-                .map_err(|error| format!("error writing to {}, {}", &warm_state_name, error))?;
+            writeln!(writer, "{}", line)?;
         }
-        Ok(())
+        writer.flush()
+    }
+
+    /// Write the warm state in the binary format read by parse_binary_warm_state
+    fn write_binary_warm_state(
+        writer: &mut impl Write,
+        warm_state: &[RISICOWarmState],
+    ) -> io::Result<()> {
+        writer.write_all(RISICO_WARM_STATE_MAGIC)?;
+        writer.write_all(&RISICO_WARM_STATE_VERSION.to_le_bytes())?;
+        writer.write_all(&(warm_state.len() as u64).to_le_bytes())?;
+        writer.write_all(&(RISICO_WARM_STATE_VALUES as u32).to_le_bytes())?;
+        for cell in warm_state {
+            for value in [
+                cell.dffm,
+                cell.snow_cover,
+                cell.snow_cover_time,
+                cell.MSI,
+                cell.MSI_TTL,
+                cell.NDVI,
+                cell.NDVI_TIME,
+                cell.NDWI,
+                cell.NDWI_TIME,
+            ] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Write a checkpoint of the state, replacing the previous one
+    pub fn write_checkpoint(&self, state: &RISICOState) -> Result<(), RISICOError> {
+        write_checkpoint(&self.warm_state_path, &self.run_date, &state.time, |file_name| {
+            self.write_warm_state_file(file_name, &state.snapshot())
+        })
+    }
+
+    /// Restore the state from the latest checkpoint of the run date, if taken after the loaded
+    /// warm state, returns the time of the checkpoint
+    pub fn resume_from_checkpoint(&self, state: &mut RISICOState) -> Option<DateTime<Utc>> {
+        let (file_name, time) = latest_checkpoint(&self.warm_state_path, &self.run_date)?;
+        if time <= state.time {
+            info!("Ignoring checkpoint {}, older than the warm state", file_name);
+            return None;
+        }
+        let file = match File::open(&file_name) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("Cannot open checkpoint {}: {}", file_name, err);
+                return None;
+            }
+        };
+        let warm_state = RISICOConfig::read_warm_state_file(file, &file_name)?;
+        if warm_state.len() != state.len() {
+            warn!(
+                "Ignoring checkpoint {}: {} cells instead of {}",
+                file_name,
+                warm_state.len(),
+                state.len()
+            );
+            return None;
+        }
+        info!("Resuming from checkpoint {}", file_name);
+        state.restore(&warm_state, &time);
+        Some(time)
+    }
+
+    /// Remove the checkpoints of the run, once it is complete
+    pub fn clear_checkpoints(&self) {
+        remove_checkpoints(&self.warm_state_path, &self.run_date, None);
+    }
+
+    /// Compare the state with the loaded warm state
+    pub fn check_warm_state_continuity(&self, state: &RISICOState) -> WarmStateContinuity {
+        let default = RISICOWarmState::default();
+        let mut continuity = WarmStateContinuity {
+            n_cells: state.data.len(),
+            ..WarmStateContinuity::default()
+        };
+        for (loaded, current) in self.warm_state.iter().zip(&state.data) {
+            if loaded.dffm == NODATAVAL {
+                continue;
+            }
+            if current.dffm == NODATAVAL {
+                continuity.n_invalid += 1;
+            } else if !current.dffm.is_finite() || current.dffm < 0.0 {
+                continuity.n_implausible += 1;
+            } else if current.dffm == default.dffm && loaded.dffm != default.dffm {
+                continuity.n_reset += 1;
+            }
+        }
+        continuity
     }
 }
 
@@ -680,19 +1492,42 @@ impl FWIConfig {
 
         let cells_file = &config_defs.cells_file_path;
 
-        let props_container = FWIConfig::properties_from_file(cells_file)
+        let mut props_container = FWIConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
 
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
 
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
         let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
-
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = FWIConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        )?;
         let (warm_state, warm_state_time) =
-            FWIConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
                 .unwrap_or((
                     vec![FWIWarmState::default(); n_cells],
                     date - Duration::try_days(1).expect("Should be a valid duration"),
@@ -707,18 +1542,37 @@ impl FWIConfig {
             output_time_resolution = 24;
         }
 
+        let defaults = FWIParameters::default();
+        let parameters = FWIParameters {
+            ffmc_init: config_defs.ffmc_init.unwrap_or(defaults.ffmc_init),
+            dmc_init: config_defs.dmc_init.unwrap_or(defaults.dmc_init),
+            dc_init: config_defs.dc_init.unwrap_or(defaults.dc_init),
+            ffmc_rain_threshold: config_defs
+                .ffmc_rain_threshold
+                .unwrap_or(defaults.ffmc_rain_threshold),
+            dmc_rain_threshold: config_defs
+                .dmc_rain_threshold
+                .unwrap_or(defaults.dmc_rain_threshold),
+            dc_rain_threshold: config_defs
+                .dc_rain_threshold
+                .unwrap_or(defaults.dc_rain_threshold),
+            compute_ifwi: config_defs.compute_ifwi.unwrap_or(defaults.compute_ifwi),
+        };
+
         let config = FWIConfig {
             run_date: date,
             // model_name: config_defs.model_name.clone(),
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
             properties: props,
             palettes,
-            output_time_resolution,
+            output_schedule: OutputSchedule::new(date, output_time_resolution),
             model_version: config_defs.model_version.clone(),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+            parameters,
         };
 
         Ok(config)
@@ -727,7 +1581,7 @@ impl FWIConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<FWICellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
 
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
@@ -771,7 +1625,8 @@ impl FWIConfig {
 
     pub fn new_state(&self) -> FWIState {
         log::info!("Model version: {}", &self.model_version);
-        let config = FWIModelConfig::new(&self.model_version);
+        let mut config = FWIModelConfig::new(&self.model_version);
+        config.parameters = self.parameters;
         FWIState::new(&self.warm_state, &self.warm_state_time, config)
     }
 
@@ -780,17 +1635,26 @@ impl FWIConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
-        ))
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        )
+        .with_model_version(&self.model_version))
     }
 
     pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+        self.output_schedule.is_due(time)
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -803,10 +1667,13 @@ impl FWIConfig {
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
+        hours: &[i64],
         lag_days: &i64,
-    ) -> Option<(Vec<FWIWarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
+    ) -> Result<Option<(Vec<FWIWarmState>, DateTime<Utc>)>, RISICOError> {
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -814,7 +1681,7 @@ impl FWIConfig {
                     "WARNING: Could not find a valid warm state file for run date {}",
                     run_date.format("%Y-%m-%d")
                 );
-                return None;
+                return Ok(None);
             }
         };
         info!(
@@ -825,50 +1692,39 @@ impl FWIConfig {
 
         let reader = io::BufReader::new(file);
 
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
-                return None;
+                return Ok(None);
             }
             let line = line.expect("Should unwrap line");
 
             let components: Vec<&str> = line.split_whitespace().collect();
-            let dates = components[0]
-                .split(",")
-                .map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M")
-                        .map(|naive| DateTime::from_naive_utc_and_offset(naive, Utc))
-                        .unwrap_or_else(|_| panic!("Could not parse date from {}", date))
-                })
-                .collect();
-            let ffmc = components[1]
-                .split(",")
-                .map(|ffmc| {
-                    ffmc.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse FFMC value from {}", ffmc))
-                })
-                .collect();
-            let dmc = components[2]
-                .split(",")
-                .map(|dmc| {
-                    dmc.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse DMC value from {}", dmc))
-                })
-                .collect();
-            let dc = components[3]
-                .split(",")
-                .map(|dc| {
-                    dc.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse DC value from {}", dc))
-                })
-                .collect();
-            let rain = components[4]
-                .split(",")
-                .map(|rain| {
-                    rain.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse rain value from {}", rain))
-                })
+            if !has_warm_state_columns(&components, 5, &file_name, index) {
+                return Ok(None);
+            }
+            // the history arrays must be aligned
+            let lengths: Vec<usize> = components[..5]
+                .iter()
+                .map(|c| c.split(',').count())
                 .collect();
+            if lengths.iter().any(|len| *len != lengths[0]) {
+                return Err(format!(
+                    "Invalid FWI warm state at line #{index}: arrays of different lengths \
+                    (dates {}, ffmc {}, dmc {}, dc {}, rain {})",
+                    lengths[0], lengths[1], lengths[2], lengths[3], lengths[4]
+                )
+                .into());
+            }
+            let (Some(dates), Some(ffmc), Some(dmc), Some(dc), Some(rain)) = (
+                parse_warm_state_dates(components[0], &file_name, index),
+                parse_warm_state_values(components[1], "FFMC", &file_name, index),
+                parse_warm_state_values(components[2], "DMC", &file_name, index),
+                parse_warm_state_values(components[3], "DC", &file_name, index),
+                parse_warm_state_values(components[4], "rain", &file_name, index),
+            ) else {
+                return Ok(None);
+            };
 
             warm_state.push(FWIWarmState {
                 dates,
@@ -879,7 +1735,7 @@ impl FWIConfig {
             });
         }
 
-        Some((warm_state, current_date))
+        Ok(Some((warm_state, current_date)))
     }
 
     #[allow(non_snake_case)]
@@ -931,6 +1787,39 @@ impl FWIConfig {
         }
         Ok(())
     }
+
+    /// Compare the state with the loaded warm state (last values of the codes)
+    pub fn check_warm_state_continuity(&self, state: &FWIState) -> WarmStateContinuity {
+        let last_codes = |ffmc: &[f32], dmc: &[f32], dc: &[f32]| {
+            let codes = (*ffmc.last()?, *dmc.last()?, *dc.last()?);
+            let valid = codes.0 != NODATAVAL && codes.1 != NODATAVAL && codes.2 != NODATAVAL;
+            valid.then_some(codes)
+        };
+        let out_of_range = |(ffmc, dmc, dc): (f32, f32, f32)| {
+            !(0.0..=101.0).contains(&ffmc) || dmc < 0.0 || dc < 0.0
+        };
+        let init = (
+            self.parameters.ffmc_init,
+            self.parameters.dmc_init,
+            self.parameters.dc_init,
+        );
+        let mut continuity = WarmStateContinuity {
+            n_cells: state.data.len(),
+            ..WarmStateContinuity::default()
+        };
+        for (loaded, current) in self.warm_state.iter().zip(&state.data) {
+            let Some(loaded) = last_codes(&loaded.ffmc, &loaded.dmc, &loaded.dc) else {
+                continue;
+            };
+            match last_codes(&current.ffmc, &current.dmc, &current.dc) {
+                None => continuity.n_invalid += 1,
+                Some(codes) if out_of_range(codes) => continuity.n_implausible += 1,
+                Some(codes) if codes == init && loaded != init => continuity.n_reset += 1,
+                Some(_) => {}
+            }
+        }
+        continuity
+    }
 }
 
 impl Mark5Config {
@@ -942,17 +1831,44 @@ impl Mark5Config {
     ) -> Result<Mark5Config, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = Mark5Config::properties_from_file(cells_file)
+        let mut props_container = Mark5Config::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.mean_rains, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             return Err(format!("All properties must have the same length").into());
         }
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
+        if let Some(hour) = config_defs.output_hour.filter(|hour| *hour > 23) {
+            return Err(format!("Invalid output_hour {hour}, expected a value in [0, 23]").into());
+        }
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
         let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
-
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = Mark5Config::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
         let (warm_state, warm_state_time) =
-            Mark5Config::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
                 .unwrap_or((
                     vec![Mark5WarmState::default(); n_cells],
                     date - Duration::try_days(1).expect("Should be a valid duration"),
@@ -963,11 +1879,17 @@ impl Mark5Config {
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
+            output_schedule: OutputSchedule::daily(
+                date,
+                config_defs.output_time_resolution,
+                config_defs.output_hour,
+            ),
             properties: props,
             palettes,
             model_version: config_defs.model_version.clone(),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -976,7 +1898,7 @@ impl Mark5Config {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<Mark5CellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("Can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("Can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let mut mean_rains: Vec<f32> = Vec::new();
@@ -1034,11 +1956,35 @@ impl Mark5Config {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
-        ))
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        )
+        .with_model_version(&self.model_version))
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Whether the daily update of the state is due at the given time
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_end_of_day(time)
+    }
+
+    /// Whether the outputs should be written at the given time
+    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
+        match &self.output_schedule {
+            Some(schedule) => schedule.is_due(time),
+            None => self.is_end_of_day(time),
+        }
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.should_write_output(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -1049,10 +1995,13 @@ impl Mark5Config {
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
+        hours: &[i64],
         lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
     ) -> Option<(Vec<Mark5WarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -1069,31 +2018,19 @@ impl Mark5Config {
         );
         let mut warm_state: Vec<Mark5WarmState> = Vec::new();
         let reader = io::BufReader::new(file);
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
                 return None;
             }
             let line = line.expect("Should unwrap line");
             let components: Vec<&str> = line.split_whitespace().collect();
-            let dates = components[0]
-                .split(",")
-                .map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M")
-                        .map(|naive| DateTime::from_naive_utc_and_offset(naive, Utc))
-                        .unwrap_or_else(|_| panic!("Could not parse date from {}", date))
-                })
-                .collect();
-            let daily_rain = components[1]
-                .split(",")
-                .map(|rain| {
-                    rain.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse FFMC value from {}", rain))
-                })
-                .collect();
-            let smd = components[2]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
+            if !has_warm_state_columns(&components, 3, &file_name, index) {
+                return None;
+            }
+            let dates = parse_warm_state_dates(components[0], &file_name, index)?;
+            let daily_rain = parse_warm_state_values(components[1], "rain", &file_name, index)?;
+            let smd = parse_warm_state_value(components[2], "SMD", &file_name, index)?;
             warm_state.push(Mark5WarmState {
                 dates,
                 daily_rain,
@@ -1148,21 +2085,69 @@ impl KbdiConfig {
     ) -> Result<KbdiConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = KbdiConfig::properties_from_file(cells_file)
+        let mut props_container = KbdiConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.mean_rains, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             return Err(format!("All properties must have the same length").into());
         }
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
+        if let Some(hour) = config_defs.output_hour.filter(|hour| *hour > 23) {
+            return Err(format!("Invalid output_hour {hour}, expected a value in [0, 23]").into());
+        }
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
         let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        if let Some(dryness) = config_defs.cold_start_dryness {
+            if !(0.0..=1.0).contains(&dryness) {
+                return Err(format!("Invalid cold_start_dryness {dryness}, expected a value in [0, 1]").into());
+            }
+        }
 
+        // without a warm state file, the cold start is seeded from the mean annual rain
+        // if cold_start_dryness is set, otherwise all the cells start from KBDI_INIT
+        let warm_state = KbdiConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
         let (warm_state, warm_state_time) =
-            KbdiConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
-                .unwrap_or((
-                    vec![KBDIWarmState::default(); n_cells],
-                    date - Duration::try_days(1).expect("Should be a valid duration"),
-                ));
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
+                .unwrap_or_else(|| {
+                    let warm_state = match config_defs.cold_start_dryness {
+                        Some(dryness) => {
+                            info!("KBDI cold start with antecedent dryness {dryness}");
+                            props_container
+                                .mean_rains
+                                .iter()
+                                .map(|mean_rain| KBDIWarmState::cold_start(*mean_rain, dryness))
+                                .collect()
+                        }
+                        None => vec![KBDIWarmState::default(); n_cells],
+                    };
+                    (
+                        warm_state,
+                        date - Duration::try_days(1).expect("Should be a valid duration"),
+                    )
+                });
         let props = KBDIProperties::new(props_container);
         let config = KbdiConfig {
             run_date: date,
@@ -1170,11 +2155,17 @@ impl KbdiConfig {
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
+            output_schedule: OutputSchedule::daily(
+                date,
+                config_defs.output_time_resolution,
+                config_defs.output_hour,
+            ),
             properties: props,
             palettes,
             model_version: config_defs.model_version.clone(),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1183,7 +2174,7 @@ impl KbdiConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<KBDICellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let mut mean_rains: Vec<f32> = Vec::new();
@@ -1236,11 +2227,35 @@ impl KbdiConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
-        ))
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        )
+        .with_model_version(&self.model_version))
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Whether the daily update of the state is due at the given time
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_end_of_day(time)
+    }
+
+    /// Whether the outputs should be written at the given time
+    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
+        match &self.output_schedule {
+            Some(schedule) => schedule.is_due(time),
+            None => self.is_end_of_day(time),
+        }
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.should_write_output(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -1251,10 +2266,13 @@ impl KbdiConfig {
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
+        hours: &[i64],
         lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
     ) -> Option<(Vec<KBDIWarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -1271,31 +2289,19 @@ impl KbdiConfig {
         );
         let mut warm_state: Vec<KBDIWarmState> = Vec::new();
         let reader = io::BufReader::new(file);
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
                 return None;
             }
             let line = line.expect("Should unwrap line");
             let components: Vec<&str> = line.split_whitespace().collect();
-            let dates = components[0]
-                .split(",")
-                .map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M")
-                        .map(|naive| DateTime::from_naive_utc_and_offset(naive, Utc))
-                        .unwrap_or_else(|_| panic!("Could not parse date from {}", date))
-                })
-                .collect();
-            let daily_rain = components[1]
-                .split(",")
-                .map(|rain| {
-                    rain.parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Could not parse FFMC value from {}", rain))
-                })
-                .collect();
-            let kbdi = components[2]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
+            if !has_warm_state_columns(&components, 3, &file_name, index) {
+                return None;
+            }
+            let dates = parse_warm_state_dates(components[0], &file_name, index)?;
+            let daily_rain = parse_warm_state_values(components[1], "rain", &file_name, index)?;
+            let kbdi = parse_warm_state_value(components[2], "KBDI", &file_name, index)?;
             warm_state.push(KBDIWarmState {
                 dates,
                 daily_rain,
@@ -1350,9 +2356,21 @@ impl AngstromConfig {
     ) -> Result<AngstromConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = AngstromConfig::properties_from_file(cells_file)
+        let mut props_container = AngstromConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
@@ -1361,8 +2379,9 @@ impl AngstromConfig {
             run_date: date,
             properties: props,
             palettes,
-            output_time_resolution: config_defs.output_time_resolution,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1371,7 +2390,7 @@ impl AngstromConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<AngstromCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let reader = BufReader::new(file);
@@ -1412,15 +2431,23 @@ impl AngstromConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     // check for writing output condition
     pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
         // the Angstrom index is computed every 24 hours (once a day)
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
     }
 }
 
@@ -1433,9 +2460,22 @@ impl FosbergConfig {
     ) -> Result<FosbergConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = FosbergConfig::properties_from_file(cells_file)
+        let mut props_container = FosbergConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.slopes, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
@@ -1444,8 +2484,9 @@ impl FosbergConfig {
             run_date: date,
             properties: props,
             palettes,
-            output_time_resolution: config_defs.output_time_resolution,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1454,9 +2495,10 @@ impl FosbergConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<FosbergCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
+        let mut slopes: Vec<f32> = Vec::new();
         let reader = BufReader::new(file);
         for (index, line) in reader.lines().enumerate() {
             let line = line.map_err(|err| format!("can't read from file: {err}."))?;
@@ -1477,10 +2519,19 @@ impl FosbergConfig {
                 format!("Invalid `lat` value in file {file_path} at line #{index}: '{line}'")
             })?;
 
+            // the slope is optional, without it the corrected index is not computed
+            let slope = match line_parts.get(2) {
+                Some(slope) => slope.parse::<f32>().map_err(|_| {
+                    format!("Invalid `slope` value in file {file_path} at line #{index}: '{line}'")
+                })?,
+                None => NODATAVAL,
+            };
+
             lons.push(lon);
             lats.push(lat);
+            slopes.push(slope);
         }
-        let props = FosbergCellPropertiesContainer { lats, lons };
+        let props = FosbergCellPropertiesContainer { lats, lons, slopes };
         Ok(props)
     }
 
@@ -1497,14 +2548,22 @@ impl FosbergConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     // check for writing output condition
     pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
     }
 }
 
@@ -1517,17 +2576,40 @@ impl NesterovConfig {
     ) -> Result<NesterovConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = NesterovConfig::properties_from_file(cells_file)
+        let mut props_container = NesterovConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
         let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
-
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = NesterovConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
         let (warm_state, warm_state_time) =
-            NesterovConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
                 .unwrap_or((
                     vec![NesterovWarmState::default(); n_cells],
                     date - Duration::try_days(1).expect("Should be a valid duration"),
@@ -1538,10 +2620,11 @@ impl NesterovConfig {
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
             properties: props,
             palettes,
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1550,7 +2633,7 @@ impl NesterovConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<NesterovCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let reader = BufReader::new(file);
@@ -1594,11 +2677,26 @@ impl NesterovConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Whether the daily update of the state is due at the given time
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_end_of_day(time)
+    }
+
+    /// Planned writes at the given time (outputs are written with the daily update)
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.is_end_of_day(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -1609,10 +2707,13 @@ impl NesterovConfig {
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
-        lag_days: &i64
+        hours: &[i64],
+        lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
     ) -> Option<(Vec<NesterovWarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -1630,16 +2731,18 @@ impl NesterovConfig {
         let mut warm_state: Vec<NesterovWarmState> = Vec::new();
 
         let reader = io::BufReader::new(file);
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
                 return None;
             }
             let line = line.expect("Should unwrap line");
             let components: Vec<&str> = line.split_whitespace().collect();
-            let nesterov = components[0]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
+            if !has_warm_state_columns(&components, 1, &file_name, index) {
+                return None;
+            }
+            let nesterov =
+                parse_warm_state_value(components[0], "Nesterov index", &file_name, index)?;
             warm_state.push(NesterovWarmState { nesterov });
         }
         Some((warm_state, current_date))
@@ -1675,19 +2778,38 @@ impl SharplesConfig {
     ) -> Result<SharplesConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = SharplesConfig::properties_from_file(cells_file)
+        let mut props_container = SharplesConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
         let props = SharplesProperties::new(props_container);
+        let model_config = SharplesModelConfig::new(
+            config_defs.temperature_offset,
+            config_defs.fmi_intercept,
+            config_defs.fmi_slope,
+        );
         let config = SharplesConfig {
             run_date: date,
             properties: props,
+            model_config,
             palettes,
-            output_time_resolution: config_defs.output_time_resolution,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1696,7 +2818,7 @@ impl SharplesConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<SharplesCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let reader = BufReader::new(file);
@@ -1731,7 +2853,7 @@ impl SharplesConfig {
     }
 
     pub fn new_state(&self) -> SharplesState {
-        SharplesState::new(&self.run_date, self.properties.len)
+        SharplesState::new(&self.run_date, self.properties.len, self.model_config.clone())
     }
 
     pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
@@ -1739,13 +2861,21 @@ impl SharplesConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
     }
 }
 
@@ -1758,17 +2888,41 @@ impl OrieuxConfig {
     ) -> Result<OrieuxConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = OrieuxConfig::properties_from_file(cells_file)
+        let mut props_container = OrieuxConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.heat_indices, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
-        let warm_state_hour = config_defs.warm_state_hour.unwrap_or(WARM_STATE_HOUR);
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
         let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
-
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = OrieuxConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
         let (warm_state, warm_state_time) =
-            OrieuxConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour, &warm_state_lag_days)
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
                 .unwrap_or((
                     vec![OrieuxWarmState::default(); n_cells],
                     date - Duration::try_days(1).expect("Should be a valid duration"),
@@ -1779,10 +2933,11 @@ impl OrieuxConfig {
             warm_state_path: config_defs.warm_state_path.clone(),
             warm_state,
             warm_state_time,
-            warm_state_hour,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
             properties: props,
             palettes,
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -1791,7 +2946,7 @@ impl OrieuxConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<OrieuxCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let mut heat_indices: Vec<f32> = Vec::new();
@@ -1844,11 +2999,26 @@ impl OrieuxConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
-        check_write_warm_state(time, self.warm_state_hour)
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Whether the daily update of the state is due at the given time
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_end_of_day(time)
+    }
+
+    /// Planned writes at the given time (outputs are written with the daily update)
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.is_end_of_day(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
     }
 
     #[allow(non_snake_case)]
@@ -1859,10 +3029,13 @@ impl OrieuxConfig {
     pub fn read_warm_state(
         base_warm_file: &str,
         run_date: DateTime<Utc>,
-        hour: &i64,
-        lag_days: &i64
+        hours: &[i64],
+        lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
     ) -> Option<(Vec<OrieuxWarmState>, DateTime<Utc>)> {
-        let (file, current_date) = find_warm_state(base_warm_file, run_date, *hour, *lag_days);
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
         let file = match file {
             Some(file) => file,
             None => {
@@ -1879,16 +3052,18 @@ impl OrieuxConfig {
         );
         let mut warm_state: Vec<OrieuxWarmState> = Vec::new();
         let reader = io::BufReader::new(file);
-        for line in reader.lines() {
+        for (index, line) in reader.lines().enumerate() {
             if let Err(line) = line {
                 warn!("Error reading warm state file: {}", line);
                 return None;
             }
             let line = line.expect("Should unwrap line");
             let components: Vec<&str> = line.split_whitespace().collect();
-            let orieux_wr = components[0]
-                .parse::<f32>()
-                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
+            if !has_warm_state_columns(&components, 1, &file_name, index) {
+                return None;
+            }
+            let orieux_wr =
+                parse_warm_state_value(components[0], "Orieux water reserve", &file_name, index)?;
             warm_state.push(OrieuxWarmState { orieux_wr });
         }
         Some((warm_state, current_date))
@@ -1915,196 +3090,206 @@ impl OrieuxConfig {
     }
 }
 
-// UNDER CONSTRUCTION
-//impl PortugueseConfig {
-//
-//    // New Portuguese index configuration
-//    pub fn new(
-//        config_defs: &PortugueseConfigBuilder,
-//        date: DateTime<Utc>,
-//        palettes: &HashMap<String, String>,
-//    ) -> Result<PortugueseConfig, RISICOError> {
-//        let palettes = load_palettes(palettes);
-//        let cells_file = &config_defs.cells_file_path;
-//        let props_container = PortugueseConfig::properties_from_file(cells_file)
-//            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
-//        let n_cells = props_container.lons.len();
-//        if n_cells != props_container.lats.len()
-//        {
-//            panic!("All properties must have the same length");
-//        }
-//        let warm_state_hour = if config_defs.warm_state_hour > 0 {
-//            config_defs.warm_state_hour.clone()
-//        } else {
-//            24
-//        };
-//        let (warm_state, warm_state_time) = PortugueseConfig::read_warm_state(&config_defs.warm_state_path, date, &warm_state_hour)
-//            .unwrap_or((
-//                vec![PortugueseWarmState::default(); n_cells],
-//                date - Duration::try_days(1).expect("Should be a valid duration"),
-//            ));
-//        let props = PortugueseProperties::new(props_container);
-//        let config = PortugueseConfig {
-//            run_date: date,
-//            warm_state_path: config_defs.warm_state_path.clone(),
-//            warm_state,
-//            warm_state_time,
-//            warm_state_hour: warm_state_hour,
-//            properties: props,
-//            palettes,
-//            output_types_defs: config_defs.output_types.clone(),
-//        };
-//        Ok(config)
-//    }
-//
-//    // Read properties from file
-//    pub fn properties_from_file(file_path: &str) -> Result<PortugueseCellPropertiesContainer, RISICOError> {
-//        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
-//        let mut lons: Vec<f32> = Vec::new();
-//        let mut lats: Vec<f32> = Vec::new();
-//        let reader = BufReader::new(file);
-//        for line in reader.lines() {
-//            let line = line.map_err(|err| format!("can't read from file: {err}."))?;
-//            if line.starts_with("#") {
-//                // skip header
-//                continue;
-//            }
-//            let line_parts: Vec<&str> = line.trim().split(char::is_whitespace).collect();
-//            if line_parts.len() < 2 {
-//                let error_message = format!("Invalid line in file: {}", line);
-//                return Err(error_message.into());
-//            }
-//            let lon = line_parts[0]
-//                .parse::<f32>()
-//                .unwrap_or_else(|_| panic!("Invalid line in file: {}", line));
-//            let lat = line_parts[1]
-//                .parse::<f32>()
-//                .unwrap_or_else(|_| panic!("Invalid line in file: {}", line));
-//            lons.push(lon);
-//            lats.push(lat);
-//        }
-//        let props = PortugueseCellPropertiesContainer {
-//            lats,
-//            lons,
-//        };
-//        Ok(props)
-//    }
-//
-//    pub fn get_properties(&self) -> &PortugueseProperties {
-//        &self.properties
-//    }
-//
-//    pub fn new_state(&self) -> PortugueseState {
-//        PortugueseState::new(&self.warm_state, &self.warm_state_time)
-//    }
-//
-//    pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
-//        Ok(OutputWriter::new(
-//            self.output_types_defs.as_slice(),
-//            &self.run_date,
-//            &self.palettes,
-//        ))
-//    }
-//
-//    pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> (bool, DateTime<Utc>) {
-//        let time_diff = time.signed_duration_since(self.run_date);
-//        let minutes = time_diff.num_minutes();
-//        // Approximation to the closest hour
-//        let approximate_hours = if minutes % 60 >= 30 {
-//            (minutes / 60) + 1
-//        } else {
-//            minutes / 60
-//        };
-//        let warm_state_time = self.run_date + Duration::try_hours(approximate_hours).expect("Should be valid");
-//        let should_write= (approximate_hours % self.warm_state_hour == 0) && (approximate_hours > 0);
-//        (should_write, warm_state_time)
-//    }
-//
-//    #[allow(non_snake_case)]
-//    /// Reads the warm state from the file
-//    /// The warm state is stored in a file with the following structure:
-//    /// base_warm_file_YYYYmmDDHHMM
-//    /// where <base_warm_file> is the base name of the file and `YYYYmmDDHHMM` is the date of the warm state
-//    pub fn read_warm_state(
-//        base_warm_file: &str,
-//        run_date: DateTime<Utc>,
-//        offset: &i64,
-//    ) -> Option<(Vec<PortugueseWarmState>, DateTime<Utc>)> {
-//        // for the last n days before date, try to read the warm state
-//        // compose the filename as base_warm_file_YYYYmmDDHHMM
-//        let mut file: Option<File> = None;
-//        let mut current_date = run_date;
-//        for days_before in 1..4 {
-//            current_date = run_date - Duration::try_days(days_before).expect("Should be valid");
-//            // add the offset to the current date
-//            current_date = current_date + Duration::try_hours(*offset).expect("Should be valid");
-//            let filename = format!("{}{}", base_warm_file, current_date.format("%Y%m%d%H%M"));
-//            let file_handle = File::open(filename);
-//            if file_handle.is_err() {
-//                continue;
-//            }
-//            file = Some(file_handle.expect("Should unwrap"));
-//            break;
-//        }
-//        let file = match file {
-//            Some(file) => file,
-//            None => {
-//                warn!(
-//                    "WARNING: Could not find a valid warm state file for run date {}",
-//                    run_date.format("%Y-%m-%d")
-//                );
-//                return None;
-//            }
-//        };
-//
-//        info!(
-//            "Loading warm state from {}",
-//            current_date.format("%Y-%m-%d %H:%M")
-//        );
-//        let mut warm_state: Vec<PortugueseWarmState> = Vec::new();
-//        let reader = io::BufReader::new(file);
-//        for line in reader.lines() {
-//            if let Err(line) = line {
-//                warn!("Error reading warm state file: {}", line);
-//                return None;
-//            }
-//            let line = line.expect("Should unwrap line");
-//            let components: Vec<&str> = line.split_whitespace().collect();
-//            let sum_ign = components[0]
-//                .parse::<f32>()
-//                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
-//            let cum_index = components[1]
-//                .parse::<f32>()
-//                .unwrap_or_else(|_| panic!("Could not parse snow_cover from {}", line));
-//            warm_state.push(PortugueseWarmState {
-//                sum_ign,
-//                cum_index
-//            });
-//        }
-//        Some((warm_state, current_date))
-//    }
-//
-//    #[allow(non_snake_case)]
-//    pub fn write_warm_state(&self, state: &PortugueseState, warm_state_time: DateTime<Utc>) -> Result<(), RISICOError> {
-//        let date_string = warm_state_time.format("%Y%m%d%H%M").to_string();
-//        let warm_state_name = format!("{}{}", self.warm_state_path, date_string);
-//        let mut warm_state_file = File::create(&warm_state_name)
-//            .map_err(|error| format!("error creating {}, {}", &warm_state_name, error))?;
-//        let mut warm_state_writer = BufWriter::new(&mut warm_state_file);
-//        for state in &state.data {
-//            let sum_ign = state.sum_ign.clone();
-//            let cum_index = state.cum_index.clone();
-//            let line = format!(
-//                "{}\t{}",
-//                sum_ign,
-//                cum_index
-//            );
-//            writeln!(warm_state_writer, "{}", line)
-//                .map_err(|error| format!("error writing to {}, {}", &warm_state_name, error))?;
-//        }
-//        Ok(())
-//    }
-//}
+impl PortugueseConfig {
+    // New Portuguese index configuration
+    pub fn new(
+        config_defs: &PortugueseConfigBuilder,
+        date: DateTime<Utc>,
+        palettes: &HashMap<String, String>,
+    ) -> Result<PortugueseConfig, RISICOError> {
+        let palettes = load_palettes(palettes);
+        let cells_file = &config_defs.cells_file_path;
+        let mut props_container = PortugueseConfig::properties_from_file(cells_file)
+            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
+        let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
+        if n_cells != props_container.lats.len() {
+            panic!("All properties must have the same length");
+        }
+        let warm_state_hours = warm_state_hours(&config_defs.warm_state_hour);
+        let warm_state_lag_days = config_defs.warm_state_lag_days.unwrap_or(WARM_STATE_LAG_DAYS);
+        let warm_state_max_age_days = config_defs
+            .warm_state_max_age_days
+            .unwrap_or(WARM_STATE_MAX_AGE_DAYS);
+
+        let warm_state = PortugueseConfig::read_warm_state(
+            &config_defs.warm_state_path,
+            date,
+            &warm_state_hours,
+            &warm_state_lag_days,
+            &warm_state_max_age_days,
+            config_defs.warm_state_file.as_deref(),
+        );
+        let (warm_state, warm_state_time) =
+            required_warm_state(warm_state, &config_defs.warm_state_file)?
+                .unwrap_or((
+                    vec![PortugueseWarmState::default(); n_cells],
+                    date - Duration::try_days(1).expect("Should be a valid duration"),
+                ));
+        let props = PortugueseProperties::new(props_container);
+        let config = PortugueseConfig {
+            run_date: date,
+            warm_state_path: config_defs.warm_state_path.clone(),
+            warm_state,
+            warm_state_time,
+            warm_state_schedule: WarmStateSchedule::new(warm_state_hours),
+            properties: props,
+            palettes,
+            output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+        };
+        Ok(config)
+    }
+
+    // Read properties from file
+    pub fn properties_from_file(
+        file_path: &str,
+    ) -> Result<PortugueseCellPropertiesContainer, RISICOError> {
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let mut lons: Vec<f32> = Vec::new();
+        let mut lats: Vec<f32> = Vec::new();
+        let reader = BufReader::new(file);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| format!("can't read from file: {err}."))?;
+            if line.starts_with("#") {
+                // skip header
+                continue;
+            }
+            let line_parts: Vec<&str> = line.trim().split(char::is_whitespace).collect();
+            if line_parts.len() < 2 {
+                let error_message = format!("Invalid line in file: {}", line);
+                return Err(error_message.into());
+            }
+            let lon = line_parts[0].parse::<f32>().map_err(|_| {
+                format!("Invalid `lon` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            let lat = line_parts[1].parse::<f32>().map_err(|_| {
+                format!("Invalid `lon` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            lons.push(lon);
+            lats.push(lat);
+        }
+
+        let props = PortugueseCellPropertiesContainer { lats, lons };
+        Ok(props)
+    }
+
+    pub fn get_properties(&self) -> &PortugueseProperties {
+        &self.properties
+    }
+
+    pub fn new_state(&self) -> PortugueseState {
+        PortugueseState::new(&self.warm_state, &self.warm_state_time)
+    }
+
+    pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
+        Ok(OutputWriter::new(
+            self.output_types_defs.as_slice(),
+            &self.run_date,
+            &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        ))
+    }
+
+    pub fn should_write_warm_state(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_due(time)
+    }
+
+    /// Whether the daily update of the state is due at the given time
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.warm_state_schedule.is_end_of_day(time)
+    }
+
+    /// Planned writes at the given time (outputs are written with the daily update)
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.is_end_of_day(time)),
+            warm_state: self.warm_state_schedule.is_due(time),
+        }
+    }
+
+    #[allow(non_snake_case)]
+    /// Reads the warm state from the file
+    /// The warm state is stored in a file with the following structure:
+    /// base_warm_file_YYYYmmDDHHMM
+    /// where <base_warm_file> is the base name of the file and `YYYYmmDDHHMM` is the date of the warm state
+    pub fn read_warm_state(
+        base_warm_file: &str,
+        run_date: DateTime<Utc>,
+        hours: &[i64],
+        lag_days: &i64,
+        max_age_days: &i64,
+        fixed_file: Option<&str>,
+    ) -> Option<(Vec<PortugueseWarmState>, DateTime<Utc>)> {
+        let (file, file_name, current_date) =
+            find_warm_state(base_warm_file, run_date, hours, *lag_days, *max_age_days, fixed_file);
+        let file = match file {
+            Some(file) => file,
+            None => {
+                warn!(
+                    "WARNING: Could not find a valid warm state file for run date {}",
+                    run_date.format("%Y-%m-%d")
+                );
+                return None;
+            }
+        };
+        info!(
+            "Loading warm state from {}",
+            current_date.format("%Y-%m-%d %H:%M")
+        );
+        let mut warm_state: Vec<PortugueseWarmState> = Vec::new();
+
+        let reader = io::BufReader::new(file);
+        for (index, line) in reader.lines().enumerate() {
+            if let Err(line) = line {
+                warn!("Error reading warm state file: {}", line);
+                return None;
+            }
+            let line = line.expect("Should unwrap line");
+            let components: Vec<&str> = line.split_whitespace().collect();
+            if !has_warm_state_columns(&components, 2, &file_name, index) {
+                return None;
+            }
+            let sum_ign = parse_warm_state_value(components[0], "sum_ign", &file_name, index)?;
+            let cum_index = parse_warm_state_value(components[1], "cum_index", &file_name, index)?;
+            warm_state.push(PortugueseWarmState { sum_ign, cum_index });
+        }
+        Some((warm_state, current_date))
+    }
+
+    #[allow(non_snake_case)]
+    pub fn write_warm_state(
+        &self,
+        state: &PortugueseState,
+        warm_state_time: DateTime<Utc>,
+    ) -> Result<(), RISICOError> {
+        let date_string = warm_state_time.format("%Y%m%d%H%M").to_string();
+        let warm_state_name = format!("{}{}", self.warm_state_path, date_string);
+        let mut warm_state_file = File::create(&warm_state_name)
+            .map_err(|error| format!("error creating {}, {}", &warm_state_name, error))?;
+        let mut warm_state_writer = BufWriter::new(&mut warm_state_file);
+        for state in &state.data {
+            let line = format!("{}\t{}", state.sum_ign, state.cum_index);
+            writeln!(warm_state_writer, "{}", line)
+                .map_err(|error| format!("error writing to {}, {}", &warm_state_name, error))?;
+        }
+        Ok(())
+    }
+}
 
 impl HdwConfig {
     // New Hot-dry-wind index configuration
@@ -2115,9 +3300,21 @@ impl HdwConfig {
     ) -> Result<HdwConfig, RISICOError> {
         let palettes = load_palettes(palettes);
         let cells_file = &config_defs.cells_file_path;
-        let props_container = HdwConfig::properties_from_file(cells_file)
+        let mut props_container = HdwConfig::properties_from_file(cells_file)
             .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
         let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
         if n_cells != props_container.lats.len() {
             panic!("All properties must have the same length");
         }
@@ -2126,8 +3323,9 @@ impl HdwConfig {
             run_date: date,
             properties: props,
             palettes,
-            output_time_resolution: config_defs.output_time_resolution,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
             output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
         };
         Ok(config)
     }
@@ -2136,7 +3334,7 @@ impl HdwConfig {
     pub fn properties_from_file(
         file_path: &str,
     ) -> Result<HdwCellPropertiesContainer, RISICOError> {
-        let file = fs::File::open(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
         let mut lons: Vec<f32> = Vec::new();
         let mut lats: Vec<f32> = Vec::new();
         let reader = BufReader::new(file);
@@ -2179,13 +3377,476 @@ impl HdwConfig {
             self.output_types_defs.as_slice(),
             &self.run_date,
             &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        ))
+    }
+
+    // check for writing output condition
+    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
+    }
+}
+
+impl HainesConfig {
+    // New Haines index configuration
+    pub fn new(
+        config_defs: &HainesConfigBuilder,
+        date: DateTime<Utc>,
+        palettes: &HashMap<String, String>,
+    ) -> Result<HainesConfig, RISICOError> {
+        let palettes = load_palettes(palettes);
+        let cells_file = &config_defs.cells_file_path;
+        let mut props_container = HainesConfig::properties_from_file(cells_file)
+            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
+        let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
+        if n_cells != props_container.lats.len() {
+            panic!("All properties must have the same length");
+        }
+        let props = HainesProperties::new(props_container);
+        let config = HainesConfig {
+            run_date: date,
+            variant: config_defs.variant,
+            properties: props,
+            palettes,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
+            output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+        };
+        Ok(config)
+    }
+
+    // Read properties from file
+    pub fn properties_from_file(
+        file_path: &str,
+    ) -> Result<HainesCellPropertiesContainer, RISICOError> {
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let mut lons: Vec<f32> = Vec::new();
+        let mut lats: Vec<f32> = Vec::new();
+        let reader = BufReader::new(file);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| format!("can't read from file: {err}."))?;
+            if line.starts_with("#") {
+                // skip header
+                continue;
+            }
+            let line_parts: Vec<&str> = line.trim().split(char::is_whitespace).collect();
+            if line_parts.len() < 2 {
+                let error_message = format!("Invalid line in file: {}", line);
+                return Err(error_message.into());
+            }
+            let lon = line_parts[0].parse::<f32>().map_err(|_| {
+                format!("Invalid `lon` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            let lat = line_parts[1].parse::<f32>().map_err(|_| {
+                format!("Invalid `lat` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            lons.push(lon);
+            lats.push(lat);
+        }
+        let props = HainesCellPropertiesContainer { lats, lons };
+        Ok(props)
+    }
+
+    pub fn get_properties(&self) -> &HainesProperties {
+        &self.properties
+    }
+
+    pub fn new_state(&self) -> HainesState {
+        HainesState::new(
+            &self.run_date,
+            self.properties.len,
+            HainesModelConfig::new(self.variant),
+        )
+    }
+
+    pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
+        Ok(OutputWriter::new(
+            self.output_types_defs.as_slice(),
+            &self.run_date,
+            &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
         ))
     }
 
     // check for writing output condition
     pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
-        let time_diff = time.signed_duration_since(self.run_date);
-        let hours = time_diff.num_hours();
-        hours % self.output_time_resolution as i64 == 0
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
+    }
+}
+
+impl ChandlerConfig {
+    // New Chandler Burning Index configuration
+    pub fn new(
+        config_defs: &ChandlerConfigBuilder,
+        date: DateTime<Utc>,
+        palettes: &HashMap<String, String>,
+    ) -> Result<ChandlerConfig, RISICOError> {
+        let palettes = load_palettes(palettes);
+        let cells_file = &config_defs.cells_file_path;
+        let mut props_container = ChandlerConfig::properties_from_file(cells_file)
+            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+        }
+
+        let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
+        if n_cells != props_container.lats.len() {
+            panic!("All properties must have the same length");
+        }
+        let props = ChandlerProperties::new(props_container);
+        let config = ChandlerConfig {
+            run_date: date,
+            properties: props,
+            palettes,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
+            output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+        };
+        Ok(config)
+    }
+
+    // Read properties from file
+    pub fn properties_from_file(
+        file_path: &str,
+    ) -> Result<ChandlerCellPropertiesContainer, RISICOError> {
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let mut lons: Vec<f32> = Vec::new();
+        let mut lats: Vec<f32> = Vec::new();
+        let reader = BufReader::new(file);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| format!("can't read from file: {err}."))?;
+            if line.starts_with("#") {
+                // skip header
+                continue;
+            }
+            let line_parts: Vec<&str> = line.trim().split(char::is_whitespace).collect();
+            if line_parts.len() < 2 {
+                let error_message = format!("Invalid line in file: {}", line);
+                return Err(error_message.into());
+            }
+            let lon = line_parts[0].parse::<f32>().map_err(|_| {
+                format!("Invalid `lon` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            let lat = line_parts[1].parse::<f32>().map_err(|_| {
+                format!("Invalid `lat` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            lons.push(lon);
+            lats.push(lat);
+        }
+        let props = ChandlerCellPropertiesContainer { lats, lons };
+        Ok(props)
+    }
+
+    pub fn get_properties(&self) -> &ChandlerProperties {
+        &self.properties
+    }
+
+    pub fn new_state(&self) -> ChandlerState {
+        ChandlerState::new(&self.run_date, self.properties.len)
+    }
+
+    pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
+        Ok(OutputWriter::new(
+            self.output_types_defs.as_slice(),
+            &self.run_date,
+            &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        ))
+    }
+
+    // check for writing output condition
+    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
+    }
+}
+
+impl GfdiConfig {
+    // New McArthur Grassland Fire Danger Index configuration
+    pub fn new(
+        config_defs: &GfdiConfigBuilder,
+        date: DateTime<Utc>,
+        palettes: &HashMap<String, String>,
+    ) -> Result<GfdiConfig, RISICOError> {
+        let palettes = load_palettes(palettes);
+        let cells_file = &config_defs.cells_file_path;
+        let curing = config_defs.curing.unwrap_or(CURING_DEFAULT);
+        let mut props_container = GfdiConfig::properties_from_file(cells_file, curing)
+            .map_err(|error| format!("error reading {}, {error}", cells_file))?;
+        let duplicates_mask = check_duplicate_cells(
+            &props_container.lats,
+            &props_container.lons,
+            config_defs.duplicate_cells,
+            cells_file,
+        );
+        if let Some(keep) = &duplicates_mask {
+            retain_cells(&mut props_container.lons, keep);
+            retain_cells(&mut props_container.lats, keep);
+            retain_cells(&mut props_container.curings, keep);
+        }
+
+        let n_cells = props_container.lons.len();
+        check_domain_size(n_cells, config_defs.max_cells, cells_file)?;
+        if n_cells != props_container.lats.len() || n_cells != props_container.curings.len() {
+            panic!("All properties must have the same length");
+        }
+        let props = GfdiProperties::new(props_container);
+        let config = GfdiConfig {
+            run_date: date,
+            properties: props,
+            palettes,
+            output_schedule: OutputSchedule::new(date, config_defs.output_time_resolution),
+            output_types_defs: config_defs.output_types.clone(),
+            duplicates_mask,
+        };
+        Ok(config)
+    }
+
+    // Read properties from file: lon, lat and the optional degree of curing [%]
+    pub fn properties_from_file(
+        file_path: &str,
+        default_curing: f32,
+    ) -> Result<GfdiCellPropertiesContainer, RISICOError> {
+        let file = open_source(file_path).map_err(|err| format!("can't open file: {err}."))?;
+        let mut lons: Vec<f32> = Vec::new();
+        let mut lats: Vec<f32> = Vec::new();
+        let mut curings: Vec<f32> = Vec::new();
+        let reader = BufReader::new(file);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| format!("can't read from file: {err}."))?;
+            if line.starts_with("#") {
+                // skip header
+                continue;
+            }
+            let line_parts: Vec<&str> = line.trim().split(char::is_whitespace).collect();
+            if line_parts.len() < 2 {
+                let error_message = format!("Invalid line in file: {}", line);
+                return Err(error_message.into());
+            }
+            let lon = line_parts[0].parse::<f32>().map_err(|_| {
+                format!("Invalid `lon` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            let lat = line_parts[1].parse::<f32>().map_err(|_| {
+                format!("Invalid `lat` value in file {file_path} at line #{index}: '{line}'")
+            })?;
+
+            let curing = match line_parts.get(2) {
+                Some(curing) => curing.parse::<f32>().map_err(|_| {
+                    format!("Invalid `curing` value in file {file_path} at line #{index}: '{line}'")
+                })?,
+                None => default_curing,
+            };
+
+            lons.push(lon);
+            lats.push(lat);
+            curings.push(curing);
+        }
+        let props = GfdiCellPropertiesContainer {
+            lats,
+            lons,
+            curings,
+        };
+        Ok(props)
+    }
+
+    pub fn get_properties(&self) -> &GfdiProperties {
+        &self.properties
+    }
+
+    pub fn new_state(&self) -> GfdiState {
+        GfdiState::new(&self.run_date, self.properties.len)
+    }
+
+    pub fn get_output_writer(&self) -> Result<OutputWriter, RISICOError> {
+        Ok(OutputWriter::new(
+            self.output_types_defs.as_slice(),
+            &self.run_date,
+            &self.palettes,
+            self.properties.len,
+            self.duplicates_mask.as_deref(),
+        ))
+    }
+
+    // check for writing output condition
+    pub fn should_write_output(&self, time: &DateTime<Utc>) -> bool {
+        self.output_schedule.is_due(time)
+    }
+
+    /// Planned writes at the given time
+    pub fn plan(&self, time: &DateTime<Utc>) -> PlannedWrites {
+        PlannedWrites {
+            output: Some(self.output_schedule.is_due(time)),
+            warm_state: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn checkpoints_are_taken_only_by_the_run_of_the_same_date() {
+        let dir = std::env::temp_dir().join(format!("risico-checkpoints-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = format!("{}/RISICO", dir.display());
+        let write = |file_name: &str| {
+            std::fs::write(file_name, "").map_err(|err| RISICOError::from(err.to_string()))
+        };
+
+        let (first_run, second_run) = (utc(2025, 7, 1, 0), utc(2025, 7, 2, 0));
+        write_checkpoint(&base, &first_run, &utc(2025, 7, 1, 6), write).unwrap();
+        write_checkpoint(&base, &first_run, &utc(2025, 7, 1, 12), write).unwrap();
+        write_checkpoint(&base, &second_run, &utc(2025, 7, 2, 3), write).unwrap();
+
+        // the previous checkpoints of a run are replaced, the ones of other runs are kept
+        assert_eq!(list_checkpoints(&base).len(), 2);
+        let (file_name, date) = latest_checkpoint(&base, &first_run).unwrap();
+        assert_eq!(date, utc(2025, 7, 1, 12));
+        assert_eq!(file_name, checkpoint_file_name(&base, &first_run, &date));
+        assert_eq!(latest_checkpoint(&base, &second_run).unwrap().1, utc(2025, 7, 2, 3));
+        assert!(latest_checkpoint(&base, &utc(2025, 7, 3, 0)).is_none());
+
+        remove_checkpoints(&base, &first_run, None);
+        assert!(latest_checkpoint(&base, &first_run).is_none());
+        assert!(latest_checkpoint(&base, &second_run).is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn risico_warm_state() -> Vec<RISICOWarmState> {
+        (0..3)
+            .map(|idx| RISICOWarmState {
+                dffm: 10.5 + idx as f32,
+                snow_cover: 0.0,
+                snow_cover_time: 0.0,
+                MSI: 0.25,
+                MSI_TTL: 48.0,
+                NDVI: 0.125 * idx as f32,
+                NDVI_TIME: 24.0,
+                NDWI: NODATAVAL,
+                NDWI_TIME: 0.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_and_binary_warm_states_are_identical() {
+        let warm_state = risico_warm_state();
+        let mut text = Vec::new();
+        RISICOConfig::write_text_warm_state(&mut text, &warm_state).unwrap();
+        let mut binary = Vec::new();
+        RISICOConfig::write_binary_warm_state(&mut binary, &warm_state).unwrap();
+
+        let from_text = RISICOConfig::parse_text_warm_state(text.as_slice(), "text").unwrap();
+        let from_binary = RISICOConfig::parse_binary_warm_state(binary.as_slice()).unwrap();
+        assert_eq!(from_text, warm_state);
+        assert_eq!(from_binary, warm_state);
+    }
+
+    #[test]
+    fn malformed_warm_state_lines_fall_back_to_the_default() {
+        let bad_value = "10.5 0 0 0.25 48 0.1 24 NaN? 0\n";
+        assert!(RISICOConfig::parse_text_warm_state(bad_value.as_bytes(), "bad").is_none());
+        let truncated = "10.5 0 0 0.25\n";
+        assert!(RISICOConfig::parse_text_warm_state(truncated.as_bytes(), "bad").is_none());
+        assert!(parse_warm_state_dates("202507010000,2025070", "bad", 0).is_none());
+        assert_eq!(parse_warm_state_values("1,2.5", "rain", "ok", 0), Some(vec![1.0, 2.5]));
+    }
+
+    #[test]
+    fn binary_warm_state_with_a_corrupted_header_is_rejected() {
+        let mut binary = Vec::new();
+        RISICOConfig::write_binary_warm_state(&mut binary, &risico_warm_state()).unwrap();
+        // number of cells overflowing the expected size
+        let offset = RISICO_WARM_STATE_MAGIC.len() + 4;
+        binary[offset..offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(RISICOConfig::parse_binary_warm_state(binary.as_slice()).is_none());
+        // truncated values
+        let mut truncated = Vec::new();
+        RISICOConfig::write_binary_warm_state(&mut truncated, &risico_warm_state()).unwrap();
+        truncated.truncate(truncated.len() - 4);
+        assert!(RISICOConfig::parse_binary_warm_state(truncated.as_slice()).is_none());
+    }
+
+    #[test]
+    fn sentinel_slopes_and_aspects_use_the_defaults() {
+        let dir = std::env::temp_dir().join(format!("risico-cells-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cells_file = dir.join("cells.txt");
+        std::fs::write(&cells_file, "10 45 30 180 1\n10 46 -9999 -9999 1\n10 47 95 400 1\n")
+            .unwrap();
+        let cells =
+            RISICOConfig::properties_from_file(cells_file.to_str().unwrap(), 5.0, 90.0).unwrap();
+        let degrees: Vec<(f32, f32)> = cells
+            .slopes
+            .iter()
+            .zip(&cells.aspects)
+            .map(|(slope, aspect)| (slope * 180.0 / PI, aspect * 180.0 / PI))
+            .collect();
+        for (actual, expected) in degrees.iter().zip([(30.0, 180.0), (5.0, 90.0), (5.0, 90.0)]) {
+            assert!(
+                (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Timelike, Utc};
+
+/// Schedule of the outputs of a model: every `resolution` hours from the reference date
+#[derive(Debug, Clone, Copy)]
+pub struct OutputSchedule {
+    reference: DateTime<Utc>,
+    resolution: i64, // [h]
+}
+
+impl OutputSchedule {
+    pub fn new(reference: DateTime<Utc>, resolution: u32) -> Self {
+        Self {
+            reference,
+            // a zero resolution writes every timestep
+            resolution: resolution.max(1) as i64,
+        }
+    }
+
+    /// Whether the outputs should be written at the given time
+    pub fn is_due(&self, time: &DateTime<Utc>) -> bool {
+        let hours = time.signed_duration_since(self.reference).num_hours();
+        hours % self.resolution == 0
+    }
+
+    /// Schedule of a daily model from its `output_time_resolution` and `output_hour`,
+    /// None if neither is set and the outputs are written with the daily update.
+    /// The output hour is the hour of the day of the run date, whatever the time of the run
+    pub fn daily(date: DateTime<Utc>, resolution: Option<u32>, hour: Option<u32>) -> Option<Self> {
+        if resolution.is_none() && hour.is_none() {
+            return None;
+        }
+        let reference = date
+            .date_naive()
+            .and_hms_opt(hour.unwrap_or(0), 0, 0)
+            .expect("Should be a valid hour of the day [0-23]");
+        Some(Self::new(
+            DateTime::from_naive_utc_and_offset(reference, Utc),
+            resolution.unwrap_or(24),
+        ))
+    }
+}
+
+/// Schedule of the warm state of a model: every day at the given hours
+#[derive(Debug, Clone)]
+pub struct WarmStateSchedule {
+    hours: Vec<i64>,
+}
+
+impl WarmStateSchedule {
+    pub fn new(hours: Vec<i64>) -> Self {
+        Self { hours }
+    }
+
+    /// Whether the warm state should be written at the given time
+    pub fn is_due(&self, time: &DateTime<Utc>) -> bool {
+        self.hours.contains(&(time.hour() as i64))
+    }
+
+    /// Whether the day of the daily models ends at the given time:
+    /// once a day, at the first of the warm state hours
+    pub fn is_end_of_day(&self, time: &DateTime<Utc>) -> bool {
+        self.hours.first() == Some(&(time.hour() as i64))
+    }
+}
+
+/// Writes planned by a model at a timestep
+#[derive(Debug, Clone, Copy)]
+pub struct PlannedWrites {
+    /// whether the outputs are written (None if it depends on the input data)
+    pub output: Option<bool>,
+    /// whether the warm state is written
+    pub warm_state: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn daily_output_hour_is_the_hour_of_the_day() {
+        let run_date = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let schedule = OutputSchedule::daily(run_date, None, Some(6)).unwrap();
+        let due = |day, hour| {
+            schedule.is_due(&Utc.with_ymd_and_hms(2025, 7, day, hour, 0, 0).unwrap())
+        };
+        assert!(due(2, 6));
+        assert!(due(3, 6));
+        assert!(!due(2, 18));
+        assert!(OutputSchedule::daily(run_date, None, None).is_none());
+    }
+
+    #[test]
+    fn daily_update_once_a_day_with_several_warm_state_hours() {
+        let schedule = WarmStateSchedule::new(vec![0, 12]);
+        let time = |hour| Utc.with_ymd_and_hms(2025, 7, 1, hour, 0, 0).unwrap();
+        assert!(schedule.is_due(&time(0)) && schedule.is_due(&time(12)));
+        assert!(schedule.is_end_of_day(&time(0)));
+        assert!(!schedule.is_end_of_day(&time(12)));
+    }
+}
@@ -1,17 +1,114 @@
-use std::{f32::consts::PI, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+    fmt::Display,
+    sync::Once,
+};
 
 use chrono::{DateTime, Utc};
 use itertools::izip;
+use log::warn;
 
 use ndarray::{azip, Array1, Zip};
 // use png::text_metadata;  // REMOVED
 use risico::{
     constants::NODATAVAL,
-    models::input::{Input, InputElement, InputVariableName::*},
+    models::input::{Input, InputElement, InputVariableName, InputVariableName::*},
+    modules::risico::config::RequiredInput,
 };
 
+use serde_derive::{Deserialize, Serialize};
+
+use crate::common::io::models::grid::InterpolationMode;
 use crate::common::io::readers::prelude::InputHandler;
 
+/// Options applied to the input data at ingestion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputOptions {
+    /// treat relative humidity values below or equal to this threshold as missing [%]
+    /// (e.g. 0.0 to discard exact zeros written by some stations as fill values)
+    #[serde(default)]
+    pub humidity_zero_as_missing: Option<f32>,
+    /// fail on cells or grid points with non-finite coordinates, instead of skipping them
+    #[serde(default)]
+    pub strict_coordinates: bool,
+    /// use the relative humidity computed from the dew point temperature, when available,
+    /// instead of the provided relative humidity
+    #[serde(default)]
+    pub humidity_from_dew_point: bool,
+    /// source of the relative humidity, the dew point temperature and the VPD (default: the
+    /// dew point if available, then the relative humidity, then the specific humidity)
+    #[serde(default)]
+    pub humidity_source: Option<HumiditySource>,
+    /// directory where the cell to source grid index mappings are cached and reused
+    /// by the runs with the same cells and grid geometry
+    #[serde(default)]
+    pub index_cache_path: Option<String>,
+    /// extensions of the files accepted from the binary input list (default: zbin)
+    #[serde(default)]
+    pub binary_extensions: Vec<String>,
+    /// offsets [s] applied to the dates of the binary input files, by variable or grid name
+    /// (the variable offset wins), as the `offset` of the NetCDF variable map
+    #[serde(default)]
+    pub binary_offsets: HashMap<String, i64>,
+    /// surface pressure used with the specific humidity when PSFC is missing
+    /// (default: DEFAULT_SURFACE_PRESSURE) [hPa]
+    #[serde(default)]
+    pub constant_surface_pressure: Option<f32>,
+    /// interpolation of the source grid values on the cells (default: Nearest)
+    #[serde(default)]
+    pub interpolation: InterpolationMode,
+}
+
+/// Input from which the relative humidity, the dew point temperature and the VPD are derived,
+/// the other humidity inputs are ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HumiditySource {
+    /// the relative humidity (F, H), the dew point temperature and the VPD are derived from H
+    #[serde(alias = "relative")]
+    Relative,
+    /// the dew point temperature (R) with the temperature, overriding the relative humidity
+    #[serde(alias = "dewpoint")]
+    Dewpoint,
+    /// the specific humidity (Q) with the surface pressure (PSFC), overriding the relative humidity
+    #[serde(alias = "specific")]
+    Specific,
+}
+
+/// Standard surface pressure, used when the specific humidity comes without surface pressure [hPa]
+pub const DEFAULT_SURFACE_PRESSURE: f32 = 1013.25;
+
+// the fallback to the constant surface pressure is reported only once per run
+static SURFACE_PRESSURE_FALLBACK: Once = Once::new();
+
+/// Check the coordinates for non-finite values (e.g. NaN in masked grids).
+/// Returns an error in strict mode, otherwise warns and leaves the points to be skipped.
+pub fn check_coordinates(
+    source: &str,
+    lats: &[f32],
+    lons: &[f32],
+    strict: bool,
+) -> Result<(), RISICOError> {
+    let invalid = izip!(lats, lons)
+        .filter(|(lat, lon)| !lat.is_finite() || !lon.is_finite())
+        .count();
+    if invalid == 0 {
+        return Ok(());
+    }
+    if strict {
+        return Err(format!("{invalid} points with non-finite coordinates in {source}").into());
+    }
+    warn!("Skipping {invalid} points with non-finite coordinates in {source}");
+    Ok(())
+}
+
+/// Set to NODATAVAL the relative humidity values below or equal to the threshold
+fn mask_humidity(humidity: &mut Option<Array1<f32>>, threshold: Option<f32>) {
+    if let (Some(h), Some(threshold)) = (humidity.as_mut(), threshold) {
+        h.mapv_inplace(|_h| if _h > (NODATAVAL + 1.0) && _h <= threshold { NODATAVAL } else { _h });
+    }
+}
+
 fn replace<'a>(
     dst: &'a mut Array1<InputElement>,
     src: &Array1<f32>,
@@ -25,6 +122,19 @@ fn replace<'a>(
     });
 }
 
+/// Set the destination values where the source values are valid
+fn overwrite<'a>(
+    dst: &'a mut Array1<InputElement>,
+    src: &Array1<f32>,
+    fun: fn(&'a mut InputElement) -> &'a mut f32,
+) {
+    Zip::from(dst).and(src).par_for_each(|d, s| {
+        if *s > (NODATAVAL + 1.0) {
+            *fun(d) = *s;
+        }
+    });
+}
+
 fn maybe_replace<'a>(
     dst: &'a mut Array1<InputElement>,
     src: &Option<Array1<f32>>,
@@ -35,9 +145,141 @@ fn maybe_replace<'a>(
     }
 }
 
+/// Groups of input variables from which get_input obtains the required input,
+/// any complete group is enough
+pub fn required_input_sources(required: RequiredInput) -> &'static [&'static [InputVariableName]] {
+    match required {
+        RequiredInput::Temperature => &[&[T], &[K]],
+        // PSFC is optional with Q, a constant surface pressure is used if missing
+        RequiredInput::Humidity => &[&[H], &[F], &[R], &[Q]],
+        RequiredInput::WindSpeed => &[&[W], &[U, V]],
+        RequiredInput::WindDir => &[&[D], &[U, V]],
+        RequiredInput::Rain => &[&[P], &[O]],
+    }
+}
+
+/// Check if the required input can be obtained from the available variables
+pub fn is_required_input_available(
+    required: RequiredInput,
+    variables: &HashSet<InputVariableName>,
+) -> bool {
+    required_input_sources(required)
+        .iter()
+        .any(|group| group.iter().all(|var| variables.contains(var)))
+}
+
+/// Relative humidity [%] and vapor pressure deficit [hPa] from the temperature and
+/// the dew point temperature [°C]
+fn humidity_from_dew_point(t: &Array1<f32>, td: &Array1<f32>) -> (Array1<f32>, Array1<f32>) {
+    let len = t.len();
+    let mut h: Array1<f32> = Array1::ones(len) * NODATAVAL;  // [%]
+    let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // [hPa]
+    azip!((
+        h in &mut h,  // %
+        v in &mut vpd,  // hPa
+        r in td,  // °C
+        t in t  // °C
+    ){
+        if *r > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) {
+            // compute the relative humidity > https://cran.r-project.org/web/packages/humidity/vignettes/humidity-measures.html
+            // August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Dew_point
+            let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
+            let e = 6.1094 * f32::exp((17.625 * r)/(r + 243.04));  // vapor pressure [hPa] > computed substituting the dew point temperature
+            *h = 100.0 * (e / es);  // relative humidity [%]
+            if *h > 100.0 {  // clip to 100%
+                *h = 100.0;
+            }
+            // compute the vapor pressure deficit
+            *v = es - e;  // difference between saturation vapor pressure and actual vapor pressure [hPa]
+            if *v < 0.0 {  // clip to 0
+                *v = 0.0;
+            }
+        }
+    });
+    (h, vpd)
+}
+
+/// Dew point temperature [°C] and vapor pressure deficit [hPa] from the temperature [°C]
+/// and the relative humidity [%]
+fn dew_point_from_humidity(t: &Array1<f32>, h: &Array1<f32>) -> (Array1<f32>, Array1<f32>) {
+    let len = t.len();
+    let mut td: Array1<f32> = Array1::ones(len) * NODATAVAL;  // °C
+    let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // hPa
+    azip!((
+        r in &mut td,  // °C
+        v in &mut vpd,  // hPa
+        h in h,  // %
+        t in t  // °C
+    ){
+        if *h > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) {
+            let mut h = *h;
+            if h > 100.0 {  // clip to 100%
+                h = 100.0;
+            }
+            // compute dew point temperature from Magnus formula (https://en.wikipedia.org/wiki/Dew_point)
+            let gamma = f32::ln(h / 100.0) + ((17.625 * t) / (t + 243.04));
+            *r = (243.04 * gamma) / (17.625 - gamma);
+            // compute the vapor pressure deficit [hPa]
+            // August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Clausius%E2%80%93Clapeyron_relation#August%E2%80%93Roche%E2%80%93Magnus_approximation
+            let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
+            // compute vapor pressure from relative humidity
+            let e = (h / 100.0) * es;  // vapor pressure [hPa]
+            // difference between saturation vapor pressure and actual vapor pressure [hPa]
+            *v = es - e;
+            if *v < 0.0 {  // clip to 0
+                *v = 0.0;
+            }
+        }
+    });
+    (td, vpd)
+}
+
+/// Relative humidity [%] and vapor pressure deficit [hPa] from the temperature [°C],
+/// the specific humidity [kg/kg] and the surface pressure [Pa]
+fn humidity_from_specific(
+    t: &Array1<f32>,
+    q: &Array1<f32>,
+    psfc: &Array1<f32>,
+) -> (Array1<f32>, Array1<f32>) {
+    let len = t.len();
+    let mut h: Array1<f32> = Array1::ones(len) * NODATAVAL;  // %
+    let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // hPa
+    azip!((
+        h in &mut h,  // %
+        v in &mut vpd,  // hPa
+        q in q, // kg/kg
+        p in psfc, // Pa
+        t in t // °C
+    ){
+        if *q > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) && *p > (NODATAVAL+1.0) {
+            // T_C=temperature in °C; P_hPa=pressure in hPa; Q2=specific humidity at 2m
+            // vapor pressure: e=(Q2*P_hPa/(0.622+0.378*Q2)) > https://cran.r-project.org/web/packages/humidity/vignettes/humidity-measures.html
+            // saturation vapor pressure: es=6.1094*exp((17.625*T_C)/(T_C+243.04)) > August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Clausius%E2%80%93Clapeyron_relation#August%E2%80%93Roche%E2%80%93Magnus_approximation
+            // RH=(e/es)*100;
+            let e = q * (p/100.0) / (0.622 + 0.378*q);  // vapor pressure [hPa]
+            let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
+            *h = 100.0 * e / es;
+            if *h > 100.0 {
+                *h = 100.0;
+            }
+            // compute the vapor pressure deficit
+            *v = es - e;  // difference between saturation vapor pressure and actual vapor pressure [hPa]
+            if *v < 0.0 {  // clip to 0
+                *v = 0.0;
+            }
+        }
+    });
+    (h, vpd)
+}
+
 /// Get the input data from the input handler and dave in the Input struct
 /// If the input data are not in the expected units, the function will convert them
-pub fn get_input(handler: &dyn InputHandler, time: &DateTime<Utc>, len: usize) -> Input {
+pub fn get_input(
+    handler: &dyn InputHandler,
+    time: &DateTime<Utc>,
+    len: usize,
+    options: &InputOptions,
+) -> Input {
     let mut data: Array1<InputElement> = Array1::default(len);
 
     // Observed temperature
@@ -48,11 +290,13 @@ pub fn get_input(handler: &dyn InputHandler, time: &DateTime<Utc>, len: usize) -
     }
 
     // Observed relative humidity
-    let humidity_obs = handler.get_values(F, time); // supposed in %
+    let mut humidity_obs = handler.get_values(F, time); // supposed in %
+    mask_humidity(&mut humidity_obs, options.humidity_zero_as_missing);
     maybe_replace(&mut data, &humidity_obs, |i| &mut i.humidity); // save observed relative humidity if any [%]
 
     // Forecasted relative humidity
-    let humidity = handler.get_values(H, time); // supposed in %
+    let mut humidity = handler.get_values(H, time); // supposed in %
+    mask_humidity(&mut humidity, options.humidity_zero_as_missing);
     maybe_replace(&mut data, &humidity, |i| &mut i.humidity); // save forecasted relative humidity if any [%]
 
     // Forecasted temperature
@@ -61,143 +305,97 @@ pub fn get_input(handler: &dyn InputHandler, time: &DateTime<Utc>, len: usize) -
         t.mapv_inplace(|_t| if _t <= (NODATAVAL + 1.0) { NODATAVAL } else if _t > 200.0 { _t - 273.15 } else { _t }); // conversion to Celsius
         replace(&mut data, &t, |i| &mut i.temperature); // save forecasted temperature [°C]
 
-        // Forecasted dew point temperature
-        let temp_dew = handler.get_values(R, time);  // supposed in K or °C
-        if let Some(mut td) = temp_dew {
-            // if the dew point temperature is available
-            td.mapv_inplace(|_t| if _t <= (NODATAVAL + 1.0) { NODATAVAL } else if _t > 200.0 { _t - 273.15 } else { _t }); // conversion to Celsius
-            replace(&mut data, &td, |i| &mut i.temp_dew);  // save dew point temperature [°C]
-
-            // computation of the relative humidity and VPD from the forecasted temperature and dew point temperature
-            let mut h: Array1<f32> = Array1::ones(len) * NODATAVAL;  // [%]
-            let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // [hPa]
-            azip!((
-                h in &mut h,  // %
-                v in &mut vpd,  // hPa
-                r in &td,  // °C
-                t in &t  // °C
-            ){
-                if *r > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) {
-                    // compute the relative humidity > https://cran.r-project.org/web/packages/humidity/vignettes/humidity-measures.html
-                    // August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Dew_point
-                    let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
-                    let e = 6.1094 * f32::exp((17.625 * r)/(r + 243.04));  // vapor pressure [hPa] > computed substituting the dew point temperature
-                    *h = 100.0 * (e / es);  // relative humidity [%]
-                    if *h > 100.0 {  // clip to 100%
-                        *h = 100.0;
-                    }
-                    // compute the vapor pressure deficit
-                    *v = es - e;  // difference between saturation vapor pressure and actual vapor pressure [hPa]
-                    if *v < 0.0 {  // clip to 0
-                        *v = 0.0;
-                    }
-                }
-            });
-            replace(&mut data, &h, |i| &mut i.humidity);  // replace the humidity values [%]
-            replace(&mut data, &vpd, |i| &mut i.vpd);  // save vapor pressure deficit [hPa]
-        
+        // Forecasted dew point temperature, read only if it can be the humidity source
+        let temp_dew = match options.humidity_source {
+            None | Some(HumiditySource::Dewpoint) => handler.get_values(R, time),  // supposed in K or °C
+            Some(_) => None,
+        };
+        // without a configured source: the dew point, then the relative humidity,
+        // then the specific humidity
+        let source = options.humidity_source.unwrap_or(if temp_dew.is_some() {
+            HumiditySource::Dewpoint
+        } else if humidity.is_some() {
+            HumiditySource::Relative
         } else {
-            // if the dew point temperature is not available, you need the relative humidity
-            // or you need to compute it from specific humidity and surface pressure
-
-            if let Some(h) = humidity {
-                // there is the relative humidity data
-
-                // compute the temperature dew point from the temperature and relative humidity
-                let mut td: Array1<f32> = Array1::ones(len) * NODATAVAL;  // °C
-                let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // hPa
-                azip!((
-                    r in &mut td,  // °C
-                    v in &mut vpd,  // hPa
-                    h in &h,  // %
-                    t in &t  // °C
-                ){
-                    if *h > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) {
-                        let mut h = *h;
-                        if h > 100.0 {  // clip to 100%
-                            h = 100.0;
-                        }
-                        // compute dew point temperature from Magnus formula (https://en.wikipedia.org/wiki/Dew_point)
-                        let gamma = f32::ln(h / 100.0) + ((17.625 * t) / (t + 243.04));
-                        *r = (243.04 * gamma) / (17.625 - gamma);
-                        // compute the vapor pressure deficit [hPa]
-                        // August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Clausius%E2%80%93Clapeyron_relation#August%E2%80%93Roche%E2%80%93Magnus_approximation
-                        let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
-                        // compute vapor pressure from relative humidity
-                        let e = (h / 100.0) * es;  // vapor pressure [hPa]
-                        // difference between saturation vapor pressure and actual vapor pressure [hPa]
-                        *v = es - e;
-                        if *v < 0.0 {  // clip to 0
-                            *v = 0.0;
-                        }
+            HumiditySource::Specific
+        });
+        // a configured source overrides the provided relative humidity
+        let override_humidity = options.humidity_source.is_some()
+            || (source == HumiditySource::Dewpoint && options.humidity_from_dew_point);
+
+        match source {
+            HumiditySource::Dewpoint => {
+                if let Some(mut td) = temp_dew {
+                    td.mapv_inplace(|_t| if _t <= (NODATAVAL + 1.0) { NODATAVAL } else if _t > 200.0 { _t - 273.15 } else { _t }); // conversion to Celsius
+                    replace(&mut data, &td, |i| &mut i.temp_dew);  // save dew point temperature [°C]
+
+                    let (h, vpd) = humidity_from_dew_point(&t, &td);
+                    if override_humidity {
+                        overwrite(&mut data, &h, |i| &mut i.humidity);  // override the provided humidity [%]
+                    } else {
+                        replace(&mut data, &h, |i| &mut i.humidity);  // replace the humidity values [%]
                     }
-                });
-                replace(&mut data, &td, |i| &mut i.temp_dew);
-                replace(&mut data, &vpd, |i| &mut i.vpd);
-              
-            } else {
-                // compute the relative humidity from specific humidity and surface pressure forecasted surface pressure
-
+                    replace(&mut data, &vpd, |i| &mut i.vpd);  // save vapor pressure deficit [hPa]
+                }
+            }
+            HumiditySource::Relative => {
+                if let Some(h) = &humidity {
+                    // the dew point temperature and VPD from the provided relative humidity
+                    let (td, vpd) = dew_point_from_humidity(&t, h);
+                    replace(&mut data, &td, |i| &mut i.temp_dew);
+                    replace(&mut data, &vpd, |i| &mut i.vpd);
+                }
+            }
+            HumiditySource::Specific => {
                 // forecasted surface pressure
-                let psfc = handler.get_values(PSFC, time); // supposed in Pa
+                let mut psfc = handler.get_values(PSFC, time); // supposed in Pa
                 // forecasted specific humidity
                 let q = handler.get_values(Q, time); // supposed in kg/kg
 
-                if let (Some(psfc), Some(q)) = (psfc, q) {
-                    // compute the relative humidity from the forecasted temperature, surface pressure and specific humidity
-                    let mut h: Array1<f32> = Array1::ones(len) * NODATAVAL;  // %
-                    let mut vpd: Array1<f32> = Array1::ones(len) * NODATAVAL;  // hPa
-                    azip!((
-                        h in &mut h,  // %
-                        v in &mut vpd,  // hPa
-                        q in &q, // kg/kg
-                        p in &psfc, // Pa
-                        t in &t // °C
-                    ){
-                        if *q > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) && *p > (NODATAVAL+1.0) {
-                            // T_C=temperature in °C; P_hPa=pressure in hPa; Q2=specific humidity at 2m
-                            // vapor pressure: e=(Q2*P_hPa/(0.622+0.378*Q2)) > https://cran.r-project.org/web/packages/humidity/vignettes/humidity-measures.html
-                            // saturation vapor pressure: es=6.1094*exp((17.625*T_C)/(T_C+243.04)) > August–Roche–Magnus formula > https://en.wikipedia.org/wiki/Clausius%E2%80%93Clapeyron_relation#August%E2%80%93Roche%E2%80%93Magnus_approximation
-                            // RH=(e/es)*100;
-                            let e = q * (p/100.0) / (0.622 + 0.378*q);  // vapor pressure [hPa]
-                            let es = 6.1094 * f32::exp((17.625 * t)/(t + 243.04));  // saturation vapor pressure [hPa]
-                            *h = 100.0 * e / es;
-                            if *h > 100.0 {
-                                *h = 100.0;
-                            }
-                            // compute the vapor pressure deficit
-                            *v = es - e;  // difference between saturation vapor pressure and actual vapor pressure [hPa]
-                            if *v < 0.0 {  // clip to 0
-                                *v = 0.0;
-                            }
-                        }
+                if psfc.is_none() && q.is_some() {
+                    // standard pressure estimate, better than no humidity update
+                    let pressure = options
+                        .constant_surface_pressure
+                        .unwrap_or(DEFAULT_SURFACE_PRESSURE);
+                    SURFACE_PRESSURE_FALLBACK.call_once(|| {
+                        warn!(
+                            "Surface pressure (PSFC) not available, computing the relative humidity from Q with a constant surface pressure of {pressure} hPa"
+                        );
                     });
-                    replace(&mut data, &h, |i| &mut i.humidity);
+                    psfc = Some(Array1::from_elem(len, pressure * 100.0));  // [Pa]
+                }
+
+                if let (Some(psfc), Some(q)) = (psfc, q) {
+                    let (h, vpd) = humidity_from_specific(&t, &q, &psfc);
+                    if override_humidity {
+                        overwrite(&mut data, &h, |i| &mut i.humidity);
+                    } else {
+                        replace(&mut data, &h, |i| &mut i.humidity);
+                    }
                     replace(&mut data, &vpd, |i| &mut i.vpd);
-                
-                    // compute the dew point temperature wiht the new computed relative humidity
-                    let mut td: Array1<f32> = Array1::ones(len) * NODATAVAL;  // [°C]
-                    azip!((
-                        r in &mut td,  // °C
-                        h in &h, // %
-                        t in &t // °C
-                    ){
-                        if *h > (NODATAVAL+1.0) && *t > (NODATAVAL+1.0) {
-                            let mut h = *h;
-                            if h > 100.0 {
-                                h = 100.0;
-                            }
-                            // computed from Magnus formula (https://en.wikipedia.org/wiki/Dew_point)
-                            let gamma = f32::ln(h / 100.0) + ((17.625 * t) / (t + 243.04));
-                            *r = (243.04 * gamma) / (17.625 - gamma);
-                        }
-                    });
-                    replace(&mut data, &td, |i| &mut i.temp_dew);           
+
+                    // compute the dew point temperature with the new computed relative humidity
+                    let (td, _) = dew_point_from_humidity(&t, &h);
+                    replace(&mut data, &td, |i| &mut i.temp_dew);
                 }
             }
         }
     }
 
+    // upper-air temperatures and dew point temperatures (Haines index)
+    let upper_air: [(InputVariableName, fn(&mut InputElement) -> &mut f32); 4] = [
+        (TL, |i| &mut i.temp_lower),
+        (TU, |i| &mut i.temp_upper),
+        (RL, |i| &mut i.temp_dew_lower),
+        (RU, |i| &mut i.temp_dew_upper),
+    ];
+    for (var, field) in upper_air {
+        if let Some(mut t) = handler.get_values(var, time) {  // supposed in K or °C
+            t.mapv_inplace(|_t| if _t <= (NODATAVAL + 1.0) { NODATAVAL } else if _t > 200.0 { _t - 273.15 } else { _t }); // conversion to Celsius
+            replace(&mut data, &t, field);
+        }
+    }
+
     // wind speed and wind direction
     let ws = handler.get_values(W, time); // supposed in m/s
     let wd = handler.get_values(D, time); // supposed in degree with meteorological convenction (wind from, 0=from North)
@@ -253,6 +451,18 @@ pub fn get_input(handler: &dyn InputHandler, time: &DateTime<Utc>, len: usize) -
         replace(&mut data, &ws, |i| &mut i.wind_speed);
     }
 
+    // wind gust
+    if let Some(wg) = handler.get_values(WG, time) {  // supposed in m/s
+        let wg = wg.mapv(|_wg| {
+            if _wg <= (NODATAVAL + 1.0) {
+                NODATAVAL
+            } else {
+                _wg * 3600.0 // conversion to m/h
+            }
+        });
+        replace(&mut data, &wg, |i| &mut i.wind_gust);
+    }
+
     // Observed precipitation
     let op = handler.get_values(O, time); // supposed in mm
     maybe_replace(&mut data, &op, |i| &mut i.rain);
@@ -279,6 +489,11 @@ pub fn get_input(handler: &dyn InputHandler, time: &DateTime<Utc>, len: usize) -
     let msi = handler.get_values(M, time);
     maybe_replace(&mut data, &msi, |i| &mut i.msi);
 
+    // FUEL VARIABLES
+
+    let fuel_load = handler.get_values(FUEL, time); // supposed in kg/m²
+    maybe_replace(&mut data, &fuel_load, |i| &mut i.fuel_load);
+
     Input {
         time: time.to_owned(),
         data,
@@ -313,3 +528,77 @@ impl Display for RISICOError {
         write!(f, "{}", self.msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use ndarray::array;
+
+    use super::*;
+
+    /// Input handler with the same values of the variables at any date
+    struct ConstantInput(HashMap<InputVariableName, Array1<f32>>);
+
+    impl InputHandler for ConstantInput {
+        fn set_coordinates(
+            &mut self,
+            _lats: &[f32],
+            _lons: &[f32],
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn get_values(
+            &self,
+            var: InputVariableName,
+            _date: &DateTime<Utc>,
+        ) -> Option<Array1<f32>> {
+            self.0.get(&var).cloned()
+        }
+
+        fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+            Vec::new()
+        }
+
+        fn get_variables(&self) -> HashSet<InputVariableName> {
+            self.0.keys().copied().collect()
+        }
+
+        fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+            self.0.keys().map(|var| (*var, 1)).collect()
+        }
+
+        fn info_input(&self, _verbose: bool) -> String {
+            String::new()
+        }
+    }
+
+    fn humidity(options: &InputOptions) -> Vec<f32> {
+        // 20 °C with a dew point of 10 °C, about 52.5% of relative humidity
+        let handler = ConstantInput(HashMap::from([
+            (T, array![20.0, 20.0]),
+            (R, array![10.0, NODATAVAL]),
+            (H, array![80.0, 80.0]),
+        ]));
+        let time = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let input = get_input(&handler, &time, 2, options);
+        input.data.iter().map(|element| element.humidity).collect()
+    }
+
+    #[test]
+    fn provided_humidity_is_kept_by_default() {
+        assert_eq!(humidity(&InputOptions::default()), vec![80.0, 80.0]);
+    }
+
+    #[test]
+    fn humidity_from_dew_point_overrides_the_provided_humidity() {
+        let options = InputOptions {
+            humidity_from_dew_point: true,
+            ..InputOptions::default()
+        };
+        let h = humidity(&options);
+        assert!((h[0] - 52.5).abs() < 0.5, "{}", h[0]);
+        // the provided humidity is kept where the dew point is missing
+        assert_eq!(h[1], 80.0);
+    }
+}
@@ -1,27 +1,176 @@
 use std::fmt::Debug;
+use std::str::FromStr;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use itertools::izip;
 use ndarray::Array1;
+use rayon::prelude::*;
 use rstar::{primitives::GeomWithData, RTree};
 use serde_derive::{Deserialize, Serialize};
 
 use strum_macros::{Display, EnumString};
 
+use risico::constants::NODATAVAL;
+
 use crate::common::{config::builder::read_config, helpers::RISICOError};
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, EnumString, Display, Serialize, Deserialize)]
-#[strum(ascii_case_insensitive)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum ClusterMode {
     Mean,
     Median,
     Min,
     Max,
+    /// percentile of the values in the grid cell [%], written as p<percentile> (e.g. p90)
+    Percentile(u8),
+}
+
+impl ClusterMode {
+    /// Percentile of the values taken in the grid cell, for the modes based on the ordered values
+    pub fn percentile(&self) -> Option<f32> {
+        match self {
+            ClusterMode::Median => Some(50.0),
+            ClusterMode::Percentile(percentile) => Some(*percentile as f32),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ClusterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mean" => Ok(ClusterMode::Mean),
+            "median" => Ok(ClusterMode::Median),
+            "min" => Ok(ClusterMode::Min),
+            "max" => Ok(ClusterMode::Max),
+            mode => match mode.strip_prefix('p').map(|p| p.parse::<u8>()) {
+                Some(Ok(percentile)) if percentile <= 100 => Ok(ClusterMode::Percentile(percentile)),
+                _ => Err(format!("Invalid cluster mode {s}")),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for ClusterMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ClusterMode::from_str(&value)
+    }
+}
+
+impl std::fmt::Display for ClusterMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClusterMode::Mean => write!(f, "Mean"),
+            ClusterMode::Median => write!(f, "Median"),
+            ClusterMode::Min => write!(f, "Min"),
+            ClusterMode::Max => write!(f, "Max"),
+            ClusterMode::Percentile(percentile) => write!(f, "p{percentile}"),
+        }
+    }
+}
+
+impl From<ClusterMode> for String {
+    fn from(value: ClusterMode) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, EnumString, Display, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum SmoothingMode {
+    Mean,
+    Median,
 }
 
 pub trait Grid {
     fn index(&self, lat: &f32, lon: &f32) -> Option<usize>;
     fn shape(&self) -> (usize, usize);
-    fn indexes(&mut self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>>;
+    fn indexes(&self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>>;
+    /// Hash of the grid geometry, used to validate the cached index mappings
+    fn geometry_hash(&self) -> u64;
+    /// Bilinear interpolation weights of the grid points around each point,
+    /// None if the grid does not support the interpolation
+    fn interpolation_weights(&self, _lats: &[f32], _lons: &[f32]) -> Option<Array1<CellWeights>> {
+        None
+    }
+}
+
+/// Interpolation of the source grid values on the cells
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, EnumString, Display, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum InterpolationMode {
+    /// value of the nearest grid point
+    #[default]
+    Nearest,
+    /// weighted average of the four grid points around the cell (regular and rectilinear grids)
+    Bilinear,
+}
+
+/// Grid points and weights of a cell, empty for the cells outside the grid
+pub type CellWeights = Vec<(usize, f32)>;
+
+/// Weighted average of the grid values, skipping the NODATAVAL points and renormalizing the weights
+pub fn interpolate(values: &[f32], weights: &[(usize, f32)]) -> f32 {
+    let (sum, total) = weights
+        .iter()
+        .filter_map(|(idx, w)| values.get(*idx).map(|v| (*v, *w)))
+        .filter(|(v, _)| *v > NODATAVAL + 1.0)
+        .fold((0.0, 0.0), |(sum, total), (v, w)| (sum + v * w, total + w));
+    if total <= 0.0 {
+        return NODATAVAL;
+    }
+    sum / total
+}
+
+/// Bilinear weights from the bracketing rows and columns (first index and fraction towards the next),
+/// the nearest grid point for the cells on the border, outside the bracketed range
+fn bilinear_weights(
+    row: Option<(usize, f32)>,
+    col: Option<(usize, f32)>,
+    ncols: usize,
+    nearest: Option<usize>,
+) -> CellWeights {
+    let (Some((i, wi)), Some((j, wj))) = (row, col) else {
+        return nearest.map(|idx| vec![(idx, 1.0)]).unwrap_or_default();
+    };
+    [
+        (i * ncols + j, (1.0 - wi) * (1.0 - wj)),
+        (i * ncols + j + 1, (1.0 - wi) * wj),
+        ((i + 1) * ncols + j, wi * (1.0 - wj)),
+        ((i + 1) * ncols + j + 1, wi * wj),
+    ]
+    .into_iter()
+    .filter(|(_, w)| *w > 0.0)
+    .collect()
+}
+
+/// Bracketing interval of an evenly spaced coordinate, as fractional position `pos` in [0, n-1],
+/// None if the coordinate has less than two values
+fn bracket_position(pos: f32, n: usize) -> Option<(usize, f32)> {
+    if n < 2 || !(0.0..=(n - 1) as f32).contains(&pos) {
+        return None;
+    }
+    let i = (pos.floor() as usize).min(n - 2);
+    Some((i, pos - i as f32))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Hash of the coordinates (FNV-1a), stable across runs and platforms
+pub fn hash_coordinates(lats: &[f32], lons: &[f32]) -> u64 {
+    (lats.len() as u64)
+        .to_le_bytes()
+        .into_iter()
+        .chain(lats.iter().chain(lons).flat_map(|v| v.to_le_bytes()))
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
 }
 
 impl Debug for dyn Grid {
@@ -85,6 +234,17 @@ impl RegularGrid {
     //     indexes
     // }
 
+    /// Get the indexes of the grid points within `radius` rows and columns from `idx` (included)
+    pub fn neighbours(&self, idx: usize, radius: usize) -> Vec<usize> {
+        let i = idx / self.ncols;
+        let j = idx % self.ncols;
+        let (min_i, max_i) = (i.saturating_sub(radius), usize::min(i + radius, self.nrows - 1));
+        let (min_j, max_j) = (j.saturating_sub(radius), usize::min(j + radius, self.ncols - 1));
+        (min_i..=max_i)
+            .flat_map(|ii| (min_j..=max_j).map(move |jj| ii * self.ncols + jj))
+            .collect()
+    }
+
     pub fn from_txt_file(grid_file: &str) -> Result<RegularGrid, RISICOError> {
         // read the file as text
         let config_map = read_config(grid_file)?;
@@ -140,6 +300,9 @@ impl RegularGrid {
 
 impl Grid for RegularGrid {
     fn index(&self, lat: &f32, lon: &f32) -> Option<usize> {
+        if !lat.is_finite() || !lon.is_finite() {
+            return None;
+        }
         if lat < &(self.min_lat - self.step_lat / 2.0)
             || lat > &(self.max_lat + self.step_lat / 2.0)
             || lon < &(self.min_lon - self.step_lon / 2.0)
@@ -159,11 +322,150 @@ impl Grid for RegularGrid {
         (self.nrows, self.ncols)
     }
 
-    fn indexes(&mut self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>> {
+    fn indexes(&self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>> {
         izip!(lats, lons)
             .map(|(lat, lon)| self.index(lat, lon))
             .collect::<Array1<_>>()
     }
+
+    fn geometry_hash(&self) -> u64 {
+        hash_coordinates(
+            &[self.nrows as f32, self.min_lat, self.max_lat],
+            &[self.ncols as f32, self.min_lon, self.max_lon],
+        )
+    }
+
+    fn interpolation_weights(&self, lats: &[f32], lons: &[f32]) -> Option<Array1<CellWeights>> {
+        let weights = izip!(lats, lons)
+            .map(|(lat, lon)| {
+                if !lat.is_finite() || !lon.is_finite() {
+                    return Vec::new();
+                }
+                let row = bracket_position((lat - self.min_lat) / self.step_lat, self.nrows);
+                let col = bracket_position((lon - self.min_lon) / self.step_lon, self.ncols);
+                bilinear_weights(row, col, self.ncols, self.index(lat, lon))
+            })
+            .collect();
+        Some(weights)
+    }
+}
+
+/// Grid defined by 1-D latitude and longitude coordinate vectors, not necessarily evenly spaced
+/// (values are stored by rows of latitude)
+#[derive(Debug, Clone)]
+pub struct RectilinearGrid {
+    lats: Vec<f32>,
+    lons: Vec<f32>,
+}
+
+impl RectilinearGrid {
+    /// Create the grid from monotonic coordinate vectors (ascending or descending)
+    pub fn new(lats: Vec<f32>, lons: Vec<f32>) -> Result<RectilinearGrid, RISICOError> {
+        for (name, coords) in [("latitude", &lats), ("longitude", &lons)] {
+            if coords.len() < 2 {
+                return Err(format!("The {name} vector must have at least 2 values").into());
+            }
+            if coords.iter().any(|c| !c.is_finite()) {
+                return Err(format!("The {name} vector has non-finite values").into());
+            }
+            let ascending = coords.windows(2).all(|w| w[0] < w[1]);
+            let descending = coords.windows(2).all(|w| w[0] > w[1]);
+            if !ascending && !descending {
+                return Err(format!("The {name} vector is not strictly monotonic").into());
+            }
+        }
+        Ok(RectilinearGrid { lats, lons })
+    }
+}
+
+/// Index of the coordinate nearest to the value, None if it is more than half a step
+/// outside the coordinates
+fn nearest_coordinate(coords: &[f32], value: f32) -> Option<usize> {
+    let n = coords.len();
+    let ascending = coords[n - 1] > coords[0];
+    // first coordinate past the value
+    let pos = coords.partition_point(|c| if ascending { *c < value } else { *c > value });
+    let (idx, half_step) = match pos {
+        0 => (0, (coords[1] - coords[0]).abs() / 2.0),
+        p if p == n => (n - 1, (coords[n - 1] - coords[n - 2]).abs() / 2.0),
+        p => {
+            let idx = if (coords[p] - value).abs() < (value - coords[p - 1]).abs() {
+                p
+            } else {
+                p - 1
+            };
+            return Some(idx);
+        }
+    };
+    if (coords[idx] - value).abs() > half_step {
+        return None;
+    }
+    Some(idx)
+}
+
+/// Bracketing interval of the value in the monotonic coordinates (first index and fraction
+/// towards the next), None if the value is outside the coordinates or they have less than
+/// two values
+fn bracket_coordinate(coords: &[f32], value: f32) -> Option<(usize, f32)> {
+    let n = coords.len();
+    if n < 2 {
+        return None;
+    }
+    let ascending = coords[n - 1] > coords[0];
+    let pos = coords.partition_point(|c| if ascending { *c <= value } else { *c >= value });
+    match pos {
+        0 => None,
+        p if p == n => (coords[n - 1] == value).then_some((n - 2, 1.0)),
+        p => Some((p - 1, (value - coords[p - 1]) / (coords[p] - coords[p - 1]))),
+    }
+}
+
+impl Grid for RectilinearGrid {
+    fn index(&self, lat: &f32, lon: &f32) -> Option<usize> {
+        if !lat.is_finite() || !lon.is_finite() {
+            return None;
+        }
+        let i = nearest_coordinate(&self.lats, *lat)?;
+        // the longitudes can be given in [-180, 180] or [0, 360]
+        let j = [*lon, lon + 360.0, lon - 360.0]
+            .into_iter()
+            .find_map(|lon| nearest_coordinate(&self.lons, lon))?;
+        Some(i * self.lons.len() + j)
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        (self.lats.len(), self.lons.len())
+    }
+
+    fn indexes(&self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>> {
+        lats.par_iter()
+            .zip(lons)
+            .map(|(lat, lon)| self.index(lat, lon))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn geometry_hash(&self) -> u64 {
+        hash_coordinates(&self.lats, &self.lons)
+    }
+
+    fn interpolation_weights(&self, lats: &[f32], lons: &[f32]) -> Option<Array1<CellWeights>> {
+        let weights: Vec<CellWeights> = lats
+            .par_iter()
+            .zip(lons)
+            .map(|(lat, lon)| {
+                if !lat.is_finite() || !lon.is_finite() {
+                    return Vec::new();
+                }
+                let row = bracket_coordinate(&self.lats, *lat);
+                let col = [*lon, lon + 360.0, lon - 360.0]
+                    .into_iter()
+                    .find_map(|lon| bracket_coordinate(&self.lons, lon));
+                bilinear_weights(row, col, self.lons.len(), self.index(lat, lon))
+            })
+            .collect();
+        Some(weights.into())
+    }
 }
 
 #[derive(Debug)]
@@ -173,15 +475,22 @@ pub struct IrregularGrid {
     // pub lats: Array1<f32>,
     // pub lons: Array1<f32>,
     tree: RTree<PointWithIndex>,
+    geometry_hash: u64,
+    // cell adjacency for each number of neighbours, built only when requested
+    neighbourhoods: Mutex<HashMap<usize, Arc<CellNeighbourhood>>>,
 }
 
 impl IrregularGrid {
     pub fn new(nrows: usize, ncols: usize, lats: Array1<f32>, lons: Array1<f32>) -> IrregularGrid {
+        // points with non-finite coordinates are left out of the tree,
+        // they would otherwise corrupt the nearest neighbour search
         let points = izip!(&lats, &lons)
             .enumerate()
+            .filter(|(_, (lat, lon))| lat.is_finite() && lon.is_finite())
             .map(|(index, (lat, lon))| PointWithIndex::new([*lat, *lon], index))
             .collect::<Vec<_>>();
         let tree = RTree::bulk_load(points);
+        let geometry_hash = hash_coordinates(&lats.to_vec(), &lons.to_vec());
 
         IrregularGrid {
             nrows,
@@ -189,7 +498,61 @@ impl IrregularGrid {
             // lats,
             // lons,
             tree,
+            geometry_hash,
+            neighbourhoods: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the k-nearest-neighbour adjacency of the grid cells, built on the first use of k
+    pub fn neighbourhood(&self, k: usize) -> Arc<CellNeighbourhood> {
+        let mut neighbourhoods = self
+            .neighbourhoods
+            .lock()
+            .expect("Should lock the neighbourhoods");
+        neighbourhoods
+            .entry(k)
+            .or_insert_with(|| {
+                let n_cells = self.nrows * self.ncols;
+                Arc::new(CellNeighbourhood::from_tree(&self.tree, k, n_cells))
+            })
+            .clone()
+    }
+}
+
+/// k-nearest-neighbour adjacency over a set of irregular cells
+#[derive(Debug)]
+pub struct CellNeighbourhood {
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl CellNeighbourhood {
+    /// Adjacency of the n cells from the tree of their points,
+    /// the cells left out of the tree have no neighbours
+    fn from_tree(tree: &RTree<PointWithIndex>, k: usize, n_cells: usize) -> Self {
+        let points: Vec<&PointWithIndex> = tree.iter().collect();
+        let found: Vec<(usize, Vec<usize>)> = points
+            .par_iter()
+            .map(|point| {
+                let nearest = tree
+                    .nearest_neighbor_iter(point.geom())
+                    .filter(|p| p.data != point.data)
+                    .take(k)
+                    .map(|p| p.data)
+                    .collect();
+                (point.data, nearest)
+            })
+            .collect();
+
+        let mut neighbours = vec![Vec::new(); n_cells];
+        for (index, nearest) in found {
+            neighbours[index] = nearest;
         }
+        CellNeighbourhood { neighbours }
+    }
+
+    /// Indexes of the neighbours of the cell, sorted by distance (empty if out of range)
+    pub fn neighbours(&self, idx: usize) -> &[usize] {
+        self.neighbours.get(idx).map_or(&[], |n| n.as_slice())
     }
 }
 
@@ -197,6 +560,9 @@ type PointWithIndex = GeomWithData<[f32; 2], usize>;
 
 impl Grid for IrregularGrid {
     fn index(&self, lat: &f32, lon: &f32) -> Option<usize> {
+        if !lat.is_finite() || !lon.is_finite() {
+            return None;
+        }
         self.tree.nearest_neighbor(&[*lat, *lon]).map(|p| p.data)
     }
 
@@ -204,9 +570,66 @@ impl Grid for IrregularGrid {
         (self.nrows, self.ncols)
     }
 
-    fn indexes(&mut self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>> {
+    fn indexes(&self, lats: &[f32], lons: &[f32]) -> Array1<Option<usize>> {
         izip!(lats, lons)
             .map(|(lat, lon)| self.index(lat, lon))
             .collect::<Array1<_>>()
     }
+
+    fn geometry_hash(&self) -> u64 {
+        self.geometry_hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cells of a 3x3 regular layout, indexed by row
+    fn layout() -> IrregularGrid {
+        let lats = Array1::from_iter((0..9).map(|idx| (idx / 3) as f32));
+        let lons = Array1::from_iter((0..9).map(|idx| (idx % 3) as f32));
+        IrregularGrid::new(9, 1, lats, lons)
+    }
+
+    fn sorted(neighbours: &[usize]) -> Vec<usize> {
+        let mut neighbours = neighbours.to_vec();
+        neighbours.sort();
+        neighbours
+    }
+
+    #[test]
+    fn neighbours_of_a_regular_layout() {
+        let grid = layout();
+        let neighbourhood = grid.neighbourhood(4);
+        assert!((0..9).all(|idx| neighbourhood.neighbours(idx).len() == 4));
+        assert!(neighbourhood.neighbours(9).is_empty());
+        // the center has its four adjacent cells at distance 1
+        assert_eq!(sorted(neighbourhood.neighbours(4)), vec![1, 3, 5, 7]);
+
+        // a different k is built separately, the corner has two adjacent cells
+        let neighbourhood = grid.neighbourhood(2);
+        assert_eq!(sorted(neighbourhood.neighbours(0)), vec![1, 3]);
+        assert_eq!(sorted(neighbourhood.neighbours(8)), vec![5, 7]);
+        assert_eq!(grid.neighbourhood(4).neighbours(0).len(), 4);
+    }
+
+    #[test]
+    fn cells_without_coordinates_have_no_neighbours() {
+        let lats = Array1::from_vec(vec![0.0, 0.0, f32::NAN]);
+        let lons = Array1::from_vec(vec![0.0, 1.0, 2.0]);
+        let grid = IrregularGrid::new(3, 1, lats, lons);
+        let neighbourhood = grid.neighbourhood(1);
+        assert_eq!(neighbourhood.neighbours(0), &[1]);
+        assert!(neighbourhood.neighbours(2).is_empty());
+    }
+
+    #[test]
+    fn single_coordinates_have_no_bracket() {
+        assert_eq!(bracket_position(0.0, 1), None);
+        assert_eq!(bracket_position(0.5, 2), Some((0, 0.5)));
+        assert_eq!(bracket_coordinate(&[], 0.0), None);
+        assert_eq!(bracket_coordinate(&[1.0], 1.0), None);
+        assert_eq!(bracket_coordinate(&[1.0, 2.0], 2.0), Some((0, 1.0)));
+    }
 }
@@ -2,3 +2,4 @@ pub mod data;
 pub mod grid;
 pub mod output;
 pub mod palette;
+pub mod projection;
@@ -1,49 +1,338 @@
-use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
-use log::debug;
+use log::{debug, info, warn};
 use ndarray::{Array1, Zip};
 use netcdf::{extent::Extents, MutableFile};
 use rayon::prelude::*;
 use risico::{
     constants::NODATAVAL,
-    models::output::{Output, OutputVariableName},
+    models::input::InputVariableName,
+    models::output::{
+        cap_derived, to_percentiles, CellDistributions, Output, OutputVariableName,
+    },
 };
 use serde_derive::{Deserialize, Serialize};
+use strum::EnumProperty;
 
 #[cfg(feature = "gdal")]
-use crate::common::io::writers::write_to_geotiff;
+use crate::common::io::writers::{write_to_cog, write_to_geotiff};
+#[cfg(feature = "flatgeobuf")]
+use crate::common::io::writers::write_to_fgb;
 
 use crate::common::{
-    config::{builder::OutputTypeConfig, models::PaletteMap},
+    config::{
+        builder::{JsonlLayout, NetcdfAppend, OutputTypeConfig},
+        models::PaletteMap,
+    },
     helpers::RISICOError,
-    io::writers::{create_nc_file, write_to_pngwjson, write_to_zbin_file},
+    io::readers::distributions::read_distributions,
+    io::writers::{
+        create_nc_file, open_nc_file, write_to_csv, write_to_jsonl, write_to_pngwjson,
+        write_to_zbin_file,
+    },
 };
 
-use super::grid::{ClusterMode, Grid, RegularGrid};
+use super::grid::{CellNeighbourhood, ClusterMode, Grid, IrregularGrid, RegularGrid, SmoothingMode};
+#[cfg(feature = "gdal")]
+use super::projection::{transform_coordinates, EPSG_LAT_LON};
+use super::palette::Palette;
+
+/// Errors writing the outputs of a timestep, one entry per variable that was not written
+#[derive(Debug, Default)]
+pub struct WriteErrors {
+    pub failures: Vec<(OutputVariableName, RISICOError)>,
+}
+
+impl WriteErrors {
+    /// The same error for all the variables, for the files holding several variables
+    fn for_variables(variables: &[OutputVariable], msg: &str) -> Self {
+        WriteErrors {
+            failures: variables
+                .iter()
+                .map(|v| (v.internal_name, RISICOError::from(msg)))
+                .collect(),
+        }
+    }
+
+    /// Collect the failed results, given in the same order as the variables
+    fn from_results(variables: &[OutputVariable], results: Vec<Result<(), RISICOError>>) -> Self {
+        WriteErrors {
+            failures: variables
+                .iter()
+                .zip(results)
+                .filter_map(|(v, res)| res.err().map(|err| (v.internal_name, err)))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn extend(&mut self, other: WriteErrors) {
+        self.failures.extend(other.failures);
+    }
+
+    /// Ok if no variable failed
+    pub fn into_result(self) -> Result<(), WriteErrors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
 
-/// Extract error message generated from writing variables to files
-fn extract_errors(
-    error_message: &str,
-    results: Vec<Result<(), RISICOError>>,
-) -> Result<(), RISICOError> {
-    let error_messages: Vec<_> = results
+impl std::fmt::Display for WriteErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self
+            .failures
+            .iter()
+            .map(|(name, err)| format!("{name}: {err}"))
+            .collect();
+        write!(
+            f,
+            "{} variables not written: {}",
+            self.failures.len(),
+            messages.join("; ")
+        )
+    }
+}
+
+impl From<WriteErrors> for RISICOError {
+    fn from(value: WriteErrors) -> Self {
+        RISICOError::from(value.to_string())
+    }
+}
+
+/// Values of the output variables computed once per timestep and shared by the output types
+pub type OutputCache = HashMap<OutputVariableName, Array1<f32>>;
+
+/// Values of the variables on the cells, as (variable name, values) columns,
+/// shared by the writers of point outputs. The cells are indexed in `cells` on the first
+/// smoothed variable, to find their neighbours
+fn get_point_values(
+    output: &Output,
+    cache: &OutputCache,
+    variables: &[OutputVariable],
+    lats: &[f32],
+    lons: &[f32],
+    cells: &OnceLock<IrregularGrid>,
+) -> Vec<(String, Array1<f32>)> {
+    variables
+        .iter()
+        .filter_map(|variable| {
+            let cells = variable.smoothing.map(|_| {
+                cells.get_or_init(|| {
+                    IrregularGrid::new(lats.len(), 1, lats.to_vec().into(), lons.to_vec().into())
+                })
+            });
+            variable
+                .get_variable_on_points(output, cache, cells)
+                .map(|values| (variable.name.clone(), values))
+        })
+        .collect()
+}
+
+/// Number of decimals of each column of the point values, from the precision of its variable
+fn get_point_decimals(
+    columns: &[(String, Array1<f32>)],
+    variables: &[OutputVariable],
+) -> Vec<usize> {
+    columns
         .iter()
-        .filter_map(|r| match r {
-            Ok(_) => None,
-            Err(e) => Some(e.to_string()),
+        .map(|(name, _)| {
+            variables
+                .iter()
+                .find(|v| &v.name == name)
+                .map_or(0, |v| v.decimals())
+        })
+        .collect()
+}
+
+/// Aggregation of an output variable over the day (UTC), written once per day
+/// instead of at each output timestep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Aggregation {
+    /// maximum of the valid values of the day
+    #[serde(alias = "daily_max")]
+    DailyMax,
+    /// minimum of the valid values of the day
+    #[serde(alias = "daily_min")]
+    DailyMin,
+    /// mean of the valid values of the day
+    #[serde(alias = "daily_mean")]
+    DailyMean,
+}
+
+/// Values of the aggregated variables accumulated over a day
+struct DailyAccumulator {
+    day: NaiveDate,
+    // accumulated values and number of valid values of each cell
+    values: HashMap<(Aggregation, OutputVariableName), (Array1<f32>, Array1<u32>)>,
+}
+
+impl DailyAccumulator {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Add the values of a timestep, skipping the NODATAVAL values
+    fn add(&mut self, aggregation: Aggregation, name: OutputVariableName, values: &Array1<f32>) {
+        let (acc, counts) = self.values.entry((aggregation, name)).or_insert_with(|| {
+            (Array1::from_elem(values.len(), NODATAVAL), Array1::zeros(values.len()))
+        });
+        Zip::from(acc).and(counts).and(values).for_each(|acc, count, value| {
+            if *value == NODATAVAL {
+                return;
+            }
+            *acc = match (aggregation, *count) {
+                (_, 0) => *value,
+                (Aggregation::DailyMax, _) => acc.max(*value),
+                (Aggregation::DailyMin, _) => acc.min(*value),
+                (Aggregation::DailyMean, _) => *acc + *value,
+            };
+            *count += 1;
+        });
+    }
+
+    /// Aggregated values of the day, NODATAVAL for the cells without valid values
+    fn aggregated(&self, aggregation: Aggregation) -> OutputCache {
+        self.values
+            .iter()
+            .filter(|((agg, _), _)| *agg == aggregation)
+            .map(|((_, name), (acc, counts))| {
+                let mut values = acc.clone();
+                if aggregation == Aggregation::DailyMean {
+                    Zip::from(&mut values).and(counts).for_each(|v, count| {
+                        if *count > 0 {
+                            *v /= *count as f32;
+                        }
+                    });
+                }
+                (*name, values)
+            })
+            .collect()
+    }
+}
+
+/// Spatial smoothing of an output variable on the output grid,
+/// or over the nearest cells for the point outputs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SmoothingConfig {
+    pub mode: SmoothingMode,
+    pub radius: usize, // number of grid points on each side of the window
+}
+
+impl SmoothingConfig {
+    /// Number of neighbours of a cell in the point outputs, as many as the other grid points
+    /// of the window
+    fn neighbours(&self) -> usize {
+        (2 * self.radius + 1).pow(2) - 1
+    }
+}
+
+/// Units of the fire intensity outputs, the model computes them in kW/m
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum IntensityUnits {
+    #[default]
+    #[serde(rename = "kW/m")]
+    KilowattPerMeter,
+    #[serde(rename = "kcal/(m*s)")]
+    KilocaloriePerMeterSecond,
+    #[serde(rename = "BTU/(ft*s)")]
+    BtuPerFootSecond,
+}
+
+impl IntensityUnits {
+    /// Conversion factor from kW/m
+    pub fn factor(&self) -> f32 {
+        match self {
+            IntensityUnits::KilowattPerMeter => 1.0,
+            IntensityUnits::KilocaloriePerMeterSecond => 0.238_846, // international table calorie
+            IntensityUnits::BtuPerFootSecond => 0.288_894,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            IntensityUnits::KilowattPerMeter => "kW/m",
+            IntensityUnits::KilocaloriePerMeterSecond => "kcal/(m*s)",
+            IntensityUnits::BtuPerFootSecond => "BTU/(ft*s)",
+        }
+    }
+}
+
+/// Smooth the values on the grid, NODATAVAL values are excluded from the window
+fn smooth_on_grid(
+    values: &Array1<f32>,
+    grid: &RegularGrid,
+    smoothing: &SmoothingConfig,
+) -> Array1<f32> {
+    let smoothed: Vec<f32> = (0..values.len())
+        .into_par_iter()
+        .map(|idx| {
+            if values[idx] == NODATAVAL {
+                return NODATAVAL;
+            }
+            let mut window: Vec<f32> = grid
+                .neighbours(idx, smoothing.radius)
+                .into_iter()
+                .map(|i| values[i])
+                .filter(|v| *v != NODATAVAL)
+                .collect();
+            smooth_window(&mut window, smoothing.mode)
         })
         .collect();
+    Array1::from_vec(smoothed)
+}
 
-    if !error_messages.is_empty() {
-        let all_messages = error_messages.join("\n");
-        Err(RISICOError::from(format!(
-            "{}: {}",
-            error_message, all_messages
-        )))
-    } else {
-        Ok(())
+/// Smooth the values of the cells with the values of their neighbours,
+/// NODATAVAL values are excluded from the window
+fn smooth_on_cells(
+    values: &Array1<f32>,
+    neighbourhood: &CellNeighbourhood,
+    mode: SmoothingMode,
+) -> Array1<f32> {
+    let smoothed: Vec<f32> = (0..values.len())
+        .into_par_iter()
+        .map(|idx| {
+            if values[idx] == NODATAVAL {
+                return NODATAVAL;
+            }
+            let mut window: Vec<f32> = std::iter::once(idx)
+                .chain(neighbourhood.neighbours(idx).iter().copied())
+                .map(|i| values[i])
+                .filter(|v| *v != NODATAVAL)
+                .collect();
+            smooth_window(&mut window, mode)
+        })
+        .collect();
+    Array1::from_vec(smoothed)
+}
+
+/// Smoothed value of a window of valid values
+fn smooth_window(window: &mut [f32], mode: SmoothingMode) -> f32 {
+    match mode {
+        SmoothingMode::Mean => window.iter().sum::<f32>() / window.len() as f32,
+        SmoothingMode::Median => {
+            window.sort_by(|a, b| a.total_cmp(b));
+            let n = window.len();
+            if n % 2 == 1 {
+                window[n / 2]
+            } else {
+                (window[n / 2 - 1] + window[n / 2]) / 2.0
+            }
+        }
     }
 }
 
@@ -53,6 +342,29 @@ pub struct OutputVariable {
     name: String,
     cluster_mode: ClusterMode,
     precision: i32,
+    // maximum value for derived variables (default: unbounded)
+    #[serde(default)]
+    max_value: Option<f32>,
+    // optional spatial smoothing before writing (default: no smoothing)
+    #[serde(default)]
+    smoothing: Option<SmoothingConfig>,
+    // file of the per-cell historical distributions: the values are written
+    // as percentiles [0-100] of the distributions (default: absolute values)
+    #[serde(default)]
+    percentile_distribution: Option<String>,
+    #[serde(skip)]
+    distributions: Option<Arc<CellDistributions>>,
+    // units of the intensity variables (default: kW/m), ignored by the other variables
+    #[serde(default)]
+    intensity_units: Option<IntensityUnits>,
+    // daily aggregation of the variable (default: the values of each output timestep)
+    #[serde(default)]
+    aggregation: Option<Aggregation>,
+    // sourceIndex only: input variable whose source grid indexes are written
+    #[serde(default)]
+    source_variable: Option<InputVariableName>,
+    #[serde(skip)]
+    source_indexes: Option<Array1<f32>>,
 }
 
 impl OutputVariable {
@@ -67,19 +379,137 @@ impl OutputVariable {
             name: name.to_string(),
             cluster_mode,
             precision,
+            max_value: None,
+            smoothing: None,
+            percentile_distribution: None,
+            distributions: None,
+            intensity_units: None,
+            aggregation: None,
+            source_variable: None,
+            source_indexes: None,
+        }
+    }
+
+    /// Name used for the written variable
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of decimals of the values rounded to the precision
+    fn decimals(&self) -> usize {
+        (-self.precision).max(0) as usize
+    }
+
+    /// Intensity units the values are converted to, None if the variable is not
+    /// an intensity or is written as percentiles
+    fn converted_units(&self) -> Option<IntensityUnits> {
+        let is_intensity = self.internal_name.get_str("units") == Some("kW/m");
+        if !is_intensity || self.distributions.is_some() {
+            return None;
+        }
+        self.intensity_units
+    }
+
+    /// Units attribute of the written variable
+    pub fn units(&self) -> &str {
+        if self.distributions.is_some() {
+            return "%";
+        }
+        match self.converted_units() {
+            Some(units) => units.label(),
+            None => self.internal_name.get_str("units").unwrap_or("unknown"),
+        }
+    }
+
+    /// Long name attribute of the written variable
+    pub fn long_name(&self) -> String {
+        let long_name = self
+            .internal_name
+            .get_str("long_name")
+            .map(str::to_owned)
+            .unwrap_or_else(|| self.internal_name.to_string());
+        match self.distributions {
+            Some(_) => format!("Percentile of {long_name}"),
+            None => long_name,
+        }
+    }
+
+    /// Values of the variable computed by the model, or the source grid indexes
+    fn raw_values(&self, output: &Output, cache: &OutputCache) -> Option<Array1<f32>> {
+        if self.internal_name == OutputVariableName::sourceIndex {
+            return self.source_indexes.clone();
+        }
+        match cache.get(&self.internal_name) {
+            Some(values) => Some(values.clone()),
+            None => output.get(&self.internal_name),
+        }
+    }
+
+    /// Values of the variable on the cells, before any gridding
+    fn get_values(&self, output: &Output, cache: &OutputCache) -> Option<Array1<f32>> {
+        let mut values = self.raw_values(output, cache)?;
+        let n_clamped = cap_derived(&self.internal_name, &mut values, self.max_value);
+        if n_clamped > 0 {
+            warn!(
+                "Variable {}: {} values clamped to {}",
+                self.name,
+                n_clamped,
+                self.max_value.unwrap_or_default()
+            );
+        }
+        if let Some(distributions) = &self.distributions {
+            return Some(to_percentiles(&values, distributions));
+        }
+        if let Some(units) = self.converted_units() {
+            let factor = units.factor();
+            values.mapv_inplace(|v| if v == NODATAVAL { NODATAVAL } else { v * factor });
         }
+        Some(values)
+    }
+
+    /// Fraction of the cells with a valid value, None if the variable is not available
+    fn valid_fraction(&self, output: &Output, cache: &OutputCache) -> Option<f32> {
+        let values = self.raw_values(output, cache)?;
+        if values.is_empty() {
+            return Some(0.0);
+        }
+        let n_valid = values.iter().filter(|v| **v != NODATAVAL).count();
+        Some(n_valid as f32 / values.len() as f32)
+    }
+
+    /// Values of the variable on the cells, smoothed over the neighbours in the cells grid and
+    /// rounded to the precision (used by point outputs)
+    pub fn get_variable_on_points(
+        &self,
+        output: &Output,
+        cache: &OutputCache,
+        cells: Option<&IrregularGrid>,
+    ) -> Option<Array1<f32>> {
+        let cutval = f32::powi(10.0, self.precision);
+        let mut values = self.get_values(output, cache)?;
+        if let (Some(smoothing), Some(cells)) = (&self.smoothing, cells) {
+            let neighbourhood = cells.neighbourhood(smoothing.neighbours());
+            values = smooth_on_cells(&values, &neighbourhood, smoothing.mode);
+        }
+        Some(values.mapv(|v| {
+            if v == NODATAVAL {
+                NODATAVAL
+            } else {
+                (v / cutval).round() * cutval
+            }
+        }))
     }
 
     pub fn get_variable_on_grid(
         &self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
     ) -> Option<Array1<f32>> {
-        let values = output.get(&self.internal_name);
+        let values = self.get_values(output, cache)?;
 
-        let values = values?;
         let cutval = f32::powi(10.0, self.precision);
 
         let n_pixels = grid.nrows * grid.ncols;
@@ -102,6 +532,17 @@ impl OutputVariable {
             return Some(grid_values);
         }
 
+        if let Some(percentile) = self.cluster_mode.percentile() {
+            // the percentile needs all the values of the grid cell
+            let mut groups: HashMap<usize, Vec<f32>> = HashMap::new();
+            indexes_and_values
+                .iter()
+                .for_each(|(idx, value)| groups.entry(*idx).or_default().push(*value));
+            groups.into_iter().for_each(|(idx, mut group)| {
+                grid_values[idx] = percentile_of(&mut group, percentile);
+            });
+        }
+
         indexes_and_values.iter().for_each(|(idx, value)| {
             let idx = *idx;
             let value = *value;
@@ -114,7 +555,8 @@ impl OutputVariable {
                     ClusterMode::Mean => grid_values[idx] += value,
                     ClusterMode::Min => grid_values[idx] = f32::min(prev_value, value),
                     ClusterMode::Max => grid_values[idx] = f32::max(prev_value, value),
-                    _ => unimplemented!("Median mode not implemented yet"),
+                    // already computed from the values of the grid cell
+                    ClusterMode::Median | ClusterMode::Percentile(_) => {}
                 }
             }
         });
@@ -129,6 +571,10 @@ impl OutputVariable {
             grid_values = grid_values / grid_count;
         }
 
+        if let Some(smoothing) = &self.smoothing {
+            grid_values = smooth_on_grid(&grid_values, grid, smoothing);
+        }
+
         // apply cutval
         let grid_values = grid_values.mapv(|v| {
             if v == NODATAVAL {
@@ -142,6 +588,14 @@ impl OutputVariable {
     }
 }
 
+/// Percentile of the values [%], interpolated between the closest ranks
+fn percentile_of(values: &mut [f32], percentile: f32) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = percentile / 100.0 * (values.len() - 1) as f32;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    values[lower] + (values[upper] - values[lower]) * (rank - lower as f32)
+}
+
 pub struct OutputType {
     // pub internal_name: String,
     name: String,
@@ -149,9 +603,13 @@ pub struct OutputType {
     grid: RegularGrid,
     format: String,
     variables: Vec<OutputVariable>,
+    // variables written once per day, aggregated over the output timesteps of the day
+    aggregated: Vec<OutputVariable>,
+    accumulator: Option<DailyAccumulator>,
     // palettes: PaletteMap,
     // run_date: DateTime<Utc>,
     writer: Box<dyn Writer>,
+    min_valid_fraction: Option<f32>,
 }
 
 unsafe impl Send for OutputType {}
@@ -161,6 +619,8 @@ impl OutputType {
         output_type_def: &OutputTypeConfig,
         run_date: &DateTime<Utc>,
         palettes: &PaletteMap,
+        n_cells: usize,
+        duplicates_mask: Option<&[bool]>,
     ) -> Result<Self, RISICOError> {
         let grid_path = &output_type_def.grid_path;
         // let internal_name = &output_type_def.internal_name;
@@ -170,27 +630,78 @@ impl OutputType {
 
         let grid = RegularGrid::from_txt_file(grid_path)?;
 
-        let writer: Box<dyn Writer> = match format.as_str() {
+        let mut writer: Box<dyn Writer> = match format.as_str() {
             "ZBIN" => Box::new(ZBinWriter::new(path, name, run_date)),
             "PNGWJSON" => Box::new(PngWriter::new(path, name, palettes, run_date)),
-            "NETCDF" => Box::new(NetcdfWriter::new(path)),
+            "NETCDF" => Box::new(NetcdfWriter::new(path, run_date)),
+            "CSV" => Box::new(CsvWriter::new(path, name, run_date)),
+            "JSONL" => Box::new(JsonlWriter::new(
+                path,
+                name,
+                run_date,
+                output_type_def.jsonl_layout,
+            )),
+            #[cfg(feature = "gdal")]
+            "GEOTIFF" => Box::new(GeotiffWriter::new(path, name, run_date, false)),
             #[cfg(feature = "gdal")]
-            "GEOTIFF" => Box::new(GeotiffWriter::new(path, name, run_date)),
+            "COG" => Box::new(GeotiffWriter::new(path, name, run_date, true)),
+            #[cfg(feature = "flatgeobuf")]
+            "FGB" => Box::new(FgbWriter::new(path, name, run_date)),
             _ => Box::new(ZBinWriter::new(path, name, run_date)),
         };
-
-        let variables = output_type_def
-            .variables
-            .iter()
-            .map(|var| {
-                OutputVariable::new(
-                    var.internal_name,
-                    &var.name,
-                    var.cluster_mode,
-                    var.precision,
+        if let Some(fill_value) = output_type_def.fill_value {
+            writer.set_fill_value(fill_value);
+        }
+        if let Some(level) = output_type_def.compression_level {
+            if !cfg!(feature = "zstd") {
+                return Err(format!(
+                    "Output type {}: the compression level requires the zstd feature",
+                    name
                 )
-            })
-            .collect();
+                .into());
+            }
+            writer.set_compression_level(level);
+        }
+        if let Some(append) = output_type_def.append {
+            writer.set_append(append);
+        }
+        writer.set_consolidate_times(output_type_def.consolidate_times);
+        if let Some(epsg) = output_type_def.epsg {
+            writer.set_epsg(epsg);
+        }
+
+        let mut variables = output_type_def.variables.to_vec();
+        if let Some(variable) = variables.iter().find(|v| {
+            v.internal_name == OutputVariableName::sourceIndex && v.source_variable.is_none()
+        }) {
+            return Err(format!(
+                "Output type {}: variable {} requires the source_variable",
+                name, variable.name
+            )
+            .into());
+        }
+        let mut distributions: HashMap<String, Arc<CellDistributions>> = HashMap::new();
+        for variable in variables.iter_mut() {
+            let Some(path) = &variable.percentile_distribution else {
+                continue;
+            };
+            if !distributions.contains_key(path) {
+                let cells = read_distributions(path, duplicates_mask)?;
+                if cells.len() != n_cells {
+                    return Err(format!(
+                        "Variable {}: {} distributions for {} cells",
+                        variable.name,
+                        cells.len(),
+                        n_cells
+                    )
+                    .into());
+                }
+                distributions.insert(path.clone(), Arc::new(cells));
+            }
+            variable.distributions = distributions.get(path).cloned();
+        }
+        let (aggregated, variables): (Vec<OutputVariable>, Vec<OutputVariable>) =
+            variables.into_iter().partition(|v| v.aggregation.is_some());
 
         Ok(Self {
             // internal_name: internal_name.to_string(),
@@ -199,45 +710,240 @@ impl OutputType {
             grid,
             format: format.to_string(),
             variables,
+            aggregated,
+            accumulator: None,
             // palettes: palettes.clone(),
             // run_date: *run_date,
             writer,
+            min_valid_fraction: output_type_def.min_valid_fraction,
         })
     }
 
+    /// Get the names of the variables written by this output type
+    pub fn variable_names(&self) -> Vec<OutputVariableName> {
+        self.variables
+            .iter()
+            .chain(&self.aggregated)
+            .map(|v| v.internal_name)
+            .collect()
+    }
+
+    /// Input variables whose source grid indexes are written
+    pub fn source_variables(&self) -> Vec<InputVariableName> {
+        self.variables
+            .iter()
+            .chain(&self.aggregated)
+            .filter_map(|v| v.source_variable)
+            .collect()
+    }
+
+    /// Set the source grid indexes of the cells for each input variable, NODATAVAL for the
+    /// cells outside the source grid
+    pub fn set_source_indexes(&mut self, indexes: &HashMap<InputVariableName, Array1<f32>>) {
+        for variable in self.variables.iter_mut().chain(self.aggregated.iter_mut()) {
+            if let Some(var) = variable.source_variable {
+                variable.source_indexes = indexes.get(&var).cloned();
+            }
+        }
+    }
+
+    /// Set the model version recorded in the output metadata
+    pub fn set_model_version(&mut self, model_version: &str) {
+        self.writer.set_global_attribute("model_version", model_version);
+    }
+
+    /// Set a global attribute recorded in the output metadata
+    pub fn set_global_attribute(&mut self, name: &str, value: &str) {
+        self.writer.set_global_attribute(name, value);
+    }
+
     // pub fn add_variable(&mut self, variable: OutputVariable) {
     //     self.variables.push(variable);
     // }
 
+    /// Names of the variables not written at this timestep, as their fraction of valid values
+    /// is below the threshold
+    fn skipped_variables(&self, output: &Output, cache: &OutputCache) -> Vec<String> {
+        let Some(min_valid_fraction) = self.min_valid_fraction else {
+            return Vec::new();
+        };
+        self.variables
+            .iter()
+            .filter_map(|variable| {
+                let fraction = variable.valid_fraction(output, cache)?;
+                if fraction >= min_valid_fraction {
+                    return None;
+                }
+                info!(
+                    "{}: skipping variable {} at {}, valid fraction {:.3} below {}",
+                    self.name, variable.name, output.time, fraction, min_valid_fraction
+                );
+                Some(variable.name.clone())
+            })
+            .collect()
+    }
+
     pub fn write_variables(
         &mut self,
         lats: &[f32],
         lons: &[f32],
         output: &Output,
-    ) -> Result<(), RISICOError> {
+        cache: &OutputCache,
+    ) -> Result<(), WriteErrors> {
         debug!("Writing variables for {}, {}", self.name, self.format);
-        let res = self
-            .writer
-            .write(output, lats, lons, &self.grid, &self.variables);
+        let mut errors = WriteErrors::default();
+        if !self.aggregated.is_empty() {
+            if let Err(err) = self.accumulate(lats, lons, output, cache) {
+                errors.extend(err);
+            }
+            if self.variables.is_empty() {
+                return errors.into_result();
+            }
+        }
+        let skipped = self.skipped_variables(output, cache);
+        let res = if skipped.is_empty() {
+            self.writer
+                .write(output, cache, lats, lons, &self.grid, &self.variables)
+        } else {
+            let variables: Vec<OutputVariable> = self
+                .variables
+                .iter()
+                .filter(|v| !skipped.contains(&v.name))
+                .cloned()
+                .collect();
+            if variables.is_empty() {
+                return Ok(());
+            }
+            self.writer
+                .write(output, cache, lats, lons, &self.grid, &variables)
+        };
+        if let Err(errors) = &res {
+            for (name, err) in &errors.failures {
+                warn!("[{}] Cannot write variable {}: {}", self.format, name, err);
+            }
+        }
         debug!("Done Writing variables for {}, {}", self.name, self.format);
+        if let Err(err) = res {
+            errors.extend(err);
+        }
+        errors.into_result()
+    }
+
+    /// Add the values of the timestep to the daily aggregates,
+    /// writing the aggregates of the previous day at the first timestep of a new day
+    fn accumulate(
+        &mut self,
+        lats: &[f32],
+        lons: &[f32],
+        output: &Output,
+        cache: &OutputCache,
+    ) -> Result<(), WriteErrors> {
+        let day = output.time.date_naive();
+        let mut res = Ok(());
+        if self.accumulator.as_ref().is_some_and(|acc| acc.day != day) {
+            res = self.finish(lats, lons);
+        }
+        let accumulator = self.accumulator.get_or_insert_with(|| DailyAccumulator::new(day));
+        for variable in &self.aggregated {
+            let Some(aggregation) = variable.aggregation else {
+                continue;
+            };
+            if let Some(values) = variable.raw_values(output, cache) {
+                accumulator.add(aggregation, variable.internal_name, &values);
+            }
+        }
         res
     }
+
+    /// Write the daily aggregates accumulated so far, also for a partial day
+    pub fn finish(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), WriteErrors> {
+        let Some(accumulator) = self.accumulator.take() else {
+            return Ok(());
+        };
+        let day_start = accumulator.day.and_hms_opt(0, 0, 0).expect("Should be valid");
+        let time = DateTime::from_naive_utc_and_offset(day_start, Utc);
+        // the writers take the values from the cache, the output only gives the time
+        let output = Output::new(time, Array1::from(vec![]));
+        let mut errors = WriteErrors::default();
+        for aggregation in [Aggregation::DailyMax, Aggregation::DailyMin, Aggregation::DailyMean] {
+            let variables: Vec<OutputVariable> = self
+                .aggregated
+                .iter()
+                .filter(|v| v.aggregation == Some(aggregation))
+                .cloned()
+                .collect();
+            if variables.is_empty() {
+                continue;
+            }
+            debug!("Writing {:?} aggregates for {}, {}", aggregation, self.name, self.format);
+            let cache = accumulator.aggregated(aggregation);
+            let res = self
+                .writer
+                .write(&output, &cache, lats, lons, &self.grid, &variables);
+            if let Err(err) = res {
+                for (name, err) in &err.failures {
+                    warn!("[{}] Cannot write aggregate {}: {}", self.format, name, err);
+                }
+                errors.extend(err);
+            }
+        }
+        errors.into_result()
+    }
 }
 
 #[derive(Debug)]
 struct NetcdfWriter {
     path: PathBuf,
     // name: String,
-    // run_date: DateTime<Utc>,
-    files: HashMap<String, Mutex<MutableFile>>,
+    // global attributes of the files: run date, model name and version, input path
+    attributes: Vec<(String, String)>,
+    // value written in place of NODATAVAL
+    fill_value: f32,
+    // append to the existing files instead of recreating them
+    append: Option<NetcdfAppend>,
+    // write the outputs at a time already written by the run into its time slice
+    consolidate_times: bool,
+    files: HashMap<String, Mutex<NetcdfOutputFile>>,
+}
+
+/// NetCDF file holding one variable for the whole run, with the times already written
+#[derive(Debug)]
+struct NetcdfOutputFile {
+    file: MutableFile,
+    times: Vec<i64>,
+    // number of times found in the file when it was opened
+    n_existing: usize,
+}
+
+impl NetcdfOutputFile {
+    /// Index of the time slice for the given time: the existing one if the time was found
+    /// in the file when it was opened, or was already written by the run when consolidating
+    /// the times, otherwise a new slice appended at the end
+    fn time_index(&mut self, time: i64, consolidate: bool) -> (usize, bool) {
+        let written = if consolidate {
+            &self.times[..]
+        } else {
+            &self.times[..self.n_existing]
+        };
+        match written.iter().position(|t| *t == time) {
+            Some(idx) => (idx, false),
+            None => {
+                self.times.push(time);
+                (self.times.len() - 1, true)
+            }
+        }
+    }
 }
 
 impl NetcdfWriter {
-    fn new(path: &str) -> Self {
+    fn new(path: &str, run_date: &DateTime<Utc>) -> Self {
         Self {
             path: PathBuf::from(path),
             // name: name.to_string(),
-            // run_date: *run_date,
+            attributes: vec![("run_date".to_string(), run_date.to_rfc3339())],
+            fill_value: NODATAVAL,
+            append: None,
+            consolidate_times: false,
             files: HashMap::new(),
         }
     }
@@ -247,6 +953,7 @@ struct ZBinWriter {
     path: PathBuf,
     name: String,
     run_date: DateTime<Utc>,
+    compression_level: Option<i32>,
 }
 
 impl ZBinWriter {
@@ -255,6 +962,7 @@ impl ZBinWriter {
             path: PathBuf::from(path),
             name: name.to_string(),
             run_date: *run_date,
+            compression_level: None,
         }
     }
 }
@@ -281,22 +989,49 @@ trait Writer {
     fn write(
         &mut self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
         variables: &[OutputVariable],
-    ) -> Result<(), RISICOError>;
+    ) -> Result<(), WriteErrors>;
+
+    /// Set a global attribute to be recorded by writers supporting metadata
+    fn set_global_attribute(&mut self, _name: &str, _value: &str) {}
+
+    /// Set the value written in place of NODATAVAL by writers supporting it
+    fn set_fill_value(&mut self, _fill_value: f32) {}
+
+    /// Set the compression level by writers supporting it
+    fn set_compression_level(&mut self, _level: i32) {}
+
+    /// Append to the existing files, by writers supporting it
+    fn set_append(&mut self, _append: NetcdfAppend) {}
+
+    /// Write the outputs at the same time into the same time slice, by writers supporting it
+    fn set_consolidate_times(&mut self, _consolidate: bool) {}
+
+    /// Set the EPSG code of the coordinates of the grid, by writers supporting it
+    fn set_epsg(&mut self, _epsg: u32) {}
+}
+
+/// Replace the NODATAVAL values with the fill value of the output
+fn fill_nodata(values: &mut Array1<f32>, fill_value: f32) {
+    if fill_value != NODATAVAL {
+        values.mapv_inplace(|v| if v == NODATAVAL { fill_value } else { v });
+    }
 }
 
 impl Writer for NetcdfWriter {
     fn write(
         &mut self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
         variables: &[OutputVariable],
-    ) -> Result<(), RISICOError> {
+    ) -> Result<(), WriteErrors> {
         let n_lats = grid.nrows;
         let n_lons = grid.ncols;
 
@@ -307,8 +1042,43 @@ impl Writer for NetcdfWriter {
             let path = self.path.as_os_str().to_str().expect("Invalid path");
 
             let file_name = format!("{}/{}.nc", path, variable.name);
-            let file = create_nc_file(&file_name, grid, &variable.name, variable.internal_name)?;
-            self.files.insert(variable.name.clone(), Mutex::new(file));
+            let existing = match self.append {
+                Some(_) if Path::new(&file_name).is_file() => Some(
+                    open_nc_file(&file_name, grid, &variable.name).map_err(|err| {
+                        WriteErrors::for_variables(&[variable.clone()], &err.to_string())
+                    })?,
+                ),
+                _ => None,
+            };
+            let (file, times) = match existing {
+                Some((file, times)) => {
+                    info!("[NC] Appending to {file_name}, {} times already written", times.len());
+                    (file, times)
+                }
+                None => {
+                    let file = create_nc_file(
+                        &file_name,
+                        grid,
+                        &variable.name,
+                        &variable.long_name(),
+                        variable.units(),
+                        &self.attributes,
+                        self.fill_value,
+                    )
+                    .map_err(|err| {
+                        WriteErrors::for_variables(&[variable.clone()], &err.to_string())
+                    })?;
+                    (file, Vec::new())
+                }
+            };
+            self.files.insert(
+                variable.name.clone(),
+                Mutex::new(NetcdfOutputFile {
+                    file,
+                    n_existing: times.len(),
+                    times,
+                }),
+            );
         }
 
         let results: Vec<Result<(), RISICOError>> = variables
@@ -319,7 +1089,16 @@ impl Writer for NetcdfWriter {
                     .get(&variable.name)
                     .expect("there should be a file");
 
-                let mut file = mutex.lock().expect("");
+                let mut output_file = mutex.lock().expect("");
+                let time: i64 = output.time.timestamp();
+                let (len, is_new) = output_file.time_index(time, self.consolidate_times);
+                // times written by a previous run, kept if requested
+                let n_existing = output_file.n_existing;
+                if !is_new && len < n_existing && self.append == Some(NetcdfAppend::Skip) {
+                    debug!("[NC] Skipping variable {} already written at {time}", variable.name);
+                    return Ok(());
+                }
+                let file = &mut output_file.file;
 
                 debug!(
                     "[NC] Writing variable {} to {:?}",
@@ -327,26 +1106,27 @@ impl Writer for NetcdfWriter {
                     file.path().expect("Should have a path")
                 );
 
-                let mut time_var = file
-                    .variable_mut("time")
-                    .ok_or_else(|| "variable not found: time".to_string())?;
-                let time: i64 = output.time.timestamp();
-                let len = time_var.len();
-                let extents: Extents = (&[len], &[1]).try_into().expect("Should convert");
+                if is_new {
+                    let mut time_var = file
+                        .variable_mut("time")
+                        .ok_or_else(|| "variable not found: time".to_string())?;
+                    let extents: Extents = (&[len], &[1]).try_into().expect("Should convert");
 
-                time_var
-                    .put_values(&[time], extents)
-                    .unwrap_or_else(|_| panic!("Add time failed"));
+                    time_var
+                        .put_values(&[time], extents)
+                        .unwrap_or_else(|_| panic!("Add time failed"));
+                }
 
                 let mut variable_var = file
                     .variable_mut(&variable.name)
                     .ok_or_else(|| format!("variable not found: {}", variable.name))?;
 
-                let values = variable.get_variable_on_grid(output, lats, lons, grid);
+                let values = variable.get_variable_on_grid(output, cache, lats, lons, grid);
                 let extents: Extents = (&[len, 0, 0], &[1, n_lats, n_lons])
                     .try_into()
                     .expect("Should convert");
-                if let Some(values) = values {
+                if let Some(mut values) = values {
+                    fill_nodata(&mut values, self.fill_value);
                     variable_var
                         .put_values(values.as_slice().expect("Should unwrap"), extents)
                         .unwrap_or_else(|err| panic!("Add variable failed: {err}"));
@@ -361,7 +1141,25 @@ impl Writer for NetcdfWriter {
             })
             .collect();
 
-        extract_errors("NC Errors", results)
+        WriteErrors::from_results(variables, results).into_result()
+    }
+
+    fn set_fill_value(&mut self, fill_value: f32) {
+        self.fill_value = fill_value;
+    }
+
+    fn set_append(&mut self, append: NetcdfAppend) {
+        self.append = Some(append);
+    }
+
+    fn set_consolidate_times(&mut self, consolidate: bool) {
+        self.consolidate_times = consolidate;
+    }
+
+    fn set_global_attribute(&mut self, name: &str, value: &str) {
+        // the last value set wins
+        self.attributes.retain(|(n, _)| n != name);
+        self.attributes.push((name.to_string(), value.to_string()));
     }
 }
 
@@ -369,11 +1167,12 @@ impl Writer for ZBinWriter {
     fn write(
         &mut self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
         variables: &[OutputVariable],
-    ) -> Result<(), RISICOError> {
+    ) -> Result<(), WriteErrors> {
         let path = self
             .path
             .as_os_str()
@@ -392,10 +1191,11 @@ impl Writer for ZBinWriter {
                 );
 
                 debug!("[ZBIN] Writing variable {} to {:?}", variable.name, file);
-                let values = variable.get_variable_on_grid(output, lats, lons, grid);
+                let values = variable.get_variable_on_grid(output, cache, lats, lons, grid);
 
                 if let Some(values) = values {
-                    write_to_zbin_file(&file, grid, values.as_slice().expect("Should unwrap"))
+                    let values = values.as_slice().expect("Should unwrap");
+                    write_to_zbin_file(&file, grid, values, self.compression_level)
                         .map_err(|err| format!("Cannot write file {}: error {err}", file))?;
 
                     debug!(
@@ -406,7 +1206,11 @@ impl Writer for ZBinWriter {
                 Ok(())
             })
             .collect();
-        extract_errors("ZBIN Errors", results)
+        WriteErrors::from_results(variables, results).into_result()
+    }
+
+    fn set_compression_level(&mut self, level: i32) {
+        self.compression_level = Some(level);
     }
 }
 
@@ -414,11 +1218,12 @@ impl Writer for PngWriter {
     fn write(
         &mut self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
         variables: &[OutputVariable],
-    ) -> Result<(), RISICOError> {
+    ) -> Result<(), WriteErrors> {
         let path = self
             .path
             .as_os_str()
@@ -438,11 +1243,19 @@ impl Writer for PngWriter {
 
                 debug!("[PNG] Writing variable {} to {:?}", variable.name, file);
 
-                let values = variable.get_variable_on_grid(output, lats, lons, grid);
-                let palette = self
-                    .palettes
-                    .get(&variable.name)
-                    .ok_or(format!("No palette found for variable {}", variable.name))?;
+                let values = variable.get_variable_on_grid(output, cache, lats, lons, grid);
+                let default_palette;
+                let palette = match self.palettes.get(&variable.name) {
+                    Some(palette) => palette.as_ref(),
+                    None => {
+                        warn!(
+                            "[PNG] No palette found for variable {}, using default palette",
+                            variable.name
+                        );
+                        default_palette = Palette::default();
+                        &default_palette
+                    }
+                };
 
                 if let Some(values) = values {
                     write_to_pngwjson(
@@ -461,8 +1274,7 @@ impl Writer for PngWriter {
                 Ok(())
             })
             .collect();
-        // check if there are any errors
-        extract_errors("PNG Errors", results)
+        WriteErrors::from_results(variables, results).into_result()
     }
 }
 
@@ -471,14 +1283,26 @@ pub struct GeotiffWriter {
     path: PathBuf,
     name: String,
     run_date: DateTime<Utc>,
+    // write Cloud-Optimized GeoTIFFs (tiled, with internal overviews)
+    cloud_optimized: bool,
+    // value written in place of NODATAVAL
+    fill_value: f32,
+    // EPSG code of the coordinates of the grid
+    epsg: u32,
+    // latitudes and longitudes of the cells transformed to the coordinates of the grid
+    projected: Option<(Vec<f32>, Vec<f32>)>,
 }
 #[cfg(feature = "gdal")]
 impl GeotiffWriter {
-    pub fn new(path: &str, name: &str, run_date: &DateTime<Utc>) -> Self {
+    pub fn new(path: &str, name: &str, run_date: &DateTime<Utc>, cloud_optimized: bool) -> Self {
         GeotiffWriter {
             path: PathBuf::from(path),
             name: name.to_string(),
             run_date: run_date.clone(),
+            cloud_optimized,
+            fill_value: NODATAVAL,
+            epsg: EPSG_LAT_LON,
+            projected: None,
         }
     }
 }
@@ -487,17 +1311,28 @@ impl Writer for GeotiffWriter {
     fn write(
         &mut self,
         output: &Output,
+        cache: &OutputCache,
         lats: &[f32],
         lons: &[f32],
         grid: &RegularGrid,
         variables: &[OutputVariable],
-    ) -> Result<(), RISICOError> {
+    ) -> Result<(), WriteErrors> {
         let path = self
             .path
             .as_os_str()
             .to_str()
             .expect("Should be a valid path");
 
+        if self.epsg != EPSG_LAT_LON && self.projected.is_none() {
+            let (xs, ys) = transform_coordinates(EPSG_LAT_LON, self.epsg, lons, lats)
+                .map_err(|err| WriteErrors::for_variables(variables, &err.to_string()))?;
+            self.projected = Some((ys, xs));
+        }
+        let (lats, lons) = match &self.projected {
+            Some((ys, xs)) => (ys.as_slice(), xs.as_slice()),
+            None => (lats, lons),
+        };
+
         let results: Vec<Result<(), RISICOError>> = variables.par_iter().map(|variable| {
             let date_string = output.time.format("%Y%m%d%H%M").to_string();
             //todo!("get run date from config");
@@ -508,10 +1343,17 @@ impl Writer for GeotiffWriter {
             );
 
             debug!("[GEOTIFF] Writing variable {} to {:?}", variable.name, file);
-            let values = variable.get_variable_on_grid(&output, lats, lons, grid);
-
-            if let Some(values) = values {
-                write_to_geotiff(&file, &grid, values.as_slice().expect("Should unwrap"))
+            let values = variable.get_variable_on_grid(&output, cache, lats, lons, grid);
+
+            if let Some(mut values) = values {
+                fill_nodata(&mut values, self.fill_value);
+                let values = values.as_slice().expect("Should unwrap");
+                let result = if self.cloud_optimized {
+                    write_to_cog(&file, &grid, values, self.fill_value, self.epsg)
+                } else {
+                    write_to_geotiff(&file, &grid, values, self.fill_value, self.epsg)
+                };
+                result
                     .map_err(|err| format!("Cannot write file {}: error {err}", file))?;
 
                 debug!(
@@ -520,7 +1362,358 @@ impl Writer for GeotiffWriter {
                 );
             }
             Ok(())
-        });
-        extract_errors("GEOTiff Errors", results)
+        }).collect();
+        WriteErrors::from_results(variables, results).into_result()
+    }
+
+    fn set_fill_value(&mut self, fill_value: f32) {
+        self.fill_value = fill_value;
+    }
+
+    fn set_epsg(&mut self, epsg: u32) {
+        self.epsg = epsg;
+    }
+}
+
+/// Writer of point outputs to CSV: one file per timestep, one row per cell
+/// with the coordinates and a column for each variable
+struct CsvWriter {
+    path: PathBuf,
+    name: String,
+    run_date: DateTime<Utc>,
+    // cells of the points, indexed only for the smoothed variables
+    cells: OnceLock<IrregularGrid>,
+}
+
+impl CsvWriter {
+    fn new(path: &str, name: &str, run_date: &DateTime<Utc>) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            name: name.to_string(),
+            run_date: *run_date,
+            cells: OnceLock::new(),
+        }
+    }
+}
+
+impl Writer for CsvWriter {
+    fn write(
+        &mut self,
+        output: &Output,
+        cache: &OutputCache,
+        lats: &[f32],
+        lons: &[f32],
+        _grid: &RegularGrid,
+        variables: &[OutputVariable],
+    ) -> Result<(), WriteErrors> {
+        let path = self
+            .path
+            .as_os_str()
+            .to_str()
+            .expect("Should be a valid path");
+        let date_string = output.time.format("%Y%m%d%H%M").to_string();
+        let run_date = self.run_date.format("%Y%m%d%H%M").to_string();
+        let file = format!("{}/{}_{}_{}.csv", path, self.name, run_date, date_string);
+
+        let columns = get_point_values(output, cache, variables, lats, lons, &self.cells);
+        if columns.is_empty() {
+            return Ok(());
+        }
+        let decimals = get_point_decimals(&columns, variables);
+        debug!("[CSV] Writing {} variables to {:?}", columns.len(), file);
+        write_to_csv(&file, lats, lons, &columns, &decimals).map_err(|err| {
+            let msg = format!("Cannot write file {}: error {err}", file);
+            WriteErrors::for_variables(variables, &msg)
+        })?;
+        debug!("[CSV] Done writing {:?}", file);
+        Ok(())
+    }
+}
+
+/// Writer of point outputs to newline-delimited JSON: one file per timestep,
+/// one line per cell (or per cell and variable) with the valid values
+struct JsonlWriter {
+    path: PathBuf,
+    name: String,
+    run_date: DateTime<Utc>,
+    layout: JsonlLayout,
+    // cells of the points, indexed only for the smoothed variables
+    cells: OnceLock<IrregularGrid>,
+}
+
+impl JsonlWriter {
+    fn new(path: &str, name: &str, run_date: &DateTime<Utc>, layout: JsonlLayout) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            name: name.to_string(),
+            run_date: *run_date,
+            layout,
+            cells: OnceLock::new(),
+        }
+    }
+}
+
+impl Writer for JsonlWriter {
+    fn write(
+        &mut self,
+        output: &Output,
+        cache: &OutputCache,
+        lats: &[f32],
+        lons: &[f32],
+        _grid: &RegularGrid,
+        variables: &[OutputVariable],
+    ) -> Result<(), WriteErrors> {
+        let path = self
+            .path
+            .as_os_str()
+            .to_str()
+            .expect("Should be a valid path");
+        let date_string = output.time.format("%Y%m%d%H%M").to_string();
+        let run_date = self.run_date.format("%Y%m%d%H%M").to_string();
+        let file = format!("{}/{}_{}_{}.jsonl", path, self.name, run_date, date_string);
+
+        let columns = get_point_values(output, cache, variables, lats, lons, &self.cells);
+        if columns.is_empty() {
+            return Ok(());
+        }
+        let decimals = get_point_decimals(&columns, variables);
+        debug!("[JSONL] Writing {} variables to {:?}", columns.len(), file);
+        write_to_jsonl(&file, &output.time, lats, lons, &columns, &decimals, self.layout)
+            .map_err(|err| {
+                let msg = format!("Cannot write file {}: error {err}", file);
+                WriteErrors::for_variables(variables, &msg)
+            })?;
+        debug!("[JSONL] Done writing {:?}", file);
+        Ok(())
+    }
+}
+
+/// Writer of point outputs to FlatGeobuf: one file per timestep, one feature per cell
+/// with the values of the variables as properties
+#[cfg(feature = "flatgeobuf")]
+struct FgbWriter {
+    path: PathBuf,
+    name: String,
+    run_date: DateTime<Utc>,
+    // cells of the points, indexed only for the smoothed variables
+    cells: OnceLock<IrregularGrid>,
+}
+
+#[cfg(feature = "flatgeobuf")]
+impl FgbWriter {
+    fn new(path: &str, name: &str, run_date: &DateTime<Utc>) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            name: name.to_string(),
+            run_date: *run_date,
+            cells: OnceLock::new(),
+        }
+    }
+}
+
+#[cfg(feature = "flatgeobuf")]
+impl Writer for FgbWriter {
+    fn write(
+        &mut self,
+        output: &Output,
+        cache: &OutputCache,
+        lats: &[f32],
+        lons: &[f32],
+        _grid: &RegularGrid,
+        variables: &[OutputVariable],
+    ) -> Result<(), WriteErrors> {
+        let path = self
+            .path
+            .as_os_str()
+            .to_str()
+            .expect("Should be a valid path");
+        let date_string = output.time.format("%Y%m%d%H%M").to_string();
+        let run_date = self.run_date.format("%Y%m%d%H%M").to_string();
+        let file = format!("{}/{}_{}_{}.fgb", path, self.name, run_date, date_string);
+
+        let columns = get_point_values(output, cache, variables, lats, lons, &self.cells);
+        if columns.is_empty() {
+            return Ok(());
+        }
+        debug!("[FGB] Writing {} variables to {:?}", columns.len(), file);
+        write_to_fgb(&file, lats, lons, &columns).map_err(|err| {
+            let msg = format!("Cannot write file {}: error {err}", file);
+            WriteErrors::for_variables(variables, &msg)
+        })?;
+        debug!("[FGB] Done writing {:?}", file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use risico::models::output::OutputElement;
+
+    use super::*;
+
+    #[test]
+    fn point_smoothing_uses_the_nearest_cells() {
+        // cells on a line, the last one without a value
+        let lats = [0.0; 5];
+        let lons = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let cells = IrregularGrid::new(5, 1, lats.to_vec().into(), lons.to_vec().into());
+        let values = Array1::from_vec(vec![1.0, 2.0, 6.0, 4.0, NODATAVAL]);
+
+        let smoothing = SmoothingConfig {
+            mode: SmoothingMode::Mean,
+            radius: 1,
+        };
+        assert_eq!(smoothing.neighbours(), 8);
+        let smoothed = smooth_on_cells(&values, &cells.neighbourhood(2), smoothing.mode);
+        assert_eq!(smoothed.to_vec(), vec![3.0, 3.0, 4.0, 5.0, NODATAVAL]);
+        let smoothed = smooth_on_cells(&values, &cells.neighbourhood(2), SmoothingMode::Median);
+        assert_eq!(smoothed.to_vec(), vec![2.0, 2.0, 4.0, 5.0, NODATAVAL]);
+    }
+
+    #[test]
+    fn point_decimals_follow_the_precision_of_the_columns() {
+        let variables = [
+            OutputVariable::new(OutputVariableName::temperature, "T", ClusterMode::Mean, -2),
+            OutputVariable::new(OutputVariableName::rain, "P", ClusterMode::Mean, 1),
+        ];
+        // only the computed variables are columns, in any order
+        let columns = vec![
+            ("P".to_string(), Array1::zeros(1)),
+            ("T".to_string(), Array1::zeros(1)),
+            ("X".to_string(), Array1::zeros(1)),
+        ];
+        assert_eq!(get_point_decimals(&columns, &variables), vec![0, 2, 0]);
+    }
+
+    #[test]
+    fn netcdf_times_are_consolidated_only_if_requested() {
+        let path = |n_existing| {
+            std::env::temp_dir()
+                .join(format!("risico-times-{}-{n_existing}.nc", std::process::id()))
+        };
+        let file = |n_existing| NetcdfOutputFile {
+            file: netcdf::create(path(n_existing)).unwrap(),
+            times: [0, 3600][..n_existing].to_vec(),
+            n_existing,
+        };
+        // a new time slice for each write by default
+        let mut output_file = file(0);
+        assert_eq!(output_file.time_index(0, false), (0, true));
+        assert_eq!(output_file.time_index(0, false), (1, true));
+        let mut output_file = file(0);
+        assert_eq!(output_file.time_index(0, true), (0, true));
+        assert_eq!(output_file.time_index(0, true), (0, false));
+        // the times found in an appended file are always reused
+        let mut output_file = file(2);
+        assert_eq!(output_file.time_index(3600, false), (1, false));
+        assert_eq!(output_file.time_index(7200, false), (2, true));
+        drop(output_file);
+        for n_existing in [0, 2] {
+            let _ = std::fs::remove_file(path(n_existing));
+        }
+    }
+
+    #[test]
+    fn source_index_variable_writes_the_indexes_of_its_input() {
+        let mut variable =
+            OutputVariable::new(OutputVariableName::sourceIndex, "IDX_T", ClusterMode::Mean, 0);
+        variable.source_variable = Some(InputVariableName::T);
+        let output = Output::new(Utc::now(), Array1::default(2));
+        let cache = OutputCache::new();
+        // not written until the indexes are set
+        assert!(variable.get_values(&output, &cache).is_none());
+        variable.source_indexes = Some(Array1::from_vec(vec![3.0, NODATAVAL]));
+        let values = variable.get_variable_on_points(&output, &cache, None).unwrap();
+        assert_eq!(values.to_vec(), vec![3.0, NODATAVAL]);
+    }
+
+    #[test]
+    fn percentile_variables_have_percentile_metadata() {
+        let mut variable =
+            OutputVariable::new(OutputVariableName::fwi, "FWI", ClusterMode::Mean, 0);
+        let long_name = OutputVariableName::fwi.get_str("long_name").unwrap();
+        assert_eq!(variable.long_name(), long_name);
+        variable.distributions = Some(Arc::new(CellDistributions::new(vec![vec![1.0]])));
+        assert_eq!(variable.units(), "%");
+        assert_eq!(variable.long_name(), format!("Percentile of {long_name}"));
+    }
+
+    #[test]
+    fn percentile_of_interpolates_between_the_ranks() {
+        let mut values = vec![50.0, 0.0, 20.0, 10.0, 40.0, 30.0];
+        assert_eq!(percentile_of(&mut values, 0.0), 0.0);
+        assert_eq!(percentile_of(&mut values, 50.0), 25.0);
+        assert_eq!(percentile_of(&mut values, 90.0), 45.0);
+        assert_eq!(percentile_of(&mut values, 100.0), 50.0);
+        // a single value is the value of any percentile
+        for percentile in [0.0, 10.0, 50.0, 90.0, 100.0] {
+            assert_eq!(percentile_of(&mut [7.0], percentile), 7.0);
+        }
+    }
+
+    #[test]
+    fn percentile_cluster_mode_on_grid() {
+        let grid = RegularGrid::new(2, 2, 0.0, 0.0, 1.0, 1.0);
+        // two cells without values, a single cell and six cells in three grid cells
+        let mut cells = vec![
+            (0.0, 0.0, NODATAVAL),
+            (0.0, 0.0, NODATAVAL),
+            (0.0, 1.0, 7.0),
+        ];
+        for value in [50.0, 0.0, 20.0, 10.0, 40.0, 30.0] {
+            cells.push((1.0, 0.0, value));
+        }
+        let lats: Vec<f32> = cells.iter().map(|c| c.0).collect();
+        let lons: Vec<f32> = cells.iter().map(|c| c.1).collect();
+        let data = cells
+            .iter()
+            .map(|c| OutputElement {
+                temperature: c.2,
+                ..OutputElement::default()
+            })
+            .collect();
+        let output = Output::new(Utc::now(), data);
+        let cache = OutputCache::new();
+
+        let on_grid = |mode: ClusterMode| {
+            OutputVariable::new(OutputVariableName::temperature, "T", mode, 0)
+                .get_variable_on_grid(&output, &cache, &lats, &lons, &grid)
+                .unwrap()
+        };
+        let index = |lat: f32, lon: f32| grid.index(&lat, &lon).unwrap();
+        for (mode, expected) in [
+            (ClusterMode::Percentile(90), 45.0),
+            (ClusterMode::Percentile(0), 0.0),
+            (ClusterMode::Median, 25.0),
+        ] {
+            let values = on_grid(mode);
+            assert_eq!(values[index(0.0, 0.0)], NODATAVAL);
+            assert_eq!(values[index(0.0, 1.0)], 7.0);
+            assert_eq!(values[index(1.0, 0.0)], expected);
+            assert_eq!(values[index(1.0, 1.0)], NODATAVAL);
+        }
+    }
+
+    #[test]
+    fn percentile_cluster_mode_from_str() {
+        assert_eq!(
+            ClusterMode::from_str("p90"),
+            Ok(ClusterMode::Percentile(90))
+        );
+        assert_eq!(
+            ClusterMode::from_str("P90"),
+            Ok(ClusterMode::Percentile(90))
+        );
+        assert_eq!(ClusterMode::from_str("p0"), Ok(ClusterMode::Percentile(0)));
+        assert_eq!(
+            ClusterMode::from_str("p100"),
+            Ok(ClusterMode::Percentile(100))
+        );
+        for invalid in ["p101", "p300", "pX", "p", "p-5", "90"] {
+            assert!(ClusterMode::from_str(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(ClusterMode::Percentile(90).to_string(), "p90");
     }
 }
@@ -1,6 +1,7 @@
 use std::io::Read;
 
 use log::warn;
+use risico::constants::NODATAVAL;
 
 use crate::common::helpers::RISICOError;
 
@@ -18,6 +19,50 @@ pub struct Palette {
     colors: Vec<Color>,
 }
 
+/// Bounds of the built-in palette, spread to be readable for most variables
+const DEFAULT_BOUNDS: [f32; 11] = [
+    0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0,
+];
+
+/// Viridis-like sequential colormap used by the built-in palette
+const DEFAULT_COLORS: [(u8, u8, u8); 11] = [
+    (68, 1, 84),
+    (72, 35, 116),
+    (64, 67, 135),
+    (52, 94, 141),
+    (41, 120, 142),
+    (32, 144, 140),
+    (34, 167, 132),
+    (68, 190, 112),
+    (121, 209, 81),
+    (189, 222, 38),
+    (253, 231, 37),
+];
+
+impl Default for Palette {
+    /// Built-in sequential palette, used when a named palette is not available.
+    /// NODATA values are rendered transparent.
+    fn default() -> Self {
+        let mut bounds = vec![NODATAVAL];
+        let mut colors = vec![Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        }];
+        for (bound, (r, g, b)) in DEFAULT_BOUNDS.iter().zip(DEFAULT_COLORS.iter()) {
+            bounds.push(*bound);
+            colors.push(Color {
+                r: *r,
+                g: *g,
+                b: *b,
+                a: 255,
+            });
+        }
+        Self { bounds, colors }
+    }
+}
+
 impl Palette {
     // pub fn new(min: f32, max: f32) -> Self {
     //     let mut palette = Self {
@@ -65,8 +110,7 @@ impl Palette {
         let mut bounds: Vec<f32> = Vec::new();
         let mut colors: Vec<Color> = Vec::new();
 
-        let lines: Vec<&str> = contents.split('\n').collect();
-        for line in lines {
+        for (line_no, line) in contents.lines().enumerate() {
             let line = line.trim();
             if line.starts_with('#') {
                 continue;
@@ -75,31 +119,32 @@ impl Palette {
                 continue;
             }
             let parts: Vec<&str> = line.split_whitespace().collect();
-            if line.len() < 5 {
-                warn!("warning skipping line: {}", line);
+            if parts.len() < 5 {
+                warn!(
+                    "{s_palette_file}:{}: skipping line with less than 5 columns: {line}",
+                    line_no + 1
+                );
                 continue;
             }
-            let val: f32 = parts[0]
-                .parse()
-                .map_err(|err| format!("Cannot parse {line} {err}"))?;
-
-            let r: u8 = parts[1]
-                .parse()
-                .map_err(|err| format!("Cannot parse {line} {err}"))?;
-            let g: u8 = parts[2]
-                .parse()
-                .map_err(|err| format!("Cannot parse {line} {err}"))?;
-            let b: u8 = parts[3]
-                .parse()
-                .map_err(|err| format!("Cannot parse {line} {err}"))?;
-            let a: u8 = parts[4]
-                .parse()
-                .map_err(|err| format!("Cannot parse {line} {err}"))?;
+            let parse_error = |err: &dyn std::fmt::Display| {
+                format!(
+                    "cannot parse palette file {s_palette_file} at line {}: \"{line}\": {err}",
+                    line_no + 1
+                )
+            };
+            let val: f32 = parts[0].parse().map_err(|err| parse_error(&err))?;
+            let r: u8 = parts[1].parse().map_err(|err| parse_error(&err))?;
+            let g: u8 = parts[2].parse().map_err(|err| parse_error(&err))?;
+            let b: u8 = parts[3].parse().map_err(|err| parse_error(&err))?;
+            let a: u8 = parts[4].parse().map_err(|err| parse_error(&err))?;
 
             let c = Color { r, g, b, a };
             bounds.push(val);
             colors.push(c);
         }
+        if bounds.is_empty() {
+            return Err(format!("palette file {s_palette_file} contains no colors").into());
+        }
         Ok(Self { bounds, colors })
     }
 
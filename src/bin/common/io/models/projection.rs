@@ -0,0 +1,59 @@
+#[cfg(feature = "gdal")]
+use gdal::spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef};
+
+use crate::common::helpers::RISICOError;
+
+/// EPSG code of the lat/lon coordinates (WGS84)
+pub const EPSG_LAT_LON: u32 = 4326;
+
+/// Spatial reference of the EPSG code, with the x/y (lon/lat) axis order
+#[cfg(feature = "gdal")]
+pub fn spatial_ref(epsg: u32) -> Result<SpatialRef, gdal::errors::GdalError> {
+    let mut srs = SpatialRef::from_epsg(epsg)?;
+    srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    Ok(srs)
+}
+
+#[cfg(feature = "gdal")]
+fn transform_with_gdal(
+    from: u32,
+    to: u32,
+    xs: &[f32],
+    ys: &[f32],
+) -> Result<(Vec<f32>, Vec<f32>), gdal::errors::GdalError> {
+    let transform = CoordTransform::new(&spatial_ref(from)?, &spatial_ref(to)?)?;
+    let mut xs: Vec<f64> = xs.iter().map(|x| *x as f64).collect();
+    let mut ys: Vec<f64> = ys.iter().map(|y| *y as f64).collect();
+    transform.transform_coords(&mut xs, &mut ys, &mut [])?;
+    Ok((
+        xs.into_iter().map(|x| x as f32).collect(),
+        ys.into_iter().map(|y| y as f32).collect(),
+    ))
+}
+
+/// Transform the x/y coordinates from the EPSG coordinate system `from` to `to`
+#[cfg(feature = "gdal")]
+pub fn transform_coordinates(
+    from: u32,
+    to: u32,
+    xs: &[f32],
+    ys: &[f32],
+) -> Result<(Vec<f32>, Vec<f32>), RISICOError> {
+    transform_with_gdal(from, to, xs, ys).map_err(|err| {
+        format!("Cannot transform the coordinates from EPSG:{from} to EPSG:{to}: {err}").into()
+    })
+}
+
+/// Transform the x/y coordinates from the EPSG coordinate system `from` to `to`
+#[cfg(not(feature = "gdal"))]
+pub fn transform_coordinates(
+    from: u32,
+    to: u32,
+    _xs: &[f32],
+    _ys: &[f32],
+) -> Result<(Vec<f32>, Vec<f32>), RISICOError> {
+    Err(format!(
+        "Cannot transform the coordinates from EPSG:{from} to EPSG:{to}: gdal support is not enabled"
+    )
+    .into())
+}
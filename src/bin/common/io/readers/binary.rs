@@ -1,26 +1,56 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
-use libflate::gzip::{self, Decoder};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use libflate::gzip;
 use log::warn;
 use ndarray::Array1;
 use risico::{constants::NODATAVAL, models::input::InputVariableName};
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     error::Error,
     fmt::{Display, Formatter},
     fs::File,
     io::{self, BufRead, Read},
     path::Path,
+    sync::Arc,
 };
 
-use crate::common::io::models::grid::Grid;
+use crate::common::io::models::grid::{
+    hash_coordinates, interpolate, CellWeights, Grid, InterpolationMode,
+};
 
 use crate::common::io::models::grid::{IrregularGrid, RegularGrid};
 use rayon::prelude::*;
 
-use super::prelude::InputHandler;
+use super::envi::{is_envi_file, read_envi_grid, read_envi_values};
+use super::index_cache::{IndexCache, MemoryIndexCache};
+use super::prelude::{format_input_summary, InputHandler};
+
+/// Magic bytes at the start of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Open a ZBIN file, decompressing it with zstd or gzip according to its magic bytes
+fn open_zbin_file(file: &str) -> Result<Box<dyn Read>, io::Error> {
+    let input = File::open(file).unwrap_or_else(|_| panic!("Can't open file: {}", file));
 
-fn read_header_from_file<T>(decoder: &mut Decoder<T>) -> Result<(u32, u32, u32), io::Error>
+    let mut input = io::BufReader::new(input);
+    let is_zstd = input.fill_buf()?.starts_with(&ZSTD_MAGIC);
+    if !is_zstd {
+        return Ok(Box::new(gzip::Decoder::new(input)?));
+    }
+    #[cfg(feature = "zstd")]
+    {
+        Ok(Box::new(zstd::stream::read::Decoder::with_buffer(input)?))
+    }
+    #[cfg(not(feature = "zstd"))]
+    {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Cannot read {file}: zstd support is not enabled"),
+        ))
+    }
+}
+
+fn read_header_from_file<T>(decoder: &mut T) -> Result<(u32, u32, u32), io::Error>
 where
     T: Read,
 {
@@ -39,7 +69,7 @@ where
     Ok((is_regular, nrows, ncols))
 }
 
-fn read_array_from_file<T>(decoder: &mut Decoder<T>, len: u32) -> Result<Array1<f32>, io::Error>
+fn read_array_from_file<T>(decoder: &mut T, len: u32) -> Result<Array1<f32>, io::Error>
 where
     T: Read,
 {
@@ -61,10 +91,10 @@ where
 }
 
 pub fn read_grid_from_file(file: &str) -> Result<Box<dyn Grid>, io::Error> {
-    let input = File::open(file).unwrap_or_else(|_| panic!("Can't open file: {}", file));
-
-    let input = io::BufReader::new(input);
-    let mut decoder = gzip::Decoder::new(input)?;
+    if is_envi_file(file) {
+        return read_envi_grid(file);
+    }
+    let mut decoder = open_zbin_file(file)?;
 
     let (is_regular, nrows, ncols) = read_header_from_file(&mut decoder)?;
     let len = nrows * ncols;
@@ -109,7 +139,7 @@ pub fn read_grid_from_file(file: &str) -> Result<Box<dyn Grid>, io::Error> {
     Ok(grid)
 }
 
-fn skip<T>(decoder: &mut Decoder<T>, len: usize) -> Result<(), io::Error>
+fn skip<T>(decoder: &mut T, len: usize) -> Result<(), io::Error>
 where
     T: Read,
 {
@@ -120,10 +150,10 @@ where
 /// read a file and returns Grid and Vector of data
 /// Grid is a struct with the following fields:
 pub fn read_values_from_file(file: &str) -> Result<Array1<f32>, io::Error> {
-    let input = File::open(file).unwrap_or_else(|_| panic!("Can't open file: {}", file));
-
-    let input = io::BufReader::new(input);
-    let mut decoder = gzip::Decoder::new(input)?;
+    if is_envi_file(file) {
+        return read_envi_values(file);
+    }
+    let mut decoder = open_zbin_file(file)?;
 
     let (is_regular, nrows, ncols) = read_header_from_file(&mut decoder)?;
     let len = nrows * ncols;
@@ -222,11 +252,45 @@ pub struct BinaryInputFile {
 #[derive(Debug)]
 pub struct BinaryInputHandler {
     pub grid_registry: HashMap<String, Array1<Option<usize>>>,
-    pub data_map: HashMap<DateTime<Utc>, HashMap<InputVariableName, BinaryInputFile>>,
+    /// input files by date and variable, shared by the handlers of the models run in parallel
+    pub data_map: Arc<HashMap<DateTime<Utc>, HashMap<InputVariableName, BinaryInputFile>>>,
+    /// cache of the index mappings, reused across runs
+    pub index_cache: Option<IndexCache>,
+    /// index mappings already computed, reused across models
+    pub memory_index_cache: MemoryIndexCache,
+    /// interpolation of the grid values on the cells
+    pub interpolation: InterpolationMode,
+    /// bilinear interpolation weights by grid name, for the grids supporting it
+    pub weights_registry: HashMap<String, Array1<CellWeights>>,
 }
 
+/// Extensions of the binary input files accepted by default
+pub const DEFAULT_EXTENSIONS: [&str; 1] = ["zbin"];
+
 impl BinaryInputHandler {
-    pub fn new(file_path: &str) -> Result<Self, Box<dyn Error>> {
+    /// Load the list of input files, keeping the files with one of the accepted extensions
+    /// (DEFAULT_EXTENSIONS if empty) and the ENVI rasters with their header file.
+    /// The offsets [s] by variable or grid name are added to the dates of the file names.
+    pub fn new(
+        file_path: &str,
+        extensions: &[String],
+        offsets: &HashMap<String, i64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let extensions: Vec<String> = if extensions.is_empty() {
+            DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+        } else {
+            extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_string())
+                .collect()
+        };
+        let has_extension = |line: &str| {
+            Path::new(line)
+                .extension()
+                .is_some_and(|ext| extensions.iter().any(|e| ext == e.as_str()))
+        };
+        let mut skipped: Vec<String> = Vec::new();
+
         let grid_registry = HashMap::new();
         let mut data_map = HashMap::new();
 
@@ -246,7 +310,12 @@ impl BinaryInputHandler {
                 }
             };
 
-            if !line.ends_with(".zbin") {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // files with an accepted extension, or ENVI rasters with their header file
+            if !has_extension(&line) && !is_envi_file(&line) {
+                skipped.push(line);
                 continue;
             }
 
@@ -258,7 +327,14 @@ impl BinaryInputHandler {
                 }
             };
 
-            let date = date.with_timezone(&Utc);
+            // the offset of the variable, or else of the grid
+            let offset = offsets
+                .get(&variable)
+                .or_else(|| offsets.get(&grid_name))
+                .copied()
+                .unwrap_or(0);
+            let date = date.with_timezone(&Utc)
+                + Duration::try_seconds(offset).expect("Should be a valid duration");
             let input_file = BinaryInputFile {
                 grid_name,
                 path: line,
@@ -276,9 +352,22 @@ impl BinaryInputHandler {
             }
         }
 
+        if let Some(first) = skipped.first() {
+            warn!(
+                "Skipped {} input files without an accepted extension {:?} (e.g. {})",
+                skipped.len(),
+                extensions,
+                first
+            );
+        }
+
         Ok(BinaryInputHandler {
             grid_registry,
-            data_map,
+            data_map: Arc::new(data_map),
+            index_cache: None,
+            memory_index_cache: MemoryIndexCache::default(),
+            interpolation: InterpolationMode::Nearest,
+            weights_registry: HashMap::new(),
         })
     }
 }
@@ -293,6 +382,12 @@ impl InputHandler for BinaryInputHandler {
         let data = read_values_from_file(file.path.as_str())
             .unwrap_or_else(|_| panic!("Error reading file {}", file.path));
 
+        if let Some(weights) = self.weights_registry.get(&file.grid_name) {
+            let values = data.as_slice().expect("Should be contiguous");
+            let data: Vec<f32> = weights.par_iter().map(|w| interpolate(values, w)).collect();
+            return Some(Array1::from(data));
+        }
+
         let indexes = self
             .grid_registry
             .get(&file.grid_name)
@@ -317,16 +412,53 @@ impl InputHandler for BinaryInputHandler {
         timeline
     }
 
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.data_map
+            .values()
+            .flat_map(|input_files| input_files.keys().copied())
+            .collect()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        let mut available: HashMap<InputVariableName, usize> = HashMap::new();
+        for var in self.data_map.values().flat_map(|input_files| input_files.keys()) {
+            *available.entry(*var).or_default() += 1;
+        }
+        available
+    }
+
     fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        // the indexes of a previous call are for other cells
+        self.grid_registry.clear();
+        self.weights_registry.clear();
+        let cells_hash = hash_coordinates(lats, lons);
         for (_, input_files) in self.data_map.iter() {
             for (_, input_file) in input_files.iter() {
                 if !self.grid_registry.contains_key(&input_file.grid_name) {
-                    let mut grid = match read_grid_from_file(input_file.path.as_str()) {
+                    let grid = match read_grid_from_file(input_file.path.as_str()) {
                         Ok(grid) => grid,
                         Err(e) => return Err(e.into()),
                     };
 
-                    let indexes = grid.indexes(lats, lons);
+                    let indexes = self.memory_index_cache.indexes(
+                        self.index_cache.as_ref(),
+                        grid.as_ref(),
+                        lats,
+                        lons,
+                        cells_hash,
+                    );
+                    if self.interpolation == InterpolationMode::Bilinear {
+                        match grid.interpolation_weights(lats, lons) {
+                            Some(weights) => {
+                                self.weights_registry
+                                    .insert(input_file.grid_name.clone(), weights);
+                            }
+                            None => warn!(
+                                "Bilinear interpolation not supported for the grid of {}, using the nearest grid point",
+                                input_file.path
+                            ),
+                        }
+                    }
                     self.grid_registry
                         .insert(input_file.grid_name.clone(), indexes);
                 }
@@ -336,7 +468,18 @@ impl InputHandler for BinaryInputHandler {
         Ok(())
     }
 
-    fn info_input(&self) -> String {
+    fn info_input(&self, verbose: bool) -> String {
+        if !verbose {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            let mut n_files = 0;
+            for input_files in self.data_map.values() {
+                for var in input_files.keys() {
+                    *counts.entry(format!("{:?}", var)).or_insert(0) += 1;
+                    n_files += 1;
+                }
+            }
+            return format_input_summary(n_files, &counts, &self.get_timeline());
+        }
         let mut info = String::new();
         for (date, input_files) in self.data_map.iter() {
             info.push_str(&format!("Date: {}\n", date));
@@ -346,4 +489,97 @@ impl InputHandler for BinaryInputHandler {
         }
         info
     }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        let first_date = self
+            .data_map
+            .iter()
+            .filter(|(_, input_files)| input_files.contains_key(&var))
+            .map(|(date, _)| date)
+            .min()?;
+        let input_file = self.data_map.get(first_date)?.get(&var)?;
+        self.grid_registry.get(&input_file.grid_name).cloned()
+    }
+
+    fn clear_index_cache(&mut self) {
+        self.memory_index_cache.clear();
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        Some(Box::new(BinaryInputHandler {
+            grid_registry: HashMap::new(),
+            data_map: Arc::clone(&self.data_map),
+            index_cache: self.index_cache.clone(),
+            memory_index_cache: self.memory_index_cache.clone(),
+            interpolation: self.interpolation,
+            weights_registry: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::io::writers::write_to_zbin_file;
+
+    fn zbin_file(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("risico-zbin-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("Should create the test directory");
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    fn check_round_trip(file: &str, compression_level: Option<i32>) {
+        let grid = RegularGrid::new(2, 3, 40.0, 8.0, 41.0, 10.0);
+        let values = [1.0, 2.5, NODATAVAL, 4.0, 5.0, 6.25];
+        write_to_zbin_file(file, &grid, &values, compression_level).unwrap();
+
+        let read_grid = read_grid_from_file(file).unwrap();
+        assert_eq!(read_grid.shape(), (2, 3));
+        assert_eq!(read_grid.geometry_hash(), grid.geometry_hash());
+        assert_eq!(read_values_from_file(file).unwrap().to_vec(), values.to_vec());
+        let _ = std::fs::remove_file(file);
+    }
+
+    #[test]
+    fn gzip_zbin_files_still_load() {
+        check_round_trip(&zbin_file("gzip.zbin"), None);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_zbin_round_trip() {
+        let file = zbin_file("zstd.zbin");
+        check_round_trip(&file, Some(3));
+    }
+
+    #[test]
+    fn shared_handler_has_its_own_coordinates() {
+        let file = zbin_file("202407011200_SHARED_T.zbin");
+        let grid = RegularGrid::new(2, 3, 40.0, 8.0, 41.0, 10.0);
+        write_to_zbin_file(&file, &grid, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], None).unwrap();
+        let list = zbin_file("shared.txt");
+        std::fs::write(&list, format!("{file}\n")).unwrap();
+
+        let mut handler = BinaryInputHandler::new(&list, &[], &HashMap::new()).unwrap();
+        let mut shared = handler.share().expect("Should share the input files");
+        handler.set_coordinates(&[40.0], &[8.0]).unwrap();
+        shared.set_coordinates(&[41.0, 40.0], &[10.0, 9.0]).unwrap();
+
+        let date = handler.get_timeline()[0];
+        assert_eq!(shared.get_timeline(), vec![date]);
+        let values = |handler: &dyn InputHandler| {
+            handler.get_values(InputVariableName::T, &date).unwrap().to_vec()
+        };
+        assert_eq!(values(&handler), vec![1.0]);
+        assert_eq!(values(shared.as_ref()), vec![6.0, 2.0]);
+        let _ = std::fs::remove_file(&file);
+        let _ = std::fs::remove_file(&list);
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn zstd_zbin_requires_the_feature() {
+        let grid = RegularGrid::new(2, 2, 0.0, 0.0, 1.0, 1.0);
+        assert!(write_to_zbin_file(&zbin_file("zstd.zbin"), &grid, &[0.0; 4], Some(3)).is_err());
+    }
 }
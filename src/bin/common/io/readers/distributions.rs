@@ -0,0 +1,71 @@
+use std::io::{BufRead, BufReader};
+
+use risico::models::output::CellDistributions;
+
+use crate::common::{config::models::retain_cells, helpers::RISICOError};
+
+use super::source::open_source;
+
+/// Read the historical distributions of a variable on the cells.
+/// The file has a line for each cell of the cells file, with the historical values
+/// separated by whitespaces (empty lines or NODATAVAL values for cells without a distribution).
+/// Lines starting with `#` are skipped.
+/// The cells removed from the cells file as duplicates are removed with the `keep` mask.
+pub fn read_distributions(
+    file_path: &str,
+    keep: Option<&[bool]>,
+) -> Result<CellDistributions, RISICOError> {
+    let file = open_source(file_path).map_err(|err| format!("can't open file {file_path}: {err}."))?;
+    let reader = BufReader::new(file);
+
+    let mut values: Vec<Vec<f32>> = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| format!("can't read from file {file_path}: {err}."))?;
+        if line.starts_with('#') {
+            continue;
+        }
+        let cell = line
+            .split_whitespace()
+            .map(|v| v.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|err| {
+                format!("Invalid value in file {file_path} at line #{index}: {err}")
+            })?;
+        values.push(cell);
+    }
+    if let Some(keep) = keep {
+        if values.len() != keep.len() {
+            return Err(format!(
+                "File {file_path} has {} distributions for {} cells in the cells file",
+                values.len(),
+                keep.len()
+            )
+            .into());
+        }
+        retain_cells(&mut values, keep);
+    }
+    Ok(CellDistributions::new(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_cells_are_removed_from_the_distributions() {
+        let dir = std::env::temp_dir().join(format!("risico-distributions-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("distributions.txt");
+        std::fs::write(&file, "# historical values\n1 2 3\n1 2 3\n10 20 30\n").unwrap();
+        let file = file.to_str().unwrap();
+
+        let distributions = read_distributions(file, Some(&[true, false, true])).unwrap();
+        assert_eq!(distributions.len(), 2);
+        // the second cell is the third line of the file
+        assert_eq!(distributions.percentile(1, 20.0), 50.0);
+
+        // the mask must cover all the lines of the file
+        assert!(read_distributions(file, Some(&[true, false])).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use ndarray::Array1;
+use risico::constants::NODATAVAL;
+
+use crate::common::io::models::grid::{Grid, RegularGrid};
+
+/// Georeferencing of an ENVI raster, from the `map info` header field
+#[derive(Debug, Clone)]
+struct MapInfo {
+    projection: String,
+    // reference pixel (1-based, upper left corner of the first pixel is 1,1)
+    ref_x: f32,
+    ref_y: f32,
+    // coordinates of the reference pixel
+    easting: f32,
+    northing: f32,
+    // pixel size
+    size_x: f32,
+    size_y: f32,
+}
+
+/// Header of an ENVI flat binary raster
+#[derive(Debug, Clone)]
+struct EnviHeader {
+    samples: usize,
+    lines: usize,
+    bands: usize,
+    header_offset: u64,
+    data_type: u32,
+    big_endian: bool,
+    interleave: String,
+    ignore_value: Option<f64>,
+    map_info: Option<MapInfo>,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Path of the header of an ENVI raster (`name.hdr` or `name.ext.hdr`), if present
+fn header_path(file: &str) -> Option<PathBuf> {
+    let path = Path::new(file);
+    [path.with_extension("hdr"), PathBuf::from(format!("{file}.hdr"))]
+        .into_iter()
+        .find(|p| p.is_file() && p != path)
+}
+
+/// Check whether the file is an ENVI raster, i.e. it has a header file beside it
+pub fn is_envi_file(file: &str) -> bool {
+    !file.ends_with(".hdr") && header_path(file).is_some()
+}
+
+/// Split the header into `key = value` fields, values in braces can span multiple lines
+fn parse_header_fields(contents: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let mut value = value.trim().to_string();
+        if value.starts_with('{') {
+            while !value.contains('}') {
+                match lines.next() {
+                    Some(next) => {
+                        value.push(' ');
+                        value.push_str(next.trim());
+                    }
+                    None => break,
+                }
+            }
+            value = value
+                .trim_start_matches('{')
+                .trim_end_matches('}')
+                .trim()
+                .to_string();
+        }
+        fields.insert(key.trim().to_lowercase(), value);
+    }
+    fields
+}
+
+fn parse_map_info(value: &str) -> Result<MapInfo, io::Error> {
+    let parts: Vec<&str> = value.split(',').map(|p| p.trim()).collect();
+    if parts.len() < 7 {
+        return Err(invalid_data(format!("Invalid map info: {value}")));
+    }
+    let number = |idx: usize| {
+        parts[idx]
+            .parse::<f32>()
+            .map_err(|err| invalid_data(format!("Invalid map info `{}`: {err}", parts[idx])))
+    };
+    Ok(MapInfo {
+        projection: parts[0].to_string(),
+        ref_x: number(1)?,
+        ref_y: number(2)?,
+        easting: number(3)?,
+        northing: number(4)?,
+        size_x: number(5)?,
+        size_y: number(6)?,
+    })
+}
+
+fn read_header(file: &str) -> Result<EnviHeader, io::Error> {
+    let path = header_path(file)
+        .ok_or_else(|| invalid_data(format!("No ENVI header found for {file}")))?;
+    let contents = std::fs::read_to_string(&path)?;
+    if !contents.trim_start().starts_with("ENVI") {
+        return Err(invalid_data(format!("{} is not an ENVI header", path.display())));
+    }
+    let fields = parse_header_fields(&contents);
+
+    let get = |key: &str| {
+        fields
+            .get(key)
+            .ok_or_else(|| invalid_data(format!("Missing `{key}` in {}", path.display())))
+    };
+    let get_number = |key: &str| -> Result<usize, io::Error> {
+        get(key)?
+            .parse::<usize>()
+            .map_err(|err| invalid_data(format!("Invalid `{key}` in {}: {err}", path.display())))
+    };
+
+    let samples = get_number("samples")?;
+    let lines = get_number("lines")?;
+    let bands = get_number("bands").unwrap_or(1);
+    let header_offset = get_number("header offset").unwrap_or(0) as u64;
+    let data_type = get_number("data type")? as u32;
+    let big_endian = get_number("byte order").unwrap_or(0) == 1;
+    let interleave = fields
+        .get("interleave")
+        .map(|s| s.to_lowercase())
+        .unwrap_or_else(|| "bsq".to_string());
+    let ignore_value = fields
+        .get("data ignore value")
+        .and_then(|s| s.parse::<f64>().ok());
+    let map_info = fields
+        .get("map info")
+        .map(String::as_str)
+        .map(parse_map_info)
+        .transpose()?;
+
+    Ok(EnviHeader {
+        samples,
+        lines,
+        bands,
+        header_offset,
+        data_type,
+        big_endian,
+        interleave,
+        ignore_value,
+        map_info,
+    })
+}
+
+/// Size in bytes of an ENVI data type
+fn data_type_size(data_type: u32) -> Result<usize, io::Error> {
+    match data_type {
+        1 => Ok(1),          // byte
+        2 | 12 => Ok(2),     // int16, uint16
+        3 | 4 | 13 => Ok(4), // int32, float32, uint32
+        5 => Ok(8),          // float64
+        _ => Err(invalid_data(format!("Unsupported ENVI data type {data_type}"))),
+    }
+}
+
+fn decode_value(bytes: &[u8], data_type: u32, big_endian: bool) -> f64 {
+    macro_rules! decode {
+        ($t:ty) => {{
+            let b = bytes.try_into().expect("Should have the type size");
+            if big_endian {
+                <$t>::from_be_bytes(b) as f64
+            } else {
+                <$t>::from_le_bytes(b) as f64
+            }
+        }};
+    }
+    match data_type {
+        1 => bytes[0] as f64,
+        2 => decode!(i16),
+        12 => decode!(u16),
+        3 => decode!(i32),
+        13 => decode!(u32),
+        4 => decode!(f32),
+        5 => decode!(f64),
+        _ => unreachable!("data type checked in data_type_size"),
+    }
+}
+
+/// Read the grid of an ENVI raster from the `map info` of its header.
+/// Only geographic (lat/lon) rasters are supported.
+pub fn read_envi_grid(file: &str) -> Result<Box<dyn Grid>, io::Error> {
+    let header = read_header(file)?;
+    let map_info = header
+        .map_info
+        .ok_or_else(|| invalid_data(format!("No map info in the header of {file}")))?;
+    if !map_info.projection.to_lowercase().starts_with("geographic") {
+        return Err(invalid_data(format!(
+            "Unsupported projection `{}` in {file}, only Geographic Lat/Lon is supported",
+            map_info.projection
+        )));
+    }
+    if header.samples < 2 || header.lines < 2 {
+        return Err(invalid_data(format!("Grid of {file} is too small")));
+    }
+
+    // coordinates of the pixel centers
+    let lon =
+        |col: usize| map_info.easting + (col as f32 + 1.5 - map_info.ref_x) * map_info.size_x;
+    let lat =
+        |row: usize| map_info.northing - (row as f32 + 1.5 - map_info.ref_y) * map_info.size_y;
+
+    let grid = RegularGrid::new(
+        header.lines,
+        header.samples,
+        lat(header.lines - 1),
+        lon(0),
+        lat(0),
+        lon(header.samples - 1),
+    );
+    Ok(Box::new(grid))
+}
+
+/// Read the values of the first band of an ENVI raster.
+/// Rows are flipped south to north to match the RegularGrid indexing,
+/// the `data ignore value` is mapped to NODATAVAL.
+pub fn read_envi_values(file: &str) -> Result<Array1<f32>, io::Error> {
+    let header = read_header(file)?;
+    let size = data_type_size(header.data_type)?;
+    let (rows, cols) = (header.lines, header.samples);
+
+    let mut reader = BufReader::new(File::open(file)?);
+    reader.seek(SeekFrom::Start(header.header_offset))?;
+    let mut buffer = vec![0u8; rows * cols * header.bands * size];
+    reader.read_exact(&mut buffer)?;
+
+    // position of a value of the first band in the file
+    let offset = |row: usize, col: usize| -> usize {
+        match header.interleave.as_str() {
+            "bil" => (row * header.bands * cols + col) * size,
+            "bip" => ((row * cols + col) * header.bands) * size,
+            _ => (row * cols + col) * size,
+        }
+    };
+
+    let mut values = Vec::with_capacity(rows * cols);
+    for row in (0..rows).rev() {
+        for col in 0..cols {
+            let start = offset(row, col);
+            let value =
+                decode_value(&buffer[start..start + size], header.data_type, header.big_endian);
+            let is_nodata = header.ignore_value == Some(value) || value.is_nan();
+            values.push(if is_nodata { NODATAVAL } else { value as f32 });
+        }
+    }
+    Ok(Array1::from(values))
+}
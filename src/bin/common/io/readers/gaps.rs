@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use ndarray::{Array1, Zip};
+use risico::{constants::NODATAVAL, models::input::InputVariableName};
+use serde_derive::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::common::helpers::RISICOError;
+
+use super::prelude::InputHandler;
+
+/// Behaviour when the input timeline has a gap larger than the allowed one
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString, Display, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum TimelineGapPolicy {
+    /// log a warning and continue
+    Warn,
+    /// add intermediate timesteps by linear interpolation of the inputs
+    /// (the wind direction along the shortest arc, no rain in the added timesteps)
+    Interpolate,
+    /// stop the run
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TimelineGapConfig {
+    pub max_gap_hours: i64,
+    pub policy: TimelineGapPolicy,
+}
+
+/// Find the gaps larger than max_gap_hours in the timeline
+fn find_gaps(
+    timeline: &[DateTime<Utc>],
+    max_gap_hours: i64,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    timeline
+        .windows(2)
+        .filter(|w| (w[1] - w[0]).num_hours() > max_gap_hours)
+        .map(|w| (w[0], w[1]))
+        .collect()
+}
+
+/// Input handler filling the gaps of the timeline of another handler
+pub struct GapFillingInputHandler {
+    handler: Box<dyn InputHandler>,
+    max_gap_hours: i64,
+    // timeline of the wrapped handler
+    source_timeline: Vec<DateTime<Utc>>,
+    timeline: Vec<DateTime<Utc>>,
+}
+
+/// Linear interpolation between the values of two timesteps, at the fraction w from the first
+fn interpolate_values(
+    var: InputVariableName,
+    prev_values: &Array1<f32>,
+    next_values: &Array1<f32>,
+    w: f32,
+) -> Array1<f32> {
+    Zip::from(prev_values)
+        .and(next_values)
+        .par_map_collect(|p, n| {
+            if *p <= (NODATAVAL + 1.0) || *n <= (NODATAVAL + 1.0) {
+                return NODATAVAL;
+            }
+            if var == InputVariableName::D {
+                // the direction [degrees] along the shortest arc, across north
+                let delta = (n - p + 540.0).rem_euclid(360.0) - 180.0;
+                return (p + w * delta).rem_euclid(360.0);
+            }
+            p + w * (n - p)
+        })
+}
+
+impl GapFillingInputHandler {
+    /// Check the gaps of the handler timeline and apply the configured policy
+    pub fn wrap(
+        handler: Box<dyn InputHandler>,
+        config: &TimelineGapConfig,
+    ) -> Result<Box<dyn InputHandler>, RISICOError> {
+        let timeline = handler.get_timeline();
+        let gaps = find_gaps(&timeline, config.max_gap_hours);
+        if gaps.is_empty() {
+            return Ok(handler);
+        }
+        for (start, end) in &gaps {
+            warn!(
+                "Gap of {} hours in input timeline between {} and {}",
+                (*end - *start).num_hours(),
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%Y-%m-%d %H:%M")
+            );
+        }
+        match config.policy {
+            TimelineGapPolicy::Warn => Ok(handler),
+            TimelineGapPolicy::Abort => Err(format!(
+                "Input timeline has {} gaps larger than {} hours",
+                gaps.len(),
+                config.max_gap_hours
+            )
+            .into()),
+            TimelineGapPolicy::Interpolate => {
+                let max_gap_hours = config.max_gap_hours.max(1);
+                let mut filled = timeline.clone();
+                let step = Duration::try_hours(max_gap_hours).expect("Should be valid");
+                for (start, end) in &gaps {
+                    let mut time = *start + step;
+                    while time < *end {
+                        filled.push(time);
+                        time += step;
+                    }
+                }
+                filled.sort();
+                Ok(Box::new(GapFillingInputHandler {
+                    handler,
+                    max_gap_hours,
+                    source_timeline: timeline,
+                    timeline: filled,
+                }))
+            }
+        }
+    }
+
+    /// Find the timesteps of the wrapped handler surrounding the date
+    fn bracket(&self, date: &DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let timeline = &self.source_timeline;
+        let next_index = timeline.partition_point(|t| t <= date);
+        if next_index == 0 || next_index == timeline.len() {
+            return None;
+        }
+        Some((timeline[next_index - 1], timeline[next_index]))
+    }
+}
+
+impl InputHandler for GapFillingInputHandler {
+    fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        self.handler.set_coordinates(lats, lons)
+    }
+
+    fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+        if let Some(values) = self.handler.get_values(var, date) {
+            return Some(values);
+        }
+        let (prev, next) = self.bracket(date)?;
+        if (next - prev).num_hours() <= self.max_gap_hours {
+            return None;
+        }
+        // the rain is an amount per timestep: sharing the neighbouring amounts with the added
+        // timesteps would inflate the rain of the gap
+        if matches!(var, InputVariableName::P | InputVariableName::O) {
+            return None;
+        }
+        let prev_values = self.handler.get_values(var, &prev)?;
+        let next_values = self.handler.get_values(var, &next)?;
+        let w = (*date - prev).num_seconds() as f32 / (next - prev).num_seconds() as f32;
+        Some(interpolate_values(var, &prev_values, &next_values, w))
+    }
+
+    fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+        self.timeline.clone()
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.handler.get_variables()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        self.handler.available_variables()
+    }
+
+    fn info_input(&self, verbose: bool) -> String {
+        self.handler.info_input(verbose)
+    }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        self.handler.get_source_indexes(var)
+    }
+
+    fn clear_index_cache(&mut self) {
+        self.handler.clear_index_cache()
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        let handler = self.handler.share()?;
+        Some(Box::new(GapFillingInputHandler {
+            handler,
+            max_gap_hours: self.max_gap_hours,
+            source_timeline: self.source_timeline.clone(),
+            timeline: self.timeline.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::common::io::readers::memory::MemoryInputHandler;
+
+    /// Input at 00 and 06, with a wind from NNW to NNE and the rain of the second step
+    fn input() -> Box<dyn InputHandler> {
+        let mut handler = MemoryInputHandler::new(1);
+        for (hour, t, d, p) in [(0, 10.0, 350.0, 0.0), (6, 16.0, 10.0, 6.0)] {
+            let time = Utc.with_ymd_and_hms(2024, 7, 1, hour, 0, 0).unwrap();
+            handler.insert(InputVariableName::T, time, Array1::from_elem(1, t)).unwrap();
+            handler.insert(InputVariableName::D, time, Array1::from_elem(1, d)).unwrap();
+            handler.insert(InputVariableName::P, time, Array1::from_elem(1, p)).unwrap();
+        }
+        Box::new(handler)
+    }
+
+    fn config(policy: TimelineGapPolicy) -> TimelineGapConfig {
+        TimelineGapConfig {
+            max_gap_hours: 3,
+            policy,
+        }
+    }
+
+    #[test]
+    fn warn_keeps_the_timeline() {
+        let handler = GapFillingInputHandler::wrap(input(), &config(TimelineGapPolicy::Warn));
+        assert_eq!(handler.unwrap().get_timeline().len(), 2);
+    }
+
+    #[test]
+    fn abort_rejects_the_gaps() {
+        assert!(GapFillingInputHandler::wrap(input(), &config(TimelineGapPolicy::Abort)).is_err());
+        // no gap larger than the allowed one
+        let config = TimelineGapConfig {
+            max_gap_hours: 6,
+            policy: TimelineGapPolicy::Abort,
+        };
+        assert!(GapFillingInputHandler::wrap(input(), &config).is_ok());
+    }
+
+    #[test]
+    fn interpolate_fills_the_gaps() {
+        let handler =
+            GapFillingInputHandler::wrap(input(), &config(TimelineGapPolicy::Interpolate))
+                .unwrap();
+        let filled = Utc.with_ymd_and_hms(2024, 7, 1, 3, 0, 0).unwrap();
+        assert_eq!(handler.get_timeline().len(), 3);
+        assert_eq!(handler.get_timeline()[1], filled);
+
+        let value = |var| handler.get_values(var, &filled).map(|values| values[0]);
+        assert_eq!(value(InputVariableName::T), Some(13.0));
+        // across north, not through south
+        let direction = value(InputVariableName::D).unwrap();
+        assert!(direction.abs() < 1e-3 || (direction - 360.0).abs() < 1e-3);
+        // the rain is not spread on the added timestep
+        assert_eq!(value(InputVariableName::P), None);
+        let first = handler.get_timeline()[0];
+        assert_eq!(handler.get_values(InputVariableName::P, &first).unwrap()[0], 0.0);
+    }
+}
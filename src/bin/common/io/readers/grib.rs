@@ -0,0 +1,429 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fs::{self, File},
+    io::BufReader,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use grib::codetables::{Code, CodeTable4_4};
+use itertools::Itertools;
+use log::{debug, warn};
+use ndarray::Array1;
+use rayon::prelude::*;
+use risico::{constants::NODATAVAL, models::input::InputVariableName};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::common::helpers::check_coordinates;
+use crate::common::io::models::grid::{hash_coordinates, Grid, IrregularGrid};
+
+use super::index_cache::{IndexCache, MemoryIndexCache};
+use super::prelude::{format_input_summary, InputHandler};
+
+/// Product identification of a GRIB2 parameter, with the fixed surface it refers to
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GribParameter {
+    discipline: u8,
+    category: u8,
+    number: u8,
+    surface_type: u8,
+    level: f64,
+}
+
+/// GRIB2 parameters of the ecCodes short names supported in the variable map
+fn parameter_from_short_name(short_name: &str) -> Option<GribParameter> {
+    // (discipline, category, number, surface type, level)
+    // surface types: 1 ground or water surface, 103 specified height above ground [m]
+    let parameter = match short_name {
+        "2t" => (0, 0, 0, 103, 2.0),
+        "2d" => (0, 0, 6, 103, 2.0),
+        "2r" => (0, 1, 1, 103, 2.0),
+        "2sh" => (0, 1, 0, 103, 2.0),
+        "10u" => (0, 2, 2, 103, 10.0),
+        "10v" => (0, 2, 3, 103, 10.0),
+        "10si" => (0, 2, 1, 103, 10.0),
+        "10wdir" => (0, 2, 0, 103, 10.0),
+        "tp" => (0, 1, 8, 1, 0.0),
+        "sp" => (0, 3, 0, 1, 0.0),
+        _ => return None,
+    };
+    let (discipline, category, number, surface_type, level) = parameter;
+    Some(GribParameter {
+        discipline,
+        category,
+        number,
+        surface_type,
+        level,
+    })
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GribVariableEntry {
+    pub internal_name: InputVariableName,
+    pub short_name: String, // ecCodes short name of the parameter (e.g. 2t, 10u, tp)
+    pub level: Option<f64>, // level of the fixed surface (default: the level of the short name)
+    pub offset: Option<i64>, // offset in seconds to be applied to the message time
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GribInputConfiguration {
+    pub variable_map: Vec<GribVariableEntry>,
+    #[serde(skip)]
+    pub strict_coordinates: bool, // reject files with non-finite coordinates (set from the input options)
+    #[serde(skip)]
+    pub index_cache_path: Option<String>, // directory of the cached index mappings (set from the input options)
+}
+
+impl Default for GribInputConfiguration {
+    fn default() -> Self {
+        use InputVariableName::*;
+        let variable_map = [
+            (T, "2t"),
+            (R, "2d"),
+            (H, "2r"),
+            (Q, "2sh"),
+            (PSFC, "sp"),
+            (U, "10u"),
+            (V, "10v"),
+            (W, "10si"),
+            (P, "tp"),
+        ]
+        .into_iter()
+        .map(|(internal_name, short_name)| GribVariableEntry {
+            internal_name,
+            short_name: short_name.to_string(),
+            level: None,
+            offset: None,
+        })
+        .collect();
+        GribInputConfiguration {
+            variable_map,
+            strict_coordinates: false,
+            index_cache_path: None,
+        }
+    }
+}
+
+/// Check whether the file has a GRIB extension
+pub fn is_grib_file(file: &str) -> bool {
+    file.ends_with(".grib") || file.ends_with(".grib2") || file.ends_with(".grb2")
+}
+
+/// Check whether the directory contains GRIB files
+pub fn has_grib_files(path: &str) -> bool {
+    let Ok(entries) = fs::read_dir(path) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.path().is_file() && is_grib_file(&entry.path().to_string_lossy()))
+}
+
+/// A message of a GRIB file providing a variable at a date
+struct GribMessageRecord {
+    file: String,
+    // position of the submessage in the file
+    index: usize,
+    variable: InputVariableName,
+    time: DateTime<Utc>,
+    // position of the grid of the message in the handler grids
+    grid: usize,
+}
+
+/// Seconds of a forecast time unit (code table 4.4)
+fn unit_seconds(unit: &Code<CodeTable4_4, u8>) -> Option<i64> {
+    use CodeTable4_4::*;
+    match unit {
+        Code::Name(Second) => Some(1),
+        Code::Name(Minute) => Some(60),
+        Code::Name(Hour) => Some(3600),
+        Code::Name(ThreeHours) => Some(3 * 3600),
+        Code::Name(SixHours) => Some(6 * 3600),
+        Code::Name(TwelveHours) => Some(12 * 3600),
+        Code::Name(Day) => Some(86400),
+        _ => None,
+    }
+}
+
+/// Validity time of a submessage: reference time + forecast time + offset
+fn message_time<R>(
+    submessage: &grib::SubMessage<'_, R>,
+    offset_seconds: i64,
+) -> Option<DateTime<Utc>> {
+    let ref_time = submessage.identification().ref_time_unchecked();
+    let ref_time = Utc
+        .with_ymd_and_hms(
+            ref_time.year as i32,
+            ref_time.month as u32,
+            ref_time.day as u32,
+            ref_time.hour as u32,
+            ref_time.minute as u32,
+            ref_time.second as u32,
+        )
+        .single()?;
+    let lead_seconds = match submessage.prod_def().forecast_time() {
+        Some(forecast_time) => unit_seconds(&forecast_time.unit)? * forecast_time.value as i64,
+        None => 0,
+    };
+    Some(ref_time + Duration::try_seconds(lead_seconds + offset_seconds)?)
+}
+
+/// Find the configured variable provided by a submessage
+fn message_variable<R>(
+    submessage: &grib::SubMessage<'_, R>,
+    variable_map: &[(GribParameter, &GribVariableEntry)],
+) -> Option<GribVariableEntry> {
+    let discipline = submessage.indicator().discipline;
+    let prod_def = submessage.prod_def();
+    let category = prod_def.parameter_category()?;
+    let number = prod_def.parameter_number()?;
+    let (surface, _) = prod_def.fixed_surfaces()?;
+    variable_map
+        .iter()
+        .find(|(parameter, _)| {
+            parameter.discipline == discipline
+                && parameter.category == category
+                && parameter.number == number
+                && parameter.surface_type == surface.surface_type
+                && (surface.value() - parameter.level).abs() < 1e-3
+        })
+        .map(|(_, entry)| (*entry).clone())
+}
+
+/// Values of the submessage at the given position of a GRIB file
+fn read_message_values(file: &str, index: usize) -> Result<Array1<f32>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(file)?);
+    let grib2 = grib::from_reader(reader)?;
+    let (_, submessage) = grib2
+        .iter()
+        .nth(index)
+        .ok_or_else(|| format!("Could not find message #{index} in {file}"))?;
+    let decoder = grib::Grib2SubmessageDecoder::from(submessage)?;
+    let values = decoder
+        .dispatch()?
+        .map(|v| if v.is_nan() { NODATAVAL } else { v })
+        .collect::<Array1<f32>>();
+    Ok(values)
+}
+
+/// Messages and grids of the GRIB files, shared by the handlers of the models run in parallel
+#[derive(Default)]
+struct GribMessages {
+    records: Vec<GribMessageRecord>,
+    grids: Vec<IrregularGrid>,
+}
+
+pub struct GribInputHandler {
+    messages: Arc<GribMessages>,
+    // indexes of the cells in each grid of the messages
+    indexes: Vec<Option<Array1<Option<usize>>>>,
+    config: GribInputConfiguration,
+    // index mappings already computed, reused across models
+    memory_index_cache: MemoryIndexCache,
+}
+
+impl GribInputHandler {
+    pub fn new(path: &str, config: &GribInputConfiguration) -> Result<Self, Box<dyn Error>> {
+        let variable_map: Vec<(GribParameter, &GribVariableEntry)> = config
+            .variable_map
+            .iter()
+            .filter_map(|entry| match parameter_from_short_name(&entry.short_name) {
+                Some(parameter) => Some((
+                    GribParameter {
+                        level: entry.level.unwrap_or(parameter.level),
+                        ..parameter
+                    },
+                    entry,
+                )),
+                None => {
+                    warn!("GRIB short name {} not supported", entry.short_name);
+                    None
+                }
+            })
+            .collect();
+
+        let mut messages = GribMessages::default();
+        // grids already registered, by geometry hash
+        let mut grid_hashes: HashMap<u64, usize> = HashMap::new();
+
+        for entry in fs::read_dir(path)? {
+            let file_path = entry?.path();
+            let file = file_path.to_string_lossy().into_owned();
+            if !file_path.is_file() || !is_grib_file(&file) {
+                continue;
+            }
+            let strict = config.strict_coordinates;
+            if let Err(err) =
+                messages.register_file(&file, &variable_map, &mut grid_hashes, strict)
+            {
+                warn!("Error inspecting file {}: {}", file, err);
+            }
+        }
+        Ok(GribInputHandler {
+            indexes: vec![None; messages.grids.len()],
+            messages: Arc::new(messages),
+            config: config.clone(),
+            memory_index_cache: MemoryIndexCache::default(),
+        })
+    }
+}
+
+impl GribMessages {
+    /// Register the messages of a GRIB file providing the configured variables
+    fn register_file(
+        &mut self,
+        file: &str,
+        variable_map: &[(GribParameter, &GribVariableEntry)],
+        grid_hashes: &mut HashMap<u64, usize>,
+        strict_coordinates: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let reader = BufReader::new(File::open(file)?);
+        let grib2 = grib::from_reader(reader)?;
+        let mut n_messages = 0;
+        for (index, (_, submessage)) in grib2.iter().enumerate() {
+            let Some(entry) = message_variable(&submessage, variable_map) else {
+                continue;
+            };
+            let Some(time) = message_time(&submessage, entry.offset.unwrap_or(0)) else {
+                warn!("Invalid time of message #{index} in {file}");
+                continue;
+            };
+
+            let (lats, lons): (Vec<f32>, Vec<f32>) = submessage.latlons()?.unzip();
+            check_coordinates(file, &lats, &lons, strict_coordinates).map_err(String::from)?;
+            let (ncols, nrows) = submessage.grid_shape()?;
+            let grid = IrregularGrid::new(nrows, ncols, Array1::from(lats), Array1::from(lons));
+            let grid = *grid_hashes.entry(grid.geometry_hash()).or_insert_with(|| {
+                self.grids.push(grid);
+                self.grids.len() - 1
+            });
+
+            self.records.push(GribMessageRecord {
+                file: file.to_owned(),
+                index,
+                variable: entry.internal_name,
+                time,
+                grid,
+            });
+            n_messages += 1;
+        }
+        if n_messages == 0 {
+            debug!("No specified variables found in file {}", file);
+        }
+        Ok(())
+    }
+}
+
+impl InputHandler for GribInputHandler {
+    fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+        for record in &self.messages.records {
+            if record.variable != var || record.time != *date {
+                continue;
+            }
+            let values = match read_message_values(&record.file, record.index) {
+                Ok(values) => values,
+                Err(err) => {
+                    warn!(
+                        "Error reading message #{} from file {}: {}",
+                        record.index, record.file, err
+                    );
+                    continue;
+                }
+            };
+            let data: Vec<f32> = self.indexes[record.grid]
+                .as_ref()
+                .expect("indexes should be set")
+                .par_iter()
+                .map(|index| index.and_then(|idx| values.get(idx).copied()).unwrap_or(NODATAVAL))
+                .collect();
+            return Some(Array1::from(data));
+        }
+        None
+    }
+
+    fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+        self.messages
+            .records
+            .iter()
+            .map(|record| record.time)
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.messages.records.iter().map(|record| record.variable).collect()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        self.messages
+            .records
+            .iter()
+            .map(|record| (record.variable, record.time))
+            .unique()
+            .counts_by(|(var, _)| var)
+    }
+
+    fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        let cache = self.config.index_cache_path.as_deref().map(IndexCache::new);
+        let cells_hash = hash_coordinates(lats, lons);
+        for (grid, indexes) in self.messages.grids.iter().zip(self.indexes.iter_mut()) {
+            *indexes = Some(self.memory_index_cache.indexes(
+                cache.as_ref(),
+                grid,
+                lats,
+                lons,
+                cells_hash,
+            ));
+        }
+        Ok(())
+    }
+
+    fn info_input(&self, verbose: bool) -> String {
+        let files: Vec<&String> = self.messages.records.iter().map(|r| &r.file).unique().collect();
+        if !verbose {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for record in &self.messages.records {
+                *counts.entry(format!("{:?}", record.variable)).or_insert(0) += 1;
+            }
+            return format_input_summary(files.len(), &counts, &self.get_timeline());
+        }
+        // print the file and variables for each file
+        let mut info = String::new();
+        for file in files {
+            let variables: Vec<InputVariableName> = self
+                .messages
+                .records
+                .iter()
+                .filter(|r| &r.file == file)
+                .map(|r| r.variable)
+                .unique()
+                .collect();
+            info.push_str(&format!("File: {}\n", file));
+            info.push_str(&format!("Variables: {:?}\n", variables));
+        }
+        info
+    }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        self.messages
+            .records
+            .iter()
+            .find(|record| record.variable == var)
+            .and_then(|record| self.indexes[record.grid].clone())
+    }
+
+    fn clear_index_cache(&mut self) {
+        self.memory_index_cache.clear();
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        Some(Box::new(GribInputHandler {
+            messages: Arc::clone(&self.messages),
+            indexes: vec![None; self.messages.grids.len()],
+            config: self.config.clone(),
+            memory_index_cache: self.memory_index_cache.clone(),
+        }))
+    }
+}
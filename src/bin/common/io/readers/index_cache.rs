@@ -0,0 +1,184 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use log::{debug, info, warn};
+use ndarray::Array1;
+
+use crate::common::io::models::grid::Grid;
+
+const MAGIC: &[u8; 4] = b"RIDX";
+const NO_INDEX: u64 = u64::MAX;
+
+/// Cache of the cell to source grid index mappings, reused across the runs
+/// with the same cells and grid geometry.
+///
+/// Each mapping is stored in `{path}/{cells_hash}_{grid_hash}.idx` as
+/// `RIDX`, cells hash, grid hash, number of cells and one index per cell
+/// (all u64 little endian, u64::MAX for the cells outside the grid).
+#[derive(Debug, Clone)]
+pub struct IndexCache {
+    path: PathBuf,
+}
+
+impl IndexCache {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn file_path(&self, cells_hash: u64, grid_hash: u64) -> PathBuf {
+        self.path
+            .join(format!("{cells_hash:016x}_{grid_hash:016x}.idx"))
+    }
+
+    /// Load the mapping, if cached for the same cells and grid
+    fn load(
+        &self,
+        cells_hash: u64,
+        grid_hash: u64,
+        n_cells: usize,
+    ) -> Result<Array1<Option<usize>>, io::Error> {
+        let mut reader = BufReader::new(File::open(self.file_path(cells_hash, grid_hash))?);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let mut read_u64 = || -> Result<u64, io::Error> {
+            let mut buffer = [0u8; 8];
+            reader.read_exact(&mut buffer)?;
+            Ok(u64::from_le_bytes(buffer))
+        };
+        let header = (read_u64()?, read_u64()?, read_u64()?);
+        if &magic != MAGIC || header != (cells_hash, grid_hash, n_cells as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cached index mapping does not match the cells and grid",
+            ));
+        }
+        (0..n_cells)
+            .map(|_| read_u64().map(|idx| (idx != NO_INDEX).then_some(idx as usize)))
+            .collect()
+    }
+
+    fn store(
+        &self,
+        cells_hash: u64,
+        grid_hash: u64,
+        indexes: &Array1<Option<usize>>,
+    ) -> Result<(), io::Error> {
+        fs::create_dir_all(&self.path)?;
+        let mut writer = BufWriter::new(File::create(self.file_path(cells_hash, grid_hash))?);
+        writer.write_all(MAGIC)?;
+        for value in [cells_hash, grid_hash, indexes.len() as u64] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for idx in indexes {
+            let idx = idx.map_or(NO_INDEX, |idx| idx as u64);
+            writer.write_all(&idx.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+}
+
+/// Indexes of the grid points sampled by the cells, from the cache if available,
+/// otherwise computed with the nearest neighbour search (and cached).
+/// `cells_hash` is the `hash_coordinates` of the cells
+pub fn cached_indexes(
+    cache: Option<&IndexCache>,
+    grid: &dyn Grid,
+    lats: &[f32],
+    lons: &[f32],
+    cells_hash: u64,
+) -> Array1<Option<usize>> {
+    let Some(cache) = cache else {
+        return grid.indexes(lats, lons);
+    };
+    let grid_hash = grid.geometry_hash();
+    match cache.load(cells_hash, grid_hash, lats.len()) {
+        Ok(indexes) => {
+            info!("Using cached index mapping {cells_hash:016x}_{grid_hash:016x}");
+            return indexes;
+        }
+        Err(err) => debug!("No cached index mapping: {err}"),
+    }
+    let indexes = grid.indexes(lats, lons);
+    if let Err(err) = cache.store(cells_hash, grid_hash, &indexes) {
+        warn!("Could not cache the index mapping: {err}");
+    }
+    indexes
+}
+
+/// Index mappings computed by a handler, by cells and grid geometry hash, reused by the
+/// models run on the same cells (e.g. several models on the same cells file).
+/// The clones share the mappings, so the handlers of the models run in parallel reuse them
+#[derive(Debug, Default, Clone)]
+pub struct MemoryIndexCache {
+    mappings: Arc<Mutex<HashMap<(u64, u64), Array1<Option<usize>>>>>,
+}
+
+impl MemoryIndexCache {
+    /// Indexes of the grid points sampled by the cells, from the mappings already computed
+    /// by the handler if available, otherwise from `cached_indexes`.
+    /// `cells_hash` is the `hash_coordinates` of the cells, computed once for all the grids
+    pub fn indexes(
+        &self,
+        cache: Option<&IndexCache>,
+        grid: &dyn Grid,
+        lats: &[f32],
+        lons: &[f32],
+        cells_hash: u64,
+    ) -> Array1<Option<usize>> {
+        let key = (cells_hash, grid.geometry_hash());
+        // held while computing, so a model run in parallel on the same cells waits for the
+        // mapping instead of computing it again
+        let mut mappings = self.lock();
+        if let Some(indexes) = mappings.get(&key) {
+            debug!("Reusing index mapping {:016x}_{:016x}", key.0, key.1);
+            return indexes.clone();
+        }
+        let indexes = cached_indexes(cache, grid, lats, lons, cells_hash);
+        mappings.insert(key, indexes.clone());
+        indexes
+    }
+
+    /// Drop the mappings held in memory, for all the handlers sharing them
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// The mappings are complete even if a holder of the lock panicked
+    fn lock(&self) -> MutexGuard<'_, HashMap<(u64, u64), Array1<Option<usize>>>> {
+        self.mappings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::io::models::grid::{hash_coordinates, RegularGrid};
+
+    #[test]
+    fn memory_mappings_are_keyed_by_the_cells_hash() {
+        let grid = RegularGrid::new(2, 2, 0.0, 0.0, 1.0, 1.0);
+        let (lats, lons) = ([0.0, 1.0], [0.0, 1.0]);
+        let cells_hash = hash_coordinates(&lats, &lons);
+        let cache = MemoryIndexCache::default();
+        let indexes = cache.indexes(None, &grid, &lats, &lons, cells_hash);
+        assert_eq!(indexes.to_vec(), vec![Some(0), Some(3)]);
+        // the mapping of the same cells is not computed again
+        let reused = cache.indexes(None, &grid, &[1.0, 0.0], &[1.0, 0.0], cells_hash);
+        assert_eq!(reused, indexes);
+        assert_eq!(cache.lock().len(), 1);
+
+        // the clones of a shared handler reuse the mapping, until it is cleared
+        let shared = cache.clone();
+        let reused = shared.indexes(None, &grid, &[1.0, 0.0], &[1.0, 0.0], cells_hash);
+        assert_eq!(reused, indexes);
+        shared.clear();
+        assert!(cache.lock().is_empty());
+    }
+}
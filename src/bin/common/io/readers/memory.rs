@@ -0,0 +1,94 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+};
+
+use chrono::{DateTime, Utc};
+use ndarray::Array1;
+use risico::models::input::InputVariableName;
+
+use super::prelude::{format_input_summary, InputHandler};
+
+/// Input handler serving values held in memory, defined on a fixed set of cells
+/// (used by the self test, where the inputs are generated rather than read from files)
+pub struct MemoryInputHandler {
+    n_cells: usize,
+    timeline: Vec<DateTime<Utc>>,
+    values: HashMap<(InputVariableName, DateTime<Utc>), Array1<f32>>,
+}
+
+impl MemoryInputHandler {
+    pub fn new(n_cells: usize) -> Self {
+        Self {
+            n_cells,
+            timeline: Vec::new(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Set the values of the variable at the date, one value per cell
+    pub fn insert(
+        &mut self,
+        var: InputVariableName,
+        date: DateTime<Utc>,
+        values: Array1<f32>,
+    ) -> Result<(), Box<dyn Error>> {
+        if values.len() != self.n_cells {
+            return Err(format!(
+                "Expected {} values for variable {var}, found {}",
+                self.n_cells,
+                values.len()
+            )
+            .into());
+        }
+        if let Err(idx) = self.timeline.binary_search(&date) {
+            self.timeline.insert(idx, date);
+        }
+        self.values.insert((var, date), values);
+        Ok(())
+    }
+}
+
+impl InputHandler for MemoryInputHandler {
+    fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        // values are already defined on the cells
+        if lats.len() != self.n_cells || lons.len() != self.n_cells {
+            return Err(format!(
+                "Expected {} cells, found {} latitudes and {} longitudes",
+                self.n_cells,
+                lats.len(),
+                lons.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+        self.values.get(&(var, *date)).cloned()
+    }
+
+    fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+        self.timeline.clone()
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.values.keys().map(|(var, _)| *var).collect()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        let mut available: HashMap<InputVariableName, usize> = HashMap::new();
+        for (var, _) in self.values.keys() {
+            *available.entry(*var).or_default() += 1;
+        }
+        available
+    }
+
+    fn info_input(&self, _verbose: bool) -> String {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (var, _) in self.values.keys() {
+            *counts.entry(var.to_string()).or_default() += 1;
+        }
+        format_input_summary(0, &counts, &self.timeline)
+    }
+}
@@ -1,3 +1,14 @@
 pub mod binary;
+pub mod distributions;
+pub mod envi;
+#[cfg(feature = "grib")]
+pub mod grib;
+pub mod index_cache;
+pub mod memory;
 pub mod netcdf;
 pub mod prelude;
+#[cfg(feature = "object_store")]
+pub mod s3;
+pub mod source;
+pub mod gaps;
+pub mod prefetch;
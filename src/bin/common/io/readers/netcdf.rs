@@ -1,9 +1,15 @@
-use std::{collections::HashMap, error::Error, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use cftime_rs::{calendars::Calendar, utils::get_datetime_and_unit_from_units, parser::Unit};
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use itertools::Itertools;
-use log::{warn, debug};
+use log::{debug, info, warn};
 use ndarray::Array1;
 use netcdf::{extent::Extents, AttrValue, Variable};
 use rayon::prelude::*;
@@ -13,10 +19,17 @@ use serde_derive::{Deserialize, Serialize};
 use serde;
 use std::fs;
 use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumString};
 
-use crate::common::io::models::grid::{Grid, IrregularGrid};
+use crate::common::helpers::check_coordinates;
+use crate::common::io::models::grid::{
+    hash_coordinates, interpolate, CellWeights, Grid, InterpolationMode, IrregularGrid,
+    RectilinearGrid,
+};
+use crate::common::io::models::projection::{transform_coordinates, EPSG_LAT_LON};
 
-use super::prelude::InputHandler;
+use super::index_cache::{IndexCache, MemoryIndexCache};
+use super::prelude::{format_input_summary, InputHandler};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VariableEntry {
@@ -30,6 +43,32 @@ impl VariableEntry {
     }
 }
 
+/// Layout of the latitude and longitude variables
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString, Display, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum NetCdfGridType {
+    /// 2-D latitude and longitude variables
+    Irregular,
+    /// 1-D latitude and longitude coordinate vectors
+    Rectilinear,
+}
+
+/// Choice of the file when several files hold a variable at the same time
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, EnumString, Display, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum NetCdfConflictPolicy {
+    /// the first file in name order, with a warning
+    #[default]
+    #[serde(alias = "first")]
+    First,
+    /// the last file in name order, with a warning
+    #[serde(alias = "last")]
+    Last,
+    /// the input is rejected
+    #[serde(alias = "error")]
+    Error,
+}
+
 // Define a helper struct for deserializing the `variable_map` in the desired YAML format.
 #[derive(Debug, Deserialize)]
 struct VariableMapEntry {
@@ -46,6 +85,18 @@ pub struct NetCdfInputConfiguration {
     pub time_name: String,
     pub coords_dims: Option<(String, String)>,
     pub time_units: Option<String>,
+    pub time_calendar: Option<String>,  // calendar of the time variable (default: standard)
+    pub time_tolerance: Option<i64>,  // tolerance for matching timesteps [s] (default: exact match)
+    pub grid_type: Option<NetCdfGridType>,  // layout of the coordinates (default: detected from their dimensions)
+    pub file_pattern: Option<String>,  // pattern of the files in the input directory, e.g. **/*.nc (default: the .nc files of the directory)
+    pub conflict_policy: Option<NetCdfConflictPolicy>,  // files holding a variable at the same time (default: first)
+    pub crs: Option<u32>,  // EPSG code of the coordinates, reprojected to lat/lon (default: 4326, lat/lon)
+    #[serde(skip)]
+    pub strict_coordinates: bool,  // reject files with non-finite coordinates (set from the input options)
+    #[serde(skip)]
+    pub index_cache_path: Option<String>,  // directory of the cached index mappings (set from the input options)
+    #[serde(skip)]
+    pub interpolation: InterpolationMode,  // interpolation on the cells (set from the input options)
 }
 
 impl Default for NetCdfInputConfiguration {
@@ -66,10 +117,26 @@ impl Default for NetCdfInputConfiguration {
             time_name: "time".into(),
             coords_dims: None,
             time_units: None,
+            time_calendar: None,
+            time_tolerance: None,
+            grid_type: None,
+            file_pattern: None,
+            conflict_policy: None,
+            crs: None,
+            strict_coordinates: false,
+            index_cache_path: None,
+            interpolation: InterpolationMode::Nearest,
         }
     }
 }
 
+impl NetCdfInputConfiguration {
+    /// Check if the coordinates of the files are not lat/lon
+    fn is_projected(&self) -> bool {
+        self.crs.is_some_and(|epsg| epsg != EPSG_LAT_LON)
+    }
+}
+
 // Custom implementation for deserializing `NetCdfInputConfiguration`.
 impl<'de> serde::Deserialize<'de> for NetCdfInputConfiguration {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -84,6 +151,17 @@ impl<'de> serde::Deserialize<'de> for NetCdfInputConfiguration {
             time_name: String,
             coords_dims: Option<(String, String)>,
             time_units: Option<String>,
+            time_calendar: Option<String>,
+            #[serde(default)]
+            time_tolerance: Option<i64>,
+            #[serde(default)]
+            grid_type: Option<NetCdfGridType>,
+            #[serde(default)]
+            file_pattern: Option<String>,
+            #[serde(default)]
+            conflict_policy: Option<NetCdfConflictPolicy>,
+            #[serde(default)]
+            crs: Option<u32>,
             variable_map: Vec<VariableMapEntry>,
         }
 
@@ -110,6 +188,15 @@ impl<'de> serde::Deserialize<'de> for NetCdfInputConfiguration {
             time_name: intermediate.time_name,
             coords_dims: intermediate.coords_dims,
             time_units: intermediate.time_units,
+            time_calendar: intermediate.time_calendar,
+            time_tolerance: intermediate.time_tolerance,
+            grid_type: intermediate.grid_type,
+            file_pattern: intermediate.file_pattern,
+            conflict_policy: intermediate.conflict_policy,
+            crs: intermediate.crs,
+            strict_coordinates: false,
+            index_cache_path: None,
+            interpolation: InterpolationMode::Nearest,
         })
     }
 }
@@ -150,6 +237,26 @@ where
             .cloned()
             .unwrap_or_else(|| "time".to_owned());
         
+        let time_calendar = raw_variable_map.get("time_calendar").cloned();
+
+        let time_tolerance = raw_variable_map
+            .get("time_tolerance")
+            .and_then(|s| s.parse::<i64>().ok());
+
+        let grid_type = raw_variable_map
+            .get("grid_type")
+            .and_then(|s| NetCdfGridType::from_str(s).ok());
+
+        let file_pattern = raw_variable_map.get("file_pattern").cloned();
+
+        let conflict_policy = raw_variable_map
+            .get("conflict_policy")
+            .and_then(|s| NetCdfConflictPolicy::from_str(s).ok());
+
+        let crs = raw_variable_map
+            .get("crs")
+            .and_then(|s| s.parse::<u32>().ok());
+
         let coords_dims = raw_variable_map
             .get("coords_dims")
             .map(|s| {
@@ -194,22 +301,88 @@ where
             time_name,
             coords_dims,
             time_units: None,
+            time_calendar,
+            time_tolerance,
+            grid_type,
+            file_pattern,
+            conflict_policy,
+            crs,
+            strict_coordinates: false,
+            index_cache_path: None,
+            interpolation: InterpolationMode::Nearest,
         }
     }
 }
 
 pub struct NetCdfFileInputRecord {
     file: String,
+    // name of the file in the messages
+    name: String,
     timeline: Array1<DateTime<Utc>>,
     variables: Vec<InputVariableName>,
-    grid: IrregularGrid,
+    grid: Box<dyn Grid + Send + Sync>,
+}
+
+/// Grid points of a record sampled by the cells, set by `set_coordinates`
+#[derive(Default)]
+struct RecordSampling {
     indexes: Option<Array1<Option<usize>>>,
+    // bilinear interpolation weights, if enabled and supported by the grid
+    weights: Option<Array1<CellWeights>>,
+    // block of the grid covering the cells, the only part read from the file
+    window: Option<ReadWindow>,
+}
+
+/// Rows and columns of the grid covering the grid points used by the cells
+#[derive(Debug, Clone)]
+struct ReadWindow {
+    nrows: usize,
+    ncols: usize,
+    rows: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+}
+
+impl ReadWindow {
+    /// Smallest window containing the grid points, None if no point is used
+    fn covering(
+        (nrows, ncols): (usize, usize),
+        points: impl Iterator<Item = usize>,
+    ) -> Option<ReadWindow> {
+        let (mut min_row, mut max_row) = (usize::MAX, 0);
+        let (mut min_col, mut max_col) = (usize::MAX, 0);
+        for idx in points {
+            let (row, col) = (idx / ncols.max(1), idx % ncols.max(1));
+            min_row = min_row.min(row);
+            max_row = max_row.max(row);
+            min_col = min_col.min(col);
+            max_col = max_col.max(col);
+        }
+        if min_row > max_row || max_row >= nrows {
+            return None;
+        }
+        Some(ReadWindow {
+            nrows,
+            ncols,
+            rows: min_row..max_row + 1,
+            cols: min_col..max_col + 1,
+        })
+    }
+
+    /// Index in the window of the grid point, None if outside the window
+    fn local(&self, idx: usize) -> Option<usize> {
+        let (row, col) = (idx / self.ncols, idx % self.ncols);
+        if !self.rows.contains(&row) || !self.cols.contains(&col) {
+            return None;
+        }
+        Some((row - self.rows.start) * self.cols.len() + (col - self.cols.start))
+    }
 }
 
 /// extract the time from a netcdf file using the given attribute
 fn extract_time(
     time_var: &Variable,
     time_units: &Option<String>,
+    time_calendar: &Option<String>,
     offset_seconds: &i64,
 ) -> Result<Array1<DateTime<Utc>>, Box<dyn Error>> {
     let default_units_name: String = String::from("units");
@@ -227,18 +400,25 @@ fn extract_time(
             })
             .collect::<Array1<DateTime<Utc>>>()
     } else {
-        let units_attr_values = units_attr
-            .expect("should have units attribute")
-            .value()
-            .expect("should have a value");
-
-        let units = if let AttrValue::Str(units) = units_attr_values {
-            units.to_owned()
-        } else {
-            return Err("Could not find units".into());
+        let units = match units_attr {
+            Some(attr) => {
+                if let AttrValue::Str(units) = attr.value()? {
+                    units.to_owned()
+                } else {
+                    return Err("Could not find units".into());
+                }
+            }
+            // the time variable has no units attribute: use the override as the units string
+            None => time_units
+                .clone()
+                .expect("time_units should be set"),
         };
 
-        let calendar = Calendar::Standard;
+        let calendar = match time_calendar {
+            Some(name) => Calendar::from_str(name)
+                .map_err(|_| format!("Invalid calendar {}", name))?,
+            None => Calendar::Standard,
+        };
         let (cf_datetime, unit) = get_datetime_and_unit_from_units(&units, calendar)?;
         let duration = unit.to_duration(calendar);
 
@@ -285,6 +465,7 @@ fn extract_time(
 /// inspect a single netcdf file and builds a record
 fn register_nc_file(
     file: &str,
+    name: &str,
     config: &NetCdfInputConfiguration,
 ) -> Result<Option<NetCdfFileInputRecord>, Box<dyn Error>> {
     let nc_file = netcdf::open(file)?;
@@ -332,8 +513,105 @@ fn register_nc_file(
             .ok_or("All variables must have the same offset")?
     };
 
-    let timeline = extract_time(time_var, &config.time_units, offset)?;
+    let timeline = extract_time(time_var, &config.time_units, &config.time_calendar, offset)?;
+
+    let rectilinear = match config.grid_type {
+        // a grid regular in projected coordinates is irregular in lat/lon
+        _ if config.is_projected() => false,
+        Some(grid_type) => grid_type == NetCdfGridType::Rectilinear,
+        None => lats_var.dimensions().len() == 1 && lons_var.dimensions().len() == 1,
+    };
+    let grid: Box<dyn Grid + Send + Sync> = if rectilinear {
+        Box::new(read_rectilinear_grid(lats_var, lons_var)?)
+    } else {
+        Box::new(read_irregular_grid(name, lats_var, lons_var, config)?)
+    };
 
+    let record = NetCdfFileInputRecord {
+        file: file.to_owned(),
+        name: name.to_owned(),
+        timeline,
+        variables,
+        grid,
+    };
+
+    Ok(Some(record))
+}
+
+/// read the 1-D coordinate vectors of a rectilinear grid
+fn read_rectilinear_grid(
+    lats_var: &Variable,
+    lons_var: &Variable,
+) -> Result<RectilinearGrid, Box<dyn Error>> {
+    if lats_var.dimensions().len() != 1 || lons_var.dimensions().len() != 1 {
+        return Err("Latitude & Longitude variables must have 1 dimension for a rectilinear grid".into());
+    }
+    let lats = lats_var.values::<f32, _>(Extents::All)?.into_iter().collect();
+    let lons = lons_var.values::<f32, _>(Extents::All)?.into_iter().collect();
+    Ok(RectilinearGrid::new(lats, lons).map_err(String::from)?)
+}
+
+/// read the coordinates of an irregular grid
+/// (1-D coordinate vectors are expanded to the full mesh)
+fn read_irregular_grid(
+    file: &str,
+    lats_var: &Variable,
+    lons_var: &Variable,
+    config: &NetCdfInputConfiguration,
+) -> Result<IrregularGrid, Box<dyn Error>> {
+    let (nrows, ncols, mut nc_lats, mut nc_lons) =
+        if lats_var.dimensions().len() == 1 && lons_var.dimensions().len() == 1 {
+            read_mesh_coordinates(lats_var, lons_var)?
+        } else {
+            read_2d_coordinates(lats_var, lons_var, config)?
+        };
+
+    // projected coordinates: the latitude variable holds y and the longitude variable x
+    if let Some(epsg) = config.crs.filter(|_| config.is_projected()) {
+        let (lons, lats) = transform_coordinates(
+            epsg,
+            EPSG_LAT_LON,
+            nc_lons.as_slice().expect("Should be contiguous"),
+            nc_lats.as_slice().expect("Should be contiguous"),
+        )
+        .map_err(|err| format!("{file}: {err}"))?;
+        nc_lats = Array1::from_vec(lats);
+        nc_lons = Array1::from_vec(lons);
+    }
+
+    check_coordinates(
+        file,
+        nc_lats.as_slice().expect("Should be contiguous"),
+        nc_lons.as_slice().expect("Should be contiguous"),
+        config.strict_coordinates,
+    )
+    .map_err(String::from)?;
+
+    Ok(IrregularGrid::new(nrows, ncols, nc_lats, nc_lons))
+}
+
+/// number of rows and columns, latitudes and longitudes of the grid points
+type Coordinates = (usize, usize, Array1<f32>, Array1<f32>);
+
+/// read 1-D coordinate vectors and expand them to the full mesh
+fn read_mesh_coordinates(
+    lats_var: &Variable,
+    lons_var: &Variable,
+) -> Result<Coordinates, Box<dyn Error>> {
+    let lats = lats_var.values::<f32, _>(Extents::All)?.into_iter().collect::<Vec<_>>();
+    let lons = lons_var.values::<f32, _>(Extents::All)?.into_iter().collect::<Vec<_>>();
+    let (nrows, ncols) = (lats.len(), lons.len());
+    let nc_lats = lats.iter().flat_map(|lat| vec![*lat; ncols]).collect::<Array1<f32>>();
+    let nc_lons = (0..nrows).flat_map(|_| lons.iter().copied()).collect::<Array1<f32>>();
+    Ok((nrows, ncols, nc_lats, nc_lons))
+}
+
+/// read 2-D latitude and longitude variables
+fn read_2d_coordinates(
+    lats_var: &Variable,
+    lons_var: &Variable,
+    config: &NetCdfInputConfiguration,
+) -> Result<Coordinates, Box<dyn Error>> {
     let dimensions = lats_var.dimensions();
 
     let (extents, nrows, ncols) = if let Some((lat_dim, lon_dim)) = &config.coords_dims {
@@ -373,65 +651,210 @@ fn register_nc_file(
         .into_iter()
         .collect::<Array1<f32>>();
 
-    let grid = IrregularGrid::new(nrows, ncols, nc_lats, nc_lons);
-
-    let record = NetCdfFileInputRecord {
-        file: file.to_owned(),
-        timeline,
-        variables,
-        grid,
-        indexes: None,
-    };
-
-    Ok(Some(record))
+    Ok((nrows, ncols, nc_lats, nc_lons))
 }
 
-/// read a slice of a variable from a netcdf file
-fn read_variable_from_file(
+/// read a slice of a variable from a netcdf file, only the window of the grid if given
+/// and the variable is laid out as (time, rows, cols). Returns the window that was read,
+/// None if the whole slice was read
+fn read_variable_from_file<'a>(
     file: &str,
     variable: &str,
     time_index: usize,
-) -> Result<Array1<f32>, Box<dyn Error>> {
+    window: Option<&'a ReadWindow>,
+) -> Result<(Array1<f32>, Option<&'a ReadWindow>), Box<dyn Error>> {
     let nc_file = netcdf::open(file)?;
 
     let var = nc_file
         .variable(variable)
-        .unwrap_or_else(|| panic!("Could not find variable '{}'", variable));
+        .ok_or_else(|| format!("Could not find variable '{}'", variable))?;
 
-    let extent: Extents = (time_index, .., ..)
-        .try_into()
-        .unwrap_or_else(|_| panic!("Could not create extent '{}'", &time_index));
+    let dims: Vec<usize> = var.dimensions().iter().map(|dim| dim.len()).collect();
+    let window = window.filter(|w| dims.len() == 3 && dims[1] == w.nrows && dims[2] == w.ncols);
+    let extent: Extents = match window {
+        Some(w) => (time_index, w.rows.clone(), w.cols.clone()).try_into(),
+        None => (time_index, .., ..).try_into(),
+    }
+    .map_err(|_| format!("Could not create extent '{}'", &time_index))?;
     let values = var
         .values::<f32, _>(extent)?
         .into_iter()
         .collect::<Array1<f32>>();
 
-    Ok(values)
+    Ok((values, window))
+}
+
+/// Files in the directory matching the pattern, relative to the directory:
+/// `*` and `?` match within a path component, `**` matches any number of directories
+fn find_files(path: &Path, pattern: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()).into());
+    }
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let mut visited = HashSet::new();
+    let mut files = Vec::new();
+    collect_files(path, &components, &mut visited, &mut files);
+    files.sort();
+    Ok(files)
+}
+
+fn collect_files(
+    dir: &Path,
+    components: &[&str],
+    visited: &mut HashSet<(PathBuf, usize)>,
+    files: &mut Vec<PathBuf>,
+) {
+    let Some((component, rest)) = components.split_first() else {
+        return;
+    };
+    // each directory is visited once per pattern position, so symlink loops end here
+    let Ok(canonical) = fs::canonicalize(dir) else {
+        return;
+    };
+    if !visited.insert((canonical, components.len())) {
+        return;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Cannot read directory {}: {}", dir.display(), err);
+            return;
+        }
+    };
+    if *component == "**" {
+        // no directory
+        collect_files(dir, rest, visited, files);
+    }
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if *component == "**" {
+            if entry_path.is_dir() {
+                collect_files(&entry_path, components, visited, files);
+            }
+            continue;
+        }
+        let name = entry.file_name();
+        if !wildcard_match(component, &name.to_string_lossy()) {
+            continue;
+        }
+        if rest.is_empty() {
+            if entry_path.is_file() {
+                files.push(entry_path);
+            }
+        } else if entry_path.is_dir() {
+            collect_files(&entry_path, rest, visited, files);
+        }
+    }
+}
+
+/// Match a name with a pattern where `*` matches any sequence of characters and `?` any character
+pub(super) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` in the pattern and of the name when it was found
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // let the `*` match one more character
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 pub struct NetCdfInputHandler {
-    records: Vec<NetCdfFileInputRecord>,
+    // records of the files, shared by the handlers of the models run in parallel
+    records: Arc<Vec<NetCdfFileInputRecord>>,
+    // sampling of each record on the cells of this handler
+    sampling: Vec<RecordSampling>,
     config: NetCdfInputConfiguration,
+    // index mappings already computed, shared by the records on the same grid
+    memory_index_cache: MemoryIndexCache,
+}
+
+/// Times of each variable held by two records, by the indexes of the records
+/// (the first record holding the variable at the time and the other one) and variable name.
+/// The times closer than the tolerance [s] are the same time, as when the values are read
+fn find_conflicts(
+    records: &[NetCdfFileInputRecord],
+    tolerance: i64,
+) -> BTreeMap<(usize, usize, String), Vec<DateTime<Utc>>> {
+    let tolerance = Duration::try_seconds(tolerance.abs()).expect("Should be a valid duration");
+    let mut owners: HashMap<InputVariableName, BTreeMap<DateTime<Utc>, usize>> = HashMap::new();
+    let mut conflicts: BTreeMap<(usize, usize, String), Vec<DateTime<Utc>>> = BTreeMap::new();
+    for (idx, record) in records.iter().enumerate() {
+        for var in &record.variables {
+            let owners = owners.entry(*var).or_default();
+            for time in &record.timeline {
+                let owner = owners
+                    .range(*time - tolerance..=*time + tolerance)
+                    .map(|(_, owner)| *owner)
+                    .min()
+                    .unwrap_or(idx);
+                if owner != idx {
+                    conflicts
+                        .entry((owner, idx, var.to_string()))
+                        .or_default()
+                        .push(*time);
+                }
+                owners.entry(*time).or_insert(idx);
+            }
+        }
+    }
+    conflicts
 }
 
 impl NetCdfInputHandler {
     pub fn new(path: &str, config: &NetCdfInputConfiguration) -> Result<Self, Box<dyn Error>> {
-        let mut records = Vec::new();
-
-        // Iterate over the files in the specified directory
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let file_path = entry.path();
-
-            // Check if the entry is a file and has the .nc extension
-            if !file_path.is_file() || file_path.extension().unwrap_or_default() != "nc" {
-                continue;
+        let files = match &config.file_pattern {
+            Some(pattern) => {
+                let files = find_files(Path::new(path), pattern)?;
+                info!("Found {} files matching {} in {}", files.len(), pattern, path);
+                files
+            }
+            None => {
+                // the files in the specified directory with the .nc extension
+                let mut files = Vec::new();
+                for entry in fs::read_dir(path)? {
+                    let file_path = entry?.path();
+                    if file_path.is_file() && file_path.extension().unwrap_or_default() == "nc" {
+                        files.push(file_path);
+                    }
+                }
+                files.sort();
+                files
             }
-            // Convert the file path to a string
-            let file_path_str = file_path.to_string_lossy().into_owned();
+        };
+        let files: Vec<(String, String)> = files
+            .iter()
+            .map(|file_path| {
+                let file_path_str = file_path.to_string_lossy().into_owned();
+                (file_path_str.clone(), file_path_str)
+            })
+            .collect();
+        Self::from_sources(&files, config)
+    }
 
+    /// Handler of the files given as (name in the messages, path or URL opened by netCDF)
+    pub fn from_sources(
+        files: &[(String, String)],
+        config: &NetCdfInputConfiguration,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut records = Vec::new();
+        for (file_path_str, location) in files {
             // Call the inspect_nc_file function to build the record
-            match register_nc_file(&file_path_str, config) {
+            match register_nc_file(location, file_path_str, config) {
                 Ok(Some(record)) => {
                     records.push(record);
                 }
@@ -444,17 +867,67 @@ impl NetCdfInputHandler {
             }
         }
 
+        let policy = config.conflict_policy.unwrap_or_default();
+        let conflicts = find_conflicts(&records, config.time_tolerance.unwrap_or(0));
+        for ((first, second, var), times) in &conflicts {
+            let (first, second) = (&records[*first].name, &records[*second].name);
+            let times = format!(
+                "{} times from {} to {}",
+                times.len(),
+                times.iter().min().expect("Should have times").format("%Y-%m-%d %H:%M"),
+                times.iter().max().expect("Should have times").format("%Y-%m-%d %H:%M")
+            );
+            match policy {
+                NetCdfConflictPolicy::First => {
+                    warn!("Variable {var} in both {first} and {second} at {times}, using {first}")
+                }
+                NetCdfConflictPolicy::Last => {
+                    warn!("Variable {var} in both {first} and {second} at {times}, using {second}")
+                }
+                NetCdfConflictPolicy::Error => {
+                    return Err(
+                        format!("Variable {var} in both {first} and {second} at {times}").into()
+                    );
+                }
+            }
+        }
+        // the values are read from the first record holding the variable at the time
+        if policy == NetCdfConflictPolicy::Last {
+            records.reverse();
+        }
+
         Ok(NetCdfInputHandler {
-            records,
+            sampling: records.iter().map(|_| RecordSampling::default()).collect(),
+            records: Arc::new(records),
             config: config.clone(),
+            memory_index_cache: MemoryIndexCache::default(),
         })
     }
+
+    /// Handler of the same records and index mappings, without coordinates
+    pub fn shared(&self) -> NetCdfInputHandler {
+        NetCdfInputHandler {
+            records: Arc::clone(&self.records),
+            sampling: self.records.iter().map(|_| RecordSampling::default()).collect(),
+            config: self.config.clone(),
+            memory_index_cache: self.memory_index_cache.clone(),
+        }
+    }
 }
 
 impl InputHandler for NetCdfInputHandler {
     fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
-        for record in &self.records {
-            let time_index = record.timeline.iter().position(|t| t == date);
+        let tolerance = self.config.time_tolerance.unwrap_or(0).abs();
+        for (record, sampling) in self.records.iter().zip(&self.sampling) {
+            // nearest timestep within the tolerance
+            let time_index = record
+                .timeline
+                .iter()
+                .enumerate()
+                .map(|(idx, t)| (idx, (*t - *date).num_seconds().abs()))
+                .filter(|(_, diff)| *diff <= tolerance)
+                .min_by_key(|(_, diff)| *diff)
+                .map(|(idx, _)| idx);
 
             if time_index.is_none() || !record.variables.contains(&var) {
                 continue;
@@ -466,21 +939,50 @@ impl InputHandler for NetCdfInputHandler {
 
             let variable = &variable_info.name;
 
-            let values = read_variable_from_file(&record.file, variable, time_index);
+            let values = read_variable_from_file(
+                &record.file,
+                variable,
+                time_index,
+                sampling.window.as_ref(),
+            );
 
             match values {
                 Err(err) => {
-                    let file = &record.file;
+                    let file = &record.name;
                     warn!("Error reading variable {variable} from file {file}: {err}");
                     continue;
                 }
-                Ok(values) => {
-                    let data: Vec<f32> = record
+                Ok((values, window)) => {
+                    // grid indexes in the values that were read
+                    let local = |idx: usize| match window {
+                        Some(window) => window.local(idx),
+                        None => Some(idx),
+                    };
+                    if let Some(weights) = &sampling.weights {
+                        let values = values.as_slice().expect("Should be contiguous");
+                        let data: Vec<f32> = weights
+                            .par_iter()
+                            .map(|w| match window {
+                                Some(_) => {
+                                    let w: CellWeights = w
+                                        .iter()
+                                        .filter_map(|(idx, wt)| local(*idx).map(|idx| (idx, *wt)))
+                                        .collect();
+                                    interpolate(values, &w)
+                                }
+                                None => interpolate(values, w),
+                            })
+                            .collect();
+                        return Some(Array1::from(data));
+                    }
+                    let data: Vec<f32> = sampling
                         .indexes
                         .as_ref()
                         .expect("indexes should be set")
                         .par_iter()
-                        .map(|index| index.and_then(|idx| Some(values[idx])).unwrap_or(NODATAVAL))
+                        .map(|index| {
+                            index.and_then(local).map(|idx| values[idx]).unwrap_or(NODATAVAL)
+                        })
                         .collect();
 
                     let data = Array1::from(data);
@@ -492,31 +994,198 @@ impl InputHandler for NetCdfInputHandler {
     }
 
     fn get_timeline(&self) -> Vec<DateTime<Utc>> {
-        self.records
+        let timeline: Vec<DateTime<Utc>> = self
+            .records
             .iter()
             .flat_map(|record| record.timeline.iter())
             .unique()
             .cloned()
             .sorted()
+            .collect();
+
+        // merge the timesteps closer than the tolerance, keeping the first one
+        let tolerance = self.config.time_tolerance.unwrap_or(0).abs();
+        if tolerance == 0 {
+            return timeline;
+        }
+        let mut merged: Vec<DateTime<Utc>> = Vec::with_capacity(timeline.len());
+        for t in timeline {
+            match merged.last() {
+                Some(last) if (t - *last).num_seconds() <= tolerance => continue,
+                _ => merged.push(t),
+            }
+        }
+        merged
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.records
+            .iter()
+            .flat_map(|record| record.variables.iter().copied())
             .collect()
     }
 
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        let mut times: HashMap<InputVariableName, HashSet<DateTime<Utc>>> = HashMap::new();
+        for record in &self.records {
+            for var in &record.variables {
+                times.entry(*var).or_default().extend(record.timeline.iter());
+            }
+        }
+        times.into_iter().map(|(var, times)| (var, times.len())).collect()
+    }
+
     fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
-        for record in &mut self.records {
-            let grid = &mut record.grid;
-            let indexes = grid.indexes(lats, lons);
-            record.indexes = Some(Array1::from(indexes));
+        let cache = self.config.index_cache_path.as_deref().map(IndexCache::new);
+        let cells_hash = hash_coordinates(lats, lons);
+        for (record, sampling) in self.records.iter().zip(&mut self.sampling) {
+            let indexes = self.memory_index_cache.indexes(
+                cache.as_ref(),
+                record.grid.as_ref(),
+                lats,
+                lons,
+                cells_hash,
+            );
+            sampling.indexes = Some(indexes);
+            sampling.weights = None;
+            if self.config.interpolation == InterpolationMode::Bilinear {
+                sampling.weights = record.grid.interpolation_weights(lats, lons);
+                if sampling.weights.is_none() {
+                    warn!(
+                        "Bilinear interpolation not supported for the grid of {}, using the nearest grid point",
+                        record.name
+                    );
+                }
+            }
+            // read only the block of the grid holding the points used by the cells
+            let indexes = sampling.indexes.as_ref().expect("indexes should be set");
+            let points = indexes.iter().flatten().copied().chain(
+                sampling.weights.iter().flatten().flat_map(|w| w.iter().map(|(idx, _)| *idx)),
+            );
+            sampling.window = ReadWindow::covering(record.grid.shape(), points);
         }
         Ok(())
     }
 
-    fn info_input(&self) -> String {
+    fn info_input(&self, verbose: bool) -> String {
+        if !verbose {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for record in &self.records {
+                for var in &record.variables {
+                    *counts.entry(format!("{:?}", var)).or_insert(0) += 1;
+                }
+            }
+            return format_input_summary(self.records.len(), &counts, &self.get_timeline());
+        }
         // print the file and variables for each record
         let mut info = String::new();
         for record in &self.records {
-            info.push_str(&format!("File: {}\n", record.file));
+            info.push_str(&format!("File: {}\n", record.name));
             info.push_str(&format!("Variables: {:?}\n", record.variables));
         }
         info
     }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        self.records
+            .iter()
+            .zip(&self.sampling)
+            .find(|(record, _)| record.variables.contains(&var))
+            .and_then(|(_, sampling)| sampling.indexes.clone())
+    }
+
+    fn clear_index_cache(&mut self) {
+        self.memory_index_cache.clear();
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        Some(Box::new(self.shared()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, minutes: &[i64]) -> NetCdfFileInputRecord {
+        let start = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        NetCdfFileInputRecord {
+            file: name.to_owned(),
+            name: name.to_owned(),
+            timeline: minutes.iter().map(|m| start + Duration::try_minutes(*m).unwrap()).collect(),
+            variables: vec![InputVariableName::T],
+            grid: Box::new(RectilinearGrid::new(vec![0.0, 1.0], vec![0.0, 1.0]).unwrap()),
+        }
+    }
+
+    #[test]
+    fn conflicts_use_the_time_tolerance() {
+        let records = [record("a.nc", &[0, 60]), record("b.nc", &[10, 120])];
+        assert!(find_conflicts(&records, 0).is_empty());
+        // 00:10 is read from a.nc within 15 minutes, at 00:00
+        let conflicts = find_conflicts(&records, 900);
+        assert_eq!(conflicts.len(), 1);
+        let times = &conflicts[&(0, 1, InputVariableName::T.to_string())];
+        assert_eq!(times, &vec![records[1].timeline[0]]);
+    }
+
+    #[test]
+    fn windowed_read_matches_the_full_read() {
+        let path = std::env::temp_dir().join(format!("risico-window-{}.nc", std::process::id()));
+        let (lats, lons): (Vec<f32>, Vec<f32>) = (
+            (0..6).map(|v| v as f32).collect(),
+            (0..8).map(|v| v as f32).collect(),
+        );
+        {
+            let mut file = netcdf::create(&path).unwrap();
+            file.add_dimension("time", 1).unwrap();
+            file.add_dimension("latitude", lats.len()).unwrap();
+            file.add_dimension("longitude", lons.len()).unwrap();
+            let values: Vec<f32> = (0..lats.len() * lons.len())
+                .map(|v| v as f32 * 1.5)
+                .collect();
+            let mut var = file
+                .add_variable::<f32>("T", &["time", "latitude", "longitude"])
+                .unwrap();
+            var.put_values(&values, Extents::All).unwrap();
+        }
+        let file = path.to_string_lossy().to_string();
+        let mut record = record(&file, &[0]);
+        record.grid = Box::new(RectilinearGrid::new(lats, lons).unwrap());
+        let date = record.timeline[0];
+        let records = Arc::new(vec![record]);
+
+        // cells in the middle of the grid, between the grid points
+        let cell_lats = [2.2, 2.7, 3.4, 3.0];
+        let cell_lons = [3.1, 4.6, 5.5, 3.9];
+        for interpolation in [InterpolationMode::Nearest, InterpolationMode::Bilinear] {
+            let mut handler = NetCdfInputHandler {
+                records: Arc::clone(&records),
+                sampling: vec![RecordSampling::default()],
+                config: NetCdfInputConfiguration {
+                    interpolation,
+                    ..NetCdfInputConfiguration::default()
+                },
+                memory_index_cache: MemoryIndexCache::default(),
+            };
+            handler.set_coordinates(&cell_lats, &cell_lons).unwrap();
+            assert_eq!(
+                handler.sampling[0].weights.is_some(),
+                interpolation == InterpolationMode::Bilinear
+            );
+            let window = handler.sampling[0].window.clone().unwrap();
+            assert!(
+                window.rows.len() < 6 && window.cols.len() < 8,
+                "{:?}",
+                window
+            );
+            let windowed = handler.get_values(InputVariableName::T, &date).unwrap();
+
+            handler.sampling[0].window = None;
+            let full = handler.get_values(InputVariableName::T, &date).unwrap();
+            assert_eq!(windowed, full, "{}", interpolation);
+            assert!(full.iter().all(|v| *v != NODATAVAL));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
 }
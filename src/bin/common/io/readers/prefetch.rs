@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use ndarray::Array1;
+use risico::models::input::InputVariableName;
+
+use super::prelude::InputHandler;
+
+/// Inputs read in background at a date
+struct Prefetched {
+    // variables requested when the read started
+    variables: HashSet<InputVariableName>,
+    // None if the read failed
+    values: Option<HashMap<InputVariableName, Array1<f32>>>,
+}
+
+/// Inputs read in background, shared between the worker and the handler
+#[derive(Default)]
+struct PrefetchCache {
+    // incremented each time the coordinates change, to discard stale reads
+    generation: u64,
+    // variables requested by the model so far, the ones read ahead
+    variables: HashSet<InputVariableName>,
+    requested: HashSet<DateTime<Utc>>,
+    data: HashMap<DateTime<Utc>, Prefetched>,
+}
+
+/// Lock the mutex even if a reader panicked while holding it: the panics of the readers
+/// are caught and reported, the data behind the lock is left consistent
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Read the variables at the given date
+fn read_variables(
+    handler: &dyn InputHandler,
+    date: &DateTime<Utc>,
+    variables: &HashSet<InputVariableName>,
+) -> HashMap<InputVariableName, Array1<f32>> {
+    variables
+        .iter()
+        .filter_map(|var| handler.get_values(*var, date).map(|values| (*var, values)))
+        .collect()
+}
+
+/// A message to the worker: the generation of the coordinates, the date and the variables
+type PrefetchRequest = (u64, DateTime<Utc>, HashSet<InputVariableName>);
+
+/// InputHandler wrapper reading the inputs of the next timesteps in a background thread,
+/// while the current timestep is computed
+pub struct PrefetchInputHandler {
+    handler: Arc<Mutex<Box<dyn InputHandler>>>,
+    timeline: Vec<DateTime<Utc>>,
+    // number of timesteps read ahead of the current one
+    depth: usize,
+    shared: Arc<(Mutex<PrefetchCache>, Condvar)>,
+    sender: Mutex<Sender<PrefetchRequest>>,
+}
+
+impl PrefetchInputHandler {
+    pub fn wrap(handler: Box<dyn InputHandler>, depth: usize) -> Box<dyn InputHandler> {
+        let timeline = handler.get_timeline();
+        let handler = Arc::new(Mutex::new(handler));
+        let shared = Arc::new((Mutex::new(PrefetchCache::default()), Condvar::new()));
+        let (sender, receiver) = mpsc::channel::<PrefetchRequest>();
+
+        let worker_handler = Arc::clone(&handler);
+        let worker_shared = Arc::clone(&shared);
+        // the worker stops when the wrapper (and the sender) is dropped
+        thread::spawn(move || {
+            for (generation, date, variables) in receiver {
+                let values = {
+                    let handler = lock(&worker_handler);
+                    // a failed read leaves the timestep without inputs, instead of leaving
+                    // the model waiting for it
+                    panic::catch_unwind(AssertUnwindSafe(|| {
+                        read_variables(handler.as_ref(), &date, &variables)
+                    }))
+                    .ok()
+                };
+                if values.is_none() {
+                    warn!("Cannot read the inputs at {}", date.format("%Y-%m-%d %H:%M"));
+                }
+                let (cache_lock, cvar) = &*worker_shared;
+                let mut cache = lock(cache_lock);
+                if cache.generation == generation && cache.requested.contains(&date) {
+                    cache.data.insert(date, Prefetched { variables, values });
+                }
+                cvar.notify_all();
+            }
+        });
+
+        Box::new(PrefetchInputHandler {
+            handler,
+            timeline,
+            depth,
+            shared,
+            sender: Mutex::new(sender),
+        })
+    }
+
+    fn request(&self, cache: &mut PrefetchCache, date: DateTime<Utc>) {
+        if cache.requested.insert(date) {
+            let request = (cache.generation, date, cache.variables.clone());
+            // the worker lives as long as the sender, so the send cannot fail
+            let _ = lock(&self.sender).send(request);
+        }
+    }
+}
+
+impl InputHandler for PrefetchInputHandler {
+    fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        lock(&self.handler).set_coordinates(lats, lons)?;
+
+        let (cache_lock, _) = &*self.shared;
+        let mut cache = lock(cache_lock);
+        cache.generation += 1;
+        cache.requested.clear();
+        cache.data.clear();
+        Ok(())
+    }
+
+    fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+        let Some(position) = self.timeline.iter().position(|t| t == date) else {
+            return lock(&self.handler).get_values(var, date);
+        };
+
+        let (cache_lock, cvar) = &*self.shared;
+        let mut cache = lock(cache_lock);
+        cache.variables.insert(var);
+
+        // drop the past timesteps and read ahead the next ones
+        cache.data.retain(|t, _| t >= date);
+        cache.requested.retain(|t| t >= date);
+        for t in self.timeline.iter().skip(position).take(self.depth + 1) {
+            self.request(&mut cache, *t);
+        }
+
+        let cache = cvar
+            .wait_while(cache, |c| !c.data.contains_key(date))
+            .unwrap_or_else(PoisonError::into_inner);
+        let prefetched = cache.data.get(date).expect("Should be read");
+        match &prefetched.values {
+            None => None,
+            Some(values) if prefetched.variables.contains(&var) => values.get(&var).cloned(),
+            // a variable requested after the read started
+            Some(_) => {
+                drop(cache);
+                lock(&self.handler).get_values(var, date)
+            }
+        }
+    }
+
+    fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+        self.timeline.clone()
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        lock(&self.handler).get_variables()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        lock(&self.handler).available_variables()
+    }
+
+    fn info_input(&self, verbose: bool) -> String {
+        lock(&self.handler).info_input(verbose)
+    }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        lock(&self.handler).get_source_indexes(var)
+    }
+
+    fn clear_index_cache(&mut self) {
+        lock(&self.handler).clear_index_cache();
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        // each handler reads ahead its own timesteps, on its own coordinates
+        let handler = lock(&self.handler).share()?;
+        Some(PrefetchInputHandler::wrap(handler, self.depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    /// Handler with one value per timestep, failing to read the `failing` timestep
+    struct FailingInput {
+        timeline: Vec<DateTime<Utc>>,
+        failing: DateTime<Utc>,
+        reads: Arc<Mutex<Vec<InputVariableName>>>,
+    }
+
+    impl InputHandler for FailingInput {
+        fn set_coordinates(&mut self, _lats: &[f32], _lons: &[f32]) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+            if *date == self.failing {
+                panic!("corrupted input file");
+            }
+            lock(&self.reads).push(var);
+            Some(Array1::from_elem(1, 1.0))
+        }
+
+        fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+            self.timeline.clone()
+        }
+
+        fn get_variables(&self) -> HashSet<InputVariableName> {
+            HashSet::from([InputVariableName::T])
+        }
+
+        fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+            HashMap::new()
+        }
+
+        fn info_input(&self, _verbose: bool) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn failed_reads_skip_the_timestep() {
+        let start = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        let timeline: Vec<_> =
+            (0..3).map(|h| start + Duration::try_hours(h).unwrap()).collect();
+        let reads = Arc::new(Mutex::new(Vec::new()));
+        let handler = PrefetchInputHandler::wrap(
+            Box::new(FailingInput {
+                timeline: timeline.clone(),
+                failing: timeline[1],
+                reads: Arc::clone(&reads),
+            }),
+            1,
+        );
+
+        assert!(handler.get_values(InputVariableName::T, &timeline[0]).is_some());
+        // the failed timestep has no inputs, and the next ones are still read
+        assert!(handler.get_values(InputVariableName::T, &timeline[1]).is_none());
+        assert!(handler.get_values(InputVariableName::T, &timeline[2]).is_some());
+        assert!(handler.get_variables().contains(&InputVariableName::T));
+        // only the variables requested by the model are read
+        assert!(lock(&reads).iter().all(|var| *var == InputVariableName::T));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 
 use chrono::{DateTime, Utc};
@@ -5,7 +6,7 @@ use ndarray::Array1;
 use risico::models::input::InputVariableName;
 
 /// Trait defining the behavior of an InputHandler for models
-pub trait InputHandler {
+pub trait InputHandler: Send {
     fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>>;
 
     /// get the desired variable at the desired date
@@ -14,6 +15,67 @@ pub trait InputHandler {
     /// Returns the timeline of the input data
     fn get_timeline(&self) -> Vec<DateTime<Utc>>;
 
-    /// Return the list of input files and associated variables
-    fn info_input(&self) -> String;
+    /// Returns the variables present in the input data, at any date
+    fn get_variables(&self) -> HashSet<InputVariableName>;
+
+    /// Returns the number of timesteps at which each variable is present in the input data
+    fn available_variables(&self) -> HashMap<InputVariableName, usize>;
+
+    /// Return a summary of the input data (counts per variable and timeline span),
+    /// or the list of input files and associated variables if `verbose`
+    fn info_input(&self, verbose: bool) -> String;
+
+    /// Return the index of the source grid point sampled for each cell for the variable
+    /// (from the first source providing it), if available. Used for diagnostics.
+    fn get_source_indexes(&self, _var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        None
+    }
+
+    /// Drop the index mappings kept in memory to be reused by the next `set_coordinates`
+    fn clear_index_cache(&mut self) {}
+
+    /// Handler with its own coordinates sharing the input data already read by this one
+    /// (files, timelines and grids), for the models run in parallel.
+    /// None if the data cannot be shared
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        None
+    }
+}
+
+/// Format the summary of the input data
+pub fn format_input_summary(
+    n_files: usize,
+    counts: &BTreeMap<String, usize>,
+    timeline: &[DateTime<Utc>],
+) -> String {
+    let mut info = format!("{} files\n", n_files);
+    for (var, count) in counts {
+        info.push_str(&format!("Variable: {} Files: {}\n", var, count));
+    }
+    match (timeline.first(), timeline.last()) {
+        (Some(first), Some(last)) => info.push_str(&format!(
+            "Timeline: {} - {} ({} timesteps)\n",
+            first.format("%Y-%m-%d %H:%M"),
+            last.format("%Y-%m-%d %H:%M"),
+            timeline.len()
+        )),
+        _ => info.push_str("Timeline: empty\n"),
+    }
+    info
+}
+
+/// Format the number of timesteps of each available variable, out of the timesteps of the input
+pub fn format_available_variables(
+    available: &HashMap<InputVariableName, usize>,
+    n_timesteps: usize,
+) -> String {
+    let counts: BTreeMap<String, usize> = available
+        .iter()
+        .map(|(var, count)| (var.to_string(), *count))
+        .collect();
+    let mut info = String::new();
+    for (var, count) in counts {
+        info.push_str(&format!("Variable: {} Timesteps: {}/{}\n", var, count, n_timesteps));
+    }
+    info
 }
@@ -0,0 +1,146 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use http::Method;
+use log::info;
+use ndarray::Array1;
+use object_store::{aws::AmazonS3Builder, path::Path, signer::Signer, ObjectStore};
+use risico::models::input::InputVariableName;
+
+use super::netcdf::{wildcard_match, NetCdfInputConfiguration, NetCdfInputHandler};
+use super::prelude::InputHandler;
+
+/// Validity of the signed URLs of the objects, long enough for the whole run
+const SIGNED_URL_EXPIRY: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Match the components of an object key, relative to the prefix, with the components of a
+/// pattern: `*` and `?` match within a component, `**` matches any number of components
+fn key_match(pattern: &[&str], key: &[&str]) -> bool {
+    match (pattern.split_first(), key.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            key_match(rest, key) || (!key.is_empty() && key_match(pattern, &key[1..]))
+        }
+        (Some((component, rest)), Some((name, key_rest))) => {
+            wildcard_match(component, name) && key_match(rest, key_rest)
+        }
+        _ => false,
+    }
+}
+
+/// Input handler of the NetCDF files stored in an S3-compatible bucket (`s3://bucket/prefix`).
+/// The objects are opened by netCDF in byte-range mode through signed URLs, so only the parts
+/// of the files that are read are downloaded (netCDF must be built with byte-range support,
+/// no local copy of the objects is made). The credentials, region and endpoint are taken from
+/// the standard environment variables (`AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`,
+/// `AWS_REGION`, `AWS_ENDPOINT`, ...)
+pub struct S3InputHandler {
+    url: String,
+    handler: NetCdfInputHandler,
+}
+
+impl S3InputHandler {
+    pub fn new(url: &str, config: &NetCdfInputConfiguration) -> Result<Self, Box<dyn Error>> {
+        let (bucket, prefix) = url
+            .strip_prefix("s3://")
+            .map(|rest| rest.split_once('/').unwrap_or((rest, "")))
+            .ok_or_else(|| format!("{url} is not an S3 URL"))?;
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        let prefix = Path::from(prefix);
+        // as for the local directories, the .nc files directly under the prefix by default
+        let pattern = config.file_pattern.as_deref().unwrap_or("*.nc");
+        let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let mut files = runtime.block_on(async {
+            let objects: Vec<_> = store.list(Some(&prefix)).try_collect().await?;
+            let mut files = Vec::new();
+            for object in objects {
+                let Some(parts) = object.location.prefix_match(&prefix) else {
+                    continue;
+                };
+                let key: Vec<String> = parts.map(|part| part.as_ref().to_owned()).collect();
+                let key: Vec<&str> = key.iter().map(String::as_str).collect();
+                if !key_match(&components, &key) {
+                    continue;
+                }
+                let signed_url = store
+                    .signed_url(Method::GET, &object.location, SIGNED_URL_EXPIRY)
+                    .await?;
+                // the signed URL is a credential: the messages show the object name
+                let name = format!("s3://{bucket}/{}", object.location);
+                files.push((name, format!("{signed_url}#mode=bytes")));
+            }
+            Ok::<_, object_store::Error>(files)
+        })?;
+        files.sort();
+        info!("Found {} files matching {} in {}", files.len(), pattern, url);
+
+        Ok(S3InputHandler {
+            url: url.to_owned(),
+            handler: NetCdfInputHandler::from_sources(&files, config)?,
+        })
+    }
+}
+
+impl InputHandler for S3InputHandler {
+    fn set_coordinates(&mut self, lats: &[f32], lons: &[f32]) -> Result<(), Box<dyn Error>> {
+        self.handler.set_coordinates(lats, lons)
+    }
+
+    fn get_values(&self, var: InputVariableName, date: &DateTime<Utc>) -> Option<Array1<f32>> {
+        self.handler.get_values(var, date)
+    }
+
+    fn get_timeline(&self) -> Vec<DateTime<Utc>> {
+        self.handler.get_timeline()
+    }
+
+    fn get_variables(&self) -> HashSet<InputVariableName> {
+        self.handler.get_variables()
+    }
+
+    fn available_variables(&self) -> HashMap<InputVariableName, usize> {
+        self.handler.available_variables()
+    }
+
+    fn info_input(&self, verbose: bool) -> String {
+        format!("Bucket: {}\n{}", self.url, self.handler.info_input(verbose))
+    }
+
+    fn get_source_indexes(&self, var: InputVariableName) -> Option<Array1<Option<usize>>> {
+        self.handler.get_source_indexes(var)
+    }
+
+    fn clear_index_cache(&mut self) {
+        self.handler.clear_index_cache();
+    }
+
+    fn share(&self) -> Option<Box<dyn InputHandler>> {
+        Some(Box::new(S3InputHandler {
+            url: self.url.clone(),
+            handler: self.handler.shared(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_match_the_components_of_the_pattern() {
+        assert!(key_match(&["*.nc"], &["a.nc"]));
+        assert!(!key_match(&["*.nc"], &["dir", "a.nc"]));
+        assert!(key_match(&["**", "*.nc"], &["dir", "sub", "a.nc"]));
+    }
+}
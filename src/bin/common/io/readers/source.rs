@@ -0,0 +1,37 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read},
+    sync::OnceLock,
+};
+
+/// stdin can be consumed only once: keep its content for the following reads
+static STDIN_CONTENT: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Open a data source given its path:
+/// - `-` reads from stdin
+/// - `http://` or `https://` fetches the content from the URL
+/// - any other value is treated as a local file path
+pub fn open_source(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        let content = match STDIN_CONTENT.get() {
+            Some(content) => content,
+            None => {
+                let mut buffer = Vec::new();
+                io::stdin().read_to_end(&mut buffer)?;
+                STDIN_CONTENT.get_or_init(|| buffer)
+            }
+        };
+        return Ok(Box::new(Cursor::new(content.clone())));
+    }
+
+    if path.starts_with("http://") || path.starts_with("https://") {
+        let response = ureq::get(path)
+            .call()
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        let mut buffer = Vec::new();
+        response.into_reader().read_to_end(&mut buffer)?;
+        return Ok(Box::new(Cursor::new(buffer)));
+    }
+
+    Ok(Box::new(File::open(path)?))
+}
@@ -1,13 +1,13 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 #[cfg(feature = "gdal")]
 #[cfg(feature = "gdal")]
 use gdal::raster::{Buffer, RasterCreationOption};
 
-use libflate::gzip::{self, Encoder};
+use libflate::gzip;
 use log::warn;
 use netcdf::extent::Extents;
 use risico::constants::NODATAVAL;
-use risico::version::FULL_VERSION;
+use risico::version::{FULL_VERSION, LONG_VERSION};
 
 use std::io::BufWriter;
 use std::path::Path;
@@ -17,14 +17,16 @@ use std::{
     io::{self, Write},
 };
 
+use crate::common::config::builder::JsonlLayout;
 use crate::common::helpers::RISICOError;
 
-use strum::EnumProperty;
 
 use super::models::{grid::RegularGrid, palette::Palette};
+#[cfg(feature = "gdal")]
+use super::models::projection::{spatial_ref, EPSG_LAT_LON};
 
 pub fn write_and_check(
-    encoder: &mut Encoder<BufWriter<File>>,
+    encoder: &mut impl Write,
     buf: &[u8],
     ok: &mut bool,
 ) -> Result<(), io::Error> {
@@ -35,32 +37,32 @@ pub fn write_and_check(
     Ok(())
 }
 
-pub fn write_to_zbin_file(file: &str, grid: &RegularGrid, values: &[f32]) -> Result<(), io::Error> {
-    let output = File::create(file)?;
-
-    let output = io::BufWriter::new(output);
-    let mut encoder = gzip::Encoder::new(output)?;
-    let mut ok = true;
-
+/// Write the header, the grid and the values of a ZBIN file
+fn write_zbin_contents(
+    encoder: &mut impl Write,
+    grid: &RegularGrid,
+    values: &[f32],
+    ok: &mut bool,
+) -> Result<(), io::Error> {
     let buf = [1u8, 0u8, 0u8, 0u8];
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
     let nrows = grid.nrows as u32;
     let ncols = grid.ncols as u32;
     let buf = nrows.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
     let buf = ncols.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
 
     let buf = grid.min_lat.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
     let buf = grid.max_lat.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
 
     let buf = grid.min_lon.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
 
     let buf = grid.max_lon.to_le_bytes();
-    write_and_check(&mut encoder, &buf, &mut ok)?;
+    write_and_check(encoder, &buf, ok)?;
 
     let mut buf = Vec::<u8>::new();
 
@@ -69,8 +71,41 @@ pub fn write_to_zbin_file(file: &str, grid: &RegularGrid, values: &[f32]) -> Res
         let val = values[index];
         buf.extend(val.to_le_bytes());
     }
-    write_and_check(&mut encoder, &buf, &mut ok)?;
-    encoder.finish();
+    write_and_check(encoder, &buf, ok)
+}
+
+/// Write a ZBIN file, gzip compressed or zstd compressed with the given level
+pub fn write_to_zbin_file(
+    file: &str,
+    grid: &RegularGrid,
+    values: &[f32],
+    compression_level: Option<i32>,
+) -> Result<(), io::Error> {
+    let output = File::create(file)?;
+
+    let output = io::BufWriter::new(output);
+    let mut ok = true;
+
+    match compression_level {
+        None => {
+            let mut encoder = gzip::Encoder::new(output)?;
+            write_zbin_contents(&mut encoder, grid, values, &mut ok)?;
+            encoder.finish();
+        }
+        #[cfg(feature = "zstd")]
+        Some(level) => {
+            let mut encoder = zstd::stream::write::Encoder::new(output, level)?;
+            write_zbin_contents(&mut encoder, grid, values, &mut ok)?;
+            encoder.finish()?.flush()?;
+        }
+        #[cfg(not(feature = "zstd"))]
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Cannot write {file}: zstd support is not enabled"),
+            ));
+        }
+    }
 
     if !ok {
         warn!("Problems writing file {}", file);
@@ -148,6 +183,8 @@ pub fn write_to_geotiff(
     file: &str,
     grid: &RegularGrid,
     values: &[f32],
+    fill_value: f32,
+    epsg: u32,
 ) -> Result<(), gdal::errors::GdalError> {
     // Open a GDAL driver for GeoTIFF files
     let driver = gdal::DriverManager::get_driver_by_name(&"GTiff")?;
@@ -173,7 +210,89 @@ pub fn write_to_geotiff(
         1,
         &options,
     )?;
+    fill_geotiff_dataset(&mut dataset, grid, values, fill_value, epsg)?;
+
+    Ok(())
+}
 
+/// Block size of the tiles of the Cloud-Optimized GeoTIFF
+#[cfg(feature = "gdal")]
+const COG_BLOCK_SIZE: usize = 256;
+
+/// Write a Cloud-Optimized GeoTIFF: the raster is built in memory with its overviews,
+/// then copied to a tiled GeoTIFF keeping the overviews inside the file
+#[cfg(feature = "gdal")]
+pub fn write_to_cog(
+    file: &str,
+    grid: &RegularGrid,
+    values: &[f32],
+    fill_value: f32,
+    epsg: u32,
+) -> Result<(), gdal::errors::GdalError> {
+    let mem_driver = gdal::DriverManager::get_driver_by_name(&"MEM")?;
+    let mut dataset = mem_driver.create_with_band_type::<f32, &str>(
+        "",
+        grid.ncols as isize,
+        grid.nrows as isize,
+        1,
+    )?;
+    fill_geotiff_dataset(&mut dataset, grid, values, fill_value, epsg)?;
+
+    // halve the resolution until the overview fits in a single tile
+    let mut overviews: Vec<i32> = Vec::new();
+    let mut factor = 2;
+    while grid.ncols.max(grid.nrows) / (factor / 2) > COG_BLOCK_SIZE {
+        overviews.push(factor as i32);
+        factor *= 2;
+    }
+    if !overviews.is_empty() {
+        // nearest resampling keeps the values rounded to the precision
+        dataset.build_overviews("NEAREST", &overviews, &[])?;
+    }
+
+    let block_size = COG_BLOCK_SIZE.to_string();
+    let options = vec![
+        RasterCreationOption {
+            key: "TILED",
+            value: "YES",
+        },
+        RasterCreationOption {
+            key: "BLOCKXSIZE",
+            value: &block_size,
+        },
+        RasterCreationOption {
+            key: "BLOCKYSIZE",
+            value: &block_size,
+        },
+        RasterCreationOption {
+            key: "COPY_SRC_OVERVIEWS",
+            value: "YES",
+        },
+        RasterCreationOption {
+            key: "COMPRESS",
+            value: "DEFLATE",
+        },
+        RasterCreationOption {
+            key: "BIGTIFF",
+            value: "IF_SAFER",
+        },
+    ];
+    let driver = gdal::DriverManager::get_driver_by_name(&"GTiff")?;
+    dataset.create_copy(&driver, file, &options)?;
+
+    Ok(())
+}
+
+/// Set georeferencing, nodata (the fill value) and the values of the grid on the first band of the dataset
+/// (the grid is in the coordinates of the EPSG code)
+#[cfg(feature = "gdal")]
+fn fill_geotiff_dataset(
+    dataset: &mut gdal::Dataset,
+    grid: &RegularGrid,
+    values: &[f32],
+    fill_value: f32,
+    epsg: u32,
+) -> Result<(), gdal::errors::GdalError> {
     // Set the geo-transform for the dataset
     let geo_transform = [
         grid.min_lon as f64,
@@ -186,12 +305,16 @@ pub fn write_to_geotiff(
     dataset.set_geo_transform(&geo_transform)?;
 
     // Set the Coordinate Reference System for the dataset
-    let proj_wkt = "+proj=longlat +datum=WGS84 +no_defs";
-    dataset.set_projection(proj_wkt)?;
+    if epsg == EPSG_LAT_LON {
+        let proj_wkt = "+proj=longlat +datum=WGS84 +no_defs";
+        dataset.set_projection(proj_wkt)?;
+    } else {
+        dataset.set_spatial_ref(&spatial_ref(epsg)?)?;
+    }
 
     // Get a reference to the first band
     let mut band = dataset.rasterband(1)?;
-    band.set_no_data_value(Some(NODATAVAL.into()))?;
+    band.set_no_data_value(Some(fill_value.into()))?;
 
     // Create a buffer with some data to write to the band
     let mut data = vec![];
@@ -213,15 +336,15 @@ pub fn write_to_geotiff(
 
 const COMPRESSION_RATE: i32 = 7;
 
-pub fn create_nc_file<T>(
+pub fn create_nc_file(
     file_name: &str,
     grid: &RegularGrid,
     output_name: &str,
-    variable_name: T,
-) -> Result<netcdf::MutableFile, RISICOError>
-where
-    T: EnumProperty + ToString,
-{
+    long_name: &str,
+    units: &str,
+    attributes: &[(String, String)],
+    fill_value: f32,
+) -> Result<netcdf::MutableFile, RISICOError> {
     let n_lats = grid.nrows;
     let n_lons = grid.ncols;
 
@@ -233,10 +356,20 @@ where
     file.add_attribute("risico_version", FULL_VERSION)
         .expect("Should add attribute 'risico_version'");
 
+    // version, commit, branch and release date of the build
+    file.add_attribute("risico_build", LONG_VERSION.trim())
+        .expect("Should add attribute 'risico_build'");
+
     file.add_attribute("creation_date", Utc::now().to_rfc3339())
         .expect("Should add attribute 'creation_date'");
 
-    file.add_attribute("missing_value", NODATAVAL)
+    // provenance of the output (run date, model name and version, input path)
+    for (name, value) in attributes {
+        file.add_attribute(name, value.as_str())
+            .map_err(|err| format!("Add attribute '{name}' failed {err}"))?;
+    }
+
+    file.add_attribute("missing_value", fill_value)
         .expect("Should add attribute");
 
     // We must create a dimension which corresponds to our data
@@ -291,21 +424,233 @@ where
         .expect("Set compression failed");
 
     variable_var
-        .add_attribute("missing_value", NODATAVAL)
+        .add_attribute("_FillValue", fill_value)
+        .expect("Should add attribute");
+
+    variable_var
+        .add_attribute("missing_value", fill_value)
         .expect("Should add attribute");
 
     variable_var
-        .add_attribute("units", variable_name.get_str("units").unwrap_or("unknown"))
+        .add_attribute("units", units)
         .expect("Should add attribute");
 
     variable_var
-        .add_attribute(
-            "long_name",
-            variable_name
-                .get_str("long_name")
-                .unwrap_or(&variable_name.to_string()),
-        )
+        .add_attribute("long_name", long_name)
         .expect("Should add attribute");
 
     Ok(file)
 }
+
+/// Open an existing NetCDF output file for appending, returns the file and the times
+/// already written. The file must hold the variable on the same grid.
+pub fn open_nc_file(
+    file_name: &str,
+    grid: &RegularGrid,
+    output_name: &str,
+) -> Result<(netcdf::MutableFile, Vec<i64>), RISICOError> {
+    let file = netcdf::append(file_name)
+        .map_err(|err| format!("can't open file {file_name}: {err}"))?;
+
+    for (dim, len) in [("latitude", grid.nrows), ("longitude", grid.ncols)] {
+        let file_len = file.dimension(dim).map(|d| d.len());
+        if file_len != Some(len) {
+            return Err(format!(
+                "{file_name}: dimension {dim} does not match the grid, expected {len}"
+            )
+            .into());
+        }
+    }
+    if file.variable(output_name).is_none() {
+        return Err(format!("{file_name}: variable not found: {output_name}").into());
+    }
+
+    let times = file
+        .variable("time")
+        .ok_or_else(|| format!("{file_name}: variable not found: time"))?
+        .values::<i64, _>(Extents::All)
+        .map_err(|err| format!("{file_name}: cannot read the times: {err}"))?
+        .into_iter()
+        .collect();
+    Ok((file, times))
+}
+
+/// Write the values on the cells as CSV, with a `lat,lon,<variables>` header and a row per cell.
+/// Values are written with the given number of decimals, NODATAVAL values as empty fields.
+pub fn write_to_csv(
+    file: &str,
+    lats: &[f32],
+    lons: &[f32],
+    columns: &[(String, ndarray::Array1<f32>)],
+    decimals: &[usize],
+) -> Result<(), io::Error> {
+    let mut writer = BufWriter::new(File::create(file)?);
+    let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+    writeln!(writer, "lat,lon,{}", names.join(","))?;
+    for (idx, (lat, lon)) in lats.iter().zip(lons).enumerate() {
+        write!(writer, "{lat},{lon}")?;
+        for ((_, values), decimals) in columns.iter().zip(decimals) {
+            let value = values[idx];
+            if value == NODATAVAL {
+                write!(writer, ",")?;
+            } else {
+                write!(writer, ",{:.*}", *decimals, value)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Write the values on the cells as newline-delimited JSON, at the time of the output.
+/// Values are written with the given number of decimals, NODATAVAL values are skipped.
+pub fn write_to_jsonl(
+    file: &str,
+    time: &DateTime<Utc>,
+    lats: &[f32],
+    lons: &[f32],
+    columns: &[(String, ndarray::Array1<f32>)],
+    decimals: &[usize],
+    layout: JsonlLayout,
+) -> Result<(), io::Error> {
+    let mut writer = BufWriter::new(File::create(file)?);
+    let time = time.to_rfc3339();
+    let names: Vec<String> = columns
+        .iter()
+        .map(|(name, _)| serde_json::to_string(name).expect("Should serialize"))
+        .collect();
+    for (idx, (lat, lon)) in lats.iter().zip(lons).enumerate() {
+        if !lat.is_finite() || !lon.is_finite() {
+            continue;
+        }
+        let values = columns
+            .iter()
+            .zip(&names)
+            .zip(decimals)
+            .map(|(((_, values), name), decimals)| (name, values[idx], *decimals))
+            .filter(|(_, value, _)| *value != NODATAVAL && value.is_finite());
+        match layout {
+            JsonlLayout::Cell => {
+                let fields: Vec<String> = values
+                    .map(|(name, value, decimals)| format!("{name}:{value:.decimals$}"))
+                    .collect();
+                if fields.is_empty() {
+                    continue;
+                }
+                writeln!(
+                    writer,
+                    "{{\"time\":\"{time}\",\"lat\":{lat},\"lon\":{lon},{}}}",
+                    fields.join(",")
+                )?;
+            }
+            JsonlLayout::Value => {
+                for (name, value, decimals) in values {
+                    writeln!(
+                        writer,
+                        "{{\"time\":\"{time}\",\"lat\":{lat},\"lon\":{lon},\
+                        \"variable\":{name},\"value\":{value:.decimals$}}}"
+                    )?;
+                }
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Point feature of a cell, passed to the FlatGeobuf writer
+#[cfg(feature = "flatgeobuf")]
+struct CellPoint {
+    lon: f64,
+    lat: f64,
+}
+
+#[cfg(feature = "flatgeobuf")]
+impl flatgeobuf::geozero::GeozeroGeometry for CellPoint {
+    fn process_geom<P: flatgeobuf::geozero::GeomProcessor>(
+        &self,
+        processor: &mut P,
+    ) -> flatgeobuf::geozero::error::Result<()> {
+        processor.point_begin(0)?;
+        processor.xy(self.lon, self.lat, 0)?;
+        processor.point_end(0)
+    }
+}
+
+#[cfg(feature = "flatgeobuf")]
+pub fn write_to_fgb(
+    file: &str,
+    lats: &[f32],
+    lons: &[f32],
+    columns: &[(String, ndarray::Array1<f32>)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use flatgeobuf::{ColumnType, FgbWriter, GeometryType};
+    use flatgeobuf::geozero::{ColumnValue, PropertyProcessor};
+
+    let mut fgb = FgbWriter::create("risico", GeometryType::Point)?;
+    for (name, _) in columns {
+        fgb.add_column(name, ColumnType::Float, |_, col| {
+            col.nullable = true;
+        });
+    }
+
+    for (idx, (lat, lon)) in lats.iter().zip(lons).enumerate() {
+        if !lat.is_finite() || !lon.is_finite() {
+            continue;
+        }
+        let point = CellPoint {
+            lon: *lon as f64,
+            lat: *lat as f64,
+        };
+        fgb.add_feature_geom(point, |feature| {
+            for (col, (name, values)) in columns.iter().enumerate() {
+                let value = values[idx];
+                // missing values are left as null properties
+                if value != NODATAVAL {
+                    feature
+                        .property(col, name, &ColumnValue::Float(value))
+                        .expect("Should set the property");
+                }
+            }
+        })?;
+    }
+
+    let mut writer = BufWriter::new(File::create(file)?);
+    fgb.write(&mut writer)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "gdal"))]
+mod tests {
+    use gdal::{Dataset, Metadata};
+
+    use super::*;
+
+    #[test]
+    fn cog_is_tiled_with_internal_overviews() {
+
+        let file = std::env::temp_dir().join(format!("risico-cog-{}.tif", std::process::id()));
+        let file = file.to_str().expect("Should be a valid path");
+        let grid = RegularGrid::new(600, 700, 40.0, 8.0, 46.0, 15.0);
+        let values: Vec<f32> = (0..600 * 700).map(|idx| (idx % 100) as f32 / 10.0).collect();
+        write_to_cog(file, &grid, &values, NODATAVAL, EPSG_LAT_LON).unwrap();
+
+        let dataset = Dataset::open(file).unwrap();
+        assert_eq!(dataset.raster_size(), (700, 600));
+        assert_eq!(
+            dataset.metadata_item("COMPRESSION", "IMAGE_STRUCTURE").as_deref(),
+            Some("DEFLATE")
+        );
+        let band = dataset.rasterband(1).unwrap();
+        assert_eq!(band.block_size(), (COG_BLOCK_SIZE, COG_BLOCK_SIZE));
+        // overviews down to a single tile: 350x300 and 175x150
+        assert_eq!(band.overview_count().unwrap(), 2);
+        assert_eq!(band.overview(1).unwrap().size(), (175, 150));
+        assert_eq!(band.no_data_value(), Some(NODATAVAL as f64));
+        // the rows are written from the north
+        let read = band.read_as::<f32>((0, 0), (700, 600), (700, 600), None).unwrap();
+        let north_first: Vec<f32> = values.chunks(700).rev().flatten().copied().collect();
+        assert_eq!(read.data, north_first);
+        drop(dataset);
+        let _ = std::fs::remove_file(file);
+    }
+}
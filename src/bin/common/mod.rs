@@ -1,4 +1,6 @@
 pub mod config;
 pub mod helpers;
 pub mod io;
+pub mod ppf;
+pub mod selftest;
 
@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use log::{info, warn};
+use ndarray::Array1;
+use risico::constants::NODATAVAL;
+
+use crate::common::{
+    config::models::RISICOConfig,
+    helpers::RISICOError,
+    io::readers::binary::{read_grid_from_file, read_values_from_file},
+};
+
+/// Season of a PPF value, as in the interpolation of the PPF over the year of the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Season {
+    /// June to September
+    Summer,
+    /// December to March
+    Winter,
+}
+
+fn season(date: &DateTime<Utc>) -> Option<Season> {
+    match date.month() {
+        6..=9 => Some(Season::Summer),
+        12 | 1..=3 => Some(Season::Winter),
+        // the transition months are interpolated by the model
+        _ => None,
+    }
+}
+
+/// Output file of the variable in the directory of the outputs,
+/// named `{name}_{rundate}_{date}_{variable}.zbin`: returns the run date and the date
+fn parse_output_file_name(
+    file_name: &str,
+    variable: &str,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let stem = file_name.strip_suffix(".zbin")?;
+    let mut parts = stem.rsplitn(4, '_');
+    let (var, date, run_date, _name) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if var != variable {
+        return None;
+    }
+    let parse = |date: &str| {
+        NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M")
+            .ok()
+            .map(|date| DateTime::from_naive_utc_and_offset(date, Utc))
+    };
+    Some((parse(run_date)?, parse(date)?))
+}
+
+/// Output files of the variable in the directory and its subdirectories, one for each date:
+/// the one of the latest run when the forecasts of several runs overlap
+fn find_output_files(
+    dir: &Path,
+    variable: &str,
+) -> Result<Vec<(DateTime<Utc>, String)>, RISICOError> {
+    let mut files: HashMap<DateTime<Utc>, (DateTime<Utc>, String)> = HashMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|err| format!("Cannot read directory {}: {err}", dir.display()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some((run_date, date)) = parse_output_file_name(&file_name, variable) else {
+                continue;
+            };
+            let file = path.to_string_lossy().into_owned();
+            match files.get(&date) {
+                Some((latest, _)) if *latest >= run_date => {}
+                _ => {
+                    files.insert(date, (run_date, file));
+                }
+            }
+        }
+    }
+    let mut files: Vec<_> = files.into_iter().map(|(date, (_, file))| (date, file)).collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Sum and number of the valid values of the cells in a season
+struct SeasonalSums {
+    sums: Vec<f64>,
+    counts: Vec<u32>,
+}
+
+impl SeasonalSums {
+    fn new(n_cells: usize) -> Self {
+        Self {
+            sums: vec![0.0; n_cells],
+            counts: vec![0; n_cells],
+        }
+    }
+
+    fn add(&mut self, values: &[f32]) {
+        for (idx, value) in values.iter().enumerate() {
+            if *value != NODATAVAL && value.is_finite() {
+                self.sums[idx] += *value as f64;
+                self.counts[idx] += 1;
+            }
+        }
+    }
+
+    /// PPF of the cells: mean of the cell over the mean of the domain, so the cells with
+    /// higher values than the rest of the domain get a PPF above 1.
+    /// The cells without values get a neutral PPF of 1
+    fn ppf(&self) -> Vec<f32> {
+        let means: Vec<Option<f64>> = self
+            .sums
+            .iter()
+            .zip(&self.counts)
+            .map(|(sum, count)| (*count > 0).then(|| sum / *count as f64))
+            .collect();
+        let valid: Vec<f64> = means.iter().flatten().copied().collect();
+        let domain_mean = valid.iter().sum::<f64>() / valid.len().max(1) as f64;
+        means
+            .iter()
+            .map(|mean| match mean {
+                Some(mean) if domain_mean > 0.0 => (mean / domain_mean) as f32,
+                _ => 1.0,
+            })
+            .collect()
+    }
+}
+
+/// Per-cell summer and winter PPF of the cells file, from the historical outputs of a variable
+/// written by the RISICO runs in the outputs directory
+pub fn compute_ppf(
+    cells_file: &str,
+    outputs_dir: &Path,
+    variable: &str,
+) -> Result<Vec<(f32, f32)>, RISICOError> {
+    // one PPF line for each line of the cells file, duplicates included
+    let cells = RISICOConfig::properties_from_file(cells_file, 0.0, 0.0)
+        .map_err(|err| format!("error reading {}, {err}", cells_file))?;
+    let n_cells = cells.lats.len();
+
+    let files = find_output_files(outputs_dir, variable)?;
+    if files.is_empty() {
+        return Err(format!(
+            "No outputs of {} found in {}",
+            variable,
+            outputs_dir.display()
+        )
+        .into());
+    }
+    info!("Computing the PPF from {} outputs of {}", files.len(), variable);
+
+    let mut summer = SeasonalSums::new(n_cells);
+    let mut winter = SeasonalSums::new(n_cells);
+    // the indexes of the cells are computed again only when the grid changes
+    let mut indexes: Option<(u64, Array1<Option<usize>>)> = None;
+    for (date, file) in files {
+        let Some(season) = season(&date) else {
+            continue;
+        };
+        let grid = read_grid_from_file(&file)
+            .map_err(|err| format!("Cannot read the grid of {}: {err}", file))?;
+        let hash = grid.geometry_hash();
+        if indexes.as_ref().map(|(cached, _)| *cached) != Some(hash) {
+            indexes = Some((hash, grid.indexes(&cells.lats, &cells.lons)));
+        }
+        let (_, cell_indexes) = indexes.as_ref().expect("Should be computed");
+        let values = match read_values_from_file(&file) {
+            Ok(values) => values,
+            Err(err) => {
+                warn!("Skipping output {}: {err}", file);
+                continue;
+            }
+        };
+        let cell_values: Vec<f32> = cell_indexes
+            .iter()
+            .map(|idx| idx.and_then(|idx| values.get(idx).copied()).unwrap_or(NODATAVAL))
+            .collect();
+        match season {
+            Season::Summer => summer.add(&cell_values),
+            Season::Winter => winter.add(&cell_values),
+        }
+    }
+
+    Ok(summer.ppf().into_iter().zip(winter.ppf()).collect())
+}
+
+/// Write the PPF in the format of the PPF file: a line `ppf_summer ppf_winter` for each cell,
+/// after a comment with the origin of the values
+pub fn write_ppf(ppf_file: &str, ppf: &[(f32, f32)], header: &str) -> Result<(), RISICOError> {
+    let file = File::create(ppf_file)
+        .map_err(|err| format!("Cannot create PPF file {}: {err}", ppf_file))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "# {}", header)
+        .map_err(|err| format!("Error writing PPF file {}: {err}", ppf_file))?;
+    for (summer, winter) in ppf {
+        writeln!(writer, "{:.3} {:.3}", summer, winter)
+            .map_err(|err| format!("Error writing PPF file {}: {err}", ppf_file))?;
+    }
+    writer
+        .flush()
+        .map_err(|err| format!("Error writing PPF file {}: {err}", ppf_file))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::common::io::{models::grid::RegularGrid, writers::write_to_zbin_file};
+
+    #[test]
+    fn ppf_from_historical_outputs() {
+        let dir = std::env::temp_dir().join(format!("risico-ppf-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("outputs")).unwrap();
+        // two cells on the columns of a 2x2 grid
+        let cells_file = dir.join("cells.txt");
+        fs::write(&cells_file, "0 0 0 0 1\n1 0 0 0 1\n").unwrap();
+        let grid = RegularGrid::new(2, 2, 0.0, 0.0, 1.0, 1.0);
+
+        let run = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let outputs = [
+            ((2024, 7, 1), [30.0, 10.0, 30.0, 10.0]),
+            ((2024, 1, 15), [5.0, 15.0, 5.0, 15.0]),
+        ];
+        for ((year, month, day), values) in outputs {
+            let date = Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap();
+            let file = format!(
+                "{}/outputs/RISICO_{}_{}_V.zbin",
+                dir.display(),
+                run.format("%Y%m%d%H%M"),
+                date.format("%Y%m%d%H%M")
+            );
+            write_to_zbin_file(&file, &grid, &values, None).unwrap();
+        }
+
+        let ppf = compute_ppf(cells_file.to_str().unwrap(), &dir.join("outputs"), "V").unwrap();
+        assert_eq!(ppf.len(), 2);
+        // the hotter cell in summer gets a higher summer PPF, the other one in winter
+        assert!((ppf[0].0 - 1.5).abs() < 1e-6 && (ppf[1].0 - 0.5).abs() < 1e-6);
+        assert!((ppf[0].1 - 0.5).abs() < 1e-6 && (ppf[1].1 - 1.5).abs() < 1e-6);
+
+        let ppf_file = dir.join("ppf.txt");
+        write_ppf(ppf_file.to_str().unwrap(), &ppf, "climatological PPF of V").unwrap();
+        let read = RISICOConfig::read_ppf(ppf_file.to_str().unwrap()).unwrap();
+        assert_eq!(read, vec![(1.5, 0.5), (0.5, 1.5)]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,149 @@
+use std::{error::Error, f32::consts::PI, fs, path::Path};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use ndarray::Array1;
+use netcdf::extent::Extents;
+use risico::{constants::NODATAVAL, models::input::InputVariableName};
+
+use crate::common::{
+    config::builder::{ConfigBuilderType, ConfigContainer},
+    helpers::RISICOError,
+    io::readers::{binary::read_values_from_file, memory::MemoryInputHandler},
+};
+
+/// Files of the bundled dataset, `$DIR` is replaced with the working directory
+const FIXTURES: [(&str, &str); 7] = [
+    ("config.yml", include_str!("../../../fixtures/selftest/config.yml")),
+    ("grid.txt", include_str!("../../../fixtures/selftest/grid.txt")),
+    ("cells.txt", include_str!("../../../fixtures/selftest/cells.txt")),
+    ("cells_risico.txt", include_str!("../../../fixtures/selftest/cells_risico.txt")),
+    ("cells_rain.txt", include_str!("../../../fixtures/selftest/cells_rain.txt")),
+    ("cells_heat.txt", include_str!("../../../fixtures/selftest/cells_heat.txt")),
+    ("vegetation.txt", include_str!("../../../fixtures/selftest/vegetation.txt")),
+];
+
+/// Number of cells of the bundled cells files
+const SELFTEST_CELLS: usize = 9;
+/// Number of hourly timesteps of the synthetic inputs
+const SELFTEST_HOURS: i64 = 48;
+
+/// Run date of the self test
+pub fn selftest_date() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0)
+        .single()
+        .expect("Should be a valid date")
+}
+
+/// Write the bundled dataset to the directory and load its configuration
+pub fn prepare_selftest(dir: &Path) -> Result<ConfigContainer, RISICOError> {
+    fs::create_dir_all(dir.join("warm"))
+        .map_err(|err| format!("Cannot create {}: {err}", dir.display()))?;
+    let dir_str = dir.to_str().ok_or("Invalid working directory")?;
+    for (name, contents) in FIXTURES {
+        let file = dir.join(name);
+        fs::write(&file, contents.replace("$DIR", dir_str))
+            .map_err(|err| format!("Cannot write {}: {err}", file.display()))?;
+    }
+
+    let config_file = dir.join("config.yml");
+    let configs = ConfigContainer::from_file(config_file.to_str().ok_or("Invalid config path")?)?;
+    for model_config in &configs.models {
+        for output_type in model_config.get_output_types() {
+            fs::create_dir_all(&output_type.path)
+                .map_err(|err| format!("Cannot create {}: {err}", output_type.path))?;
+        }
+    }
+    Ok(configs)
+}
+
+/// Synthetic hourly weather on the cells of the bundled dataset:
+/// a dry summer diurnal cycle with a rain event in the first night
+pub fn selftest_input(date: &DateTime<Utc>) -> Result<MemoryInputHandler, Box<dyn Error>> {
+    let mut handler = MemoryInputHandler::new(SELFTEST_CELLS);
+    for hour in 0..SELFTEST_HOURS {
+        let time = *date + Duration::try_hours(hour).expect("Should be a valid duration");
+        // peak of the cycle at 15:00
+        let cycle = f32::sin(2.0 * PI * (hour as f32 - 9.0) / 24.0);
+        let cells = |value: f32, spread: f32| {
+            Array1::from_iter((0..SELFTEST_CELLS).map(|idx| value + spread * idx as f32))
+        };
+        handler.insert(InputVariableName::T, time, cells(24.0 + 8.0 * cycle, 0.2))?;
+        handler.insert(InputVariableName::H, time, cells(45.0 - 20.0 * cycle, -0.5))?;
+        handler.insert(InputVariableName::W, time, cells(3.0 + 2.0 * cycle.max(0.0), 0.1))?;
+        handler.insert(InputVariableName::D, time, cells(270.0, 5.0))?;
+        let rain = if hour == 4 { 2.0 } else { 0.0 };
+        handler.insert(InputVariableName::P, time, cells(rain, 0.0))?;
+        // upper-air levels of the Haines index (850 and 700 hPa)
+        handler.insert(InputVariableName::TL, time, cells(18.0 + 2.0 * cycle, 0.5))?;
+        handler.insert(InputVariableName::TU, time, cells(8.0, 0.1))?;
+        handler.insert(InputVariableName::RL, time, cells(6.0, -0.8))?;
+        handler.insert(InputVariableName::RU, time, cells(-4.0, -0.5))?;
+    }
+    Ok(handler)
+}
+
+/// Values written for the variable in the ZBIN files of the directory, at all the dates
+fn read_zbin_outputs(path: &str, name: &str) -> Result<Vec<f32>, Box<dyn Error>> {
+    let suffix = format!("_{name}.zbin");
+    let mut values = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let file = entry?.path();
+        let Some(file) = file.to_str() else {
+            continue;
+        };
+        if file.ends_with(&suffix) {
+            values.extend(read_values_from_file(file)?);
+        }
+    }
+    Ok(values)
+}
+
+/// Values written for the variable in its NetCDF file, at all the dates
+fn read_netcdf_output(path: &str, name: &str) -> Result<Vec<f32>, Box<dyn Error>> {
+    let file = format!("{path}/{name}.nc");
+    if !Path::new(&file).is_file() {
+        return Ok(Vec::new());
+    }
+    let nc_file = netcdf::open(&file)?;
+    let var = nc_file
+        .variable(name)
+        .ok_or_else(|| format!("Could not find variable '{name}' in {file}"))?;
+    Ok(var.values::<f32, _>(Extents::All)?.into_iter().collect())
+}
+
+/// Problem with the values written for a variable, None if they are valid
+fn check_values(values: &[f32]) -> Option<String> {
+    if values.is_empty() {
+        return Some("no output written".to_string());
+    }
+    let n_not_finite = values.iter().filter(|v| !v.is_finite()).count();
+    if n_not_finite > 0 {
+        return Some(format!("{n_not_finite} values are not finite"));
+    }
+    if values.iter().all(|v| *v == NODATAVAL) {
+        return Some("no valid output, all the values are nodata".to_string());
+    }
+    None
+}
+
+/// Check the outputs written by the model, returns a message for each variable without valid output
+pub fn check_outputs(model_config: &ConfigBuilderType) -> Vec<String> {
+    let mut problems = Vec::new();
+    for output_type in model_config.get_output_types() {
+        for variable in &output_type.variables {
+            let name = variable.name();
+            let values = match output_type.format.as_str() {
+                "NETCDF" => read_netcdf_output(&output_type.path, name),
+                _ => read_zbin_outputs(&output_type.path, name),
+            };
+            let problem = match values {
+                Ok(values) => check_values(&values),
+                Err(err) => Some(format!("cannot read output: {err}")),
+            };
+            if let Some(problem) = problem {
+                problems.push(format!("{} variable {name}: {problem}", output_type.format));
+            }
+        }
+    }
+    problems
+}
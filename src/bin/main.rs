@@ -1,33 +1,65 @@
 mod common;
+use std::collections::HashMap;
 use std::env::{set_var, var};
 use std::error::Error;
-use std::path::Path;
+use std::fs::File;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 
 use chrono::prelude::*;
-use clap::Parser;
+use chrono::Duration;
+use clap::{Parser, Subcommand};
 
 use common::config::builder::{
     AngstromConfigBuilder,
+    ChandlerConfigBuilder,
     ConfigBuilderType,
     ConfigContainer,
     FWIConfigBuilder,
     FosbergConfigBuilder,
-    //    PortugueseConfigBuilder,
+    GfdiConfigBuilder,
+    HainesConfigBuilder,
     HdwConfigBuilder,
     KbdiConfigBuilder,
     Mark5ConfigBuilder,
     NesterovConfigBuilder,
     OrieuxConfigBuilder,
     PaletteMap,
+    PortugueseConfigBuilder,
     RISICOConfigBuilder,
     SharplesConfigBuilder,
 };
-use common::helpers::{get_input, RISICOError};
+use common::config::models::{OutputWriter, WarmStateContinuity};
+use common::config::schedule::PlannedWrites;
+use common::helpers::{check_coordinates, get_input, InputOptions, RISICOError};
 use common::io::readers::binary::BinaryInputHandler;
+use common::io::readers::gaps::GapFillingInputHandler;
+#[cfg(feature = "grib")]
+use common::io::readers::grib::{has_grib_files, GribInputConfiguration, GribInputHandler};
+use common::io::readers::index_cache::IndexCache;
 use common::io::readers::netcdf::{NetCdfInputConfiguration, NetCdfInputHandler};
-use common::io::readers::prelude::InputHandler;
-use log::{info, trace, warn};
+use common::io::readers::prefetch::PrefetchInputHandler;
+use common::io::readers::prelude::{format_available_variables, InputHandler};
+#[cfg(feature = "object_store")]
+use common::io::readers::s3::S3InputHandler;
+use common::ppf::{compute_ppf, write_ppf};
+use common::selftest::{check_outputs, prepare_selftest, selftest_date, selftest_input};
+use log::{info, log_enabled, trace, warn, Level};
+use ndarray::Array1;
+use risico::constants::NODATAVAL;
+use risico::models::input::InputVariableName;
+use risico::models::output::OutputVariableName;
+use risico::modules::fwi::models::FWIState;
+use risico::modules::kbdi::models::KBDIState;
+use risico::modules::mark5::models::Mark5State;
+use risico::modules::nesterov::models::NesterovState;
+use risico::modules::orieux::models::OrieuxState;
+use risico::modules::portuguese::models::PortugueseState;
+use risico::modules::risico::constants::MAX_DT;
+use risico::modules::risico::models::RISICOState;
 use risico::version::LONG_VERSION;
+use serde_derive::Serialize;
+use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -36,21 +68,342 @@ use risico::version::LONG_VERSION;
     long_version=LONG_VERSION,
     about="risico-2023 Wildfire Risk Assessment Model by CIMA Research Foundation", 
     long_about="RISICO  (Rischio Incendi E Coordinamento) is a wildfire risk forecast model written in rust and developed by CIMA Research Foundation. 
-It is designed to predict the likelihood and potential impact of wildfires in a given region, given a set of input parameters."
+It is designed to predict the likelihood and potential impact of wildfires in a given region, given a set of input parameters.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(
         required = true,
         help = "Model date in the format YYYYMMDDHHMM",
         index = 1
     )]
-    date: String,
+    date: Option<String>,
 
     #[arg(required = true, help = "Path to the configuration file", index = 2)]
-    config_path: String,
+    config_path: Option<String>,
 
     #[arg(required = true, help = "Path to the input data file", index = 3)]
+    input_path: Option<String>,
+
+    #[arg(
+        long,
+        help = "Last model date of a date-range run, in the format YYYYMMDDHHMM: the models run for each day from the model date, reusing the input data"
+    )]
+    end_date: Option<String>,
+
+    #[arg(
+        long,
+        help = "Fail if the warm state hour is not the same for all models"
+    )]
+    strict_warm_state_hour: bool,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Process only every Nth timestep of the input timeline"
+    )]
+    step: usize,
+
+    #[arg(
+        long,
+        help = "List every input file and its variables instead of a summary"
+    )]
+    verbose_input: bool,
+
+    #[arg(
+        long,
+        help = "Abort the run with an error on the first output write failure"
+    )]
+    fail_on_write_error: bool,
+
+    #[arg(long, help = "Write a JSON summary of the run to the given path")]
+    summary: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Read the inputs of the next N timesteps in background (0 to disable)"
+    )]
+    prefetch: usize,
+
+    #[arg(
+        long,
+        help = "Run the models in parallel, sharing the input data read once"
+    )]
+    parallel_models: bool,
+
+    #[arg(
+        long,
+        help = "Print the timesteps to process and the planned outputs and warm states, then exit"
+    )]
+    plan: bool,
+
+    #[arg(
+        long,
+        help = "Check the configuration, the referenced files, the output directories and the input data, then exit without running the models"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        help = "Log and skip the timesteps that fail (e.g. for a bad input file) instead of aborting the run"
+    )]
+    continue_on_error: bool,
+
+    #[arg(
+        long,
+        help = "Compare the final state with the loaded warm state (RISICO and FWI) and report the cells reset or out of range"
+    )]
+    check_warm_state: bool,
+
+    #[arg(
+        long,
+        help = "Save a checkpoint of the RISICO state every N timesteps next to the warm states, and resume an interrupted run from it"
+    )]
+    checkpoint_every: Option<usize>,
+
+    #[arg(
+        long,
+        exclusive = true,
+        help = "Print the output variables accepted in the configuration, with their long name, units and aliases, then exit"
+    )]
+    list_variables: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run all the models on a small bundled dataset and check that they produce valid outputs
+    Selftest {
+        #[arg(
+            long,
+            help = "Working directory of the self test (default: a temporary directory, removed if the test passes)"
+        )]
+        dir: Option<String>,
+    },
+    /// Compute the climatological summer and winter PPF of the cells from the outputs of
+    /// historical runs, and write them in the format of the PPF file
+    Ppf {
+        #[arg(help = "Path to the cells file of the model")]
+        cells_file: String,
+        #[arg(help = "Directory of the ZBIN outputs of the historical runs (searched recursively)")]
+        outputs_dir: String,
+        #[arg(help = "Path of the PPF file to write")]
+        ppf_file: String,
+        #[arg(long, default_value = "V", help = "Output variable the PPF is computed from")]
+        variable: String,
+    },
+}
+
+/// Options shared by all the model runs
+struct RunOptions {
+    /// process one timestep every `step`
+    step: usize,
+    /// options applied to the input data at ingestion
+    input: InputOptions,
+    /// abort the run on the first output write error
+    fail_on_write_error: bool,
+    /// skip the timesteps that fail instead of aborting the run
+    continue_on_error: bool,
+    /// compare the final state with the loaded warm state
+    check_warm_state: bool,
+    /// process only the timesteps in [start, end) (date-range runs)
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// path of the input data, recorded in the output metadata
     input_path: String,
+    /// maximum number of concurrent output writes
+    max_write_concurrency: Option<usize>,
+    /// save a checkpoint of the state every N timesteps
+    checkpoint_every: Option<usize>,
+}
+
+/// State of a model carried in memory from a model date to the next one (date-range runs)
+enum CarriedState {
+    RISICO(RISICOState),
+    FWI(FWIState),
+    Mark5(Mark5State),
+    KBDI(KBDIState),
+    Nesterov(NesterovState),
+    Orieux(OrieuxState),
+    Portuguese(PortugueseState),
+}
+
+/// Statistics of a model run
+#[derive(Debug, Default, Serialize)]
+struct RunStats {
+    /// number of processed timesteps
+    timesteps: usize,
+    /// number of timesteps for which the outputs were written
+    outputs_written: usize,
+    /// number of timesteps skipped because of a failure
+    failed_timesteps: usize,
+    /// number of output variables that could not be written, over all the timesteps
+    failed_writes: usize,
+    /// non fatal errors collected during the run
+    warnings: Vec<String>,
+}
+
+/// Status of a model in the run summary
+#[derive(Debug, Serialize)]
+struct ModelSummary {
+    model: String,
+    run_date: String,
+    status: String,
+    elapsed_seconds: i64,
+    #[serde(flatten)]
+    stats: RunStats,
+    error: Option<String>,
+}
+
+/// Machine-readable summary written at the end of the run
+#[derive(Debug, Serialize)]
+struct RunSummary {
+    run_date: String,
+    models: Vec<ModelSummary>,
+}
+
+impl RunSummary {
+    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Check the cells coordinates and set them on the input handler,
+/// with the source grid indexes written by the outputs
+fn prepare_coordinates(
+    handler: &mut dyn InputHandler,
+    output_writer: &mut OutputWriter,
+    cells_file: &str,
+    lats: &[f32],
+    lons: &[f32],
+    options: &RunOptions,
+) -> Result<(), RISICOError> {
+    check_coordinates(cells_file, lats, lons, options.input.strict_coordinates)?;
+    handler
+        .set_coordinates(lats, lons)
+        .expect("Should set coordinates");
+    let mut indexes: HashMap<InputVariableName, Array1<f32>> = HashMap::new();
+    for var in output_writer.source_variables() {
+        match handler.get_source_indexes(var) {
+            Some(idx) => {
+                let idx = idx.mapv(|idx| idx.map_or(NODATAVAL, |idx| idx as f32));
+                indexes.insert(var, idx);
+            }
+            None => warn!("Source grid indexes of {} not available", var),
+        }
+    }
+    output_writer.set_source_indexes(&indexes);
+    Ok(())
+}
+
+/// Write the daily aggregates of the last day at the end of the timeline
+fn finish_outputs(
+    output_writer: &mut OutputWriter,
+    lats: &[f32],
+    lons: &[f32],
+    options: &RunOptions,
+    stats: &mut RunStats,
+) -> Result<(), RISICOError> {
+    if let Err(err) = output_writer.finish(lats, lons) {
+        if options.fail_on_write_error {
+            return Err(format!("Error writing daily aggregates: {}", err).into());
+        }
+        warn!("Error writing daily aggregates: {}", err);
+        stats.failed_writes += err.failures.len();
+        stats.warnings.push(format!("Error writing daily aggregates: {}", err));
+    }
+    Ok(())
+}
+
+/// Run the body of a timestep. With `continue_on_error`, a panic in the body is logged
+/// and returned as the failure of the timestep, to be skipped by restoring the state taken
+/// before the timestep; errors are always returned
+fn run_timestep<F>(
+    time: &DateTime<Utc>,
+    continue_on_error: bool,
+    body: F,
+) -> Result<Option<String>, RISICOError>
+where
+    F: FnOnce() -> Result<(), RISICOError>,
+{
+    if !continue_on_error {
+        return body().map(|_| None);
+    }
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result.map(|_| None),
+        Err(cause) => {
+            let cause = cause
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| cause.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown error".to_string());
+            let err = format!(
+                "Timestep {} failed, skipped: {}",
+                time.format("%Y-%m-%d %H:%M"),
+                cause
+            );
+            warn!("{}", err);
+            Ok(Some(err))
+        }
+    }
+}
+
+/// Report the warm state continuity of a model, adding a warning if any cell is not consistent
+fn report_warm_state_continuity(
+    model: &str,
+    continuity: &WarmStateContinuity,
+    stats: &mut RunStats,
+) {
+    if continuity.is_ok() {
+        info!("{model} warm state continuity: {}", continuity.summary());
+        return;
+    }
+    let msg = format!("{model} warm state continuity: {}", continuity.summary());
+    warn!("{}", msg);
+    stats.warnings.push(msg);
+}
+
+/// Get the input timeline in the window (if any), keeping one timestep every `step`
+fn get_timeline(
+    handler: &dyn InputHandler,
+    step: usize,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<DateTime<Utc>> {
+    let timeline: Vec<DateTime<Utc>> = handler
+        .get_timeline()
+        .into_iter()
+        .filter(|t| match window {
+            Some((start, end)) => *t >= start && *t < end,
+            None => true,
+        })
+        .step_by(step.max(1))
+        .collect();
+    if step > 1 {
+        let max_gap = timeline
+            .windows(2)
+            .map(|w| (w[1] - w[0]).num_hours())
+            .max()
+            .unwrap_or(0);
+        info!(
+            "Processing every {} timesteps ({} timesteps, max gap {} hours)",
+            step,
+            timeline.len(),
+            max_gap
+        );
+        if max_gap as f32 > MAX_DT {
+            warn!(
+                "Gap of {} hours exceeds the maximum time step of {} hours: \
+                 the RISICO moisture is updated as if only {} hours had passed",
+                max_gap, MAX_DT, MAX_DT
+            );
+        }
+    }
+    timeline
 }
 
 fn run_risico(
@@ -58,7 +411,9 @@ fn run_risico(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     // run risico
     let config = model_config
         .build(date, palettes)
@@ -66,17 +421,36 @@ fn run_risico(
 
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
 
     let props = config.get_properties();
-    let mut state = config.new_state();
+    // in a date-range run the state of the previous model date is carried in memory
+    let (mut state, resumed) = match carried.take() {
+        Some(CarriedState::RISICO(state)) => (state, None),
+        _ => {
+            let mut state = config.new_state();
+            // resume an interrupted run from its last checkpoint
+            let resumed = match options.checkpoint_every {
+                Some(_) => config.resume_from_checkpoint(&mut state),
+                None => None,
+            };
+            (state, resumed)
+        }
+    };
 
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
 
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
 
     let current_time = Utc::now();
     trace!(
@@ -85,38 +459,88 @@ fn run_risico(
     );
 
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let mut timeline = get_timeline(handler, options.step, options.window);
+    if let Some(resumed) = resumed {
+        // the timesteps up to the checkpoint are already in the state
+        timeline.retain(|time| *time > resumed);
+    }
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
 
-        let c = Utc::now();
-        state.update(props, &input);
-        trace!("Updating state took {} seconds", Utc::now() - c);
+            let n_missing = state.count_missing_required_inputs(&input);
+            if n_missing > 0 {
+                info!("{n_missing} cells not computed for missing required inputs");
+            }
 
-        if config.should_write_output(&state.time) {
             let c = Utc::now();
-            let output = state.output(props, &input);
-            trace!("Generating output took {} seconds", Utc::now() - c);
+            state.update(props, &input);
+            trace!("Updating state took {} seconds", Utc::now() - c);
 
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+            if config.should_write_output(&state.time, &input) {
+                let c = Utc::now();
+                let output = state.output(props, &input);
+                trace!("Generating output took {} seconds", Utc::now() - c);
+
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
         }
         if config.should_write_warm_state(&state.time) {
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
+        if let Some(every) = options.checkpoint_every {
+            if stats.timesteps % every == 0 {
+                if let Err(err) = config.write_checkpoint(&state) {
+                    warn!("Error writing checkpoint: {}", err);
+                    stats.warnings.push(format!("Error writing checkpoint: {}", err));
+                }
+            }
+        }
     }
-    Ok(())
+    if options.checkpoint_every.is_some() {
+        config.clear_checkpoints();
+    }
+    if options.check_warm_state {
+        let continuity = config.check_warm_state_continuity(&state);
+        report_warm_state_continuity("RISICO", &continuity, &mut stats);
+    }
+    *carried = Some(CarriedState::RISICO(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 fn run_fwi(
@@ -124,7 +548,9 @@ fn run_fwi(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     // run risico
     let config = model_config
         .build(date, palettes)
@@ -132,17 +558,28 @@ fn run_fwi(
 
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
 
     let props = config.get_properties();
-    let mut state = config.new_state();
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::FWI(state)) => state,
+        _ => config.new_state(),
+    };
 
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
 
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
 
     let current_time = Utc::now();
     trace!(
@@ -151,39 +588,68 @@ fn run_fwi(
     );
 
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
 
-        let c = Utc::now();
-        state.update(props, &input);
-        trace!("Updating state took {} seconds", Utc::now() - c);
-
-        if config.should_write_output(&state.time) {
             let c = Utc::now();
-            let output = state.output(&props);
-            trace!("Generating output took {} seconds", Utc::now() - c);
+            state.update(props, &input);
+            trace!("Updating state took {} seconds", Utc::now() - c);
 
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+            if config.should_write_output(&state.time) {
+                let c = Utc::now();
+                let output = state.output(&props);
+                trace!("Generating output took {} seconds", Utc::now() - c);
+
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
         }
-
         if config.should_write_warm_state(&state.time) {
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    if options.check_warm_state {
+        let continuity = config.check_warm_state_continuity(&state);
+        report_warm_state_continuity("FWI", &continuity, &mut stats);
+    }
+    *carried = Some(CarriedState::FWI(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 fn run_mark5(
@@ -191,7 +657,9 @@ fn run_mark5(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     // run risico
     let config = model_config
         .build(date, palettes)
@@ -199,17 +667,28 @@ fn run_mark5(
 
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
 
     let props = config.get_properties();
-    let mut state = config.new_state();
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::Mark5(state)) => state,
+        _ => config.new_state(),
+    };
 
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
 
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
 
     let current_time = Utc::now();
     trace!(
@@ -218,37 +697,73 @@ fn run_mark5(
     );
 
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
 
-        // store the input of the day
-        state.store(&input, props);
+            // store the input of the day
+            state.store(&input, props);
 
-        if config.should_write_warm_state(&state.time) {
-            // update the state with the input of the day and compute output
-            let c = Utc::now();
-            let output = state.output(props);
-            trace!("Generating output took {} seconds", Utc::now() - c);
+            let write_output = config.should_write_output(&state.time);
+            let end_of_day = config.is_end_of_day(&state.time);
+            if end_of_day || write_output {
+                let c = Utc::now();
+                let output = if end_of_day {
+                    // update the state with the input of the day and compute output
+                    state.output(props)
+                } else {
+                    // output between the daily updates, the state is left unchanged
+                    state.current_output(props)
+                };
+                trace!("Generating output took {} seconds", Utc::now() - c);
 
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+                if write_output {
+                    let c = Utc::now();
+                    if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                        if options.fail_on_write_error {
+                            return Err(format!("Error writing output: {}", err).into());
+                        }
+                        warn!("Error writing output: {}", err);
+                        stats.failed_writes += err.failures.len();
+                        stats.warnings.push(format!("Error writing output: {}", err));
+                    } else {
+                        stats.outputs_written += 1;
+                    }
+                    trace!("Writing output took {} seconds", Utc::now() - c);
+                }
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
-
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
+            }
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
+        }
+        if config.should_write_warm_state(&state.time) {
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    *carried = Some(CarriedState::Mark5(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 fn run_kbdi(
@@ -256,7 +771,9 @@ fn run_kbdi(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // build configuration
     let config = model_config
@@ -264,54 +781,103 @@ fn run_kbdi(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let props = config.get_properties(); // get properties
-    let mut state = config.new_state(); // get state
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::KBDI(state)) => state,
+        _ => config.new_state(),
+    };
                                         // set coordinates for the input handlerq
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input of the day
-        state.store(&input);
-        // check if we should write the output
-        if config.should_write_warm_state(&state.time) {
-            // update the state with the input of the day
-            let c = Utc::now();
-            state.update(props);
-            trace!("updating state took {} seconds", Utc::now() - c);
-            // compute output
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            // write the output
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input of the day
+            state.store(&input);
+            let write_output = config.should_write_output(&state.time);
+            let end_of_day = config.is_end_of_day(&state.time);
+            if end_of_day {
+                // update the state with the input of the day
+                let c = Utc::now();
+                state.update(props);
+                trace!("updating state took {} seconds", Utc::now() - c);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            // check if we should write the output
+            if end_of_day || write_output {
+                // compute output, the daily values are cleaned at the end of the day
+                let c = Utc::now();
+                let output = if end_of_day {
+                    state.output()
+                } else {
+                    state.current_output()
+                };
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                if write_output {
+                    let c = Utc::now();
+                    if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                        if options.fail_on_write_error {
+                            return Err(format!("Error writing output: {}", err).into());
+                        }
+                        warn!("Error writing output: {}", err);
+                        stats.failed_writes += err.failures.len();
+                        stats.warnings.push(format!("Error writing output: {}", err));
+                    } else {
+                        stats.outputs_written += 1;
+                    }
+                    trace!("Writing output took {} seconds", Utc::now() - c);
+                }
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
+            }
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
+        }
+        if config.should_write_warm_state(&state.time) {
             // write the warm state
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    *carried = Some(CarriedState::KBDI(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 /// Run Angstrom index
@@ -320,7 +886,8 @@ fn run_angstrom(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configure the model
     let config = model_config
@@ -328,41 +895,65 @@ fn run_angstrom(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let mut state = config.new_state(); // inizialized the state
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input
-        state.store(&input);
-        // check if we should write the output
-        if config.should_write_output(&state.time) {
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input
+            state.store(&input);
+            // check if we should write the output
+            if config.should_write_output(&state.time) {
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 /// Run Fosberg index
@@ -371,7 +962,8 @@ fn run_fosberg(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configure the model
     let config = model_config
@@ -379,41 +971,66 @@ fn run_fosberg(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let mut state = config.new_state(); // initialize the state
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
+    let props = config.get_properties();
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input data
-        state.store(&input);
-        // check if we should write the output
-        if config.should_write_output(&state.time) {
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input data
+            state.store(&input);
+            // check if we should write the output
+            if config.should_write_output(&state.time) {
+                let c = Utc::now();
+                let output = state.output(props);
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 /// Run Nesterov index
@@ -422,7 +1039,9 @@ fn run_nesterov(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configure the model
     let config = model_config
@@ -430,55 +1049,94 @@ fn run_nesterov(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let props = config.get_properties(); // get properties
-    let mut state = config.new_state(); // initialize the state
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::Nesterov(state)) => state,
+        _ => config.new_state(),
+    };
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input of the day
-        state.store(&input, props);
-        // check if we should write the output
-        if config.should_write_warm_state(&state.time) {
-            // update the state with the input of the day
-            let c = Utc::now();
-            state.update();
-            trace!("Generating update took {} seconds", Utc::now() - c);
-            // compute output
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            // write the output
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input of the day
+            state.store(&input, props);
+            // check if we should write the output
+            if config.is_end_of_day(&state.time) {
+                // update the state with the input of the day
+                let c = Utc::now();
+                state.update();
+                trace!("Generating update took {} seconds", Utc::now() - c);
+                // compute output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
+        }
+        if config.should_write_warm_state(&state.time) {
             // write the warm state
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    *carried = Some(CarriedState::Nesterov(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 /// Run Sharples index
@@ -487,7 +1145,8 @@ fn run_sharples(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configure the model
     let config = model_config
@@ -495,42 +1154,66 @@ fn run_sharples(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let mut state = config.new_state(); // initialize the state
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        // store the input
-        let input = get_input(handler, &time, len);
-        state.store(&input);
-        if config.should_write_output(&state.time) {
-            // compute the output
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            // write the output
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            // store the input
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            state.store(&input);
+            if config.should_write_output(&state.time) {
+                // compute the output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 // Run Orieux index
@@ -539,7 +1222,9 @@ fn run_orieux(
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configure the model
     let config = model_config
@@ -547,127 +1232,209 @@ fn run_orieux(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let props = config.get_properties(); // get properties
-    let mut state = config.new_state(); // initialize the state
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::Orieux(state)) => state,
+        _ => config.new_state(),
+    };
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input of the day
-        state.store(&input);
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input of the day
+            state.store(&input);
+            if config.is_end_of_day(&state.time) {
+                // update the state with the input of the day
+                let c = Utc::now();
+                state.update(props);
+                trace!("Generating update took {} seconds", Utc::now() - c);
+                // compute output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
+            }
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
+        }
         if config.should_write_warm_state(&state.time) {
-            // update the state with the input of the day
-            let c = Utc::now();
-            state.update(props);
-            trace!("Generating update took {} seconds", Utc::now() - c);
-            // compute output
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            // write the output
+            // write the warm state
+            info!("Writing warm state");
             let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+            if let Err(err) = config.write_warm_state(&state, state.time) {
+                warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
+            }
+            trace!("Writing warm state took {} seconds", Utc::now() - c);
+        }
+    }
+    *carried = Some(CarriedState::Orieux(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
+}
+
+/// Run Portuguese index
+fn run_portuguese(
+    model_config: &PortugueseConfigBuilder,
+    date: &DateTime<Utc>,
+    handler: &mut dyn InputHandler,
+    palettes: &PaletteMap,
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
+    let current_time = Utc::now();
+    // configure the model
+    let config = model_config
+        .build(date, palettes)
+        .map_err(|err| format!("Could not configure model {err}"))?;
+    let mut output_writer = config
+        .get_output_writer()
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
+    let props = config.get_properties(); // get properties
+    // in a date-range run the state of the previous model date is carried in memory
+    let mut state = match carried.take() {
+        Some(CarriedState::Portuguese(state)) => state,
+        _ => config.new_state(),
+    };
+                                        // set coordinates for the input handler
+    let (lats, lons) = config.get_properties().get_coords();
+    let (lats, lons) = (lats.as_slice(), lons.as_slice());
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
+    trace!(
+        "Loading input configuration took {} seconds",
+        Utc::now() - current_time
+    );
+    // explore the timeline
+    let len = state.len();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
+    for time in timeline {
+        // state before the timestep, restored if the timestep fails
+        let rollback = options.continue_on_error.then(|| (state.snapshot(), state.time));
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input of the day
+            state.store(&input, props);
+            // check if we should write the output
+            if config.is_end_of_day(&state.time) {
+                // update the state with the input of the day
+                let c = Utc::now();
+                state.update();
+                trace!("Generating update took {} seconds", Utc::now() - c);
+                // compute output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            // the timestep is skipped, without writing the partially updated state
+            if let Some((snapshot, time)) = &rollback {
+                state.restore(snapshot, time);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+            continue;
+        }
+        if config.should_write_warm_state(&state.time) {
             // write the warm state
             info!("Writing warm state");
             let c = Utc::now();
             if let Err(err) = config.write_warm_state(&state, state.time) {
                 warn!("Error writing warm state: {}", err);
+                stats.warnings.push(format!("Error writing warm state: {}", err));
             }
             trace!("Writing warm state took {} seconds", Utc::now() - c);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    *carried = Some(CarriedState::Portuguese(state));
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
-// Run Portuguese index
-// fn run_portuguese(
-//     model_config: &PortugueseConfigBuilder,
-//     date: &DateTime<Utc>,
-//     handler: &mut dyn InputHandler,
-//     palettes: &PaletteMap,
-// ) -> Result<(), RISICOError> {
-//     let current_time = Utc::now();
-//     // configuration of the model
-//     let config = model_config
-//         .build(date, palettes)
-//         .maerrp_err(|_| format!("Could not configure model {err}"))?;
-//     let mut output_writer = config
-//         .get_output_writer()
-//         .map_err(|_| "Could not configure output writer")?;
-//     let props = config.get_properties();  // get the properties
-//     let mut state = config.new_state();  // initialize the state
-//     // set coordinates for the input handler
-//     let (lats, lons) = config.get_properties().get_coords();
-//     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-//     handler.set_coordinates(lats, lons).expect("Should set coordinates");
-//     trace!(
-//         "Loading input configuration took {} seconds",
-//         Utc::now() - current_time
-//     );
-//     // explore the timeline
-//     let len = state.len();
-//     let timeline = handler.get_timeline();
-//     for time in timeline {
-//         let step_time = Utc::now();
-//         info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-//         let input = get_input(handler, &time, len);
-//         // store the input of the day
-//         state.store(&input, &props);
-//         // check if we should write the output
-//         let (should_write_warm, warm_state_time) = config.should_write_warm_state(&time);
-//         if  should_write_warm{
-//             // update the state with the input of the day
-//             let c = Utc::now();
-//             state.update();
-//             trace!("Generating update took {} seconds", Utc::now() - c);
-//             // compute output
-//             let c = Utc::now();
-//             let output = state.output();
-//             trace!("Generating output took {} seconds", Utc::now() - c);
-//             // write the outut
-//             let c = Utc::now();
-//             if let Err(err) = output_writer.write_output(lats, lons, &output) {
-//                 warn!("Error writing output: {}", err);
-//             }
-//             trace!("Writing output took {} seconds", Utc::now() - c);
-//             // write the warm state
-//             info!("Writing warm state");
-//             let c = Utc::now();
-//             if let Err(err) = config.write_warm_state(&state, warm_state_time) {
-//                 warn!("Error writing warm state: {}", err);
-//             }
-//             trace!("Writing warm state took {} seconds", Utc::now() - c);
-//         }
-//         trace!("Step took {} seconds", Utc::now() - step_time);
-//     }
-//     Ok(())
-// }
-
 // Run Hot-Dry-Wind index
 fn run_hdw(
     model_config: &HdwConfigBuilder,
     date: &DateTime<Utc>,
     handler: &mut dyn InputHandler,
     palettes: &PaletteMap,
-) -> Result<(), RISICOError> {
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
     let current_time = Utc::now();
     // configuration of the model
     let config = model_config
@@ -675,57 +1442,345 @@ fn run_hdw(
         .map_err(|err| format!("Could not configure model {err}"))?;
     let mut output_writer = config
         .get_output_writer()
-        .map_err(|_| "Could not configure output writer")?;
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
     let mut state = config.new_state(); // initialize the state
                                         // set coordinates for the input handler
     let (lats, lons) = config.get_properties().get_coords();
     let (lats, lons) = (lats.as_slice(), lons.as_slice());
-    handler
-        .set_coordinates(lats, lons)
-        .expect("Should set coordinates");
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
     trace!(
         "Loading input configuration took {} seconds",
         Utc::now() - current_time
     );
     // explore the timeline
     let len = state.len();
-    let timeline = handler.get_timeline();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
     for time in timeline {
-        let step_time = Utc::now();
-        info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
-        let input = get_input(handler, &time, len);
-        // store the input
-        state.store(&input);
-        if config.should_write_output(&state.time) {
-            // compute the output
-            let c = Utc::now();
-            let output = state.output();
-            trace!("Generating output took {} seconds", Utc::now() - c);
-            // write the output
-            let c = Utc::now();
-            if let Err(err) = output_writer.write_output(lats, lons, &output) {
-                warn!("Error writing output: {}", err);
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input
+            state.store(&input);
+            if config.should_write_output(&state.time) {
+                // compute the output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
             }
-            trace!("Writing output took {} seconds", Utc::now() - c);
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
         }
-        trace!("Step took {} seconds", Utc::now() - step_time);
     }
-    Ok(())
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
+}
+
+// Run Haines index
+fn run_haines(
+    model_config: &HainesConfigBuilder,
+    date: &DateTime<Utc>,
+    handler: &mut dyn InputHandler,
+    palettes: &PaletteMap,
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
+    let current_time = Utc::now();
+    // configuration of the model
+    let config = model_config
+        .build(date, palettes)
+        .map_err(|err| format!("Could not configure model {err}"))?;
+    let mut output_writer = config
+        .get_output_writer()
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
+    let mut state = config.new_state(); // initialize the state
+                                        // set coordinates for the input handler
+    let (lats, lons) = config.get_properties().get_coords();
+    let (lats, lons) = (lats.as_slice(), lons.as_slice());
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
+    trace!(
+        "Loading input configuration took {} seconds",
+        Utc::now() - current_time
+    );
+    // explore the timeline
+    let len = state.len();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
+    for time in timeline {
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input
+            state.store(&input);
+            if config.should_write_output(&state.time) {
+                // compute the output
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                // write the output
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+        }
+    }
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
+}
+
+/// Run Chandler Burning Index
+fn run_chandler(
+    model_config: &ChandlerConfigBuilder,
+    date: &DateTime<Utc>,
+    handler: &mut dyn InputHandler,
+    palettes: &PaletteMap,
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
+    let current_time = Utc::now();
+    // configure the model
+    let config = model_config
+        .build(date, palettes)
+        .map_err(|err| format!("Could not configure model {err}"))?;
+    let mut output_writer = config
+        .get_output_writer()
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
+    let mut state = config.new_state(); // initialize the state
+                                        // set coordinates for the input handler
+    let (lats, lons) = config.get_properties().get_coords();
+    let (lats, lons) = (lats.as_slice(), lons.as_slice());
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
+    trace!(
+        "Loading input configuration took {} seconds",
+        Utc::now() - current_time
+    );
+    // explore the timeline
+    let len = state.len();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
+    for time in timeline {
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input data
+            state.store(&input);
+            // check if we should write the output
+            if config.should_write_output(&state.time) {
+                let c = Utc::now();
+                let output = state.output();
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+        }
+    }
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
+}
+
+/// Run McArthur Grassland Fire Danger Index
+fn run_gfdi(
+    model_config: &GfdiConfigBuilder,
+    date: &DateTime<Utc>,
+    handler: &mut dyn InputHandler,
+    palettes: &PaletteMap,
+    options: &RunOptions,
+) -> Result<RunStats, RISICOError> {
+    let current_time = Utc::now();
+    // configure the model
+    let config = model_config
+        .build(date, palettes)
+        .map_err(|err| format!("Could not configure model {err}"))?;
+    let mut output_writer = config
+        .get_output_writer()
+        .map_err(|_| "Could not configure output writer")?
+        .with_provenance(&model_config.model_name, &options.input_path)
+        .with_max_concurrency(options.max_write_concurrency);
+    let props = config.get_properties();
+    let mut state = config.new_state(); // initialize the state
+                                        // set coordinates for the input handler
+    let (lats, lons) = config.get_properties().get_coords();
+    let (lats, lons) = (lats.as_slice(), lons.as_slice());
+    prepare_coordinates(
+        handler,
+        &mut output_writer,
+        &model_config.cells_file_path,
+        lats,
+        lons,
+        options,
+    )?;
+    trace!(
+        "Loading input configuration took {} seconds",
+        Utc::now() - current_time
+    );
+    // explore the timeline
+    let len = state.len();
+    let timeline = get_timeline(handler, options.step, options.window);
+    let mut stats = RunStats::default();
+    for time in timeline {
+        let failure = run_timestep(&time, options.continue_on_error, || {
+            let step_time = Utc::now();
+            info!("Processing {}", time.format("%Y-%m-%d %H:%M"));
+            let input = get_input(handler, &time, len, &options.input);
+            stats.timesteps += 1;
+            // store the input data
+            state.store(&input);
+            // check if we should write the output
+            if config.should_write_output(&state.time) {
+                let c = Utc::now();
+                let output = state.output(props);
+                trace!("Generating output took {} seconds", Utc::now() - c);
+                let c = Utc::now();
+                if let Err(err) = output_writer.write_output(lats, lons, &output) {
+                    if options.fail_on_write_error {
+                        return Err(format!("Error writing output: {}", err).into());
+                    }
+                    warn!("Error writing output: {}", err);
+                    stats.failed_writes += err.failures.len();
+                    stats.warnings.push(format!("Error writing output: {}", err));
+                } else {
+                    stats.outputs_written += 1;
+                }
+                trace!("Writing output took {} seconds", Utc::now() - c);
+            }
+            trace!("Step took {} seconds", Utc::now() - step_time);
+            Ok(())
+        })?;
+        if let Some(err) = failure {
+            stats.failed_timesteps += 1;
+            stats.warnings.push(err);
+        }
+    }
+    finish_outputs(&mut output_writer, lats, lons, options, &mut stats)?;
+    Ok(stats)
 }
 
 fn get_input_handler(
     input_path_str: &str,
     configs: &ConfigContainer,
 ) -> Result<Box<dyn InputHandler>, Box<dyn Error>> {
+    if input_path_str.starts_with("s3://") {
+        return get_s3_input_handler(input_path_str, configs);
+    }
     // check if input_path is a file or a directory
     let input_path = Path::new(&input_path_str);
+    #[cfg(feature = "grib")]
+    if input_path.is_dir() && has_grib_files(input_path_str) {
+        info!(
+            "Loading input data from {} using GribInputHandler",
+            input_path_str
+        );
+        let grib_config = configs.grib_input_configuration.clone().unwrap_or_default();
+        if configs.input_options.interpolation == common::io::models::grid::InterpolationMode::Bilinear {
+            warn!("Bilinear interpolation not supported for the GRIB inputs, using the nearest grid point");
+        }
+        let grib_config = GribInputConfiguration {
+            strict_coordinates: configs.input_options.strict_coordinates,
+            index_cache_path: configs.input_options.index_cache_path.clone(),
+            ..grib_config
+        };
+        let handler = GribInputHandler::new(input_path_str, &grib_config)
+            .map_err(|_| "Could not load input data")?;
+        return Ok(Box::new(handler));
+    }
     let handler: Box<dyn InputHandler> = if input_path.is_file() {
         info!(
             "Loading input data from {} using BinaryInputHandler",
             input_path_str
         );
         // if it is a file, we are loading the legacy input.txt file and binary inputs
-        Box::new(BinaryInputHandler::new(input_path_str).map_err(|_| "Could not load input data")?)
+        let mut handler = BinaryInputHandler::new(
+            input_path_str,
+            &configs.input_options.binary_extensions,
+            &configs.input_options.binary_offsets,
+        )
+        .map_err(|_| "Could not load input data")?;
+        handler.index_cache = configs
+            .input_options
+            .index_cache_path
+            .as_deref()
+            .map(IndexCache::new);
+        handler.interpolation = configs.input_options.interpolation;
+        Box::new(handler)
     } else if input_path.is_dir() {
         info!(
             "Loading input data from {} using NetCdfInputHandler",
@@ -737,6 +1792,12 @@ fn get_input_handler(
         } else {
             NetCdfInputConfiguration::default()
         };
+        let nc_config = NetCdfInputConfiguration {
+            strict_coordinates: configs.input_options.strict_coordinates,
+            index_cache_path: configs.input_options.index_cache_path.clone(),
+            interpolation: configs.input_options.interpolation,
+            ..nc_config
+        };
 
         Box::new(
             NetCdfInputHandler::new(input_path_str, &nc_config)
@@ -749,18 +1810,497 @@ fn get_input_handler(
     Ok(handler)
 }
 
+/// Input handler of the NetCDF files in an S3-compatible bucket
+#[cfg(feature = "object_store")]
+fn get_s3_input_handler(
+    input_path_str: &str,
+    configs: &ConfigContainer,
+) -> Result<Box<dyn InputHandler>, Box<dyn Error>> {
+    info!(
+        "Loading input data from {} using S3InputHandler",
+        input_path_str
+    );
+    let nc_config = configs.get_netcdf_input_config().clone().unwrap_or_default();
+    let nc_config = NetCdfInputConfiguration {
+        strict_coordinates: configs.input_options.strict_coordinates,
+        index_cache_path: configs.input_options.index_cache_path.clone(),
+        interpolation: configs.input_options.interpolation,
+        ..nc_config
+    };
+    let handler = S3InputHandler::new(input_path_str, &nc_config)
+        .map_err(|err| format!("Could not load input data from {input_path_str}: {err}"))?;
+    Ok(Box::new(handler))
+}
+
+#[cfg(not(feature = "object_store"))]
+fn get_s3_input_handler(
+    input_path_str: &str,
+    _configs: &ConfigContainer,
+) -> Result<Box<dyn InputHandler>, Box<dyn Error>> {
+    Err(format!("Input path {input_path_str} requires the object_store feature").into())
+}
+
+/// Run a single model of the configuration, starting from the carried state if set
+/// and leaving the final state in it
+fn run_model(
+    model_config: &ConfigBuilderType,
+    date: &DateTime<Utc>,
+    handler: &mut dyn InputHandler,
+    palettes: &PaletteMap,
+    options: &RunOptions,
+    carried: &mut Option<CarriedState>,
+) -> Result<RunStats, RISICOError> {
+    match model_config {
+        ConfigBuilderType::FWI(model_config) => run_fwi(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::RISICO(model_config) => run_risico(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::Mark5(model_config) => run_mark5(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::KBDI(model_config) => run_kbdi(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::Angstrom(model_config) => run_angstrom(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Fosberg(model_config) => run_fosberg(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Nesterov(model_config) => run_nesterov(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::Sharples(model_config) => run_sharples(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Orieux(model_config) => run_orieux(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::Portuguese(model_config) => run_portuguese(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+            carried,
+        ),
+        ConfigBuilderType::Hdw(model_config) => run_hdw(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Haines(model_config) => run_haines(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Chandler(model_config) => run_chandler(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+        ConfigBuilderType::Gfdi(model_config) => run_gfdi(
+            model_config,
+            date,
+            handler,
+            palettes,
+            options,
+        ),
+    }
+}
+
+/// Load the input data, wrapped by the gap filling and prefetching handlers if configured
+fn build_input_handler(
+    input_path_str: &str,
+    configs: &ConfigContainer,
+    prefetch: usize,
+) -> Result<Box<dyn InputHandler>, Box<dyn Error>> {
+    let mut input_handler = get_input_handler(input_path_str, configs)?;
+    if let Some(gaps_config) = &configs.timeline_gaps {
+        input_handler = GapFillingInputHandler::wrap(input_handler, gaps_config)
+            .map_err(|err| format!("Invalid input timeline: {}", err))?;
+    }
+    if prefetch > 0 {
+        input_handler = PrefetchInputHandler::wrap(input_handler, prefetch);
+    }
+    Ok(input_handler)
+}
+
+/// Summary of a model run
+fn model_summary(
+    model_config: &ConfigBuilderType,
+    date: &DateTime<Utc>,
+    elapsed_seconds: i64,
+    model_run: Result<RunStats, RISICOError>,
+) -> ModelSummary {
+    let model = model_config.get_model_name().to_string();
+    let run_date = date.to_rfc3339();
+    match model_run {
+        Ok(stats) => ModelSummary {
+            model,
+            run_date,
+            status: "ok".to_string(),
+            elapsed_seconds,
+            stats,
+            error: None,
+        },
+        Err(err) => {
+            warn!("Error running model {}: {}", model, err);
+            ModelSummary {
+                model,
+                run_date,
+                status: "error".to_string(),
+                elapsed_seconds,
+                stats: RunStats::default(),
+                error: Some(err.to_string()),
+            }
+        }
+    }
+}
+
+/// Writes planned by a model for each timestep of the timeline
+fn plan_model(
+    model_config: &ConfigBuilderType,
+    date: &DateTime<Utc>,
+    palettes: &PaletteMap,
+    timeline: &[DateTime<Utc>],
+) -> Result<Vec<PlannedWrites>, RISICOError> {
+    let plan = match model_config {
+        ConfigBuilderType::FWI(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::RISICO(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Mark5(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::KBDI(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Angstrom(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Fosberg(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Nesterov(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Sharples(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Orieux(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Portuguese(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Hdw(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Haines(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Chandler(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+        ConfigBuilderType::Gfdi(model_config) => {
+            let config = model_config.build(date, palettes)?;
+            timeline.iter().map(|time| config.plan(time)).collect()
+        }
+    };
+    Ok(plan)
+}
+
+/// Model dates of a date-range run, one per day from the start to the end date (included)
+fn get_run_dates(start: &DateTime<Utc>, end: &DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let mut run_dates = Vec::new();
+    let mut run_date = *start;
+    while run_date <= *end {
+        run_dates.push(run_date);
+        run_date += Duration::try_days(1).expect("Should be a valid duration");
+    }
+    run_dates
+}
+
+/// Print the timesteps that would be processed and what would be written at each of them
+fn print_plan(
+    configs: &ConfigContainer,
+    date: &DateTime<Utc>,
+    handler: &dyn InputHandler,
+    step: usize,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Result<(), RISICOError> {
+    let timeline = get_timeline(handler, step, window);
+    for model_config in &configs.models {
+        let model = model_config.get_model_name();
+        let plan = plan_model(model_config, date, &configs.palettes, &timeline)
+            .map_err(|err| format!("Could not configure model {model}: {err}"))?;
+        println!("Model {model}: {} timesteps", timeline.len());
+        for (time, planned) in timeline.iter().zip(plan) {
+            let output = match planned.output {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "on satellite update",
+            };
+            let warm_state = if planned.warm_state { "yes" } else { "no" };
+            println!(
+                "  {}  output: {output}  warm state: {warm_state}",
+                time.format("%Y-%m-%d %H:%M")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Check that the output directory exists and is writable, writing and removing a probe file
+fn check_output_dir(path: &str) -> Result<(), String> {
+    let dir = Path::new(path);
+    if !dir.is_dir() {
+        return Err(format!("output directory {path} does not exist"));
+    }
+    let probe = dir.join(format!(".risico_dry_run_{}", std::process::id()));
+    std::fs::write(&probe, b"")
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|err| format!("output directory {path} is not writable: {err}"))
+}
+
+/// Build the model configurations and check the palettes, the output directories
+/// and the input timeline, without running the models
+fn dry_run(
+    configs: &ConfigContainer,
+    date: &DateTime<Utc>,
+    handler: &dyn InputHandler,
+    step: usize,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Result<(), RISICOError> {
+    let mut problems: Vec<String> = Vec::new();
+    for (name, path) in &configs.palettes {
+        if !Path::new(path).is_file() {
+            problems.push(format!("palette {name}: file {path} not found"));
+        }
+    }
+
+    let timeline = get_timeline(handler, step, window);
+    match (timeline.first(), timeline.last()) {
+        (Some(first), Some(last)) => println!(
+            "Timeline: {} - {} ({} timesteps)",
+            first.format("%Y-%m-%d %H:%M"),
+            last.format("%Y-%m-%d %H:%M"),
+            timeline.len()
+        ),
+        _ => problems.push("the input timeline is empty".to_string()),
+    }
+
+    for model_config in &configs.models {
+        let model = model_config.get_model_name();
+        // building the configuration reads the cells, vegetation and PPF files
+        if let Err(err) = plan_model(model_config, date, &configs.palettes, &timeline) {
+            problems.push(format!("model {model}: {err}"));
+            continue;
+        }
+        for output_type in model_config.get_output_types() {
+            if let Err(err) = check_output_dir(&output_type.path) {
+                problems.push(format!("model {model}: {err}"));
+            }
+        }
+        println!("Model {model}: configuration OK");
+    }
+
+    if !problems.is_empty() {
+        return Err(format!("Dry run failed: {}", problems.join("; ")).into());
+    }
+    Ok(())
+}
+
+/// Run all the models on the bundled dataset and check the variables they write
+fn run_selftest(dir: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let (dir, is_temporary) = match dir {
+        Some(dir) => (PathBuf::from(dir), false),
+        None => (
+            std::env::temp_dir().join(format!("risico-selftest-{}", std::process::id())),
+            true,
+        ),
+    };
+    let date = selftest_date();
+    let configs = prepare_selftest(&dir)
+        .map_err(|err| format!("Could not prepare the self test in {}: {}", dir.display(), err))?;
+    let mut handler = selftest_input(&date)?;
+
+    let options = RunOptions {
+        step: 1,
+        input: configs.input_options.clone(),
+        fail_on_write_error: true,
+        continue_on_error: false,
+        check_warm_state: false,
+        window: None,
+        input_path: "selftest".to_string(),
+        max_write_concurrency: configs.max_write_concurrency,
+        checkpoint_every: None,
+    };
+
+    let mut failed_models = Vec::new();
+    for model_config in &configs.models {
+        let model = model_config.get_model_name();
+        info!("Running model: {:?}", model);
+        let model_run =
+            run_model(model_config, &date, &mut handler, &configs.palettes, &options, &mut None);
+        let problems = match model_run {
+            Ok(_) => check_outputs(model_config),
+            Err(err) => vec![format!("run failed: {}", err)],
+        };
+        if problems.is_empty() {
+            println!("{model}: ok");
+        } else {
+            for problem in &problems {
+                println!("{model}: {problem}");
+            }
+            failed_models.push(model);
+        }
+    }
+
+    if !failed_models.is_empty() {
+        return Err(format!(
+            "Self test failed for {}, outputs kept in {}",
+            failed_models.join(", "),
+            dir.display()
+        )
+        .into());
+    }
+    if is_temporary {
+        if let Err(err) = std::fs::remove_dir_all(&dir) {
+            warn!("Could not remove {}: {}", dir.display(), err);
+        }
+    }
+    println!("Self test passed for {} models", configs.models.len());
+    Ok(())
+}
+
+/// Print the output variable names accepted in the configuration (case insensitive)
+fn print_output_variables() {
+    println!("{:<24} {:<60} {:<10} ALIASES", "NAME", "LONG NAME", "UNITS");
+    for var in OutputVariableName::iter() {
+        let name = format!("{:?}", var);
+        let aliases: Vec<&str> = var
+            .get_serializations()
+            .iter()
+            .copied()
+            .filter(|alias| *alias != name)
+            .collect();
+        println!(
+            "{:<24} {:<60} {:<10} {}",
+            name,
+            var.get_str("long_name").unwrap_or("-"),
+            var.get_str("units").unwrap_or("-"),
+            aliases.join(", ")
+        );
+    }
+}
+
 /// main function
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    let date_str = args.date;
-    let config_path_str = args.config_path;
-    let input_path_str = args.input_path;
 
     if var("RUST_LOG").is_err() {
         set_var("RUST_LOG", "info")
     }
     pretty_env_logger::init();
 
+    match &args.command {
+        Some(Command::Selftest { dir }) => return run_selftest(dir.as_deref()),
+        Some(Command::Ppf {
+            cells_file,
+            outputs_dir,
+            ppf_file,
+            variable,
+        }) => {
+            let ppf = compute_ppf(cells_file, Path::new(outputs_dir), variable)?;
+            let header = format!(
+                "ppf_summer ppf_winter, climatology of {} in {}",
+                variable, outputs_dir
+            );
+            write_ppf(ppf_file, &ppf, &header)?;
+            info!("PPF of {} cells written to {}", ppf.len(), ppf_file);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if args.list_variables {
+        print_output_variables();
+        return Ok(());
+    }
+
+    let (Some(date_str), Some(config_path_str), Some(input_path_str)) =
+        (args.date, args.config_path, args.input_path)
+    else {
+        return Err("The model date, configuration file and input path are required".into());
+    };
+
     if !Path::new(&config_path_str).is_file() {
         return Err(format!("Config file {} is not a file", config_path_str).into());
     }
@@ -770,93 +2310,332 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let date = DateTime::from_naive_utc_and_offset(date, Utc);
 
+    let run_dates = match &args.end_date {
+        Some(end_date_str) => {
+            let end_date = NaiveDateTime::parse_from_str(end_date_str, "%Y%m%d%H%M")
+                .map_err(|_| format!("Could not parse end date '{}'", end_date_str))?;
+            let end_date = DateTime::from_naive_utc_and_offset(end_date, Utc);
+            if end_date < date {
+                return Err(
+                    format!("End date {} is before the model date {}", end_date_str, date_str).into(),
+                );
+            }
+            get_run_dates(&date, &end_date)
+        }
+        None => vec![date],
+    };
+    let is_range = args.end_date.is_some();
+    // in a date-range run each model date processes the inputs of its day,
+    // continuing from the state of the previous date kept in memory
+    let window = |run_date: &DateTime<Utc>| {
+        let next_date = *run_date + Duration::try_days(1).expect("Should be a valid duration");
+        is_range.then_some((*run_date, next_date))
+    };
+
     let configs = ConfigContainer::from_file(&config_path_str)
         .map_err(|err| format!("Failed to load config: {}", err))?;
 
-    // check if input_path is a file or a directory
-    let mut input_handler = get_input_handler(&input_path_str, &configs)?;
-    info!("Input files:\n{}", input_handler.info_input());
+    configs
+        .check_warm_state_hours(args.strict_warm_state_hour)
+        .map_err(|err| format!("Invalid config: {}", err))?;
 
-    for model_config in &configs.models {
-        info!("Running model: {:?}", model_config.get_model_name());
-        let start_time = Utc::now();
-
-        let model_run = match model_config {
-            ConfigBuilderType::FWI(model_config) => run_fwi(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::RISICO(model_config) => run_risico(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Mark5(model_config) => run_mark5(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::KBDI(model_config) => run_kbdi(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Angstrom(model_config) => run_angstrom(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Fosberg(model_config) => run_fosberg(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Nesterov(model_config) => run_nesterov(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Sharples(model_config) => run_sharples(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            ConfigBuilderType::Orieux(model_config) => run_orieux(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-            // ConfigBuilderType::Portuguese(model_config) => run_portuguese(
-            //     model_config,
-            //     &date,
-            //     input_handler.as_mut(),
-            //     &configs.palettes,
-            // ),
-            ConfigBuilderType::Hdw(model_config) => run_hdw(
-                model_config,
-                &date,
-                input_handler.as_mut(),
-                &configs.palettes,
-            ),
-        };
+    configs
+        .check_output_collisions()
+        .map_err(|err| format!("Invalid config: {}", err))?;
+
+    let mut input_handler = build_input_handler(&input_path_str, &configs, args.prefetch)?;
+    let verbose_input = args.verbose_input || log_enabled!(Level::Debug);
+    info!("Input files:\n{}", input_handler.info_input(verbose_input));
+    info!(
+        "Available variables:\n{}",
+        format_available_variables(
+            &input_handler.available_variables(),
+            input_handler.get_timeline().len()
+        )
+    );
+
+    configs
+        .check_input_timeline(&input_handler.get_timeline())
+        .map_err(|err| format!("Invalid input: {}", err))?;
+    configs
+        .check_required_inputs(&input_handler.get_variables())
+        .map_err(|err| format!("Invalid input: {}", err))?;
+
+    if args.plan {
+        for run_date in &run_dates {
+            if is_range {
+                println!("Model date {}", run_date.format("%Y-%m-%d %H:%M"));
+            }
+            print_plan(&configs, run_date, input_handler.as_ref(), args.step, window(run_date))
+                .map_err(String::from)?;
+        }
+        return Ok(());
+    }
+
+    if args.dry_run {
+        for run_date in &run_dates {
+            if is_range {
+                println!("Model date {}", run_date.format("%Y-%m-%d %H:%M"));
+            }
+            dry_run(&configs, run_date, input_handler.as_ref(), args.step, window(run_date))
+                .map_err(String::from)?;
+        }
+        println!("Dry run passed for {} models", configs.models.len());
+        return Ok(());
+    }
+
+    let mut options = RunOptions {
+        step: args.step,
+        input: configs.input_options.clone(),
+        fail_on_write_error: args.fail_on_write_error,
+        continue_on_error: args.continue_on_error,
+        check_warm_state: args.check_warm_state,
+        window: None,
+        input_path: input_path_str.clone(),
+        max_write_concurrency: configs.max_write_concurrency,
+        checkpoint_every: args.checkpoint_every.filter(|every| *every > 0),
+    };
+    if options.checkpoint_every.is_some() {
+        for model_config in &configs.models {
+            let has_warm_state = model_config.get_warm_state_hours().is_some();
+            if has_warm_state && !matches!(model_config, ConfigBuilderType::RISICO(_)) {
+                warn!(
+                    "Checkpoints are supported only by RISICO, {} resumes from its warm state",
+                    model_config.get_model_name()
+                );
+            }
+        }
+    }
+
+    let mut summary = RunSummary {
+        run_date: date.to_rfc3339(),
+        models: Vec::new(),
+    };
+
+    let parallel = args.parallel_models && configs.models.len() > 1;
+    // each model sets its own coordinates, so in parallel it needs its own input handler,
+    // sharing the input data already read by the first one when the handler supports it
+    let mut handlers = vec![input_handler];
+    if parallel {
+        info!("Running {} models in parallel", configs.models.len());
+        for _ in 1..configs.models.len() {
+            let handler = match handlers[0].share() {
+                Some(handler) => handler,
+                None => build_input_handler(&input_path_str, &configs, args.prefetch)?,
+            };
+            handlers.push(handler);
+        }
+    }
+
+    // state of each model at the end of the previous model date
+    let mut carried: Vec<Option<CarriedState>> = configs.models.iter().map(|_| None).collect();
+    'dates: for run_date in &run_dates {
+        if is_range {
+            info!("Model date: {}", run_date.format("%Y-%m-%d %H:%M"));
+        }
+        options.window = window(run_date);
+
+        if parallel {
+            let options = &options;
+            let palettes = &configs.palettes;
+            let runs: Vec<ModelSummary> = std::thread::scope(|scope| {
+                let runs: Vec<_> = configs
+                    .models
+                    .iter()
+                    .zip(handlers.iter_mut())
+                    .zip(carried.iter_mut())
+                    .map(|((model_config, handler), carried)| {
+                        scope.spawn(move || {
+                            info!("Running model: {:?}", model_config.get_model_name());
+                            let start_time = Utc::now();
+                            let model_run = run_model(
+                                model_config,
+                                run_date,
+                                handler.as_mut(),
+                                palettes,
+                                options,
+                                carried,
+                            );
+                            let elapsed_time = Utc::now() - start_time;
+                            info!(
+                                "Model {} elapsed time: {} seconds",
+                                model_config.get_model_name(),
+                                elapsed_time.num_seconds()
+                            );
+                            model_summary(model_config, run_date, elapsed_time.num_seconds(), model_run)
+                        })
+                    })
+                    .collect();
+                runs.into_iter()
+                    .map(|run| run.join().expect("Model run thread panicked"))
+                    .collect()
+            });
+            let failed = runs.iter().any(|m| m.error.is_some());
+            summary.models.extend(runs);
+            if failed && args.fail_on_write_error {
+                break 'dates;
+            }
+        } else {
+            for (model_config, carried) in configs.models.iter().zip(carried.iter_mut()) {
+                info!("Running model: {:?}", model_config.get_model_name());
+                let start_time = Utc::now();
 
-        if let Err(err) = model_run {
-            warn!("Error running model: {}", err);
+                let model_run = run_model(
+                    model_config,
+                    run_date,
+                    handlers[0].as_mut(),
+                    &configs.palettes,
+                    &options,
+                    carried,
+                );
+
+                let elapsed_time = Utc::now() - start_time;
+                info!("Elapsed time: {} seconds", elapsed_time.num_seconds());
+
+                let model_run =
+                    model_summary(model_config, run_date, elapsed_time.num_seconds(), model_run);
+                let failed = model_run.error.is_some();
+                summary.models.push(model_run);
+                if failed && args.fail_on_write_error {
+                    break 'dates;
+                }
+            }
         }
+    }
 
-        let elapsed_time = Utc::now() - start_time;
-        info!("Elapsed time: {} seconds", elapsed_time.num_seconds());
+    let failure = summary
+        .models
+        .iter()
+        .find_map(|m| m.error.as_ref())
+        .filter(|_| args.fail_on_write_error)
+        .map(|err| format!("Error running model: {}", err));
+
+    if let Some(summary_path) = &args.summary {
+        summary
+            .write(summary_path)
+            .map_err(|err| format!("Could not write run summary: {}", err))?;
+    }
+
+    if let Some(failure) = failure {
+        return Err(failure.into());
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use common::io::readers::binary::read_values_from_file;
+
+    use super::*;
+
+    type Window = (DateTime<Utc>, DateTime<Utc>);
+
+    fn test_options(configs: &ConfigContainer, window: Window) -> RunOptions {
+        RunOptions {
+            step: 1,
+            input: configs.input_options.clone(),
+            fail_on_write_error: true,
+            continue_on_error: false,
+            check_warm_state: false,
+            window: Some(window),
+            input_path: "test".to_string(),
+            max_write_concurrency: configs.max_write_concurrency,
+            checkpoint_every: None,
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("risico-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Run all the models of the self test on the model dates, each processing the inputs
+    /// of its window, with the states carried from a date to the next one
+    fn run_dates(dir: &Path, dates: &[(DateTime<Utc>, Window)]) -> ConfigContainer {
+        let configs = prepare_selftest(dir).expect("Should prepare the self test");
+        let mut handler = selftest_input(&selftest_date()).expect("Should build the inputs");
+        for model_config in &configs.models {
+            let mut carried = None;
+            for (date, window) in dates {
+                let options = test_options(&configs, *window);
+                let palettes = &configs.palettes;
+                run_model(
+                    model_config,
+                    date,
+                    &mut handler,
+                    palettes,
+                    &options,
+                    &mut carried,
+                )
+                .expect("Should run the model");
+            }
+        }
+        configs
+    }
+
+    /// Values of the ZBIN files of the directory by timestep and variable,
+    /// the run date in the file names (NAME_RUNDATE_DATE_VARIABLE.zbin) is left out
+    fn zbin_outputs(path: &str) -> BTreeMap<String, Vec<f32>> {
+        let mut outputs = BTreeMap::new();
+        for entry in fs::read_dir(path).expect("Should read the output directory") {
+            let file = entry.expect("Should read the directory entry").path();
+            let name = file
+                .file_name()
+                .expect("Should be a file")
+                .to_string_lossy()
+                .into_owned();
+            let key = name
+                .splitn(3, '_')
+                .nth(2)
+                .expect("Should be a ZBIN file name")
+                .to_string();
+            let values = read_values_from_file(file.to_str().expect("Should be a valid path"))
+                .expect("Should read the ZBIN file");
+            outputs.insert(key, values.to_vec());
+        }
+        outputs
+    }
+
+    #[test]
+    fn date_range_run_matches_a_continuous_run() {
+        let day = Duration::try_days(1).expect("Should be a valid duration");
+        let first = selftest_date();
+        let second = first + day;
+
+        // two model dates, one day of inputs each
+        let range_dir = test_dir("range");
+        let range_configs = run_dates(
+            &range_dir,
+            &[(first, (first, second)), (second, (second, second + day))],
+        );
+        // the same two days in a single run
+        let single_dir = test_dir("single");
+        let single_configs = run_dates(&single_dir, &[(first, (first, second + day))]);
+
+        for (range_model, single_model) in range_configs.models.iter().zip(&single_configs.models) {
+            for (range_output, single_output) in range_model
+                .get_output_types()
+                .iter()
+                .zip(single_model.get_output_types())
+                .filter(|(output, _)| output.format == "ZBIN")
+            {
+                let range_values = zbin_outputs(&range_output.path);
+                let single_values = zbin_outputs(&single_output.path);
+                assert!(!range_values.is_empty(), "{}", range_model.get_model_name());
+                assert_eq!(
+                    range_values,
+                    single_values,
+                    "{}",
+                    range_model.get_model_name()
+                );
+            }
+        }
+        let _ = fs::remove_dir_all(&range_dir);
+        let _ = fs::remove_dir_all(&single_dir);
+    }
+}
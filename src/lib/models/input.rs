@@ -18,6 +18,8 @@ pub struct InputElement {
     pub wind_speed: f32,
     /// wind direction [rad]
     pub wind_dir: f32,
+    /// wind gust [m/h]
+    pub wind_gust: f32,
     /// relative humidity [%]
     pub humidity: f32,
     /// snow depth [cm]
@@ -27,11 +29,20 @@ pub struct InputElement {
     // vapor pressure deficit [hPa]
     pub vpd: f32,
 
+    // upper-air variables, at the lower and upper pressure levels of the Haines index [°C]
+    pub temp_lower: f32,
+    pub temp_upper: f32,
+    pub temp_dew_lower: f32,
+    pub temp_dew_upper: f32,
+
     // satellite variables
     pub ndvi: f32,
     pub ndwi: f32,
     pub msi: f32,
     pub swi: f32,
+
+    // dynamic fuel load of the dead fine fuel [kg/m²]
+    pub fuel_load: f32,
 }
 
 impl Default for InputElement {
@@ -41,14 +52,20 @@ impl Default for InputElement {
             rain: NODATAVAL,
             wind_speed: NODATAVAL,
             wind_dir: NODATAVAL,
+            wind_gust: NODATAVAL,
             humidity: NODATAVAL,
             snow_cover: NODATAVAL,
             temp_dew: NODATAVAL,
             vpd: NODATAVAL,
+            temp_lower: NODATAVAL,
+            temp_upper: NODATAVAL,
+            temp_dew_lower: NODATAVAL,
+            temp_dew_upper: NODATAVAL,
             ndvi: NODATAVAL,
             ndwi: NODATAVAL,
             msi: NODATAVAL,
             swi: NODATAVAL,
+            fuel_load: NODATAVAL,
         }
     }
 }
@@ -58,6 +75,15 @@ pub struct Input {
     pub data: Array1<InputElement>,
 }
 
+impl Input {
+    /// Check if any cell received satellite data (NDVI, NDWI or MSI) at this timestep
+    pub fn has_satellite_data(&self) -> bool {
+        self.data
+            .iter()
+            .any(|i| i.ndvi != NODATAVAL || i.ndwi != NODATAVAL || i.msi != NODATAVAL)
+    }
+}
+
 #[allow(clippy::upper_case_acronyms, non_camel_case_types)]
 #[derive(
     Debug, PartialEq, Eq, Hash, Copy, Clone, EnumString, EnumIter, Display, Serialize, Deserialize,
@@ -73,7 +99,9 @@ pub enum InputVariableName {
     K,
     /// Forecasted temperature [K or C]
     T,
-    /// Forecasted dew point temperature [K or C]
+    /// Forecasted dew point temperature [K or C, values above 200 are taken as K],
+    /// also accepted as TD
+    #[strum(to_string = "R", serialize = "TD")]
     R,
     /// Forecasted specific humidity [Kg/Kg]
     Q,
@@ -87,6 +115,8 @@ pub enum InputVariableName {
     U,
     /// V value of the wind [m/s]
     V,
+    /// Wind gust [m/s]
+    WG,
     /// Observed precipitation [mm]
     O,
     /// Forecasted precipitation [mm]
@@ -94,6 +124,16 @@ pub enum InputVariableName {
     /// Forecasted snow cover depth [cm]
     SNOW,
 
+    /// UPPER-AIR VARIABLES (pressure levels of the Haines index)
+    /// Temperature at the lower (higher pressure) level [K or C]
+    TL,
+    /// Temperature at the upper (lower pressure) level [K or C]
+    TU,
+    /// Dew point temperature at the lower level [K or C]
+    RL,
+    /// Dew point temperature at the upper level [K or C]
+    RU,
+
     /// SATELLITE VARIABLES
     /// NDWI value
     NDWI,
@@ -104,4 +144,8 @@ pub enum InputVariableName {
     /// SWI value
     SWI,
 
+    /// FUEL VARIABLES
+    /// Dynamic fuel load of the dead fine fuel [kg/m²]
+    FUEL,
+
 }
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use ndarray::Array1;
 use rayon::prelude::*;
 use serde_derive::{Deserialize, Serialize};
-use strum_macros::{Display, EnumProperty, EnumString};
+use strum_macros::{Display, EnumIter, EnumMessage, EnumProperty, EnumString};
 
 use crate::constants::NODATAVAL;
 
@@ -17,6 +17,10 @@ pub struct OutputElement {
     pub wind_speed: f32,
     /// Input wind direction [deg]
     pub wind_dir: f32,
+    /// Zonal wind component, derived from speed and direction [m/s]
+    pub wind_u: f32,
+    /// Meridional wind component, derived from speed and direction [m/s]
+    pub wind_v: f32,
     /// Input relative humidity [%]
     pub humidity: f32,
     /// Input snow cover [cm]
@@ -31,10 +35,16 @@ pub struct OutputElement {
     pub dffm: f32,
     /// Wind effect on fire spread [-]
     pub W: f32,
+    /// Moisture effect on fire spread [-]
+    pub moisture_effect: f32,
     /// Rate of spread [m/h]
     pub V: f32,
-    /// Intensity [kW/m]
+    /// Rate of spread in the input wind direction [m/h]
+    pub V_wind_dir: f32,
+    /// Intensity, modulated by the relative greenness [kW/m]
     pub I: f32,
+    /// Intensity from the fuel load only, without relative greenness [kW/m]
+    pub I_raw: f32,
     /// NDVI effect [-]
     pub NDVI: f32,
     /// NDWI effect [-]
@@ -46,6 +56,10 @@ pub struct OutputElement {
     // pub SWI: f32,
     /// Meteorological index [-]
     pub meteo_index: f32,
+    /// Saturation value of the vegetation dffm [%]
+    pub sat: f32,
+    /// Equilibrium moisture content of the drying dynamics [%]
+    pub emc: f32,
 
     // ---------------- FWI ----------------- //
     /// Fine Fuel Moisture Code [-]
@@ -65,6 +79,8 @@ pub struct OutputElement {
 
     // ------------- Keetch-Byram Drought Index ----------------- //
     pub kbdi: f32, // [mm]
+    /// Daily rain stored in the state [mm]
+    pub daily_rain: f32,
 
     // ------------- Mark 5 ----------------- //
     /// Drought Factor [-]
@@ -77,6 +93,13 @@ pub struct OutputElement {
 
     // ------------- Fosberg Index ----------------- //
     pub ffwi: f32, // [-]
+    pub ffwi_corrected: f32, // [-]
+
+    // ------------- Chandler Burning Index ----------------- //
+    pub cbi: f32, // [-]
+
+    // ------------- McArthur Grassland Fire Danger Index ----------------- //
+    pub gfdi: f32, // [-]
 
     // ------------- Nesterov Index ----------------- //
     pub nesterov: f32, // [-]
@@ -103,6 +126,9 @@ pub struct OutputElement {
 
     // ------------- Hot-Dry-Wind Index ----------------- //
     pub hdw: f32,  // [-]
+
+    // ------------- Haines Index ----------------- //
+    pub haines: f32,  // [-]
 }
 
 
@@ -114,6 +140,8 @@ impl Default for OutputElement {
             rain: NODATAVAL,
             wind_speed: NODATAVAL,
             wind_dir: NODATAVAL,
+            wind_u: NODATAVAL,
+            wind_v: NODATAVAL,
             humidity: NODATAVAL,
             snow_cover: NODATAVAL,
             temp_dew: NODATAVAL,
@@ -122,13 +150,18 @@ impl Default for OutputElement {
             // RISICO
             dffm: NODATAVAL,
             W: NODATAVAL,
+            moisture_effect: NODATAVAL,
             V: NODATAVAL,
+            V_wind_dir: NODATAVAL,
             I: NODATAVAL,
+            I_raw: NODATAVAL,
             NDVI: NODATAVAL,
             NDWI: NODATAVAL,
             PPF: NODATAVAL,
             t_effect: NODATAVAL,
             meteo_index: NODATAVAL,
+            sat: NODATAVAL,
+            emc: NODATAVAL,
 
             // FWI
             ffmc: NODATAVAL,
@@ -141,6 +174,7 @@ impl Default for OutputElement {
 
             // Keech-Byram Drought Index
             kbdi: NODATAVAL,
+            daily_rain: NODATAVAL,
 
             // Mark 5
             df: NODATAVAL,
@@ -151,6 +185,13 @@ impl Default for OutputElement {
 
             // Fosberg
             ffwi: NODATAVAL,
+            ffwi_corrected: NODATAVAL,
+
+            // Chandler
+            cbi: NODATAVAL,
+
+            // McArthur grassland
+            gfdi: NODATAVAL,
 
             // Nesterov
             nesterov: NODATAVAL,
@@ -170,6 +211,9 @@ impl Default for OutputElement {
 
             // Hot-Dry-Wind
             hdw: NODATAVAL,
+
+            // Haines
+            haines: NODATAVAL,
         }
     }
 }
@@ -184,6 +228,8 @@ impl Default for OutputElement {
     Clone,
     EnumString,
     EnumProperty,
+    EnumIter,
+    EnumMessage,
     Display,
     Serialize,
     Deserialize,
@@ -204,6 +250,12 @@ pub enum OutputVariableName {
     /// Input Wind Direction
     #[strum(props(long_name = "Input Wind Direction", units = "°"))]
     windDir,
+    /// Zonal Wind Component
+    #[strum(props(long_name = "Zonal Wind Component", units = "m/s"))]
+    windU,
+    /// Meridional Wind Component
+    #[strum(props(long_name = "Meridional Wind Component", units = "m/s"))]
+    windV,
     /// Input Relative Humidity
     #[strum(props(long_name = "Input Relative Humidity", units = "%"))]
     humidity,
@@ -216,6 +268,9 @@ pub enum OutputVariableName {
     /// Vapor Pressure Deficit
     #[strum(props(long_name = "Vapor Pressure Deficit", units = "hPa"))]
     vpd,
+    /// Index of the source grid point sampled by the cell for an input variable (diagnostic)
+    #[strum(props(long_name = "Source Grid Index", units = "-"))]
+    sourceIndex,
 
     /// ----------- RISICO ----------------- //
     /// Fine Fuel Moisture
@@ -224,12 +279,21 @@ pub enum OutputVariableName {
     /// Wind Effect on Fire Spread
     #[strum(props(long_name = "Wind Effect on Fire Spread", units = "-"))]
     W,
+    /// Moisture Effect on Fire Spread
+    #[strum(props(long_name = "Moisture Effect on Fire Spread", units = "-"))]
+    moistureEffect,
     /// Fire Spread Rate
     #[strum(props(long_name = "Fire Spread Rate", units = "m/h"))]
     V,
+    /// Fire Spread Rate in the wind direction
+    #[strum(props(long_name = "Fire Spread Rate in the wind direction", units = "m/h"))]
+    VWindDir,
     /// Fire Intensity
     #[strum(props(long_name = "Fire Intensity", units = "kW/m"))]
     I,
+    /// Fire Intensity without the relative greenness effect
+    #[strum(props(long_name = "Fire Intensity without greenness effect", units = "kW/m"))]
+    Iraw,
     /// Temperature Effect on Fire Spread
     #[strum(props(long_name = "Temperature Effect on Fire Spread", units = "-"))]
     contrT,
@@ -246,6 +310,12 @@ pub enum OutputVariableName {
         serialize = "meteoIndex2"
     )]
     meteoIndex2,
+    /// Saturation value of the fine fuel moisture
+    #[strum(props(long_name = "Fine Fuel Moisture Saturation", units = "%"))]
+    sat,
+    /// Equilibrium moisture content
+    #[strum(props(long_name = "Equilibrium Moisture Content", units = "%"))]
+    EMC,
     /// Fire Spread Rate + PPF
     #[strum(props(long_name = "Fire Spread Rate + PPF", units = "m/h"))]
     VPPF,
@@ -258,7 +328,7 @@ pub enum OutputVariableName {
     /// Fire Spread rate + NDWI factor
     #[strum(props(long_name = "Fire Spread rate + NDWI factor", units = "m/h"))]
     VNDWI,
-    /// Fire Intensity + NDVI factor
+    /// Fire Intensity (without greenness effect) + NDVI factor
     #[strum(props(long_name = "Fire Intensity + NDVI factor", units = "kW/m"))]
     INDVI,
     /// Fire Spread rate + NDVI factor
@@ -273,7 +343,7 @@ pub enum OutputVariableName {
     /// Fire Spread rate + PPF + NDVI factor
     #[strum(props(long_name = "Fire Spread rate + PPF + NDVI factor", units = "m/h"))]
     VPPFNDVI,
-    /// Fire Intensity + PPF + NDVI factor
+    /// Fire Intensity (without greenness effect) + PPF + NDVI factor
     #[strum(props(long_name = "Fire Intensity + PPF + NDVI factor", units = "kW/m"))]
     IPPFNDVI,
 
@@ -303,6 +373,9 @@ pub enum OutputVariableName {
     /// ---------- Keetch-Byram Drought Index ----------------- //
     #[strum(props(long_name = "Keetch-Byram Drought Index", units = "mm"))]
     kbdi,
+    /// Daily rain stored in the state (KBDI and Mark5)
+    #[strum(props(long_name = "Daily Rain", units = "mm"))]
+    dailyRain,
 
     /// ---------- Mark 5 ----------------- //
     // Drought Factor
@@ -319,6 +392,16 @@ pub enum OutputVariableName {
     // ---------- Fosberg Index ----------------- //
     #[strum(props(long_name = "Fosberg Fire Weather Index", units = "-"))]
     ffwi,
+    #[strum(props(long_name = "Slope Corrected Fosberg Fire Weather Index", units = "-"))]
+    ffwi_corrected,
+
+    // ---------- Chandler Burning Index ----------------- //
+    #[strum(props(long_name = "Chandler Burning Index", units = "-"))]
+    cbi,
+
+    // ---------- McArthur Grassland Fire Danger Index ----------------- //
+    #[strum(props(long_name = "McArthur Grassland Fire Danger Index", units = "-"))]
+    gfdi,
 
     // ---------- Nesterov Index ----------------- //
     #[strum(props(long_name = "Nesterov Index", units = "-"))]
@@ -353,6 +436,10 @@ pub enum OutputVariableName {
     // ---------- Hot-Dry-Wind Index ----------------- //
     #[strum(props(long_name = "Hot-Dry-Wind Index", units = "-"))]
     hdw,
+
+    // ---------- Haines Index ----------------- //
+    #[strum(props(long_name = "Haines Index (2:very low - 6:high)", units = "-"))]
+    haines,
 }
 
 
@@ -371,6 +458,101 @@ fn get_derived(a: &f32, b: &f32, c: Option<&f32>) -> f32 {
     r
 }
 
+impl OutputVariableName {
+    /// Check if the variable is derived from the product of several outputs
+    pub fn is_derived(&self) -> bool {
+        use OutputVariableName::*;
+        matches!(
+            self,
+            VPPF | IPPF
+                | INDWI
+                | VNDWI
+                | INDVI
+                | VNDVI
+                | VPPFNDWI
+                | IPPFNDWI
+                | VPPFNDVI
+                | IPPFNDVI
+        )
+    }
+}
+
+/// Clamp the values of a derived variable to max_value (if given).
+/// Returns the number of clamped values
+pub fn cap_derived(
+    variable: &OutputVariableName,
+    values: &mut Array1<f32>,
+    max_value: Option<f32>,
+) -> usize {
+    let max_value = match max_value {
+        Some(max_value) if variable.is_derived() => max_value,
+        _ => return 0,
+    };
+    let mut n_clamped: usize = 0;
+    values.iter_mut().for_each(|v| {
+        if *v != NODATAVAL && *v > max_value {
+            *v = max_value;
+            n_clamped += 1;
+        }
+    });
+    n_clamped
+}
+
+/// Historical distributions of a variable on the cells, used to express
+/// the current values as percentiles
+#[derive(Debug, Clone)]
+pub struct CellDistributions {
+    // sorted valid values of each cell
+    sorted: Vec<Vec<f32>>,
+}
+
+impl CellDistributions {
+    pub fn new(values: Vec<Vec<f32>>) -> Self {
+        let sorted = values
+            .into_iter()
+            .map(|mut cell| {
+                cell.retain(|v| *v != NODATAVAL && v.is_finite());
+                cell.sort_by(|a, b| a.total_cmp(b));
+                cell
+            })
+            .collect();
+        Self { sorted }
+    }
+
+    /// Number of cells
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    /// Percentile [0-100] of the value in the distribution of the cell
+    /// (ties count half), NODATAVAL if the cell has no distribution
+    pub fn percentile(&self, cell: usize, value: f32) -> f32 {
+        let Some(distribution) = self.sorted.get(cell) else {
+            return NODATAVAL;
+        };
+        if value == NODATAVAL || distribution.is_empty() {
+            return NODATAVAL;
+        }
+        let below = distribution.partition_point(|v| *v < value);
+        let not_above = distribution.partition_point(|v| *v <= value);
+        let rank = below as f32 + (not_above - below) as f32 / 2.0;
+        100.0 * rank / distribution.len() as f32
+    }
+}
+
+/// Percentiles of the values of the cells in their historical distributions
+pub fn to_percentiles(values: &Array1<f32>, distributions: &CellDistributions) -> Array1<f32> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(cell, value)| distributions.percentile(cell, *value))
+        .collect()
+}
+
 pub struct Output {
     pub time: DateTime<Utc>,
     pub data: Array1<OutputElement>,
@@ -395,31 +577,42 @@ impl Output {
             rain => Some(self.get_array(|o| o.rain)),
             windSpeed => Some(self.get_array(|o| o.wind_speed)),
             windDir => Some(self.get_array(|o| o.wind_dir)),
+            windU => Some(self.get_array(|o| o.wind_u)),
+            windV => Some(self.get_array(|o| o.wind_v)),
             humidity => Some(self.get_array(|o| o.humidity)),
             snowCover => Some(self.get_array(|o| o.snow_cover)),
             temp_dew => Some(self.get_array(|o| o.temp_dew)),
             vpd => Some(self.get_array(|o| o.vpd)),
+            // given by the input handler, not computed by the models
+            sourceIndex => None,
 
             // RISICO
             dffm => Some(self.get_array(|o| o.dffm)),
             W => Some(self.get_array(|o| o.W)),
+            moistureEffect => Some(self.get_array(|o| o.moisture_effect)),
             V => Some(self.get_array(|o| o.V)),
+            VWindDir => Some(self.get_array(|o| o.V_wind_dir)),
             I => Some(self.get_array(|o| o.I)),
+            Iraw => Some(self.get_array(|o| o.I_raw)),
             contrT => Some(self.get_array(|o| o.t_effect)),
             NDVI => Some(self.get_array(|o| o.NDVI)),
             NDWI => Some(self.get_array(|o| o.NDWI)),
             meteoIndex2 => Some(self.get_array(|o| o.meteo_index)),
+            sat => Some(self.get_array(|o| o.sat)),
+            EMC => Some(self.get_array(|o| o.emc)),
             // RISICO - Derived variables
             VPPF => Some(self.get_array(|o| get_derived(&o.V, &o.PPF, None))),
             IPPF => Some(self.get_array(|o| get_derived(&o.I, &o.PPF, None))),
             INDWI => Some(self.get_array(|o| get_derived(&o.I, &o.NDWI, None))),
             VNDWI => Some(self.get_array(|o| get_derived(&o.V, &o.NDWI, None))),
-            INDVI => Some(self.get_array(|o| get_derived(&o.I, &o.NDVI, None))),
+            // the NDVI factor is applied to the intensity without the greenness effect,
+            // which already accounts for the vegetation greenness
+            INDVI => Some(self.get_array(|o| get_derived(&o.I_raw, &o.NDVI, None))),
             VNDVI => Some(self.get_array(|o| get_derived(&o.V, &o.NDVI, None))),
             VPPFNDWI => Some(self.get_array(|o| get_derived(&o.V, &o.NDWI, Some(&o.PPF)))),
             IPPFNDWI => Some(self.get_array(|o| get_derived(&o.I, &o.NDWI, Some(&o.PPF)))),
             VPPFNDVI => Some(self.get_array(|o| get_derived(&o.V, &o.NDVI, Some(&o.PPF)))),
-            IPPFNDVI => Some(self.get_array(|o| get_derived(&o.I, &o.NDVI, Some(&o.PPF)))),
+            IPPFNDVI => Some(self.get_array(|o| get_derived(&o.I_raw, &o.NDVI, Some(&o.PPF)))),
 
             // FWI
             ffmc => Some(self.get_array(|o| o.ffmc)),
@@ -432,6 +625,7 @@ impl Output {
         
             // Keech-Byram Drought Index
             kbdi => Some(self.get_array(|o| o.kbdi)),
+            dailyRain => Some(self.get_array(|o| o.daily_rain)),
 
             // Mark 5
             df => Some(self.get_array(|o| o.df)),
@@ -442,6 +636,13 @@ impl Output {
 
             // Fosberg
             ffwi => Some(self.get_array(|o| o.ffwi)),
+            ffwi_corrected => Some(self.get_array(|o| o.ffwi_corrected)),
+
+            // Chandler
+            cbi => Some(self.get_array(|o| o.cbi)),
+
+            // McArthur grassland
+            gfdi => Some(self.get_array(|o| o.gfdi)),
 
             // Nesterov
             nesterov => Some(self.get_array(|o| o.nesterov)),
@@ -461,6 +662,42 @@ impl Output {
 
             // Hot-Dry-Wind
             hdw => Some(self.get_array(|o| o.hdw)),
+
+            // Haines
+            haines => Some(self.get_array(|o| o.haines)),
         }
     }
+
+    /// Get the values of a variable, clamping derived variables to max_value (if given).
+    /// Returns the values and the number of clamped values
+    pub fn get_capped(
+        &self,
+        variable: &OutputVariableName,
+        max_value: Option<f32>,
+    ) -> Option<(Array1<f32>, usize)> {
+        let mut values = self.get(variable)?;
+        let n_clamped = cap_derived(variable, &mut values, max_value);
+        Some((values, n_clamped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ndvi_products_use_the_intensity_without_greenness() {
+        let element = OutputElement {
+            I: 300.0,
+            I_raw: 500.0,
+            NDVI: 0.5,
+            PPF: 2.0,
+            ..OutputElement::default()
+        };
+        let output = Output::new(Utc::now(), Array1::from_vec(vec![element]));
+        let indvi = output.get(&OutputVariableName::INDVI).unwrap();
+        assert_eq!(indvi.to_vec(), vec![500.0 * 0.5]);
+        let ippfndvi = output.get(&OutputVariableName::IPPFNDVI).unwrap();
+        assert_eq!(ippfndvi.to_vec(), vec![500.0 * 0.5 * 2.0]);
+    }
 }
@@ -0,0 +1 @@
+pub const NODATAVAL: f32 = -9999.0;
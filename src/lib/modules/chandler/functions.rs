@@ -0,0 +1,29 @@
+use super::{constants::NODATAVAL, models::ChandlerStateElement};
+use crate::models::output::OutputElement;
+
+// Chandler Burning Index
+// values below 50 are low fire danger, above 97.5 extreme fire danger
+// some info: https://www.spc.noaa.gov/exper/firecomp/INFO/chandlerinfo.html
+pub fn cbi(
+    temperature: f32, // temperature [°C]
+    humidity: f32,    // relative humidity [%]
+) -> f32 {
+    let cbi = (((110.0 - 1.373 * humidity) - 0.54 * (10.20 - temperature))
+        * (124.0 * 10.0_f32.powf(-0.0142 * humidity)))
+        / 60.0;
+    // negative values for very humid conditions
+    cbi.max(0.0)
+}
+
+pub fn get_output_fn(state: &ChandlerStateElement) -> OutputElement {
+    if (state.temp == NODATAVAL) || (state.humidity == NODATAVAL) {
+        return OutputElement::default();
+    }
+    let cbi = cbi(state.temp, state.humidity);
+    OutputElement {
+        cbi,                      // [-]
+        temperature: state.temp,  // [°C]
+        humidity: state.humidity, // [%]
+        ..OutputElement::default()
+    }
+}
@@ -0,0 +1,126 @@
+use crate::models::{input::Input, output::Output};
+use chrono::prelude::*;
+use ndarray::{Array1, Zip};
+
+use super::{
+    constants::*,
+    functions::get_output_fn,
+};
+
+/// Chandler Burning Index
+/// Source: https://www.spc.noaa.gov/exper/firecomp/INFO/chandlerinfo.html
+
+// CELLS PROPERTIES
+#[derive(Debug)]
+pub struct ChandlerPropertiesElement {
+    pub lon: f32,
+    pub lat: f32,
+}
+
+#[derive(Debug)]
+pub struct ChandlerProperties {
+    pub data: Array1<ChandlerPropertiesElement>,
+    pub len: usize,
+}
+
+pub struct ChandlerCellPropertiesContainer {
+    pub lons: Vec<f32>,
+    pub lats: Vec<f32>,
+}
+
+impl ChandlerProperties {
+    pub fn new(props: ChandlerCellPropertiesContainer) -> Self {
+        let data: Array1<ChandlerPropertiesElement> = props
+            .lons
+            .iter()
+            .enumerate()
+            .map(|(idx, lon)| ChandlerPropertiesElement {
+                lon: *lon,
+                lat: props.lats[idx],
+            })
+            .collect();
+    
+        let len = data.len();
+        Self {
+            data,
+            len,
+        }
+    }
+
+    pub fn get_coords(&self) -> (Vec<f32>, Vec<f32>) {
+        let lats: Vec<f32> = self.data.iter().map(|p| p.lat).collect();
+        let lons: Vec<f32> = self.data.iter().map(|p| p.lon).collect();
+        (lats, lons)
+    }
+
+}
+
+
+// STATE
+#[derive(Debug)]
+#[allow(non_snake_case)]
+pub struct ChandlerStateElement {
+    pub temp: f32,  // temperature [°C]
+    pub humidity: f32,  // relative humidity [%]
+}
+
+
+#[derive(Debug)]
+pub struct ChandlerState {
+    pub time: DateTime<Utc>,
+    pub data: Array1<ChandlerStateElement>,
+    len: usize,
+}
+
+impl ChandlerState {
+    #[allow(dead_code, non_snake_case)]
+    /// Create a new state.
+    pub fn new(time: &DateTime<Utc>, n_cells: usize) -> ChandlerState {
+        let data: Array1<ChandlerStateElement> = Array1::from(
+            (0..n_cells)
+                .map(|_| ChandlerStateElement {
+                    temp: NODATAVAL,
+                    humidity: NODATAVAL,
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        ChandlerState {
+            time: *time,
+            data,
+            len: n_cells,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn store(&mut self, input: &Input) {
+        self.time = input.time;  // reference time of the input
+        Zip::from(&mut self.data)
+            .and(&input.data)
+            .par_for_each(|state, input_data| {
+                state.temp = input_data.temperature;
+                state.humidity = input_data.humidity;
+            });
+    }
+
+    #[allow(non_snake_case)]
+    pub fn get_output(&mut self) -> Output {
+        let time = &self.time;
+        let output_data = self.data
+                    .map(|state| {
+                        get_output_fn(state)
+                    });
+        Output::new(*time, output_data)
+    }
+
+    pub fn output(&mut self) -> Output {
+        self.get_output()
+    }
+}
\ No newline at end of file
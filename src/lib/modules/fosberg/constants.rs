@@ -1 +1,4 @@
 pub const NODATAVAL: f32 = -9999.0;
+
+// slope effect of the corrected index, from the McArthur slope correction of the rate of spread
+pub const SLOPE_FACTOR: f32 = 0.069; // [1/°]
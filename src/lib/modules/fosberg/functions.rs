@@ -1,4 +1,7 @@
-use super::{constants::NODATAVAL, models::FosbergStateElement};
+use super::{
+    constants::{NODATAVAL, SLOPE_FACTOR},
+    models::{FosbergPropertiesElement, FosbergStateElement},
+};
 use crate::models::output::OutputElement;
 
 // Equilibrium Moisture Content [%] -> Simard formulation
@@ -36,15 +39,34 @@ pub fn ffwi(
     ffwi.clamp(0.0, 100.0)
 }
 
-pub fn get_output_fn(state: &FosbergStateElement) -> OutputElement {
+// Slope corrected Fosberg Fire Weather Index
+// the upslope spread increases the index by a factor exp(0.069 * slope), clipped in [0, 100]
+pub fn ffwi_corrected(
+    ffwi: f32,  // Fosberg Fire Weather Index [-]
+    slope: f32, // terrain slope [°]
+) -> f32 {
+    (ffwi * f32::exp(SLOPE_FACTOR * slope.clamp(0.0, 90.0))).clamp(0.0, 100.0)
+}
+
+pub fn get_output_fn(
+    state: &FosbergStateElement,
+    props: &FosbergPropertiesElement,
+) -> OutputElement {
     if (state.temp == NODATAVAL) || (state.humidity == NODATAVAL) || (state.wind_speed == NODATAVAL)
     {
         return OutputElement::default();
     }
     let ffwi = ffwi(state.temp, state.humidity, state.wind_speed);
+    // the correction is computed only for the cells with a slope
+    let ffwi_corrected = if props.slope == NODATAVAL {
+        NODATAVAL
+    } else {
+        ffwi_corrected(ffwi, props.slope)
+    };
     let ws_out = state.wind_speed / 3600.0; // convert from m/h to m/s
     OutputElement {
         ffwi,                     // [-]
+        ffwi_corrected,           // [-]
         temperature: state.temp,  // [°C]
         humidity: state.humidity, // [%]
         wind_speed: ws_out,       // [m/s]
@@ -15,6 +15,7 @@ use super::{
 pub struct FosbergPropertiesElement {
     pub lon: f32,
     pub lat: f32,
+    pub slope: f32,  // terrain slope [°], NODATAVAL if not available
 }
 
 #[derive(Debug)]
@@ -26,6 +27,7 @@ pub struct FosbergProperties {
 pub struct FosbergCellPropertiesContainer {
     pub lons: Vec<f32>,
     pub lats: Vec<f32>,
+    pub slopes: Vec<f32>,
 }
 
 impl FosbergProperties {
@@ -37,6 +39,7 @@ impl FosbergProperties {
             .map(|(idx, lon)| FosbergPropertiesElement {
                 lon: *lon,
                 lat: props.lats[idx],
+                slope: props.slopes[idx],
             })
             .collect();
     
@@ -114,16 +117,17 @@ impl FosbergState {
     }
 
     #[allow(non_snake_case)]
-    pub fn get_output(&mut self) -> Output {
+    pub fn get_output(&mut self, props: &FosbergProperties) -> Output {
         let time = &self.time;
-        let output_data = self.data
-                    .map(|state| {
-                        get_output_fn(state)
+        let output_data = Zip::from(&self.data)
+                    .and(&props.data)
+                    .map_collect(|state, props| {
+                        get_output_fn(state, props)
                     });
         Output::new(*time, output_data)
     }
 
-    pub fn output(&mut self) -> Output {
-        self.get_output()
+    pub fn output(&mut self, props: &FosbergProperties) -> Output {
+        self.get_output(props)
     }
 }
\ No newline at end of file
@@ -3,24 +3,60 @@ use super::functions::{
     update_state_legacy, update_state_sliding,
     get_output_legacy, get_output_sliding
 };
+use super::constants::{
+    DC_INIT, DC_RAIN_THRESHOLD, DMC_INIT, DMC_RAIN_THRESHOLD, FFMC_INIT, FFMC_RAIN_THRESHOLD,
+};
 use super::models::{
     FWIStateElement, FWIPropertiesElement
 };
 use crate::models::{input::InputElement, output::OutputElement};
 
+type UpdateStateFnType = fn(&mut FWIStateElement, &FWIPropertiesElement, &InputElement, &DateTime<Utc>, &FWIParameters);
+type GetOutputFnType = fn(&mut FWIStateElement, &FWIPropertiesElement, &DateTime<Utc>, &FWIParameters) -> OutputElement;
+
+/// calibration parameters of the FWI codes
+#[derive(Debug, Clone, Copy)]
+pub struct FWIParameters {
+    // codes used at cold start (no warm state available)
+    pub ffmc_init: f32,
+    pub dmc_init: f32,
+    pub dc_init: f32,
+    // 24h rain thresholds for the wetting of the codes [mm]
+    pub ffmc_rain_threshold: f32,
+    pub dmc_rain_threshold: f32,
+    pub dc_rain_threshold: f32,
+    // compute the Intensity FWI output
+    pub compute_ifwi: bool,
+}
+
+impl Default for FWIParameters {
+    fn default() -> Self {
+        Self {
+            ffmc_init: FFMC_INIT,
+            dmc_init: DMC_INIT,
+            dc_init: DC_INIT,
+            ffmc_rain_threshold: FFMC_RAIN_THRESHOLD,
+            dmc_rain_threshold: DMC_RAIN_THRESHOLD,
+            dc_rain_threshold: DC_RAIN_THRESHOLD,
+            compute_ifwi: true,
+        }
+    }
+}
+
 /// configuration structure for model config
 /// can be used to store functions and constants
 #[derive(Debug)]
 pub struct FWIModelConfig {
     pub model_version: String,
-    update_state_fn: fn(&mut FWIStateElement, &FWIPropertiesElement, &InputElement, &DateTime<Utc>),
-    get_output_fn: fn(&mut FWIStateElement, &FWIPropertiesElement, &DateTime<Utc>) -> OutputElement
+    pub parameters: FWIParameters,
+    update_state_fn: UpdateStateFnType,
+    get_output_fn: GetOutputFnType,
 }
 
 impl FWIModelConfig {
     pub fn new(model_version_str: &str) -> Self {
-        let update_state_fn: fn(&mut FWIStateElement, &FWIPropertiesElement, &InputElement, &DateTime<Utc>);
-        let get_output_fn: fn(&mut FWIStateElement, &FWIPropertiesElement, &DateTime<Utc>) -> OutputElement;
+        let update_state_fn: UpdateStateFnType;
+        let get_output_fn: GetOutputFnType;
 
         match model_version_str {
             "legacy" => {
@@ -40,6 +76,7 @@ impl FWIModelConfig {
 
         FWIModelConfig {
             model_version: model_version_str.to_owned(),
+            parameters: FWIParameters::default(),
             update_state_fn,
             get_output_fn
         }
@@ -53,7 +90,7 @@ impl FWIModelConfig {
         input: &InputElement,
         time: &DateTime<Utc>
     ) {
-        (self.update_state_fn)(state, props, input, time)
+        (self.update_state_fn)(state, props, input, time, &self.parameters)
     }
 
     #[allow(non_snake_case)]
@@ -63,6 +100,6 @@ impl FWIModelConfig {
         props: &FWIPropertiesElement,
         time: &DateTime<Utc>
     ) -> OutputElement {
-        (self.get_output_fn)(state, props, time)
+        (self.get_output_fn)(state, props, time, &self.parameters)
     }
 }
@@ -4,4 +4,14 @@ pub const FFMC_INIT: f32 = 85.0;
 pub const DMC_INIT: f32 = 6.0;
 pub const DC_INIT: f32 = 15.0;
 
+// 24h rain thresholds for the wetting of the codes [mm]
+pub const FFMC_RAIN_THRESHOLD: f32 = 0.5;
+pub const DMC_RAIN_THRESHOLD: f32 = 1.5;
+pub const DC_RAIN_THRESHOLD: f32 = 2.8;
+
+// coefficients of the Intensity FWI: ifwi = exp(A * ln(fwi)^B) / C
+pub const IFWI_A: f32 = 0.98;
+pub const IFWI_B: f32 = 1.546;
+pub const IFWI_C: f32 = 0.289;
+
 pub const TIME_WINDOW: i64 = 24;  // hours
@@ -7,7 +7,7 @@ use tzf_rs::DefaultFinder;
 use crate::models::{input::InputElement, output::OutputElement};
 
 use super::{
-    config::FWIModelConfig,
+    config::{FWIModelConfig, FWIParameters},
     constants::*,
     models::{FWIPropertiesElement, FWIStateElement},
 };
@@ -44,8 +44,8 @@ pub fn from_moisture_to_ffmc(moisture: f32) -> f32 {
     59.5 * (250.0 - moisture) / (147.2 + moisture)
 }
 
-pub fn moisture_rain_effect(moisture: f32, rain24: f32) -> f32 {
-    let rain_eff: f32 = rain24 - 0.5;
+pub fn moisture_rain_effect(moisture: f32, rain24: f32, rain_threshold: f32) -> f32 {
+    let rain_eff: f32 = rain24 - rain_threshold;
     let mut moisture_new: f32 = moisture
         + 42.5
             * (rain_eff
@@ -59,13 +59,20 @@ pub fn moisture_rain_effect(moisture: f32, rain24: f32) -> f32 {
     moisture_new.clamp(0.0, 250.0)
 }
 
-pub fn update_moisture(moisture: f32, rain24: f32, hum: f32, temp: f32, w_speed: f32) -> f32 {
+pub fn update_moisture(
+    moisture: f32,
+    rain24: f32,
+    hum: f32,
+    temp: f32,
+    w_speed: f32,
+    rain_threshold: f32,
+) -> f32 {
     // conversion from m/h into km/h - required by the FFMC formula
     let ws: f32 = w_speed / 1000.0;
     let mut moisture_new: f32 = moisture;
-    if rain24 > 0.5 {
+    if rain24 > rain_threshold {
         // rain24 effect
-        moisture_new = moisture_rain_effect(moisture, rain24);
+        moisture_new = moisture_rain_effect(moisture, rain24, rain_threshold);
     }
     // no-rain conditions
     let emc_dry: f32 = 0.942 * f32::powf(hum, 0.679)
@@ -185,9 +192,9 @@ pub fn dmc_rain_effect(dmc: f32, rain24: f32) -> f32 {
     dmc_new
 }
 
-pub fn update_dmc(dmc: f32, rain24: f32, temp: f32, hum: f32, l_e: f32) -> f32 {
+pub fn update_dmc(dmc: f32, rain24: f32, temp: f32, hum: f32, l_e: f32, rain_threshold: f32) -> f32 {
     let mut dmc_new: f32 = dmc;
-    if rain24 > 1.5 {
+    if rain24 > rain_threshold {
         // rain effect
         dmc_new = dmc_rain_effect(dmc, rain24);
     }
@@ -255,9 +262,9 @@ pub fn dc_rain_effect(dc: f32, rain24: f32) -> f32 {
     dc_new
 }
 
-pub fn update_dc(dc: f32, rain24: f32, temp: f32, l_f: f32) -> f32 {
+pub fn update_dc(dc: f32, rain24: f32, temp: f32, l_f: f32, rain_threshold: f32) -> f32 {
     let mut dc_new = dc;
-    if rain24 > 2.8 {
+    if rain24 > rain_threshold {
         // rain effect
         dc_new = dc_rain_effect(dc, rain24);
     }
@@ -283,7 +290,8 @@ pub fn compute_moisture_codes(
     temperature: f32,
     wind_speed: f32,
     time: &DateTime<Utc>,
-    lat: f32
+    lat: f32,
+    params: &FWIParameters,
 ) -> (f32, f32, f32) {
 
     // managing nodataval > keep initial values
@@ -298,17 +306,24 @@ pub fn compute_moisture_codes(
     // FFMC MODULE
     // convert ffmc to moisture scale [0, 250]
     let mut moisture: f32 = from_ffmc_to_moisture(ffmc_init);
-    moisture = update_moisture(moisture, rain24h, humidity, temperature, wind_speed);
+    moisture = update_moisture(
+        moisture,
+        rain24h,
+        humidity,
+        temperature,
+        wind_speed,
+        params.ffmc_rain_threshold,
+    );
     // convert to ffmc scale and update state
     let new_ffmc = from_moisture_to_ffmc(moisture);
 
     // DMC MODULE
     let l_e = get_dmc_param(time, lat);
-    let new_dmc = update_dmc(dmc_init, rain24h, temperature, humidity, l_e);
+    let new_dmc = update_dmc(dmc_init, rain24h, temperature, humidity, l_e, params.dmc_rain_threshold);
 
     // DC MODULE
     let l_f = get_dc_param(time, lat);
-    let new_dc = update_dc(dc_init, rain24h, temperature, l_f);
+    let new_dc = update_dc(dc_init, rain24h, temperature, l_f, params.dc_rain_threshold);
     (new_ffmc, new_dmc, new_dc)
 
 }
@@ -367,15 +382,31 @@ pub fn compute_fwi(bui: f32, isi: f32) -> f32 {
     fwi
 }
 
+// IFWI MODULE
+/// Intensity FWI (the "S scale" of Van Wagner, 1987), a daily severity rating:
+/// ifwi = exp(A * ln(fwi)^B) / C for fwi > 1, 0 otherwise.
+/// It depends only on the FWI value, so it is the same for every model version.
 pub fn compute_ifwi(fwi: f32) -> f32 {
+    if fwi == NODATAVAL {
+        return NODATAVAL;
+    }
     let ifwi: f32 = if fwi > 1.0 {
-        (f32::exp(0.98 * f32::powf(f32::ln(fwi), 1.546))) / 0.289
+        (f32::exp(IFWI_A * f32::powf(f32::ln(fwi), IFWI_B))) / IFWI_C
     } else {
         0.0
     };
     ifwi
 }
 
+/// Intensity FWI if enabled in the parameters, NODATAVAL otherwise
+pub fn get_ifwi(fwi: f32, params: &FWIParameters) -> f32 {
+    if params.compute_ifwi {
+        compute_ifwi(fwi)
+    } else {
+        NODATAVAL
+    }
+}
+
 
 // WEATHER NOON - HELPERS FUNCTIONS
 
@@ -490,7 +521,8 @@ pub fn update_state_legacy(
     state: &mut FWIStateElement,
     _prop: &FWIPropertiesElement,
     input: &InputElement,
-    time: &DateTime<Utc>
+    time: &DateTime<Utc>,
+    _params: &FWIParameters,
 ) {
     let rain_in = input.rain;
     let humidity_in = input.humidity;
@@ -542,7 +574,8 @@ pub fn update_state_sliding(
     state: &mut FWIStateElement,
     prop: &FWIPropertiesElement,
     input: &InputElement,
-    time: &DateTime<Utc>
+    time: &DateTime<Utc>,
+    params: &FWIParameters,
 ) {
     // first get weather
     let rain_in = input.rain;
@@ -598,9 +631,9 @@ pub fn update_state_sliding(
     .map(|(t, ffmc, dmc, dc)| (*t, *ffmc, *dmc, *dc))
     .collect::<Vec<_>>();
 
-    let ffmc_init = combined_moisture_init.iter().map(|(_, ffmc, _, _)| *ffmc).collect::<Vec<_>>().first().copied().unwrap_or(FFMC_INIT);
-    let dmc_init = combined_moisture_init.iter().map(|(_, _, dmc, _)| *dmc).collect::<Vec<_>>().first().copied().unwrap_or(DMC_INIT);
-    let dc_init = combined_moisture_init.iter().map(|(_, _, _, dc)| *dc).collect::<Vec<_>>().first().copied().unwrap_or(DC_INIT);
+    let ffmc_init = combined_moisture_init.iter().map(|(_, ffmc, _, _)| *ffmc).collect::<Vec<_>>().first().copied().unwrap_or(params.ffmc_init);
+    let dmc_init = combined_moisture_init.iter().map(|(_, _, dmc, _)| *dmc).collect::<Vec<_>>().first().copied().unwrap_or(params.dmc_init);
+    let dc_init = combined_moisture_init.iter().map(|(_, _, _, dc)| *dc).collect::<Vec<_>>().first().copied().unwrap_or(params.dc_init);
 
     // compute moisture
     let (new_ffmc, new_dmc, new_dc) = compute_moisture_codes(
@@ -612,7 +645,8 @@ pub fn update_state_sliding(
         temperature_in,
         wind_speed_in,
         time,
-        prop.lat
+        prop.lat,
+        params,
     );
 
     // update moisture states
@@ -651,15 +685,16 @@ pub fn get_output_legacy(
     state: &mut FWIStateElement,
     prop: &FWIPropertiesElement,
     time: &DateTime<Utc>,
+    params: &FWIParameters,
 ) -> OutputElement {
 
     // get weather conditions at local noon
     let (rain24h, humidity, temperature, wind_speed) = get_weather_noon(state, prop).unwrap_or((NODATAVAL, NODATAVAL, NODATAVAL, NODATAVAL));
 
     // get initial moisture values > in legacy, state moisture values are composed by just one element
-    let ffmc_init = *state.ffmc.first().unwrap_or(&FFMC_INIT);
-    let dmc_init = *state.dmc.first().unwrap_or(&DMC_INIT);
-    let dc_init = *state.dc.first().unwrap_or(&DC_INIT);
+    let ffmc_init = *state.ffmc.first().unwrap_or(&params.ffmc_init);
+    let dmc_init = *state.dmc.first().unwrap_or(&params.dmc_init);
+    let dc_init = *state.dc.first().unwrap_or(&params.dc_init);
 
     // compute moisture
     let (new_ffmc, new_dmc, new_dc) = compute_moisture_codes(
@@ -671,7 +706,8 @@ pub fn get_output_legacy(
         temperature,
         wind_speed,
         time,
-        prop.lat
+        prop.lat,
+        params,
     );
 
     // update moisture states > in legacy, state moisture values are composed by just one element
@@ -685,7 +721,7 @@ pub fn get_output_legacy(
     let isi = compute_isi(new_moisture, wind_speed);
     let bui = compute_bui(new_dmc, new_dc);
     let fwi = compute_fwi(bui, isi);
-    let ifwi = compute_ifwi(fwi);
+    let ifwi = get_ifwi(fwi, params);
 
     // compute other outputs information
     let dffm = (new_moisture / (100.0 + new_moisture)) * 100.0;  // moisture in [0, 100]
@@ -713,7 +749,8 @@ pub fn get_output_legacy(
 pub fn get_output_sliding(
     state: &mut FWIStateElement,
     _prop: &FWIPropertiesElement,
-    _time: &DateTime<Utc>
+    _time: &DateTime<Utc>,
+    params: &FWIParameters,
 ) -> OutputElement {
 
     // get weather conditions > last ones
@@ -723,9 +760,9 @@ pub fn get_output_sliding(
     let wind_speed = state.wind_speed.iter().copied().last().unwrap_or(NODATAVAL);
 
     // get moisture values > last ones
-    let ffmc = state.ffmc.iter().copied().last().unwrap_or(FFMC_INIT);
-    let dmc = state.dmc.iter().copied().last().unwrap_or(DMC_INIT);
-    let dc = state.dc.iter().copied().last().unwrap_or(DC_INIT);
+    let ffmc = state.ffmc.iter().copied().last().unwrap_or(params.ffmc_init);
+    let dmc = state.dmc.iter().copied().last().unwrap_or(params.dmc_init);
+    let dc = state.dc.iter().copied().last().unwrap_or(params.dc_init);
 
     // compute other indices
     let moisture = from_ffmc_to_moisture(ffmc);
@@ -733,7 +770,7 @@ pub fn get_output_sliding(
     let isi = compute_isi(moisture, wind_speed);
     let bui = compute_bui(dmc, dc);
     let fwi = compute_fwi(bui, isi);
-    let ifwi = compute_ifwi(fwi);
+    let ifwi = get_ifwi(fwi, params);
 
     // get other outputs information
     let dffm = (moisture / (100.0 + moisture)) * 100.0; // moisture in [0, 100]
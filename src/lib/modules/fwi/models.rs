@@ -89,7 +89,19 @@ impl FWIState {
         time: &DateTime<Utc>,
         config: FWIModelConfig,
     ) -> FWIState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+
+        FWIState {
+            time: *time,
+            data,
+            len: warm_state.len(),
+            config,
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[FWIWarmState]) -> Array1<FWIStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| {
@@ -107,14 +119,7 @@ impl FWIState {
                     }
                 })
                 .collect(),
-        );
-
-        FWIState {
-            time: *time,
-            data,
-            len: warm_state.len(),
-            config,
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
@@ -125,6 +130,28 @@ impl FWIState {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<FWIWarmState> {
+        self.data
+            .iter()
+            .map(|state| FWIWarmState {
+                dates: state.dates.clone(),
+                ffmc: state.ffmc.clone(),
+                dmc: state.dmc.clone(),
+                dc: state.dc.clone(),
+                rain: state.rain.clone(),
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[FWIWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     #[allow(non_snake_case)]
     fn update_state(&mut self, props: &FWIProperties, input: &Input) {
         let time = &self.time;
@@ -158,3 +185,77 @@ impl FWIState {
         self.get_output(props)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::input::InputElement;
+    use crate::modules::fwi::config::FWIParameters;
+
+    /// Codes (FFMC, DMC, DC) at the end of each day of a cold start run, with the same weather
+    /// every day and the rain falling in the morning
+    fn spin_up(parameters: FWIParameters, days: i64, rain: f32) -> Vec<(f32, f32, f32)> {
+        let mut config = FWIModelConfig::new("legacy");
+        config.parameters = parameters;
+        let props = FWIProperties::new(FWICellPropertiesContainer {
+            lons: vec![10.0],
+            lats: vec![45.0],
+        });
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let mut state = FWIState::new(&[FWIWarmState::default()], &start, config);
+        let mut codes = Vec::new();
+        for step in 0..24 * days {
+            let time = start + Duration::hours(step);
+            let input = InputElement {
+                temperature: 25.0,
+                humidity: 40.0,
+                wind_speed: 10_000.0,
+                rain: if step % 24 == 8 { rain } else { 0.0 },
+                ..InputElement::default()
+            };
+            state.update(&props, &Input { time, data: Array1::from_vec(vec![input]) });
+            if step % 24 == 23 {
+                let output = &state.output(&props).data[0];
+                codes.push((output.ffmc, output.dmc, output.dc));
+            }
+        }
+        codes
+    }
+
+    #[test]
+    fn cold_start_from_custom_codes() {
+        let default = spin_up(FWIParameters::default(), 3, 0.0);
+        let custom = spin_up(
+            FWIParameters {
+                dmc_init: 30.0,
+                dc_init: 200.0,
+                ..FWIParameters::default()
+            },
+            3,
+            0.0,
+        );
+        // on dry days DMC and DC grow by the same amount from any starting value
+        for ((_, default_dmc, default_dc), (_, dmc, dc)) in default.iter().zip(&custom) {
+            assert!((dmc - default_dmc - 24.0).abs() < 1e-3, "{} {}", dmc, default_dmc);
+            assert!((dc - default_dc - 185.0).abs() < 1e-2, "{} {}", dc, default_dc);
+        }
+        assert!(custom.windows(2).all(|days| days[1].1 > days[0].1 && days[1].2 > days[0].2));
+    }
+
+    #[test]
+    fn rain_below_the_custom_threshold_does_not_wet_the_codes() {
+        let dry = spin_up(FWIParameters::default(), 2, 0.0);
+        let wet = spin_up(FWIParameters::default(), 2, 2.0);
+        assert!(wet[0].1 < dry[0].1);
+
+        let thresholds = FWIParameters {
+            ffmc_rain_threshold: 3.0,
+            dmc_rain_threshold: 3.0,
+            ..FWIParameters::default()
+        };
+        let wet = spin_up(thresholds, 2, 2.0);
+        assert_eq!(wet, dry);
+    }
+}
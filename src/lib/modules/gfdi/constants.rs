@@ -0,0 +1,2 @@
+pub const NODATAVAL: f32 = -9999.0;
+pub const CURING_DEFAULT: f32 = 100.0; // degree of curing of the grass [%], fully cured
@@ -0,0 +1,77 @@
+use super::{
+    constants::NODATAVAL,
+    models::{GfdiPropertiesElement, GfdiStateElement},
+};
+use crate::models::output::OutputElement;
+
+// McArthur Grassland Fire Danger Index (Mark 4)
+// Noble et al. (1980), for a standard fuel load of 4.5 t/ha
+// classes: low-moderate < 12, high < 25, very high < 50, severe < 75, extreme < 100,
+// catastrophic from 100
+pub fn gfdi(
+    temperature: f32, // temperature [°C]
+    humidity: f32,    // relative humidity [%]
+    wind_speed: f32,  // wind speed at 10 m [km/h]
+    curing: f32,      // degree of curing of the grass [%]
+) -> f32 {
+    if curing <= 0.0 {
+        // green grass does not burn
+        return 0.0;
+    }
+    let humidity = humidity.clamp(0.0, 100.0);
+    let wind_speed = wind_speed.max(0.0);
+    2.0 * f32::exp(
+        -23.6 + 5.01 * curing.min(100.0).ln() + 0.0281 * temperature - 0.226 * humidity.sqrt()
+            + 0.633 * wind_speed.sqrt(),
+    )
+}
+
+pub fn get_output_fn(state: &GfdiStateElement, props: &GfdiPropertiesElement) -> OutputElement {
+    if (state.temp == NODATAVAL) || (state.humidity == NODATAVAL) || (state.wind_speed == NODATAVAL)
+    {
+        return OutputElement::default();
+    }
+    let ws_kmh = state.wind_speed / 1000.0; // convert from m/h to km/h
+    let gfdi = gfdi(state.temp, state.humidity, ws_kmh, props.curing);
+    let ws_out = state.wind_speed / 3600.0; // convert from m/h to m/s
+    OutputElement {
+        gfdi,                     // [-]
+        temperature: state.temp,  // [°C]
+        humidity: state.humidity, // [%]
+        wind_speed: ws_out,       // [m/s]
+        ..OutputElement::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(curing: f32) -> GfdiPropertiesElement {
+        GfdiPropertiesElement {
+            lon: 0.0,
+            lat: 0.0,
+            curing,
+        }
+    }
+
+    #[test]
+    fn dry_windy_day_is_extreme() {
+        // 40 °C, 10 % and 38 km/h on fully cured grass
+        let state = GfdiStateElement {
+            temp: 40.0,
+            humidity: 10.0,
+            wind_speed: 38.0 * 1000.0,
+        };
+        let output = get_output_fn(&state, &cell(100.0));
+        // extreme class: [75, 100)
+        assert!((75.0..100.0).contains(&output.gfdi), "{}", output.gfdi);
+        assert!((output.wind_speed - 38.0 / 3.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn mild_humid_day_and_green_grass_are_low() {
+        assert!(gfdi(20.0, 60.0, 10.0, 100.0) < 12.0);
+        assert_eq!(gfdi(40.0, 10.0, 38.0, 0.0), 0.0);
+    }
+}
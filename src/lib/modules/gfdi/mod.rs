@@ -0,0 +1,3 @@
+pub mod constants;
+pub mod functions;
+pub mod models;
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::models::{input::Input, output::Output};
+use chrono::prelude::*;
+use ndarray::{Array1, Zip};
+
+use super::{
+    constants::*,
+    functions::get_output_fn,
+};
+
+/// McArthur Grassland Fire Danger Index (Mark 4)
+/// Source: Noble, Bary and Gill (1980), McArthur's fire-danger meters expressed as equations
+
+// CELLS PROPERTIES
+#[derive(Debug)]
+pub struct GfdiPropertiesElement {
+    pub lon: f32,
+    pub lat: f32,
+    pub curing: f32,  // degree of curing of the grass [%]
+}
+
+#[derive(Debug)]
+pub struct GfdiProperties {
+    pub data: Array1<GfdiPropertiesElement>,
+    pub len: usize,
+}
+
+pub struct GfdiCellPropertiesContainer {
+    pub lons: Vec<f32>,
+    pub lats: Vec<f32>,
+    pub curings: Vec<f32>,
+}
+
+impl GfdiProperties {
+    pub fn new(props: GfdiCellPropertiesContainer) -> Self {
+        let data: Array1<GfdiPropertiesElement> = props
+            .lons
+            .iter()
+            .enumerate()
+            .map(|(idx, lon)| GfdiPropertiesElement {
+                lon: *lon,
+                lat: props.lats[idx],
+                curing: props.curings[idx],
+            })
+            .collect();
+    
+        let len = data.len();
+        Self {
+            data,
+            len,
+        }
+    }
+
+    pub fn get_coords(&self) -> (Vec<f32>, Vec<f32>) {
+        let lats: Vec<f32> = self.data.iter().map(|p| p.lat).collect();
+        let lons: Vec<f32> = self.data.iter().map(|p| p.lon).collect();
+        (lats, lons)
+    }
+
+}
+
+
+// STATE
+#[derive(Debug)]
+pub struct GfdiStateElement {
+    pub temp: f32,  // temperature [°C]
+    pub humidity: f32,  // relative humidity [%]
+    pub wind_speed: f32,  // wind speed [m/h]
+}
+
+
+#[derive(Debug)]
+pub struct GfdiState {
+    pub time: DateTime<Utc>,
+    pub data: Array1<GfdiStateElement>,
+    len: usize,
+}
+
+impl GfdiState {
+    /// Create a new state.
+    pub fn new(time: &DateTime<Utc>, n_cells: usize) -> GfdiState {
+        let data: Array1<GfdiStateElement> = Array1::from(
+            (0..n_cells)
+                .map(|_| GfdiStateElement {
+                    temp: NODATAVAL,
+                    humidity: NODATAVAL,
+                    wind_speed: NODATAVAL,
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        GfdiState {
+            time: *time,
+            data,
+            len: n_cells,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn store(&mut self, input: &Input) {
+        self.time = input.time;  // reference time of the input
+        Zip::from(&mut self.data)
+            .and(&input.data)
+            .par_for_each(|state, input_data| {
+                state.temp = input_data.temperature;
+                state.humidity = input_data.humidity;
+                state.wind_speed = input_data.wind_speed;
+            });
+    }
+
+    pub fn get_output(&mut self, props: &GfdiProperties) -> Output {
+        let time = &self.time;
+        let output_data = Zip::from(&self.data)
+                    .and(&props.data)
+                    .par_map_collect(|state, props_data| {
+                        get_output_fn(state, props_data)
+                    });
+        Output::new(*time, output_data)
+    }
+
+    pub fn output(&mut self, props: &GfdiProperties) -> Output {
+        self.get_output(props)
+    }
+}
\ No newline at end of file
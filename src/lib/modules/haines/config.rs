@@ -0,0 +1,65 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// Elevation variant of the Haines index, selecting the pressure levels of the terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HainesVariant {
+    /// stability 950-850 hPa, moisture at 850 hPa
+    Low,
+    /// stability 850-700 hPa, moisture at 850 hPa
+    #[default]
+    Mid,
+    /// stability 700-500 hPa, moisture at 700 hPa
+    High,
+}
+
+/// configuration structure for model config
+/// can be used to store functions and constants
+#[derive(Debug)]
+pub struct HainesModelConfig {
+    pub variant: HainesVariant,
+    // limits of the stability term classes [°C]: class 1 below the first, class 3 from the second
+    stability_limits: (f32, f32),
+    // limits of the moisture term classes [°C]
+    moisture_limits: (f32, f32),
+}
+
+impl HainesModelConfig {
+    pub fn new(variant: HainesVariant) -> Self {
+        let (stability_limits, moisture_limits) = match variant {
+            HainesVariant::Low => ((4.0, 8.0), (6.0, 10.0)),
+            HainesVariant::Mid => ((6.0, 11.0), (6.0, 13.0)),
+            HainesVariant::High => ((18.0, 22.0), (15.0, 21.0)),
+        };
+        HainesModelConfig {
+            variant,
+            stability_limits,
+            moisture_limits,
+        }
+    }
+
+    /// Stability term class [1-3] from the temperature difference between the levels [°C]
+    pub fn stability_term(&self, temp_diff: f32) -> f32 {
+        term_class(temp_diff, self.stability_limits)
+    }
+
+    /// Moisture term class [1-3] from the dew point depression at the moisture level [°C]
+    pub fn moisture_term(&self, dew_depression: f32) -> f32 {
+        term_class(dew_depression, self.moisture_limits)
+    }
+
+    /// The moisture term is computed at the upper level of the low variant,
+    /// at the lower level otherwise
+    pub fn moisture_at_upper_level(&self) -> bool {
+        self.variant == HainesVariant::Low
+    }
+}
+
+fn term_class(value: f32, (low, high): (f32, f32)) -> f32 {
+    if value < low {
+        1.0
+    } else if value < high {
+        2.0
+    } else {
+        3.0
+    }
+}
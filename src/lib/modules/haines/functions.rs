@@ -0,0 +1,39 @@
+use crate::models::output::OutputElement;
+use super::{config::HainesModelConfig, constants::NODATAVAL, models::HainesStateElement};
+
+
+// Haines index
+pub fn haines(
+    temp_lower: f32,  // temperature at the lower level [°C]
+    temp_upper: f32,  // temperature at the upper level [°C]
+    temp_moist: f32,  // temperature at the moisture level [°C]
+    temp_dew_moist: f32,  // dew point temperature at the moisture level [°C]
+    config: &HainesModelConfig,
+) -> f32 {
+    let stability = config.stability_term(temp_lower - temp_upper);
+    let moisture = config.moisture_term(temp_moist - temp_dew_moist);
+    stability + moisture
+}
+
+
+pub fn get_output_fn(
+    state: &HainesStateElement,
+    config: &HainesModelConfig,
+) -> OutputElement {
+    let (temp_moist, temp_dew_moist) = if config.moisture_at_upper_level() {
+        (state.temp_upper, state.temp_dew_upper)
+    } else {
+        (state.temp_lower, state.temp_dew_lower)
+    };
+    if state.temp_lower == NODATAVAL
+        || state.temp_upper == NODATAVAL
+        || temp_dew_moist == NODATAVAL
+    {
+        return OutputElement::default()
+    }
+    let haines = haines(state.temp_lower, state.temp_upper, temp_moist, temp_dew_moist, config);
+    OutputElement {
+        haines,  // [-]
+        ..OutputElement::default()
+    }
+}
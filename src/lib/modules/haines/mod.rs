@@ -0,0 +1,4 @@
+pub mod config;
+pub mod constants;
+pub mod functions;
+pub mod models;
\ No newline at end of file
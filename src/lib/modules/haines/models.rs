@@ -0,0 +1,132 @@
+use crate::models::{input::Input, output::Output};
+use chrono::prelude::*;
+use ndarray::{Array1, Zip};
+
+use super::{
+    config::HainesModelConfig,
+    constants::*,
+    functions::get_output_fn,
+};
+
+/// Haines Index (Lower Atmosphere Stability Index)
+/// Source: Haines, D.A. A lower atmosphere severity index for wildland fires. National Weather Digest 1988, 13, 23-27.
+
+// CELLS PROPERTIES
+#[derive(Debug)]
+pub struct HainesPropertiesElement {
+    pub lon: f32,
+    pub lat: f32,
+}
+
+#[derive(Debug)]
+pub struct HainesProperties {
+    pub data: Array1<HainesPropertiesElement>,
+    pub len: usize,
+}
+
+pub struct HainesCellPropertiesContainer {
+    pub lons: Vec<f32>,
+    pub lats: Vec<f32>,
+}
+
+impl HainesProperties {
+    pub fn new(props: HainesCellPropertiesContainer) -> Self {
+        let data: Array1<HainesPropertiesElement> = props
+            .lons
+            .iter()
+            .enumerate()
+            .map(|(idx, lon)| HainesPropertiesElement {
+                lon: *lon,
+                lat: props.lats[idx],
+            })
+            .collect();
+
+        let len = data.len();
+        Self {
+            data,
+            len,
+        }
+    }
+
+    pub fn get_coords(&self) -> (Vec<f32>, Vec<f32>) {
+        let lats: Vec<f32> = self.data.iter().map(|p| p.lat).collect();
+        let lons: Vec<f32> = self.data.iter().map(|p| p.lon).collect();
+        (lats, lons)
+    }
+
+}
+
+
+// STATE
+#[derive(Debug)]
+pub struct HainesStateElement {
+    pub temp_lower: f32,  // temperature at the lower level [°C]
+    pub temp_upper: f32,  // temperature at the upper level [°C]
+    pub temp_dew_lower: f32,  // dew point temperature at the lower level [°C]
+    pub temp_dew_upper: f32,  // dew point temperature at the upper level [°C]
+}
+
+
+#[derive(Debug)]
+pub struct HainesState {
+    pub time: DateTime<Utc>,
+    pub data: Array1<HainesStateElement>,
+    len: usize,
+    config: HainesModelConfig,
+}
+
+impl HainesState {
+    /// Create a new state.
+    pub fn new(time: &DateTime<Utc>, n_cells: usize, config: HainesModelConfig) -> HainesState {
+        let data: Array1<HainesStateElement> = Array1::from(
+            (0..n_cells)
+                .map(|_| HainesStateElement {
+                    temp_lower: NODATAVAL,
+                    temp_upper: NODATAVAL,
+                    temp_dew_lower: NODATAVAL,
+                    temp_dew_upper: NODATAVAL,
+                })
+                .collect::<Vec<_>>(),
+        );
+        HainesState {
+            time: *time,
+            data,
+            len: n_cells,
+            config,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn store(&mut self, input: &Input) {
+        self.time = input.time;  // reference time of the input
+        Zip::from(&mut self.data)
+            .and(&input.data)
+            .par_for_each(|state, input_data| {
+                state.temp_lower = input_data.temp_lower;
+                state.temp_upper = input_data.temp_upper;
+                state.temp_dew_lower = input_data.temp_dew_lower;
+                state.temp_dew_upper = input_data.temp_dew_upper;
+            });
+    }
+
+    pub fn get_output(&mut self) -> Output {
+        let time = &self.time;
+        let config = &self.config;
+        let output_data = self.data
+                    .map(|state| {
+                        get_output_fn(state, config)
+                    });
+        Output::new(*time, output_data)
+    }
+
+    pub fn output(&mut self) -> Output {
+        self.get_output()
+    }
+}
@@ -49,6 +49,18 @@ pub fn kbdi_update_mm(
     kbdi_new.clamp(0.0, 200.0)
 }
 
+// Initial KBDI of a cold start (no warm state), from an assumed antecedent dryness [0-1]
+// scaled with the mean annual rain term of the evapotranspiration:
+// the wetter climates start from a smaller soil moisture deficit
+pub fn kbdi_cold_start(
+    mean_annual_rain: f32, // mean annual rain [mm]
+    dryness: f32,          // assumed antecedent dryness [0-1]
+) -> f32 {
+    let rain_term = 10.88 * f32::exp(-0.001736 * mean_annual_rain);
+    let kbdi = 200.0 * dryness * rain_term / (1.0 + rain_term);
+    kbdi.clamp(0.0, 200.0)
+}
+
 pub fn update_fn(
     state: &mut KBDIStateElement,
     prop: &KBDIPropertiesElement,
@@ -70,6 +82,7 @@ pub fn update_fn(
 pub fn get_output_fn(state: &KBDIStateElement) -> OutputElement {
     OutputElement {
         kbdi: state.kbdi,            // [mm]
+        daily_rain: state.daily_rain.last().copied().unwrap_or(NODATAVAL), // [mm]
         rain: state.cum_rain,        // [mm]
         temperature: state.max_temp, // [°C]
         ..OutputElement::default()
@@ -6,7 +6,7 @@ use itertools::izip;
 use super::{
     constants::*,
     config::KBDIModelConfig,
-    functions::{store_day_fn, update_fn, get_output_fn},
+    functions::{store_day_fn, update_fn, get_output_fn, kbdi_cold_start},
 };
 
 
@@ -81,6 +81,17 @@ impl Default for KBDIWarmState {
     }
 }
 
+impl KBDIWarmState {
+    /// Warm state of a cold start, seeded from the mean annual rain of the cell
+    /// and the assumed antecedent dryness [0-1]
+    pub fn cold_start(mean_annual_rain: f32, dryness: f32) -> Self {
+        Self {
+            kbdi: kbdi_cold_start(mean_annual_rain, dryness),
+            ..Self::default()
+        }
+    }
+}
+
 // STATE
 #[derive(Debug)]
 #[allow(non_snake_case)]
@@ -145,7 +156,18 @@ impl KBDIState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state.
     pub fn new(warm_state: &[KBDIWarmState], time: &DateTime<Utc>, config: KBDIModelConfig) -> KBDIState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+        KBDIState {
+            time: *time,
+            data,
+            len: warm_state.len(),
+            config,
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[KBDIWarmState]) -> Array1<KBDIStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| KBDIStateElement {
@@ -156,13 +178,7 @@ impl KBDIState {
                     max_temp: NODATAVAL,
                 })
                 .collect(),
-        );
-        KBDIState {
-            time: *time,
-            data,
-            len: warm_state.len(),
-            config,
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
@@ -173,6 +189,26 @@ impl KBDIState {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<KBDIWarmState> {
+        self.data
+            .iter()
+            .map(|state| KBDIWarmState {
+                dates: state.dates.clone(),
+                daily_rain: state.daily_rain.clone(),
+                kbdi: state.kbdi,
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[KBDIWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     #[allow(non_snake_case)]
     fn store_day(&mut self, input: &Input) {
         self.time = input.time;  // reference time of the input
@@ -215,4 +251,10 @@ impl KBDIState {
     pub fn output(&mut self) -> Output {
         self.get_output()
     }
+
+    /// Output at the current time, the daily values are kept
+    pub fn current_output(&self) -> Output {
+        let output_data = self.data.map(get_output_fn);
+        Output::new(self.time, output_data)
+    }
 }
@@ -182,7 +182,7 @@ pub fn get_output_fn(
         ffdi(state.temp_15, state.humidity_15, state.wind_speed_15, df)
     };
     // return output
-    config.get_output(
+    let output = config.get_output(
         state.smd,
         df,
         ffdi,
@@ -190,7 +190,12 @@ pub fn get_output_fn(
         state.cum_rain,
         state.wind_speed_15,
         state.humidity_15,
-    )
+    );
+    // daily rain stored in the state for the day of the run
+    OutputElement {
+        daily_rain: daily_rains.last().copied().unwrap_or(NODATAVAL),
+        ..output
+    }
 }
 
 pub fn kbdi_output(
@@ -77,7 +77,7 @@ impl Default for Mark5WarmState {
 }
 
 // STATE
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(non_snake_case)]
 pub struct Mark5StateElement {
     pub dates: Vec<DateTime<Utc>>,  // dates of the previous time window
@@ -146,7 +146,19 @@ impl Mark5State {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state.
     pub fn new(warm_state: &[Mark5WarmState], time: &DateTime<Utc>, config: Mark5ModelConfig) -> Mark5State {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+
+        Mark5State {
+            time: *time,
+            data,
+            len: warm_state.len(),
+            config,
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[Mark5WarmState]) -> Array1<Mark5StateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| Mark5StateElement {
@@ -160,14 +172,7 @@ impl Mark5State {
                     wind_speed_15: NODATAVAL,
                 })
                 .collect(),
-        );
-
-        Mark5State {
-            time: *time,
-            data,
-            len: warm_state.len(),
-            config,
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
@@ -178,6 +183,26 @@ impl Mark5State {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<Mark5WarmState> {
+        self.data
+            .iter()
+            .map(|state| Mark5WarmState {
+                dates: state.dates.clone(),
+                daily_rain: state.daily_rain.clone(),
+                smd: state.smd,
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[Mark5WarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     #[allow(non_snake_case)]
     fn store_day(&mut self, input: &Input, prop: &Mark5Properties) {
         let time = input.time;  // reference time of the input
@@ -211,4 +236,15 @@ impl Mark5State {
     pub fn output(&mut self, props: &Mark5Properties) -> Output {
         self.get_output(props)
     }
+
+    /// Output as if the day ended at the current time, the state is left unchanged
+    pub fn current_output(&self, props: &Mark5Properties) -> Output {
+        let time = &self.time;
+        let output_data = Zip::from(&self.data)
+            .and(&props.data)
+            .par_map_collect(|state, props_data| {
+                get_output_fn(&mut state.clone(), props_data, &self.config, time)
+            });
+        Output::new(*time, output_data)
+    }
 }
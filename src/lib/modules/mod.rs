@@ -8,5 +8,8 @@ pub mod fosberg;
 pub mod nesterov;
 pub mod sharples;
 pub mod orieux;
-// pub mod portuguese;  -> UNDER CONSTRUCTION
-pub mod hdw;
\ No newline at end of file
+pub mod portuguese;
+pub mod hdw;
+pub mod haines;
+pub mod chandler;
+pub mod gfdi;
\ No newline at end of file
@@ -103,7 +103,17 @@ impl NesterovState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state
     pub fn new(warm_state: &[NesterovWarmState], time: &DateTime<Utc>) -> NesterovState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+        NesterovState {
+            time: *time,
+            data,
+            len: warm_state.len(),
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[NesterovWarmState]) -> Array1<NesterovStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| NesterovStateElement {
@@ -113,12 +123,7 @@ impl NesterovState {
                     cum_rain: 0.0,
                 })
                 .collect(),
-        );
-        NesterovState {
-            time: *time,
-            data,
-            len: warm_state.len(),
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
@@ -129,6 +134,24 @@ impl NesterovState {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<NesterovWarmState> {
+        self.data
+            .iter()
+            .map(|state| NesterovWarmState {
+                nesterov: state.nesterov,
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[NesterovWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     // Store the daily info at 15:00 local time
     #[allow(non_snake_case)]
     fn store_day(&mut self, input: &Input, prop: &NesterovProperties) {
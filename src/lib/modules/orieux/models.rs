@@ -113,7 +113,18 @@ impl OrieuxState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state
     pub fn new(warm_state: &[OrieuxWarmState], time: &DateTime<Utc>) -> OrieuxState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+
+        OrieuxState {
+            time: *time,
+            data,
+            len: warm_state.len(),
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[OrieuxWarmState]) -> Array1<OrieuxStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| OrieuxStateElement {
@@ -126,13 +137,7 @@ impl OrieuxState {
                     max_wind_speed: NODATAVAL,
                 })
                 .collect(),
-        );
-
-        OrieuxState {
-            time: *time,
-            data,
-            len: warm_state.len(),
-        }
+        )
     }
 
 
@@ -144,6 +149,24 @@ impl OrieuxState {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<OrieuxWarmState> {
+        self.data
+            .iter()
+            .map(|state| OrieuxWarmState {
+                orieux_wr: state.orieux_wr,
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[OrieuxWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     #[allow(non_snake_case)]
     fn store_day(&mut self, input: &Input) {
         self.time = input.time;  // reference time of the input
@@ -41,16 +41,19 @@ pub fn ignition_index(
     temp_12: f32,  // temperature [°C] at 12:00
     temp_dew_12: f32,  // dew temperature [°C] at 12:00
 ) -> f32 {
-    let ign = temp_12 * (temp_12 - temp_dew_12);
-    ign
+    temp_12 * (temp_12 - temp_dew_12)
 }
 
 
 pub fn update_fn(
     state: &mut PortugueseStateElement,
 ) {
+    // no weather at 12:00 for this day, keep the cumulated values
+    if state.temp_12 == NODATAVAL || state.temp_dew_12 == NODATAVAL {
+        return;
+    }
     // ignition index
-    let ign = state.temp_12 * (state.temp_12 - state.temp_dew_12);
+    let ign = ignition_index(state.temp_12, state.temp_dew_12);
     state.ign = ign;
     state.sum_ign += ign;  // add to the sum for the warm state
     // fire index
@@ -88,3 +91,47 @@ pub fn get_output_fn(
         ..OutputElement::default()
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn cumulative_index_increases_on_dry_days() {
+        // a cell in Lisbon, UTC+1 in summer
+        let prop = PortuguesePropertiesElement { lon: -9.14, lat: 38.72 };
+        let mut state = PortugueseStateElement {
+            ign: NODATAVAL,
+            sum_ign: 0.0,
+            cum_index: 0.0,
+            fire_index: NODATAVAL,
+            temp_12: NODATAVAL,
+            temp_dew_12: NODATAVAL,
+            cum_rain: 0.0,
+        };
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let mut cum_indices = vec![state.cum_index];
+        for step in 0..48 {
+            let time = start + Duration::hours(step);
+            let input = InputElement {
+                temperature: 20.0 + 10.0 * (step % 24) as f32 / 23.0,
+                temp_dew: 10.0,
+                rain: 0.0,
+                ..InputElement::default()
+            };
+            store_day_fn(&mut state, &input, &prop, &time);
+            // daily update at the end of the day
+            if step % 24 == 23 {
+                update_fn(&mut state);
+                assert!(state.ign > 0.0);
+                cum_indices.push(state.cum_index);
+                state.clean_day();
+            }
+        }
+        assert_eq!(cum_indices.len(), 3);
+        assert!(cum_indices.windows(2).all(|pair| pair[1] > pair[0]), "{:?}", cum_indices);
+    }
+}
@@ -12,8 +12,6 @@ use super::{
 /// Source: https://wikifire.wsl.ch/tiki-index656a.html?page=Portuguese+index&structure=Fire
 
 
-/// NOTE: problems in the computation of the cumulative index, it should go on overflow!!!
-
 // CELLS PROPERTIES
 #[derive(Debug)]
 pub struct PortuguesePropertiesElement {
@@ -60,21 +58,12 @@ impl PortugueseProperties {
 
 // WARM STATE
 #[allow(non_snake_case)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PortugueseWarmState {
     pub sum_ign: f32,  // sum of the ignition indices
     pub cum_index: f32,  // coefficient B index of the previous day
 }
 
-impl Default for PortugueseWarmState {
-    fn default() -> Self {
-        Self {
-            sum_ign: 0.0,
-            cum_index: 0.0
-        }
-    }
-}
-
 // STATE
 #[derive(Debug)]
 #[allow(non_snake_case)]
@@ -112,12 +101,22 @@ impl PortugueseState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state
     pub fn new(warm_state: &[PortugueseWarmState], time: &DateTime<Utc>) -> PortugueseState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+        PortugueseState {
+            time: *time,
+            data,
+            len: warm_state.len(),
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[PortugueseWarmState]) -> Array1<PortugueseStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| PortugueseStateElement {
-                    sum_ign: w.sum_ign.clone(),
-                    cum_index: w.cum_index.clone(),
+                    sum_ign: w.sum_ign,
+                    cum_index: w.cum_index,
                     ign: NODATAVAL,
                     fire_index: NODATAVAL,
                     temp_12: NODATAVAL,
@@ -125,12 +124,7 @@ impl PortugueseState {
                     cum_rain: 0.0,
                 })
                 .collect(),
-        );
-        PortugueseState {
-            time: *time,
-            data,
-            len: warm_state.len(),
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
@@ -141,6 +135,25 @@ impl PortugueseState {
         self.len() == 0
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<PortugueseWarmState> {
+        self.data
+            .iter()
+            .map(|state| PortugueseWarmState {
+                sum_ign: state.sum_ign,
+                cum_index: state.cum_index,
+            })
+            .collect()
+    }
+
+    /// Restore the state from a snapshot taken at the given time,
+    /// the values accumulated since the last daily update are reset as in a new state
+    pub fn restore(&mut self, warm_state: &[PortugueseWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     #[allow(non_snake_case)]
     fn store_day(&mut self, input: &Input, prop: &PortugueseProperties) {
         let time = input.time;  // reference time of the input
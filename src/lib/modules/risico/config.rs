@@ -1,8 +1,42 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::constants::NODATAVAL;
+use crate::models::input::InputElement;
+
+use super::constants::NDWI_SECONDS_VALIDITY;
 use super::functions::{
     get_v_v2025, get_v_v2023, get_v_legacy, update_dffm_dry, update_dffm_dry_legacy, update_dffm_rain,
     update_dffm_rain_legacy, get_meteo_index_v2023, get_meteo_index_v2025, get_meteo_index_legacy,
+    get_emc, get_emc_legacy,
+    get_v_wind_dir_v2023, get_v_wind_dir_v2025, get_moisture_effect_legacy,
+    get_moisture_effect_v2023, get_moisture_effect_v2025, get_v_no_wind_dir_v2023,
+    get_v_no_wind_dir_v2025,
 };
 
+/// Input variables that can be required for computing a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequiredInput {
+    Temperature,
+    Humidity,
+    WindSpeed,
+    WindDir,
+    Rain,
+}
+
+impl RequiredInput {
+    /// Check if the input variable is available for the cell
+    pub fn is_available(&self, input: &InputElement) -> bool {
+        let value = match self {
+            RequiredInput::Temperature => input.temperature,
+            RequiredInput::Humidity => input.humidity,
+            RequiredInput::WindSpeed => input.wind_speed,
+            RequiredInput::WindDir => input.wind_dir,
+            RequiredInput::Rain => input.rain,
+        };
+        value != NODATAVAL
+    }
+}
+
 type RosFnType = fn(f32, f32, f32, f32, f32, f32, f32, f32, f32, f32) -> (f32, f32);
 
 /// configuration structure for model config
@@ -11,9 +45,26 @@ type RosFnType = fn(f32, f32, f32, f32, f32, f32, f32, f32, f32, f32) -> (f32, f
 pub struct RISICOModelConfig {
     pub model_version: String,
     pub use_t_effect: bool,
+    // validity of the NDWI data [s]
+    pub ndwi_seconds_validity: i64,
+    // use a direction-independent wind effect if the wind direction is missing
+    pub wind_dir_fallback: bool,
+    // v2025 only: the rate of spread uses max(wind speed, factor * wind gust) when the gust
+    // is available (default: None, wind speed only)
+    pub wind_gust_factor: Option<f32>,
+    // inputs required for computing a cell, cells without them are set to NODATAVAL
+    pub required_inputs: Vec<RequiredInput>,
     ffmc_no_rain_fn: fn(f32, f32, f32, f32, f32, f32, f32) -> f32,
     ffmc_rain_fn: fn(f32, f32, f32) -> f32,
+    // equilibrium moisture content of the drying dynamics
+    emc_fn: fn(f32, f32) -> f32,
     ros_fn: RosFnType,
+    // rate of spread in the input wind direction
+    ros_wind_dir_fn: RosFnType,
+    // rate of spread with missing wind direction
+    ros_no_wind_dir_fn: RosFnType,
+    // moisture effect on the rate of spread
+    moisture_effect_fn: fn(f32) -> f32,
     meteo_index_fn: fn(f32, f32) -> f32,
 }
 
@@ -21,14 +72,24 @@ impl RISICOModelConfig {
     pub fn new(model_version_str: &str) -> Self {
         let ffmc_no_rain_fn: fn(f32, f32, f32, f32, f32, f32, f32) -> f32;
         let ffmc_rain_fn: fn(f32, f32, f32) -> f32;
+        let emc_fn: fn(f32, f32) -> f32;
         let ros_fn: RosFnType;
+        let ros_wind_dir_fn: RosFnType;
+        let ros_no_wind_dir_fn: RosFnType;
+        let moisture_effect_fn: fn(f32) -> f32;
         let meteo_index_fn: fn(f32, f32) -> f32;
 
         match model_version_str {
             "legacy" => {
                 ffmc_no_rain_fn = update_dffm_dry_legacy;
                 ffmc_rain_fn = update_dffm_rain_legacy;
+                emc_fn = get_emc_legacy;
                 ros_fn = get_v_legacy;
+                // get_wind_effect_legacy has no search over the propagation angles, it takes
+                // the wind direction against the aspect, and is 1 for a missing wind direction
+                ros_wind_dir_fn = get_v_legacy;
+                ros_no_wind_dir_fn = get_v_legacy;
+                moisture_effect_fn = get_moisture_effect_legacy;
                 meteo_index_fn = get_meteo_index_legacy;
             }
             // DEPRECATED
@@ -47,19 +108,31 @@ impl RISICOModelConfig {
             "v2023" => {
                 ffmc_no_rain_fn = update_dffm_dry;
                 ffmc_rain_fn = update_dffm_rain;
+                emc_fn = get_emc;
                 ros_fn = get_v_v2023;
+                ros_wind_dir_fn = get_v_wind_dir_v2023;
+                ros_no_wind_dir_fn = get_v_no_wind_dir_v2023;
+                moisture_effect_fn = get_moisture_effect_v2023;
                 meteo_index_fn = get_meteo_index_v2023;
             },
             "v2025" => {
                 ffmc_no_rain_fn = update_dffm_dry;
                 ffmc_rain_fn = update_dffm_rain;
+                emc_fn = get_emc;
                 ros_fn = get_v_v2025;
+                ros_wind_dir_fn = get_v_wind_dir_v2025;
+                ros_no_wind_dir_fn = get_v_no_wind_dir_v2025;
+                moisture_effect_fn = get_moisture_effect_v2025;
                 meteo_index_fn = get_meteo_index_v2025;
             }
             _ => {
                 ffmc_no_rain_fn = update_dffm_dry_legacy;
                 ffmc_rain_fn = update_dffm_rain_legacy;
+                emc_fn = get_emc_legacy;
                 ros_fn = get_v_legacy;
+                ros_wind_dir_fn = get_v_legacy;
+                ros_no_wind_dir_fn = get_v_legacy;
+                moisture_effect_fn = get_moisture_effect_legacy;
                 meteo_index_fn = get_meteo_index_legacy;
             }
         }
@@ -67,13 +140,26 @@ impl RISICOModelConfig {
         RISICOModelConfig {
             model_version: model_version_str.to_owned(),
             use_t_effect: false,
+            ndwi_seconds_validity: NDWI_SECONDS_VALIDITY,
+            wind_dir_fallback: false,
+            wind_gust_factor: None,
+            required_inputs: Vec::new(),
             ffmc_no_rain_fn,
             ffmc_rain_fn,
+            emc_fn,
             ros_fn,
+            ros_wind_dir_fn,
+            ros_no_wind_dir_fn,
+            moisture_effect_fn,
             meteo_index_fn,
         }
     }
 
+    /// Check if the cell has all the required inputs
+    pub fn has_required_inputs(&self, input: &InputElement) -> bool {
+        self.required_inputs.iter().all(|r| r.is_available(input))
+    }
+
     #[allow(non_snake_case, clippy::too_many_arguments)]
     pub fn ffmc_no_rain(
         &self,
@@ -92,6 +178,12 @@ impl RISICOModelConfig {
         (self.ffmc_rain_fn)(r, dffm, sat)
     }
 
+    /// Equilibrium moisture content [%] for temperature [°C] and relative humidity [%]
+    #[allow(non_snake_case)]
+    pub fn emc(&self, T: f32, H: f32) -> f32 {
+        (self.emc_fn)(T, H)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn ros(
         &self,
@@ -106,13 +198,97 @@ impl RISICOModelConfig {
         wind_dir: f32,
         t_effect: f32,
     ) -> (f32, f32) {
-        (self.ros_fn)(
+        let ros_fn = if self.use_no_wind_dir(wind_speed, wind_dir) {
+            self.ros_no_wind_dir_fn
+        } else {
+            self.ros_fn
+        };
+        ros_fn(
             v0, d0, _d1, snow_cover, dffm, slope, aspect, wind_speed, wind_dir, t_effect,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn ros_wind_dir(
+        &self,
+        v0: f32,
+        d0: f32,
+        _d1: f32,
+        dffm: f32,
+        snow_cover: f32,
+        slope: f32,
+        aspect: f32,
+        wind_speed: f32,
+        wind_dir: f32,
+        t_effect: f32,
+    ) -> (f32, f32) {
+        let ros_fn = if self.use_no_wind_dir(wind_speed, wind_dir) {
+            self.ros_no_wind_dir_fn
+        } else {
+            self.ros_wind_dir_fn
+        };
+        ros_fn(
+            v0, d0, _d1, snow_cover, dffm, slope, aspect, wind_speed, wind_dir, t_effect,
+        )
+    }
+
+    /// Wind speed [m/h] of the rate of spread, overridden by the scaled wind gust if enabled
+    pub fn ros_wind_speed(&self, wind_speed: f32, wind_gust: f32) -> f32 {
+        match self.wind_gust_factor {
+            Some(factor)
+                if self.model_version == "v2025"
+                    && wind_speed != NODATAVAL
+                    && wind_gust != NODATAVAL =>
+            {
+                f32::max(wind_speed, factor * wind_gust)
+            }
+            _ => wind_speed,
+        }
+    }
+
+    // direction-independent wind effect only if enabled and only the direction is missing
+    fn use_no_wind_dir(&self, wind_speed: f32, wind_dir: f32) -> bool {
+        self.wind_dir_fallback && wind_dir == NODATAVAL && wind_speed != NODATAVAL
+    }
+
+    pub fn moisture_effect(&self, dffm: f32) -> f32 {
+        if dffm == NODATAVAL {
+            return NODATAVAL;
+        }
+        (self.moisture_effect_fn)(dffm)
+    }
+
     #[allow(non_snake_case, clippy::too_many_arguments)]
     pub fn meteo_index(&self, dffm: f32, W: f32) -> f32 {
         (self.meteo_index_fn)(dffm, W)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rate of spread and wind effect of a dry grass cell on flat terrain
+    fn ros(config: &RISICOModelConfig, wind_speed: f32, wind_dir: f32) -> (f32, f32) {
+        config.ros(140.0, 5.0, 0.0, 5.0, 0.0, 0.0, 0.0, wind_speed, wind_dir, 1.0)
+    }
+
+    #[test]
+    fn missing_wind_direction_zeroes_the_ros_by_default() {
+        let config = RISICOModelConfig::new("v2025");
+        assert_eq!(ros(&config, 10_000.0, NODATAVAL), (0.0, NODATAVAL));
+        assert!(ros(&config, 10_000.0, 0.0).0 > 0.0);
+    }
+
+    #[test]
+    fn missing_wind_direction_falls_back_to_the_wind_speed() {
+        let mut config = RISICOModelConfig::new("v2025");
+        config.wind_dir_fallback = true;
+        assert!(ros(&config, 10_000.0, NODATAVAL).0 > 0.0);
+        // a valid direction is not affected by the fallback
+        let expected = ros(&RISICOModelConfig::new("v2025"), 10_000.0, 0.0);
+        assert_eq!(ros(&config, 10_000.0, 0.0), expected);
+        // nothing to fall back on without the wind speed
+        assert_eq!(ros(&config, NODATAVAL, NODATAVAL).0, 0.0);
+    }
+}
@@ -6,6 +6,11 @@ pub const W_STANDARD: f32 = 0.0;
 pub const H_STANDARD: f32 = 20.0;
 pub const MAXRAIN: f32 = 0.1;
 
+/// bounds of the time step used in the moisture update [hours]:
+/// longer gaps between the inputs are updated as a MAX_DT step
+pub const MIN_DT: f32 = 1.0;
+pub const MAX_DT: f32 = 72.0;
+
 /// old constants for legacy ffmc functions
 pub const A1_LEGACY: f32 = 1.0; //OLD
 pub const R1_LEGACY: f32 = 12.119; //OLD
@@ -77,6 +82,10 @@ pub const CLOUDCOVER: f32 = 1.0;
 pub const SNOW_COVER_THRESHOLD: f32 = 1.0;
 
 pub const SATELLITE_DATA_SECONDS_VALIDITY: i64 = 10 * 24 * 3600;
+/// NDWI validity [s]: NDWI older than this value is discarded.
+/// It is kept separate from the general satellite validity so that it can be tuned
+/// to the NDWI product cadence (see RISICOModelConfig::ndwi_seconds_validity)
+pub const NDWI_SECONDS_VALIDITY: i64 = 240 * 3600;
 pub const SNOW_SECONDS_VALIDITY: i64 = 10 * 24 * 3600;
 
 // ROS NEW CONSTANTS
@@ -0,0 +1,171 @@
+use chrono::prelude::*;
+
+use crate::models::{input::Input, output::Output};
+
+use super::{
+    config::RISICOModelConfig,
+    models::{RISICOProperties, RISICOState, RISICOWarmState},
+};
+
+/// Run a single timestep of the model: update the state with the input and compute the output
+pub fn run_timestep(state: &mut RISICOState, props: &RISICOProperties, input: &Input) -> Output {
+    state.update(props, input);
+    state.output(props, input)
+}
+
+/// Run the model one timestep at a time, owning the state and the cell properties.
+/// Reading and writing the warm state is left to the caller.
+pub struct StepDriver {
+    state: RISICOState,
+    props: RISICOProperties,
+}
+
+impl StepDriver {
+    /// Create a driver starting at the given time.
+    /// Without a warm state all the cells start from the default one.
+    ///
+    /// Fails if the warm state is not defined on the same cells as the properties.
+    pub fn new(
+        props: RISICOProperties,
+        warm_state: Option<&[RISICOWarmState]>,
+        time: &DateTime<Utc>,
+        config: RISICOModelConfig,
+    ) -> Result<Self, String> {
+        let default_warm_state;
+        let warm_state = match warm_state {
+            Some(warm_state) if warm_state.len() != props.len => {
+                return Err(format!(
+                    "Warm state has {} cells, properties have {}",
+                    warm_state.len(),
+                    props.len
+                ));
+            }
+            Some(warm_state) => warm_state,
+            None => {
+                default_warm_state = vec![RISICOWarmState::default(); props.len];
+                default_warm_state.as_slice()
+            }
+        };
+        let state = RISICOState::new(warm_state, time, config);
+        Ok(Self { state, props })
+    }
+
+    /// Advance the model to the time of the input and return the output
+    pub fn advance(&mut self, input: &Input) -> Output {
+        run_timestep(&mut self.state, &self.props, input)
+    }
+
+    /// Time of the last processed input
+    pub fn time(&self) -> DateTime<Utc> {
+        self.state.time
+    }
+
+    pub fn state(&self) -> &RISICOState {
+        &self.state
+    }
+
+    pub fn properties(&self) -> &RISICOProperties {
+        &self.props
+    }
+
+    /// Current warm state of the cells, to be saved for a later run
+    pub fn warm_state(&self) -> Vec<RISICOWarmState> {
+        self.state.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
+    use ndarray::Array1;
+
+    use super::*;
+    use crate::models::{input::InputElement, output::OutputVariableName};
+    use crate::modules::risico::models::RISICOCellPropertiesContainer;
+
+    fn properties(n_cells: usize) -> RISICOProperties {
+        RISICOProperties::new(
+            RISICOCellPropertiesContainer {
+                lons: vec![10.0; n_cells],
+                lats: vec![45.0; n_cells],
+                slopes: vec![0.0; n_cells],
+                aspects: vec![0.0; n_cells],
+                vegetations: vec!["default".to_string(); n_cells],
+            },
+            HashMap::new(),
+            vec![1.0; n_cells],
+            vec![1.0; n_cells],
+        )
+    }
+
+    fn input(time: DateTime<Utc>, n_cells: usize, rain: f32) -> Input {
+        let data = (0..n_cells)
+            .map(|_| InputElement {
+                temperature: 25.0,
+                humidity: 40.0,
+                wind_speed: 10_000.0,
+                wind_dir: 0.0,
+                rain,
+                ..InputElement::default()
+            })
+            .collect();
+        Input {
+            time,
+            data: Array1::from_vec(data),
+        }
+    }
+
+    #[test]
+    fn mismatched_warm_state_is_an_error() {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let warm_state = vec![RISICOWarmState::default(); 3];
+        let driver = StepDriver::new(
+            properties(2),
+            Some(&warm_state),
+            &start,
+            RISICOModelConfig::new("v2025"),
+        );
+        assert!(driver.is_err());
+    }
+
+    #[test]
+    fn advance_matches_update_and_output() {
+        let n_cells = 2;
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let warm_state = vec![RISICOWarmState::default(); n_cells];
+        let mut driver = StepDriver::new(
+            properties(n_cells),
+            Some(&warm_state),
+            &start,
+            RISICOModelConfig::new("v2025"),
+        )
+        .unwrap();
+        let props = properties(n_cells);
+        let mut state = RISICOState::new(&warm_state, &start, RISICOModelConfig::new("v2025"));
+
+        for step in 1..=48 {
+            let time = start + Duration::try_hours(step).expect("Should be a valid duration");
+            let input = input(time, n_cells, if step % 24 == 8 { 5.0 } else { 0.0 });
+            let advanced = driver.advance(&input);
+            state.update(&props, &input);
+            let expected = state.output(&props, &input);
+            for variable in [
+                OutputVariableName::dffm,
+                OutputVariableName::W,
+                OutputVariableName::V,
+                OutputVariableName::I,
+            ] {
+                assert_eq!(
+                    advanced.get(&variable),
+                    expected.get(&variable),
+                    "{}",
+                    variable
+                );
+            }
+        }
+        assert_eq!(driver.time(), state.time);
+        assert_eq!(driver.warm_state(), state.snapshot());
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
 use super::{
     config::RISICOModelConfig,
     constants::*,
-    models::{RISICOPropertiesElement, RISICOStateElement},
+    models::{RISICOPropertiesElement, RISICOStateElement, RISICOVegetation},
 };
 
 
@@ -29,6 +29,22 @@ pub fn update_dffm_rain_legacy(r: f32, dffm: f32, sat: f32) -> f32 {
     f32::min(dffm, sat)
 }
 
+/// Equilibrium moisture content [%] (legacy), the value the dffm tends to while drying
+#[allow(non_snake_case)]
+pub fn get_emc_legacy(T: f32, H: f32) -> f32 {
+    A1_LEGACY * f32::powf(H, A2)
+        + A3 * f32::exp((H - 100.0) / 10.0)
+        + A4 * (30.0 - f32::min(T, 30.0)) * (1.0 - f32::exp(-A5 * H))
+}
+
+/// Equilibrium moisture content [%], the value the dffm tends to while drying
+#[allow(non_snake_case)]
+pub fn get_emc(T: f32, H: f32) -> f32 {
+    A1 * f32::powf(H, A2)
+        + A3 * f32::exp((H - 100.0) / 10.0)
+        + A4 * (30.0 - f32::min(T, 30.0)) * (1.0 - f32::exp(-A5 * H))
+}
+
 #[allow(non_snake_case)]
 ///Get the new value for the dfmm when there is no rain (p<p*)
 pub fn update_dffm_dry_legacy(
@@ -40,9 +56,7 @@ pub fn update_dffm_dry_legacy(
     T0: f32,
     dT: f32,
 ) -> f32 {
-    let EMC = A1_LEGACY * f32::powf(H, A2)
-        + A3 * f32::exp((H - 100.0) / 10.0)
-        + A4 * (30.0 - f32::min(T, 30.0)) * (1.0 - f32::exp(-A5 * H));
+    let EMC = get_emc_legacy(T, H);
     let K1 = T0 / (1.0 + A6 * f32::powf(T, B1) + A7 * f32::powf(W, B2));
 
     // drying-wtting dynamic
@@ -88,6 +102,26 @@ pub fn get_ppf(time: &DateTime<Utc>, ppf_summer: f32, ppf_winter: f32) -> f32 {
     }
 }
 
+///calculate the vegetation parameters (d0, d1, umid) modulated by the phenology at the date
+pub fn get_phenology_params(veg: &RISICOVegetation, time: &DateTime<Utc>) -> (f32, f32, f32) {
+    let phenology = match &veg.phenology {
+        Some(phenology) => phenology,
+        None => return (veg.d0, veg.d1, veg.umid),
+    };
+    let modulate = |value: f32, summer: f32, winter: f32| {
+        if value == NODATAVAL {
+            NODATAVAL
+        } else {
+            value * get_ppf(time, summer, winter)
+        }
+    };
+    (
+        modulate(veg.d0, phenology.d0_summer, phenology.d0_winter),
+        modulate(veg.d1, phenology.d1_summer, phenology.d1_winter),
+        modulate(veg.umid, phenology.umid_summer, phenology.umid_winter),
+    )
+}
+
 ///calculate the wind effect on fire propagation
 pub fn get_wind_effect_legacy(wind_speed: f32, wind_dir: f32, slope: f32, aspect: f32) -> f32 {
     if wind_speed == NODATAVAL || wind_dir == NODATAVAL {
@@ -203,6 +237,15 @@ pub fn get_intensity(
     v * (lhv_dff * d0 + lhv_l1 * d1) / 3600.0
 }
 
+/// Dead fine fuel load used for the intensity: the dynamic fuel load from the input
+/// if available, the vegetation one otherwise
+pub fn get_fuel_load(d0: f32, fuel_load: f32) -> f32 {
+    if fuel_load == NODATAVAL || fuel_load < 0.0 {
+        return d0;
+    }
+    fuel_load
+}
+
 pub fn index_from_swi(dffm: f32, swi: f32) -> f32 {
     if swi <= 10.0 {
         return 0.0;
@@ -263,9 +306,7 @@ pub fn update_dffm_rain(r: f32, dffm: f32, sat: f32) -> f32 {
 pub fn update_dffm_dry(dffm: f32, _sat: f32, T: f32, W: f32, H: f32, T0: f32, dT: f32) -> f32 {
     let W = W / 3600.0; //wind is in m/h, should be in m/s
 
-    let EMC = A1 * f32::powf(H, A2)
-        + A3 * f32::exp((H - 100.0) / 10.0)
-        + A4 * (30.0 - f32::min(T, 30.0)) * (1.0 - f32::exp(-A5 * H));
+    let EMC = get_emc(T, H);
 
     let D_dry: f32 =
         (1.0 + B1_D * f32::powf(T_STANDARD, C1_D) + B2_D * f32::powf(W_STANDARD, C2_D))
@@ -377,6 +418,54 @@ pub fn get_wind_slope_effect(slope: f32, aspect: f32, wind_speed: f32, wind_dir:
     ws_effect
 }
 
+/// Get the wind and slope effect on the fire propagation in the wind direction (head fire)
+/// # Arguments
+/// * `slope` - Slope \[radians\]
+/// * `aspect` - Aspect \[radians\]
+/// * `wind_speed` - Wind speed \[m/h\]
+/// * `wind_dir` - Wind direction \[radians\]
+/// # Returns
+/// * `ws_effect` - Wind and slope effect in wind direction \[adim\]
+pub fn get_wind_slope_effect_wind_dir(slope: f32, aspect: f32, wind_speed: f32, wind_dir: f32) -> f32 {
+    get_wind_slope_effect_angle(slope, aspect, wind_speed, wind_dir, wind_dir)
+}
+
+/// Get the wind and slope effect when the wind direction is missing,
+/// assuming the wind aligned with each propagation angle (direction-independent)
+/// # Arguments
+/// * `slope` - Slope \[radians\]
+/// * `aspect` - Aspect \[radians\]
+/// * `wind_speed` - Wind speed \[m/h\]
+/// # Returns
+/// * `ws_effect` - Wind and slope effect \[adim\]
+pub fn get_wind_slope_effect_no_wind_dir(slope: f32, aspect: f32, wind_speed: f32) -> f32 {
+    let angles: Array1<f32> = Array::linspace(0., 2. * PI, N_ANGLES_ROS);
+    angles
+        .iter()
+        .map(|x| get_wind_slope_effect_angle(slope, aspect, wind_speed, *x, *x))
+        .reduce(f32::max)
+        .unwrap_or(NODATAVAL)
+}
+
+/// rate of spread of the v2023 and v2025 versions from the wind-slope effect,
+/// shared by the wind effect over all the angles, in the wind direction or without it
+fn get_v_from_wind_slope_effect(
+    v0: f32,
+    d0: f32,
+    snow_cover: f32,
+    dffm: f32,
+    w_s_eff: f32,
+    t_effect: f32,
+    moisture_effect_fn: fn(f32) -> f32,
+) -> (f32, f32) {
+    if snow_cover > SNOW_COVER_THRESHOLD || d0 == NODATAVAL || dffm == NODATAVAL {
+        return (0.0, w_s_eff);
+    }
+    // moisture effect and wind-slope contribution
+    let ros = v0 * moisture_effect_fn(dffm) * w_s_eff * t_effect;
+    (ros, w_s_eff)
+}
+
 pub fn get_moisture_effect_v2023(dffm: f32) -> f32 {
     // normalize in [0, 1] and divide by moisture of extintion
     let x: f32 = (dffm / 100.) / MX;
@@ -405,17 +494,49 @@ pub fn get_v_v2023(
     t_effect: f32,
 ) -> (f32, f32) {
     let w_s_eff: f32 = get_wind_slope_effect(slope, aspect, wind_speed, wind_dir);
-    if snow_cover > SNOW_COVER_THRESHOLD || d0 == NODATAVAL {
-        return (0.0, w_s_eff);
-    }
-    if dffm == NODATAVAL {
-        return (0.0, w_s_eff);
-    }
-    // moisture effect
-    let moist_coeff: f32 = get_moisture_effect_v2023(dffm);
-    // wind-slope contribution
-    let ros = v0 * moist_coeff * w_s_eff * t_effect;
-    (ros, w_s_eff)
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2023,
+    )
+}
+
+/// rate of spread v2023 in the input wind direction
+#[allow(clippy::too_many_arguments)]
+pub fn get_v_wind_dir_v2023(
+    v0: f32,
+    d0: f32,
+    _d1: f32,
+    snow_cover: f32,
+    dffm: f32,
+    slope: f32,
+    aspect: f32,
+    wind_speed: f32,
+    wind_dir: f32,
+    t_effect: f32,
+) -> (f32, f32) {
+    let w_s_eff: f32 = get_wind_slope_effect_wind_dir(slope, aspect, wind_speed, wind_dir);
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2023,
+    )
+}
+
+/// rate of spread v2023 with missing wind direction
+#[allow(clippy::too_many_arguments)]
+pub fn get_v_no_wind_dir_v2023(
+    v0: f32,
+    d0: f32,
+    _d1: f32,
+    snow_cover: f32,
+    dffm: f32,
+    slope: f32,
+    aspect: f32,
+    wind_speed: f32,
+    _wind_dir: f32,
+    t_effect: f32,
+) -> (f32, f32) {
+    let w_s_eff: f32 = get_wind_slope_effect_no_wind_dir(slope, aspect, wind_speed);
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2023,
+    )
 }
 
 ///compute the meteo index v2023
@@ -487,17 +608,52 @@ pub fn get_v_v2025(
         return (0.0, NODATAVAL);
     }
     let w_s_eff: f32 = get_wind_slope_effect(slope, aspect, wind_speed, wind_dir);
-    if snow_cover > SNOW_COVER_THRESHOLD || d0 == NODATAVAL {
-        return (0.0, w_s_eff);
-    }
-    if dffm == NODATAVAL {
-        return (0.0, w_s_eff);
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2025,
+    )
+}
+
+/// rate of spread v2025 in the input wind direction
+#[allow(clippy::too_many_arguments)]
+pub fn get_v_wind_dir_v2025(
+    v0: f32,
+    d0: f32,
+    _d1: f32,
+    snow_cover: f32,
+    dffm: f32,
+    slope: f32,
+    aspect: f32,
+    wind_speed: f32,
+    wind_dir: f32,
+    t_effect: f32,
+) -> (f32, f32) {
+    if wind_speed == NODATAVAL || wind_dir == NODATAVAL {
+        return (0.0, NODATAVAL);
     }
-    // moisture effect
-    let moist_coeff: f32 = get_moisture_effect_v2025(dffm);
-    // wind-slope contribution
-    let ros = v0 * moist_coeff * w_s_eff * t_effect;
-    (ros, w_s_eff)
+    let w_s_eff: f32 = get_wind_slope_effect_wind_dir(slope, aspect, wind_speed, wind_dir);
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2025,
+    )
+}
+
+/// rate of spread v2025 with missing wind direction
+#[allow(clippy::too_many_arguments)]
+pub fn get_v_no_wind_dir_v2025(
+    v0: f32,
+    d0: f32,
+    _d1: f32,
+    snow_cover: f32,
+    dffm: f32,
+    slope: f32,
+    aspect: f32,
+    wind_speed: f32,
+    _wind_dir: f32,
+    t_effect: f32,
+) -> (f32, f32) {
+    let w_s_eff: f32 = get_wind_slope_effect_no_wind_dir(slope, aspect, wind_speed);
+    get_v_from_wind_slope_effect(
+        v0, d0, snow_cover, dffm, w_s_eff, t_effect, get_moisture_effect_v2025,
+    )
 }
 
 ///compute the meteo index v2025
@@ -559,6 +715,9 @@ pub fn update_moisture_fn(
     if d0 <= 0.0 {
         state.dffm = NODATAVAL;
         return;
+    } else if !config.has_required_inputs(input_data) {
+        // keep the current state if the required inputs are missing
+        return;
     } else if state.snow_cover > SNOW_COVER_THRESHOLD {
         state.dffm = sat;
         return;
@@ -589,6 +748,16 @@ pub fn update_moisture_fn(
 }
 
 
+/// Wind components (u, v) [m/s] from speed [m/h] and direction [rad]
+/// (meteorological convention: direction the wind blows from)
+pub fn get_wind_components(wind_speed: f32, wind_dir: f32) -> (f32, f32) {
+    if wind_speed == NODATAVAL || wind_dir == NODATAVAL {
+        return (NODATAVAL, NODATAVAL);
+    }
+    let wind_speed = wind_speed / 3600.0; // convert to m/s
+    (-wind_speed * wind_dir.sin(), -wind_speed * wind_dir.cos())
+}
+
 #[allow(non_snake_case)]
 pub fn get_output_fn(
     state: &RISICOStateElement,
@@ -597,6 +766,9 @@ pub fn get_output_fn(
     config: &RISICOModelConfig,
     time: &DateTime<Utc>,
 ) -> OutputElement {
+    if !config.has_required_inputs(input) {
+        return OutputElement::default();
+    }
     let veg = &props.vegetation;
 
     let wind_dir = input.wind_dir;
@@ -632,38 +804,69 @@ pub fn get_output_fn(
         1.0
     };
 
+    // seasonal vegetation parameters
+    let (d0, d1, umid) = get_phenology_params(veg, time);
+
+    // the wind gust can drive the spread in place of the sustained wind
+    let ros_wind_speed = config.ros_wind_speed(wind_speed, input.wind_gust);
+
     let (ros, wind_effect) = config.ros(
-        veg.v0, veg.d0, veg.d1, dffm, snow_cover, slope, aspect, wind_speed, wind_dir, t_effect,
+        veg.v0, d0, d1, dffm, snow_cover, slope, aspect, ros_wind_speed, wind_dir, t_effect,
+    );
+
+    let (ros_wind_dir, _) = config.ros_wind_dir(
+        veg.v0, d0, d1, dffm, snow_cover, slope, aspect, ros_wind_speed, wind_dir, t_effect,
     );
 
     let meteo_index = config.meteo_index(dffm, wind_effect);
 
+    let moisture_effect = config.moisture_effect(dffm);
+
+    let emc = if temperature != NODATAVAL && humidity != NODATAVAL {
+        config.emc(temperature, humidity)
+    } else {
+        NODATAVAL
+    };
+
     let ppf = get_ppf(time, props.ppf_summer, props.ppf_winter);
 
-    let intensity = if ros != NODATAVAL && veg.hhv != NODATAVAL {
+    let (intensity, intensity_raw) = if ros != NODATAVAL && veg.hhv != NODATAVAL {
         let LHVdff = get_lhv_dff(veg.hhv, dffm);
         // calcolo LHV per la vegetazione viva
-        let LHVl1 = get_lhv_l1(veg.umid, state.MSI, veg.hhv);
-        // Calcolo Intensità
-        get_intensity(veg.d0, veg.d1, ros, state.NDVI, LHVdff, LHVl1)
+        let LHVl1 = get_lhv_l1(umid, state.MSI, veg.hhv);
+        // dynamic fuel load from the input, if available
+        let d0_fuel = get_fuel_load(d0, input.fuel_load);
+        // Calcolo Intensità (modulated by the relative greenness)
+        let intensity = get_intensity(d0_fuel, d1, ros, state.NDVI, LHVdff, LHVl1);
+        // pure fuel-load intensity, without the relative greenness
+        let intensity_raw = get_intensity(d0_fuel, d1, ros, NODATAVAL, LHVdff, LHVl1);
+        (intensity, intensity_raw)
     } else {
-        NODATAVAL
+        (NODATAVAL, NODATAVAL)
     };
 
     let wind_speed_out = wind_speed / 3600.0; // convert to m/s
     let wind_dir_out = wind_dir.to_degrees();
+    let (wind_u, wind_v) = get_wind_components(wind_speed, wind_dir);
     OutputElement {
         V: ros,
+        V_wind_dir: ros_wind_dir,
         W: wind_effect,
+        moisture_effect,
         PPF: ppf,
         I: intensity,
+        I_raw: intensity_raw,
         temperature,
         humidity,
         wind_speed: wind_speed_out,
         wind_dir: wind_dir_out,
+        wind_u,
+        wind_v,
         rain,
         snow_cover,
         dffm,
+        sat: veg.sat,
+        emc,
         t_effect,
         NDWI: ndwi,
         NDVI: ndvi,
@@ -671,3 +874,47 @@ pub fn get_output_fn(
         ..OutputElement::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dynamic_fuel_load_changes_the_intensity() {
+        let (d0, d1, ros, lhv_dff, lhv_l1) = (0.5, 0.2, 300.0, 17000.0, 6000.0);
+        let static_intensity = get_intensity(d0, d1, ros, NODATAVAL, lhv_dff, lhv_l1);
+        // twice the dead fine fuel load of the vegetation
+        let fuel_load = get_fuel_load(d0, 1.0);
+        assert_eq!(fuel_load, 1.0);
+        let dynamic_intensity = get_intensity(fuel_load, d1, ros, NODATAVAL, lhv_dff, lhv_l1);
+        let expected = static_intensity + ros * lhv_dff * (1.0 - d0) / 3600.0;
+        assert!((dynamic_intensity - expected).abs() < 1e-2, "{}", dynamic_intensity);
+    }
+
+    #[test]
+    fn missing_fuel_load_uses_the_vegetation_one() {
+        assert_eq!(get_fuel_load(0.5, NODATAVAL), 0.5);
+        assert_eq!(get_fuel_load(0.5, -1.0), 0.5);
+    }
+
+    #[test]
+    fn wind_dir_ros_shares_the_body_of_the_ros() {
+        type RosFn = fn(f32, f32, f32, f32, f32, f32, f32, f32, f32, f32) -> (f32, f32);
+        let versions: [(RosFn, RosFn); 2] = [
+            (get_v_v2023, get_v_wind_dir_v2023),
+            (get_v_v2025, get_v_wind_dir_v2025),
+        ];
+        // the wind from north is one of the propagation angles of the ros
+        let ros = |ros_fn: RosFn, snow_cover| {
+            ros_fn(300.0, 0.5, 0.2, snow_cover, 8.0, 0.3, 1.0, 2000.0, 0.0, 1.0)
+        };
+        for (ros_fn, ros_wind_dir_fn) in versions {
+            let (v, w_s_eff) = ros(ros_fn, 0.0);
+            let (v_wind_dir, w_s_eff_wind_dir) = ros(ros_wind_dir_fn, 0.0);
+            assert!(v_wind_dir > 0.0 && v_wind_dir <= v);
+            // same moisture effect and fuel: the ratio is the one of the wind-slope effects
+            assert!((v_wind_dir / v - w_s_eff_wind_dir / w_s_eff).abs() < 1e-5);
+            assert_eq!(ros(ros_wind_dir_fn, 2.0), (0.0, w_s_eff_wind_dir));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod config;
 pub mod constants;
+pub mod driver;
 pub mod functions;
 pub mod models;
@@ -11,7 +11,7 @@ use crate::{
 
 use super::{
     config::RISICOModelConfig,
-    constants::SATELLITE_DATA_SECONDS_VALIDITY,
+    constants::{MAX_DT, MIN_DT, SATELLITE_DATA_SECONDS_VALIDITY},
     functions::{get_output_fn, update_moisture_fn},
 };
 
@@ -26,8 +26,20 @@ pub struct RISICOPropertiesElement {
     pub vegetation: Arc<RISICOVegetation>,
 }
 
+/// Seasonal factors modulating the vegetation parameters by day of year
+/// (interpolated between summer and winter values as for the PPF)
+#[derive(Debug, Clone)]
+pub struct RISICOPhenology {
+    pub d0_summer: f32,
+    pub d0_winter: f32,
+    pub d1_summer: f32,
+    pub d1_winter: f32,
+    pub umid_summer: f32,
+    pub umid_winter: f32,
+}
+
 #[allow(non_snake_case)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RISICOVegetation {
     pub id: String,
     pub d0: f32,
@@ -39,6 +51,7 @@ pub struct RISICOVegetation {
     pub sat: f32,
     pub name: String,
     pub use_ndvi: bool,
+    pub phenology: Option<RISICOPhenology>,
 }
 
 impl Default for RISICOVegetation {
@@ -54,12 +67,13 @@ impl Default for RISICOVegetation {
             sat: 40.0,
             name: "default".to_string(),
             use_ndvi: false,
+            phenology: None,
         }
     }
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RISICOWarmState {
     pub dffm: f32,
     pub snow_cover: f32,
@@ -114,7 +128,20 @@ impl RISICOState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state.
     pub fn new(warm_state: &[RISICOWarmState], time: &DateTime<Utc>, config: RISICOModelConfig) -> RISICOState {
-        let data = Array1::from_vec(
+        let data = Self::new_cells(warm_state);
+
+        RISICOState {
+            time: *time,
+            // props,
+            data,
+            len: warm_state.len(),
+            config,
+        }
+    }
+
+    /// Cells of a new state from the warm state
+    fn new_cells(warm_state: &[RISICOWarmState]) -> Array1<RISICOStateElement> {
+        Array1::from_vec(
             warm_state
                 .iter()
                 .map(|w| RISICOStateElement {
@@ -129,25 +156,42 @@ impl RISICOState {
                     NDWI_TIME: w.NDWI_TIME,
                 })
                 .collect(),
-        );
-
-        RISICOState {
-            time: *time,
-            // props,
-            data,
-            len: warm_state.len(),
-            config,
-        }
+        )
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
 
+    /// Warm state of the cells, to be saved for a later run or restored
+    pub fn snapshot(&self) -> Vec<RISICOWarmState> {
+        self.data
+            .iter()
+            .map(|state| RISICOWarmState {
+                dffm: state.dffm,
+                snow_cover: state.snow_cover,
+                snow_cover_time: state.snow_cover_time,
+                MSI: state.MSI,
+                MSI_TTL: state.MSI_TTL,
+                NDVI: state.NDVI,
+                NDVI_TIME: state.NDVI_TIME,
+                NDWI: state.NDWI,
+                NDWI_TIME: state.NDWI_TIME,
+            })
+            .collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    /// Restore the state from a snapshot taken at the given time
+    pub fn restore(&mut self, warm_state: &[RISICOWarmState], time: &DateTime<Utc>) {
+        self.data = Self::new_cells(warm_state);
+        self.len = warm_state.len();
+        self.time = *time;
+    }
+
     fn update_snow_cover(&mut self, input: &Input) {
         let time = input.time.timestamp() as f32;
 
@@ -216,7 +260,7 @@ impl RISICOState {
                     state.NDWI = NODATAVAL;
                 }
 
-                if self.time.timestamp() - state.NDWI_TIME as i64 > 240 * 3600 {
+                if self.time.timestamp() - state.NDWI_TIME as i64 > self.config.ndwi_seconds_validity {
                     state.NDWI = NODATAVAL;
                 }
 
@@ -233,8 +277,6 @@ impl RISICOState {
 
     #[allow(non_snake_case)]
     fn update_moisture(&mut self, props: &RISICOProperties, input: &Input, dt: f32) {
-        let dt = dt.clamp(1.0, 72.0);
-
         Zip::from(&mut self.data)
             // .and(&self.snow_cover)
             .and(&props.data)
@@ -261,16 +303,36 @@ impl RISICOState {
     /// Update the state of the cells.
     pub fn update(&mut self, props: &RISICOProperties, input: &Input) {
         let new_time = &input.time;
-        let dt = new_time.signed_duration_since(self.time).num_seconds() as f32 / 3600.0;
+        let dt = self.time_step(new_time);
         self.time = *new_time;
         self.update_satellite(input);
         self.update_snow_cover(input);
         self.update_moisture(props, input, dt);
     }
 
+    /// Time step of the moisture update from the current time to `new_time` [hours],
+    /// clamped to [MIN_DT, MAX_DT]. After a gap longer than MAX_DT (e.g. a decimated
+    /// timeline or missing inputs) the moisture is updated as if only MAX_DT hours had passed.
+    fn time_step(&self, new_time: &DateTime<Utc>) -> f32 {
+        let dt = new_time.signed_duration_since(self.time).num_seconds() as f32 / 3600.0;
+        dt.clamp(MIN_DT, MAX_DT)
+    }
+
     pub fn output(&self, props: &RISICOProperties, input: &Input) -> Output {
         self.get_output(props, input)
     }
+
+    /// Number of cells not computed because of missing required inputs
+    pub fn count_missing_required_inputs(&self, input: &Input) -> usize {
+        if self.config.required_inputs.is_empty() {
+            return 0;
+        }
+        input
+            .data
+            .iter()
+            .filter(|i| !self.config.has_required_inputs(i))
+            .count()
+    }
 }
 
 #[derive(Debug)]
@@ -335,3 +397,85 @@ pub struct RISICOCellPropertiesContainer {
     pub aspects: Vec<f32>,
     pub vegetations: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::input::InputElement;
+
+    fn hours(hours: i64) -> Duration {
+        Duration::try_hours(hours).expect("Should be a valid duration")
+    }
+
+    fn properties() -> RISICOProperties {
+        RISICOProperties::new(
+            RISICOCellPropertiesContainer {
+                lons: vec![10.0],
+                lats: vec![45.0],
+                slopes: vec![0.0],
+                aspects: vec![0.0],
+                vegetations: vec!["default".to_string()],
+            },
+            HashMap::new(),
+            vec![1.0],
+            vec![1.0],
+        )
+    }
+
+    fn input(time: DateTime<Utc>) -> Input {
+        let input = InputElement {
+            temperature: 25.0,
+            humidity: 40.0,
+            wind_speed: 10_000.0,
+            wind_dir: 0.0,
+            rain: 0.0,
+            ..InputElement::default()
+        };
+        Input {
+            time,
+            data: Array1::from_vec(vec![input]),
+        }
+    }
+
+    #[test]
+    fn time_step_follows_the_decimated_timeline() {
+        let props = properties();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let config = RISICOModelConfig::new("v2025");
+        let mut state = RISICOState::new(&[RISICOWarmState::default()], &start, config);
+
+        // hourly timeline processed every 6 timesteps, then a gap of 100 hours
+        let mut timeline: Vec<DateTime<Utc>> =
+            (1..=48).map(|h| start + hours(h)).step_by(6).collect();
+        timeline.push(*timeline.last().unwrap() + hours(100));
+        let mut dts = Vec::new();
+        for time in timeline {
+            dts.push(state.time_step(&time));
+            state.update(&props, &input(time));
+            assert_eq!(state.time, time);
+        }
+        assert_eq!(dts[0], 1.0);
+        assert!(
+            dts[1..dts.len() - 1].iter().all(|dt| *dt == 6.0),
+            "{:?}",
+            dts
+        );
+        assert_eq!(*dts.last().unwrap(), MAX_DT);
+    }
+
+    #[test]
+    fn gaps_above_max_dt_update_as_max_dt() {
+        let props = properties();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let dffm_after = |gap: i64| {
+            let config = RISICOModelConfig::new("v2025");
+            let mut state = RISICOState::new(&[RISICOWarmState::default()], &start, config);
+            state.update(&props, &input(start + hours(gap)));
+            state.data[0].dffm
+        };
+        assert_eq!(dffm_after(100), dffm_after(MAX_DT as i64));
+        assert_ne!(dffm_after(6), dffm_after(MAX_DT as i64));
+    }
+}
@@ -0,0 +1,41 @@
+use super::constants::{FMI_INTERCEPT, FMI_SLOPE};
+
+/// configuration structure for model config
+/// can be used to store functions and constants
+#[derive(Debug, Clone)]
+pub struct SharplesModelConfig {
+    // offset added to the air temperature to obtain the dead fuel temperature [°C],
+    // None to use the air temperature
+    pub temperature_offset: Option<f32>,
+    pub fmi_intercept: f32,
+    pub fmi_slope: f32,
+}
+
+impl Default for SharplesModelConfig {
+    fn default() -> Self {
+        SharplesModelConfig {
+            temperature_offset: None,
+            fmi_intercept: FMI_INTERCEPT,
+            fmi_slope: FMI_SLOPE,
+        }
+    }
+}
+
+impl SharplesModelConfig {
+    pub fn new(
+        temperature_offset: Option<f32>,
+        fmi_intercept: Option<f32>,
+        fmi_slope: Option<f32>,
+    ) -> Self {
+        SharplesModelConfig {
+            temperature_offset,
+            fmi_intercept: fmi_intercept.unwrap_or(FMI_INTERCEPT),
+            fmi_slope: fmi_slope.unwrap_or(FMI_SLOPE),
+        }
+    }
+
+    /// Temperature used by the fuel moisture index [°C]
+    pub fn fuel_temperature(&self, temperature: f32) -> f32 {
+        temperature + self.temperature_offset.unwrap_or(0.0)
+    }
+}
@@ -1 +1,5 @@
 pub const NODATAVAL: f32 = -9999.0;
+
+// coefficients of the fuel moisture index FMI = intercept - slope * (T - RH)
+pub const FMI_INTERCEPT: f32 = 10.0;
+pub const FMI_SLOPE: f32 = 0.25;
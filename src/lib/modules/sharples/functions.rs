@@ -1,4 +1,5 @@
 use crate::models::output::OutputElement;
+use super::config::SharplesModelConfig;
 use super::models::SharplesStateElement;
 use super::constants::NODATAVAL;
 
@@ -6,9 +7,10 @@ use super::constants::NODATAVAL;
 pub fn index_fmi(
     temperature: f32,  // temperature [°C]
     humidity: f32, // relative humidity [%]
+    config: &SharplesModelConfig,
 ) -> f32 {
-    
-    10.0 - 0.25*(temperature - humidity)
+    let temperature = config.fuel_temperature(temperature);
+    config.fmi_intercept - config.fmi_slope * (temperature - humidity)
 }
 
 // fire index [-]
@@ -23,11 +25,12 @@ pub fn index_f(
 
 pub fn get_output_fn(
     state: &SharplesStateElement,
+    config: &SharplesModelConfig,
 ) -> OutputElement {
     if (state.temp == NODATAVAL) || (state.humidity == NODATAVAL) || (state.wind_speed == NODATAVAL) {
         return OutputElement::default()
     }
-    let fmi = index_fmi(state.temp, state.humidity);
+    let fmi = index_fmi(state.temp, state.humidity, config);
     let f = index_f(fmi, state.wind_speed);
     // convert the wind speed in m/s
     let ws_out = state.wind_speed / 3600.0;
@@ -41,3 +44,27 @@ pub fn get_output_fn(
         ..OutputElement::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_correction_lowers_the_fmi() {
+        let state = SharplesStateElement {
+            temp: 25.0,
+            humidity: 40.0,
+            wind_speed: 20_000.0,
+        };
+        let uncorrected = get_output_fn(&state, &SharplesModelConfig::default());
+        let corrected = get_output_fn(&state, &SharplesModelConfig::new(Some(4.0), None, None));
+        // 10 - 0.25 * (25 - 40) and 10 - 0.25 * (29 - 40)
+        assert_eq!(uncorrected.fmi, 13.75);
+        assert_eq!(corrected.fmi, 12.75);
+        // the drier fuel gives a higher fire index
+        assert!(corrected.f > uncorrected.f);
+
+        let coefficients = SharplesModelConfig::new(None, Some(12.0), Some(0.5));
+        assert_eq!(index_fmi(25.0, 40.0, &coefficients), 19.5);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod constants;
 pub mod functions;
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod config;
\ No newline at end of file
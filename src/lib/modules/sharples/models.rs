@@ -3,6 +3,7 @@ use chrono::prelude::*;
 use ndarray::{Array1, Zip};
 
 use super::{
+    config::SharplesModelConfig,
     constants::*,
     functions::get_output_fn,
 };
@@ -73,12 +74,13 @@ pub struct SharplesState {
     pub time: DateTime<Utc>,
     pub data: Array1<SharplesStateElement>,
     len: usize,
+    config: SharplesModelConfig,
 }
 
 impl SharplesState {
     #[allow(dead_code, non_snake_case)]
     /// Create a new state
-    pub fn new(time: &DateTime<Utc>, n_cells: usize) -> SharplesState {
+    pub fn new(time: &DateTime<Utc>, n_cells: usize, config: SharplesModelConfig) -> SharplesState {
         let data: Array1<SharplesStateElement> = Array1::from(
             (0..n_cells)
                 .map(|_| SharplesStateElement {
@@ -92,6 +94,7 @@ impl SharplesState {
             time: *time,
             data,
             len: n_cells,
+            config,
         }
     }
 
@@ -106,9 +109,10 @@ impl SharplesState {
     #[allow(non_snake_case)]
     pub fn get_output(&mut self) -> Output {
         let time = &self.time;
+        let config = &self.config;
         let output_data = self.data
                     .map(|state| {
-                        get_output_fn(state)
+                        get_output_fn(state, config)
                     });
         Output::new(*time, output_data)
     }